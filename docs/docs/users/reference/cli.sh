@@ -44,6 +44,8 @@ generate_markdown_section "forest-cli" "chain read-obj"
 generate_markdown_section "forest-cli" "chain set-head"
 generate_markdown_section "forest-cli" "chain prune"
 generate_markdown_section "forest-cli" "chain list"
+generate_markdown_section "forest-cli" "chain finality"
+generate_markdown_section "forest-cli" "chain reorgs"
 
 generate_markdown_section "forest-cli" "auth"
 generate_markdown_section "forest-cli" "auth create-token"
@@ -58,6 +60,8 @@ generate_markdown_section "forest-cli" "sync"
 generate_markdown_section "forest-cli" "sync wait"
 generate_markdown_section "forest-cli" "sync check-bad"
 generate_markdown_section "forest-cli" "sync mark-bad"
+generate_markdown_section "forest-cli" "sync unmark-bad"
+generate_markdown_section "forest-cli" "sync validate-block"
 
 generate_markdown_section "forest-cli" "mpool"
 generate_markdown_section "forest-cli" "mpool pending"
@@ -91,6 +95,7 @@ generate_markdown_section "forest-cli" "f3 status"
 generate_markdown_section "forest-cli" "f3 certs"
 generate_markdown_section "forest-cli" "f3 certs get"
 generate_markdown_section "forest-cli" "f3 certs list"
+generate_markdown_section "forest-cli" "f3 certs verify"
 generate_markdown_section "forest-cli" "f3 powertable"
 generate_markdown_section "forest-cli" "f3 powertable get"
 generate_markdown_section "forest-cli" "f3 powertable get-proportion"
@@ -101,6 +106,7 @@ generate_markdown_section "forest-tool" ""
 generate_markdown_section "forest-tool" "backup"
 generate_markdown_section "forest-tool" "backup create"
 generate_markdown_section "forest-tool" "backup restore"
+generate_markdown_section "forest-tool" "backup verify"
 
 generate_markdown_section "forest-tool" "completion"
 
@@ -128,6 +134,7 @@ generate_markdown_section "forest-tool" "archive info"
 generate_markdown_section "forest-tool" "archive export"
 generate_markdown_section "forest-tool" "archive checkpoints"
 generate_markdown_section "forest-tool" "archive f3-header"
+generate_markdown_section "forest-tool" "archive verify-f3"
 generate_markdown_section "forest-tool" "archive metadata"
 generate_markdown_section "forest-tool" "archive merge"
 generate_markdown_section "forest-tool" "archive merge-f3"
@@ -162,6 +169,9 @@ generate_markdown_section "forest-tool" "shed openrpc"
 generate_markdown_section "forest-tool" "index"
 generate_markdown_section "forest-tool" "index backfill"
 
+generate_markdown_section "forest-tool" "beacon"
+generate_markdown_section "forest-tool" "beacon verify"
+
 generate_markdown_section "forest-dev" ""
 
 generate_markdown_section "forest-dev" "fetch-test-snapshots"
@@ -1,15 +1,25 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::key_management::KeyInfo;
+mod revocation;
+
+pub use revocation::TokenRevocations;
+
+use crate::db::SettingsStore;
+use crate::key_management::{KeyInfo, KeyStore};
 use crate::shim::crypto::SignatureType;
+use anyhow::Context as _;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, decode, encode, errors::Result as JWTResult};
 use rand::Rng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
 
 /// constant string that is used to identify the JWT secret key in `KeyStore`
 pub const JWT_IDENTIFIER: &str = "auth-jwt-private";
+/// constant string that is used to identify the rotated-out JWT secret key in `KeyStore`
+pub const JWT_PREVIOUS_IDENTIFIER: &str = "auth-jwt-private-previous";
 /// Admin permissions
 pub const ADMIN: &[&str] = &["read", "write", "sign", "admin"];
 /// Signing permissions
@@ -20,27 +30,103 @@ pub const WRITE: &[&str] = &["read", "write"];
 pub const READ: &[&str] = &["read"];
 
 /// Claim structure for JWT Tokens
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Claims {
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
     #[serde(rename = "Allow")]
-    allow: Vec<String>,
+    pub allow: Vec<String>,
     // Expiration time (as UTC timestamp)
     #[serde(default)]
-    exp: Option<usize>,
+    pub exp: Option<usize>,
+    /// Unique token ID, used for revocation. Absent in tokens created by Lotus or by older
+    /// Forest versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Forest-specific restrictions on top of `Allow`. Empty for Lotus-compatible tokens.
+    #[serde(flatten)]
+    pub scope: TokenScope,
+}
+
+/// Optional restrictions narrowing down what a token's permissions grant.
+///
+/// These claims are not understood by Lotus, so they are only emitted when set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TokenScope {
+    /// Method name patterns the token may invoke, e.g. `Filecoin.ChainHead`, `ChainHead`
+    /// or `Eth*`. A trailing `*` matches any suffix, and patterns without a namespace match
+    /// the method name after the namespace. The method must still be permitted by the
+    /// token's `Allow` claim. `None` means no method restriction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub methods: Option<Vec<String>>,
+    /// Maximum number of requests per minute accepted with this token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<u32>")]
+    pub rate_limit: Option<NonZeroU32>,
+}
+
+impl TokenScope {
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_none() && self.rate_limit.is_none()
+    }
+
+    /// Checks that every method pattern is well-formed, i.e. non-empty and with `*` only
+    /// as the last character.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for pattern in self.methods.iter().flatten() {
+            let body = pattern.strip_suffix('*').unwrap_or(pattern);
+            anyhow::ensure!(
+                !pattern.is_empty() && !body.contains('*'),
+                "invalid method pattern '{pattern}': `*` is only allowed as the last character"
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the method with the canonical `name` (e.g. `Filecoin.EthGetLogs`) is within
+    /// this scope.
+    pub fn allows_method(&self, name: &str) -> bool {
+        let Some(patterns) = &self.methods else {
+            return true;
+        };
+        let short = name.split_once('.').map_or(name, |(_, short)| short);
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix) || short.starts_with(prefix),
+                None => name == pattern || short == pattern,
+            })
+    }
 }
 
 /// Create a new JWT Token
 pub fn create_token(perms: Vec<String>, key: &[u8], token_exp: Duration) -> JWTResult<String> {
+    create_scoped_token(perms, TokenScope::default(), key, token_exp)
+}
+
+/// Create a new JWT Token restricted by `scope`
+pub fn create_scoped_token(
+    perms: Vec<String>,
+    scope: TokenScope,
+    key: &[u8],
+    token_exp: Duration,
+) -> JWTResult<String> {
     let exp_time = Utc::now() + token_exp;
     let payload = Claims {
         allow: perms,
         exp: Some(exp_time.timestamp() as usize),
+        jti: Some(crate::utils::rand::new_uuid_v4().to_string()),
+        scope,
     };
     encode(&Header::default(), &payload, &EncodingKey::from_secret(key))
 }
 
 /// Verify JWT Token and return the allowed permissions from token
 pub fn verify_token(token: &str, key: &[u8]) -> JWTResult<Vec<String>> {
+    Ok(verify_token_claims(token, key)?.allow)
+}
+
+/// Verify JWT Token and return all of its claims
+pub fn verify_token_claims(token: &str, key: &[u8]) -> JWTResult<Claims> {
     crate::def_is_env_truthy!(disable_exp_validation, "FOREST_JWT_DISABLE_EXP_VALIDATION");
     let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::default());
     if disable_exp_validation() {
@@ -51,7 +137,91 @@ pub fn verify_token(token: &str, key: &[u8]) -> JWTResult<Vec<String>> {
         validation.validate_exp = false;
     }
     let token = decode::<Claims>(token, &DecodingKey::from_secret(key), &validation)?;
-    Ok(token.claims.allow)
+    Ok(token.claims)
+}
+
+/// Verify JWT Token against the signing keys in `keystore`, and return all of its claims.
+///
+/// Tokens signed with the previous key are accepted during the key rotation grace period,
+/// and revoked tokens are rejected.
+pub fn verify_token_with_keystore(
+    token: &str,
+    keystore: &KeyStore,
+    revocations: &TokenRevocations,
+) -> anyhow::Result<Claims> {
+    Ok(verify_token_signing_key(token, keystore, revocations)?.0)
+}
+
+/// Same as [`verify_token_with_keystore`], but also returns the key the token is signed with,
+/// for [`is_token_still_valid`].
+pub fn verify_token_signing_key(
+    token: &str,
+    keystore: &KeyStore,
+    revocations: &TokenRevocations,
+) -> anyhow::Result<(Claims, KeyInfo)> {
+    let key_info = keystore.get(JWT_IDENTIFIER)?;
+    let (claims, key_info) = match verify_token_claims(token, key_info.private_key()) {
+        Ok(claims) => (claims, key_info),
+        Err(e) => match keystore.get(JWT_PREVIOUS_IDENTIFIER) {
+            Ok(previous) if revocations.is_previous_key_valid() => (
+                verify_token_claims(token, previous.private_key())?,
+                previous,
+            ),
+            _ => return Err(e.into()),
+        },
+    };
+    if let Some(jti) = &claims.jti {
+        anyhow::ensure!(!revocations.is_revoked(jti), "token has been revoked");
+    }
+    Ok((claims, key_info))
+}
+
+/// Whether a token verified earlier with [`verify_token_signing_key`] is still accepted, i.e.
+/// it has not been revoked since and its signing `key` is still the current one, or the
+/// previous one during the key rotation grace period.
+pub fn is_token_still_valid(
+    jti: Option<&str>,
+    key: &KeyInfo,
+    keystore: &KeyStore,
+    revocations: &TokenRevocations,
+) -> bool {
+    if jti.is_some_and(|jti| revocations.is_revoked(jti)) {
+        return false;
+    }
+    let is_key = |id| {
+        keystore
+            .get(id)
+            .is_ok_and(|stored| stored.private_key() == key.private_key())
+    };
+    is_key(JWT_IDENTIFIER)
+        || (revocations.is_previous_key_valid() && is_key(JWT_PREVIOUS_IDENTIFIER))
+}
+
+/// Replace the JWT signing key with a freshly generated one. Tokens signed with the
+/// replaced key remain valid for `grace_period`, if positive.
+///
+/// The new key is written first, so that a failure leaves the node with a signing key.
+pub fn rotate_key(
+    keystore: &mut KeyStore,
+    revocations: &TokenRevocations,
+    db: &impl SettingsStore,
+    grace_period: Duration,
+) -> anyhow::Result<()> {
+    keystore
+        .get(JWT_IDENTIFIER)
+        .context("no JWT signing key to rotate")?;
+    let current = keystore
+        .replace(JWT_IDENTIFIER, generate_priv_key())?
+        .context("no JWT signing key to rotate")?;
+    if grace_period > Duration::zero() {
+        // Replaces the key rotated out before, which is no longer needed.
+        keystore.replace(JWT_PREVIOUS_IDENTIFIER, current)?;
+        revocations.set_previous_key_valid_until(db, Some(Utc::now() + grace_period))?;
+    } else {
+        revocations.set_previous_key_valid_until(db, None)?;
+        let _ = keystore.remove(JWT_PREVIOUS_IDENTIFIER);
+    }
+    Ok(())
 }
 
 pub fn generate_priv_key() -> KeyInfo {
@@ -71,6 +241,7 @@ mod tests {
         let payload = Claims {
             allow: perms,
             exp: None,
+            ..Default::default()
         };
         encode(&Header::default(), &payload, &EncodingKey::from_secret(key))
     }
@@ -148,4 +319,130 @@ mod tests {
             std::env::remove_var("FOREST_JWT_DISABLE_EXP_VALIDATION");
         }
     }
+
+    #[test]
+    #[serial]
+    fn create_and_verify_scoped_token() {
+        let key = generate_priv_key();
+        let scope = TokenScope {
+            methods: Some(vec!["Eth*".into(), "Filecoin.ChainHead".into()]),
+            rate_limit: NonZeroU32::new(60),
+        };
+        let token = create_scoped_token(
+            vec!["read".into()],
+            scope.clone(),
+            key.private_key(),
+            Duration::hours(1),
+        )
+        .unwrap();
+        let claims = verify_token_claims(&token, key.private_key()).unwrap();
+        assert_eq!(claims.allow, vec!["read".to_owned()]);
+        assert_eq!(claims.scope, scope);
+
+        // Unscoped tokens keep the Lotus-compatible claim set.
+        let token =
+            create_token(vec!["read".into()], key.private_key(), Duration::hours(1)).unwrap();
+        let claims = verify_token_claims(&token, key.private_key()).unwrap();
+        assert!(claims.scope.is_empty());
+    }
+
+    fn keystore_with_key() -> KeyStore {
+        let mut keystore = KeyStore::new(crate::KeyStoreConfig::Memory).unwrap();
+        keystore.put(JWT_IDENTIFIER, generate_priv_key()).unwrap();
+        keystore
+    }
+
+    fn token_from(keystore: &KeyStore) -> String {
+        let key = keystore.get(JWT_IDENTIFIER).unwrap();
+        create_token(vec!["read".into()], key.private_key(), Duration::hours(1)).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn revoked_token_is_rejected() {
+        let db = crate::db::MemoryDB::default();
+        let keystore = keystore_with_key();
+        let revocations = TokenRevocations::default();
+        let token = token_from(&keystore);
+        let other = token_from(&keystore);
+
+        let claims = verify_token_with_keystore(&token, &keystore, &revocations).unwrap();
+        let jti = claims.jti.expect("tokens are created with an ID");
+        revocations.revoke(&db, jti, claims.exp).unwrap();
+
+        assert!(verify_token_with_keystore(&token, &keystore, &revocations).is_err());
+        assert!(verify_token_with_keystore(&other, &keystore, &revocations).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn rotated_key_is_valid_during_grace_period() {
+        let db = crate::db::MemoryDB::default();
+        let mut keystore = keystore_with_key();
+        let revocations = TokenRevocations::default();
+
+        let token = token_from(&keystore);
+        rotate_key(&mut keystore, &revocations, &db, Duration::hours(1)).unwrap();
+        assert!(verify_token_with_keystore(&token, &keystore, &revocations).is_ok());
+        let rotated = token_from(&keystore);
+        assert!(verify_token_with_keystore(&rotated, &keystore, &revocations).is_ok());
+
+        // Without a grace period, tokens signed with the replaced key are rejected at once.
+        rotate_key(&mut keystore, &revocations, &db, Duration::zero()).unwrap();
+        assert!(verify_token_with_keystore(&rotated, &keystore, &revocations).is_err());
+        assert!(verify_token_with_keystore(&token, &keystore, &revocations).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn verified_tokens_are_invalidated_by_revocation_and_rotation() {
+        let db = crate::db::MemoryDB::default();
+        let mut keystore = keystore_with_key();
+        let revocations = TokenRevocations::default();
+        let token = token_from(&keystore);
+        let other = token_from(&keystore);
+
+        let (claims, key) = verify_token_signing_key(&token, &keystore, &revocations).unwrap();
+        let (other_claims, _) = verify_token_signing_key(&other, &keystore, &revocations).unwrap();
+        let is_valid = |claims: &Claims, keystore: &KeyStore| {
+            is_token_still_valid(claims.jti.as_deref(), &key, keystore, &revocations)
+        };
+        assert!(is_valid(&claims, &keystore));
+
+        revocations
+            .revoke(&db, claims.jti.clone().unwrap(), claims.exp)
+            .unwrap();
+        assert!(!is_valid(&claims, &keystore));
+        assert!(is_valid(&other_claims, &keystore));
+
+        rotate_key(&mut keystore, &revocations, &db, Duration::hours(1)).unwrap();
+        assert!(is_valid(&other_claims, &keystore));
+        rotate_key(&mut keystore, &revocations, &db, Duration::zero()).unwrap();
+        assert!(!is_valid(&other_claims, &keystore));
+    }
+
+    #[test]
+    fn token_scope_method_matching() {
+        let scope = TokenScope {
+            methods: Some(vec![
+                "Eth*".into(),
+                "Filecoin.ChainHead".into(),
+                "Forest.*".into(),
+            ]),
+            rate_limit: None,
+        };
+        assert!(scope.allows_method("Filecoin.EthGetLogs"));
+        assert!(scope.allows_method("Filecoin.ChainHead"));
+        assert!(scope.allows_method("Forest.ChainExport"));
+        assert!(!scope.allows_method("Filecoin.ChainHeadSomething"));
+        assert!(!scope.allows_method("Filecoin.WalletNew"));
+        assert!(TokenScope::default().allows_method("Filecoin.WalletNew"));
+
+        assert!(scope.validate().is_ok());
+        let invalid = TokenScope {
+            methods: Some(vec!["Eth*Logs".into()]),
+            rate_limit: None,
+        };
+        assert!(invalid.validate().is_err());
+    }
 }
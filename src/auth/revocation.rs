@@ -0,0 +1,109 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::db::{SettingsStore, SettingsStoreExt as _, setting_keys::JWT_REVOCATIONS_KEY};
+use ahash::HashMap;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct RevocationState {
+    /// Revoked token IDs, with the expiration timestamp of each token. Entries are dropped
+    /// once their token has expired anyway.
+    revoked: HashMap<String, Option<usize>>,
+    /// Tokens signed with the rotated-out key are accepted until this time.
+    previous_key_valid_until: Option<DateTime<Utc>>,
+}
+
+/// Revoked JWT token IDs and the grace period of the previous JWT signing key.
+///
+/// The state is kept in memory for the per-connection checks and written through to the
+/// settings store on every change, so it survives restarts.
+#[derive(Debug, Default)]
+pub struct TokenRevocations {
+    state: RwLock<RevocationState>,
+}
+
+impl TokenRevocations {
+    /// Loads the persisted state, if any.
+    pub fn load(db: &impl SettingsStore) -> anyhow::Result<Self> {
+        let state = db.read_obj(JWT_REVOCATIONS_KEY)?.unwrap_or_default();
+        Ok(Self {
+            state: RwLock::new(state),
+        })
+    }
+
+    pub fn is_revoked(&self, jti: &str) -> bool {
+        self.state.read().revoked.contains_key(jti)
+    }
+
+    /// Revokes the token with the given ID. `exp` is the token's expiration timestamp.
+    pub fn revoke(
+        &self,
+        db: &impl SettingsStore,
+        jti: String,
+        exp: Option<usize>,
+    ) -> anyhow::Result<()> {
+        let now = Utc::now().timestamp() as usize;
+        let mut state = self.state.write();
+        state
+            .revoked
+            .retain(|_, exp| exp.is_none_or(|exp| exp > now));
+        state.revoked.insert(jti, exp);
+        db.write_obj(JWT_REVOCATIONS_KEY, &*state)
+    }
+
+    /// Whether tokens signed with the previous signing key are still accepted.
+    pub fn is_previous_key_valid(&self) -> bool {
+        self.state
+            .read()
+            .previous_key_valid_until
+            .is_some_and(|until| Utc::now() < until)
+    }
+
+    pub fn set_previous_key_valid_until(
+        &self,
+        db: &impl SettingsStore,
+        until: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        let mut state = self.state.write();
+        state.previous_key_valid_until = until;
+        db.write_obj(JWT_REVOCATIONS_KEY, &*state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+
+    #[test]
+    fn revocations_are_persisted() {
+        let db = MemoryDB::default();
+        let revocations = TokenRevocations::load(&db).unwrap();
+        assert!(!revocations.is_revoked("Nyarlathotep"));
+        assert!(!revocations.is_previous_key_valid());
+
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        revocations.revoke(&db, "Dagon".into(), Some(1)).unwrap();
+        revocations.revoke(&db, "Hastur".into(), None).unwrap();
+        // Expired tokens are forgotten on the next revocation.
+        revocations
+            .revoke(
+                &db,
+                "Nyarlathotep".into(),
+                Some(tomorrow.timestamp() as usize),
+            )
+            .unwrap();
+        revocations
+            .set_previous_key_valid_until(&db, Some(tomorrow))
+            .unwrap();
+
+        let reloaded = TokenRevocations::load(&db).unwrap();
+        assert!(reloaded.is_revoked("Nyarlathotep"));
+        assert!(reloaded.is_revoked("Hastur"));
+        assert!(!reloaded.is_revoked("Dagon"));
+        assert!(reloaded.is_previous_key_valid());
+    }
+}
@@ -37,7 +37,18 @@ pub static IGNORE_DRAND: LazyLock<bool> = LazyLock::new(|| is_env_truthy(IGNORE_
 
 /// Type of the `drand` network. `mainnet` is chained and `quicknet` is unchained.
 /// For the details, see <https://github.com/filecoin-project/FIPs/blob/1bd887028ac1b50b6f2f94913e07ede73583da5b/FIPS/fip-0063.md#specification>
-#[derive(PartialEq, Eq, Copy, Clone, Debug, SerdeSerialize, SerdeDeserialize)]
+#[derive(
+    PartialEq,
+    Eq,
+    Copy,
+    Clone,
+    Debug,
+    SerdeSerialize,
+    SerdeDeserialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[strum(serialize_all = "lowercase")]
 pub enum DrandNetwork {
     Mainnet,
     Quicknet,
@@ -135,6 +146,23 @@ impl BeaconSchedule {
         }
     }
 
+    /// Returns the beacons scheduled at or before `epoch`, the latest last.
+    pub fn beacons_until_epoch(&self, epoch: ChainEpoch) -> impl Iterator<Item = &BeaconImpl> {
+        self.0
+            .iter()
+            .take_while(move |upgrade| upgrade.height <= epoch)
+            .map(|upgrade| &upgrade.beacon)
+    }
+
+    /// Returns the latest beacon of `network` in the schedule.
+    pub fn beacon_for_network(&self, network: DrandNetwork) -> Option<&BeaconImpl> {
+        self.0
+            .iter()
+            .rev()
+            .map(|upgrade| &upgrade.beacon)
+            .find(|beacon| beacon.network() == network)
+    }
+
     pub fn beacon_for_epoch(&self, epoch: ChainEpoch) -> anyhow::Result<(ChainEpoch, &BeaconImpl)> {
         // Iterate over beacon schedule to find the latest randomness beacon to use.
         self.0
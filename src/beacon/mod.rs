@@ -4,8 +4,10 @@
 pub mod beacon_entries;
 mod drand;
 pub mod signatures;
+mod store;
 pub use beacon_entries::*;
 pub use drand::*;
+pub use store::*;
 
 #[cfg(test)]
 pub mod mock_beacon;
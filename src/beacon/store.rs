@@ -0,0 +1,279 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A persistent store of the `drand` beacon entries found in the block headers, indexed by
+//! network and round, so that they can be served and verified without reaching `drand`.
+
+use super::{Beacon, BeaconEntry, BeaconImpl, BeaconSchedule, DrandNetwork, IGNORE_DRAND};
+use crate::blocks::CachingBlockHeader;
+use crate::db::{SettingsStore, setting_keys::BEACON_ENTRIES_KEY_PREFIX};
+use crate::shim::{clock::ChainEpoch, version::NetworkVersion};
+use std::ops::RangeInclusive;
+
+/// Beacon entries stored in the settings store, under
+/// `{BEACON_ENTRIES_KEY_PREFIX}/{network}/{round}`. Rounds are zero-padded so that the keys of a
+/// network sort by round.
+pub struct BeaconEntryStore<DB> {
+    db: DB,
+}
+
+impl<DB: SettingsStore> BeaconEntryStore<DB> {
+    pub fn new(db: DB) -> Self {
+        Self { db }
+    }
+
+    fn network_prefix(network: DrandNetwork) -> String {
+        format!("{BEACON_ENTRIES_KEY_PREFIX}/{network}/")
+    }
+
+    fn key(network: DrandNetwork, round: u64) -> String {
+        format!("{}{round:020}", Self::network_prefix(network))
+    }
+
+    /// Returns the entry of `round` of `network`, if it is in the store.
+    pub fn get(&self, network: DrandNetwork, round: u64) -> anyhow::Result<Option<BeaconEntry>> {
+        Ok(self
+            .db
+            .read_bin(&Self::key(network, round))?
+            .map(|signature| BeaconEntry::new(round, signature)))
+    }
+
+    /// Stores `entry` of `network`, unless it is already stored.
+    pub fn put(&self, network: DrandNetwork, entry: &BeaconEntry) -> anyhow::Result<()> {
+        let key = Self::key(network, entry.round());
+        if !self.db.exists(&key)? {
+            self.db.write_bin(&key, entry.signature())?;
+        }
+        Ok(())
+    }
+
+    /// Stores the beacon entries of a validated block header, under the network of the beacon
+    /// scheduled at its epoch. Nothing is stored when `drand` is ignored, as the entries are
+    /// not verified then.
+    pub fn put_from_header(
+        &self,
+        schedule: &BeaconSchedule,
+        header: &CachingBlockHeader,
+    ) -> anyhow::Result<()> {
+        if *IGNORE_DRAND || header.beacon_entries.is_empty() {
+            return Ok(());
+        }
+        let (_, beacon) = schedule.beacon_for_epoch(header.epoch)?;
+        for entry in &header.beacon_entries {
+            self.put(beacon.network(), entry)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the entries of `network` before `round`, returning how many were deleted.
+    pub fn prune_network(&self, network: DrandNetwork, round: u64) -> anyhow::Result<usize> {
+        let end = Self::key(network, round);
+        let mut pruned = 0;
+        for key in self.db.setting_keys(&Self::network_prefix(network))? {
+            if key >= end {
+                break;
+            }
+            self.db.delete_setting(&key)?;
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+
+    /// Deletes the entries of the rounds before `epoch`, i.e. those of the earlier rounds of the
+    /// beacon scheduled at `epoch` and all those of the beacons it replaced. Returns how many
+    /// were deleted.
+    pub fn prune(
+        &self,
+        schedule: &BeaconSchedule,
+        network_version: NetworkVersion,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<usize> {
+        let (_, current) = schedule.beacon_for_epoch(epoch)?;
+        let round = current.max_beacon_round_for_epoch(network_version, epoch)?;
+        let mut pruned = 0;
+        for beacon in schedule.beacons_until_epoch(epoch) {
+            let network = beacon.network();
+            let before = if network == current.network() {
+                round
+            } else {
+                u64::MAX
+            };
+            pruned += self.prune_network(network, before)?;
+        }
+        Ok(pruned)
+    }
+
+    /// Verifies the signatures of the stored entries of `rounds` against the public key of
+    /// `beacon`. Entries of chained networks are verified against the previous round, so an
+    /// entry following a missing one can't be verified.
+    pub fn verify(
+        &self,
+        beacon: &BeaconImpl,
+        rounds: RangeInclusive<u64>,
+    ) -> anyhow::Result<BeaconVerification> {
+        let network = beacon.network();
+        let mut verification = BeaconVerification::default();
+        let mut prev = match rounds.start().checked_sub(1) {
+            Some(round) if network.is_chained() => self.get(network, round)?,
+            _ => None,
+        };
+        for round in rounds {
+            let Some(entry) = self.get(network, round)? else {
+                verification.missing += 1;
+                prev = None;
+                continue;
+            };
+            let is_valid = if network.is_unchained() {
+                beacon.verify_entries(std::slice::from_ref(&entry), &BeaconEntry::default())
+            } else if let Some(prev) = &prev {
+                beacon.verify_entries(std::slice::from_ref(&entry), prev)
+            } else {
+                verification.unverifiable += 1;
+                prev = Some(entry);
+                continue;
+            };
+            // Malformed signatures are invalid too.
+            if is_valid.unwrap_or(false) {
+                verification.valid += 1;
+            } else {
+                verification.invalid.push(round);
+            }
+            prev = Some(entry);
+        }
+        Ok(verification)
+    }
+}
+
+/// Outcome of [`BeaconEntryStore::verify`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BeaconVerification {
+    /// Number of entries with a valid signature.
+    pub valid: usize,
+    /// Rounds of the entries with an invalid signature.
+    pub invalid: Vec<u64>,
+    /// Number of rounds not in the store.
+    pub missing: usize,
+    /// Number of chained entries whose previous round is not in the store.
+    pub unverifiable: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::BeaconPoint;
+    use crate::beacon::tests::drand::{new_beacon_mainnet, new_beacon_quicknet};
+    use crate::db::MemoryDB;
+    use crate::utils::encoding::hex;
+
+    #[test]
+    fn entries_are_stored_by_network_and_round() {
+        let store = BeaconEntryStore::new(MemoryDB::default());
+        let entry = BeaconEntry::new(7, vec![1, 2, 3]);
+        store.put(DrandNetwork::Quicknet, &entry).unwrap();
+        assert_eq!(store.get(DrandNetwork::Quicknet, 7).unwrap(), Some(entry));
+        assert_eq!(store.get(DrandNetwork::Mainnet, 7).unwrap(), None);
+        assert_eq!(store.get(DrandNetwork::Quicknet, 8).unwrap(), None);
+    }
+
+    #[test]
+    fn entries_before_the_cutoff_are_pruned() {
+        let store = BeaconEntryStore::new(MemoryDB::default());
+        let schedule = BeaconSchedule(vec![
+            BeaconPoint::new(0, new_beacon_mainnet()),
+            BeaconPoint::new(100, new_beacon_quicknet()),
+        ]);
+        let (nv, epoch) = (NetworkVersion::V16, 4_000_000);
+        let (_, quicknet) = schedule.beacon_for_epoch(epoch).unwrap();
+        let cutoff = quicknet.max_beacon_round_for_epoch(nv, epoch).unwrap();
+        for round in [1, 2, cutoff - 1, cutoff, cutoff + 10] {
+            let entry = BeaconEntry::new(round, vec![1]);
+            store.put(DrandNetwork::Mainnet, &entry).unwrap();
+            store.put(DrandNetwork::Quicknet, &entry).unwrap();
+        }
+
+        assert_eq!(store.prune(&schedule, nv, epoch).unwrap(), 8);
+        for round in [1, 2, cutoff - 1, cutoff, cutoff + 10] {
+            assert_eq!(store.get(DrandNetwork::Mainnet, round).unwrap(), None);
+            assert_eq!(
+                store.get(DrandNetwork::Quicknet, round).unwrap().is_some(),
+                round >= cutoff
+            );
+        }
+    }
+
+    // https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971/public/2
+    const QUICKNET_SIGNATURE_2: &str = "b6b6a585449b66eb12e875b64fcbab3799861a00e4dbf092d99e969a5eac57dd3f798acf61e705fe4f093db926626807";
+
+    // https://api.drand.sh/8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce/public/{2,3,4}
+    const MAINNET_SIGNATURES: [(u64, &str); 3] = [
+        (
+            2,
+            "aa18facd2d51b616511d542de6f9af8a3b920121401dad1434ed1db4a565f10e04fad8d9b2b4e3e0094364374caafe9b10478bf75650124831509c638b5a36a7a232ec70289f8751a2adb47fc32eb70b57dc81c39d48cbcac9fec46cdfc31663",
+        ),
+        (
+            3,
+            "a7b0877eaea7a0222f4c39a2c03434c34f5fe3ea47c533d24b88e5c3053b84775ccb78e984addcb55173f40428513f280cc6e0fccc3c89bb1625c7c0b477deb6faae43fc6ec036f09233bf38da16586b3042dd01a7e9ed97c8bafa343cc6071e",
+        ),
+        (
+            4,
+            "b3d74f1ab9da993e3e3c01d1a395cce8834b42de57f5ad922ac63b2e715c238f986f3098d379ce6aad07b8581e4cfd6d1533835d5e2a7e299beec8851a21c8f9ca2d714d87a471641427d21838fb2ca1a406707bb0b372f74ab667f0509fa341",
+        ),
+    ];
+
+    #[test]
+    fn unchained_entries_are_verified_offline() {
+        let signature = hex::decode(QUICKNET_SIGNATURE_2).unwrap();
+        let store = BeaconEntryStore::new(MemoryDB::default());
+        store
+            .put(
+                DrandNetwork::Quicknet,
+                &BeaconEntry::new(2, signature.clone()),
+            )
+            .unwrap();
+        // The signature of another round.
+        store
+            .put(DrandNetwork::Quicknet, &BeaconEntry::new(3, signature))
+            .unwrap();
+
+        let beacon = BeaconImpl::from(new_beacon_quicknet());
+        assert_eq!(
+            store.verify(&beacon, 2..=4).unwrap(),
+            BeaconVerification {
+                valid: 1,
+                invalid: vec![3],
+                missing: 1,
+                unverifiable: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn chained_entries_are_verified_against_the_previous_round() {
+        let store = BeaconEntryStore::new(MemoryDB::default());
+        for (round, signature) in MAINNET_SIGNATURES {
+            let entry = BeaconEntry::new(round, hex::decode(signature).unwrap());
+            store.put(DrandNetwork::Mainnet, &entry).unwrap();
+        }
+
+        let beacon = BeaconImpl::from(new_beacon_mainnet());
+        // Round 1 is missing, so round 2 can't be verified.
+        assert_eq!(
+            store.verify(&beacon, 2..=4).unwrap(),
+            BeaconVerification {
+                valid: 2,
+                invalid: vec![],
+                missing: 0,
+                unverifiable: 1,
+            }
+        );
+        assert_eq!(
+            store.verify(&beacon, 3..=5).unwrap(),
+            BeaconVerification {
+                valid: 2,
+                invalid: vec![],
+                missing: 1,
+                unverifiable: 0,
+            }
+        );
+    }
+}
@@ -18,7 +18,7 @@ use std::borrow::Cow;
 use std::sync::LazyLock;
 use std::time::Duration;
 
-fn new_beacon_mainnet() -> DrandBeacon {
+pub fn new_beacon_mainnet() -> DrandBeacon {
     DrandBeacon::new(
         1598306400,
         30,
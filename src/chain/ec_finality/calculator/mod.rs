@@ -43,7 +43,6 @@ pub static DEFAULT_GUARANTEE: LazyLock<f64> =
 /// Computes the upper-bound probability that a confirmed
 /// tipset could be reorganized out of the canonical chain. This is a port
 /// of the Python reference implementation from `FRC-0089`(`finality_calc_validator.py`).
-#[allow(dead_code)]
 pub fn calc_validator_prob(
     chain: &[i64],
     finality: ChainEpoch,
@@ -210,7 +209,6 @@ pub fn calc_validator_prob(
 /// Performs a bisect search to find the shallowest depth at
 /// which the reorg probability drops below the given guarantee. Returns -1 if
 /// the guarantee is not met within the search range.
-#[allow(dead_code)]
 pub fn find_threshold_depth(
     chain: &[i64],
     finality: i64,
@@ -0,0 +1,37 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::Arc;
+
+use prometheus_client::{
+    collector::Collector,
+    encoding::{DescriptorEncoder, EncodeMetric},
+    metrics::gauge::Gauge,
+};
+
+#[derive(derive_more::Constructor, derive_more::Debug)]
+pub struct EcFinalityCollector<F>
+where
+    F: Fn() -> i64,
+{
+    #[debug(skip)]
+    get_ec_finality_threshold_depth: Arc<F>,
+}
+
+impl<F> Collector for EcFinalityCollector<F>
+where
+    F: Fn() -> i64 + Send + Sync + 'static,
+{
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+        let threshold_depth: Gauge = Default::default();
+        threshold_depth.set((self.get_ec_finality_threshold_depth)());
+        let metric_encoder = encoder.encode_descriptor(
+            "ec_finality_threshold_depth",
+            "Number of epochs below the chain head for a 2^-30 EC reorg probability, -1 if not met",
+            None,
+            threshold_depth.metric_type(),
+        )?;
+        threshold_depth.encode(metric_encoder)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A bounded in-memory store of block propagation timelines, i.e. when the node first received,
+//! fetched, validated and switched its head to each recent block, relative to the block's
+//! timestamp. Only blocks first seen near the head are tracked, so that the blocks fetched while
+//! catching up don't skew the metrics.
+
+use crate::blocks::CachingBlockHeader;
+use crate::lotus_json::{LotusJson, lotus_json_with_self};
+use crate::prelude::*;
+use ahash::HashMap;
+use parking_lot::Mutex;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+
+/// Maximum number of blocks kept in the store; the oldest ones are dropped first.
+pub const BLOCK_TIMELINE_CAPACITY: usize = 1024;
+
+/// Number of epochs after its timestamp within which a block must first be recorded to be
+/// tracked. Older blocks are being caught up with rather than propagated.
+const MAX_DELAY_EPOCHS: i64 = 4;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct BlockStageLabel {
+    stage: &'static str,
+}
+
+static BLOCK_PROPAGATION_DELAY: LazyLock<Family<BlockStageLabel, Histogram>> =
+    LazyLock::new(|| {
+        let metric = Family::<BlockStageLabel, Histogram>::new_with_constructor(|| {
+            // Buckets from 0.25s to 128s.
+            Histogram::new(exponential_buckets(0.25, 2., 10))
+        });
+        crate::metrics::default_registry().register(
+            "block_propagation_delay",
+            "Time from the block timestamp to each stage of its propagation, in seconds",
+            metric.clone(),
+        );
+        metric
+    });
+
+/// A stage of the propagation of a block through the node.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr,
+)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "snake_case")]
+pub enum BlockStage {
+    /// The block header was received over gossip.
+    GossipReceived,
+    /// The full block was fetched from a peer, following a gossip block or a hello message, or
+    /// while syncing.
+    Fetched,
+    ValidationStarted,
+    /// The block passed validation.
+    ValidationFinished,
+    /// The node switched its head to the tipset of the block.
+    HeadSwitched,
+}
+
+/// When a stage of the propagation of a block was reached.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTimelineEvent {
+    pub stage: BlockStage,
+    /// Unix timestamp of the event, in milliseconds.
+    pub time_ms: i64,
+    /// Time from the block timestamp to the event, in milliseconds.
+    pub delay_ms: i64,
+}
+
+/// The propagation timeline of a block, with the first time each stage was reached.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTimeline {
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<Cid>")]
+    pub cid: Cid,
+    pub epoch: ChainEpoch,
+    /// Block timestamp, in seconds.
+    pub timestamp: u64,
+    pub events: Vec<BlockTimelineEvent>,
+}
+lotus_json_with_self!(BlockTimeline);
+
+/// Timelines of the latest [`BLOCK_TIMELINE_CAPACITY`] blocks.
+#[derive(Debug)]
+pub struct BlockTimelines {
+    inner: Mutex<BlockTimelinesInner>,
+    /// Maximum delay of the first event of a tracked block, in milliseconds.
+    max_delay_ms: i64,
+}
+
+#[derive(Debug, Default)]
+struct BlockTimelinesInner {
+    timelines: HashMap<Cid, BlockTimeline>,
+    /// Blocks in the order they were first recorded.
+    order: VecDeque<Cid>,
+}
+
+impl BlockTimelines {
+    pub fn new(block_delay_secs: u32) -> Self {
+        Self {
+            inner: Default::default(),
+            max_delay_ms: i64::from(block_delay_secs) * 1000 * MAX_DELAY_EPOCHS,
+        }
+    }
+
+    /// Records that the block of `header` reached `stage` now, unless it already did or the block
+    /// is not tracked.
+    pub fn record(&self, header: &CachingBlockHeader, stage: BlockStage) {
+        self.record_at(header, stage, chrono::Utc::now().timestamp_millis());
+    }
+
+    /// Records that the blocks of `headers` reached `stage` now.
+    pub fn record_all<'a>(
+        &self,
+        headers: impl IntoIterator<Item = &'a CachingBlockHeader>,
+        stage: BlockStage,
+    ) {
+        let time_ms = chrono::Utc::now().timestamp_millis();
+        for header in headers {
+            self.record_at(header, stage, time_ms);
+        }
+    }
+
+    fn record_at(&self, header: &CachingBlockHeader, stage: BlockStage, time_ms: i64) {
+        let cid = *header.cid();
+        let delay_ms = time_ms.saturating_sub((header.timestamp as i64).saturating_mul(1000));
+        let mut inner = self.inner.lock();
+        if !inner.timelines.contains_key(&cid) {
+            if delay_ms > self.max_delay_ms {
+                return;
+            }
+            if inner.order.len() >= BLOCK_TIMELINE_CAPACITY
+                && let Some(oldest) = inner.order.pop_front()
+            {
+                inner.timelines.remove(&oldest);
+            }
+            inner.order.push_back(cid);
+        }
+        let timeline = inner.timelines.entry(cid).or_insert_with(|| BlockTimeline {
+            cid,
+            epoch: header.epoch,
+            timestamp: header.timestamp,
+            events: vec![],
+        });
+        if timeline.events.iter().any(|event| event.stage == stage) {
+            return;
+        }
+        timeline.events.push(BlockTimelineEvent {
+            stage,
+            time_ms,
+            delay_ms,
+        });
+        BLOCK_PROPAGATION_DELAY
+            .get_or_create(&BlockStageLabel {
+                stage: stage.into(),
+            })
+            .observe(delay_ms as f64 / 1000.);
+    }
+
+    /// Returns the timeline of the block with the given CID, if it is in the store.
+    pub fn get(&self, cid: &Cid) -> Option<BlockTimeline> {
+        self.inner.lock().timelines.get(cid).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::RawBlockHeader;
+
+    fn header(epoch: ChainEpoch) -> CachingBlockHeader {
+        CachingBlockHeader::new(RawBlockHeader {
+            epoch,
+            timestamp: 1_000,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn first_event_of_each_stage_is_kept() {
+        let timelines = BlockTimelines::new(30);
+        let header = header(1);
+        timelines.record_at(&header, BlockStage::GossipReceived, 1_000_500);
+        timelines.record_at(&header, BlockStage::Fetched, 1_001_000);
+        timelines.record_at(&header, BlockStage::GossipReceived, 1_002_000);
+
+        let timeline = timelines.get(header.cid()).unwrap();
+        assert_eq!(timeline.epoch, 1);
+        assert_eq!(
+            timeline.events,
+            vec![
+                BlockTimelineEvent {
+                    stage: BlockStage::GossipReceived,
+                    time_ms: 1_000_500,
+                    delay_ms: 500,
+                },
+                BlockTimelineEvent {
+                    stage: BlockStage::Fetched,
+                    time_ms: 1_001_000,
+                    delay_ms: 1_000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn oldest_blocks_are_dropped() {
+        let timelines = BlockTimelines::new(30);
+        let headers = (0..=BLOCK_TIMELINE_CAPACITY as ChainEpoch)
+            .map(header)
+            .collect_vec();
+        for header in &headers {
+            timelines.record_at(header, BlockStage::Fetched, 1_001_000);
+        }
+        assert!(timelines.get(headers[0].cid()).is_none());
+        assert!(timelines.get(headers[1].cid()).is_some());
+        assert!(
+            timelines
+                .get(headers[BLOCK_TIMELINE_CAPACITY].cid())
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn blocks_far_behind_the_head_are_not_tracked() {
+        let timelines = BlockTimelines::new(30);
+        let (synced, caught_up) = (header(1), header(2));
+        // Four epochs late, the block is still tracked, and so are its later stages.
+        timelines.record_at(&synced, BlockStage::Fetched, 1_120_000);
+        timelines.record_at(&synced, BlockStage::HeadSwitched, 1_200_000);
+        // An hour late, it is being caught up with.
+        timelines.record_at(&caught_up, BlockStage::Fetched, 4_600_000);
+        timelines.record_at(&caught_up, BlockStage::HeadSwitched, 4_601_000);
+
+        assert_eq!(timelines.get(synced.cid()).unwrap().events.len(), 2);
+        assert!(timelines.get(caught_up.cid()).is_none());
+    }
+}
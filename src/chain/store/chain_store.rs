@@ -3,7 +3,9 @@
 
 use super::{
     Error,
+    block_timeline::{BlockStage, BlockTimelines},
     index::{ChainIndex, ResolveNullTipset},
+    reorg_log::{ChainReorg, ReorgLog},
     tipset_tracker::TipsetTracker,
 };
 use crate::networks::{ChainConfig, Height};
@@ -45,6 +47,9 @@ use std::{
 // Assume a tipset has 5 blocks on average, we cache 1-day-worth of validated blocks. (5 * 2 * 60 * 24 = 14400)
 const VALIDATED_BLOCKS_CACHE_SIZE: NonZeroUsize = nonzero!(14400usize);
 
+/// Maximum depth of the reorgs detected on head switches when nobody subscribes to head changes.
+const REORG_DETECTION_MAX_DEPTH: ChainEpoch = 20;
+
 /// Disambiguate the type to signify that we are expecting a delta and not an actual epoch/height
 /// while maintaining the same type.
 pub type ChainEpochDelta = ChainEpoch;
@@ -110,6 +115,12 @@ pub struct ChainStore {
 
     /// Head key of the last clean [`Self::repair_tipset_lookup`] scan, for debouncing.
     last_clean_lookup_repair_head: Arc<ArcSwapOption<TipsetKey>>,
+
+    /// The latest chain reorgs.
+    reorg_log: Arc<ReorgLog>,
+
+    /// Propagation timelines of the latest blocks.
+    block_timelines: Arc<BlockTimelines>,
 }
 
 impl ShallowClone for ChainStore {
@@ -126,6 +137,8 @@ impl ShallowClone for ChainStore {
             chain_config: self.chain_config.shallow_clone(),
             messages_in_tipset_cache: self.messages_in_tipset_cache.shallow_clone(),
             last_clean_lookup_repair_head: self.last_clean_lookup_repair_head.shallow_clone(),
+            reorg_log: self.reorg_log.shallow_clone(),
+            block_timelines: self.block_timelines.shallow_clone(),
         }
     }
 }
@@ -161,6 +174,7 @@ impl ChainStore {
                 epoch <= finalized
             }
         }));
+        let block_timelines = Arc::new(BlockTimelines::new(chain_config.block_delay_secs));
         Ok(Self {
             head_changes: Publisher::default(),
             chain_index,
@@ -176,6 +190,8 @@ impl ChainStore {
             chain_config,
             messages_in_tipset_cache: Default::default(),
             last_clean_lookup_repair_head: Default::default(),
+            reorg_log: Default::default(),
+            block_timelines,
         })
     }
 
@@ -249,8 +265,13 @@ impl ChainStore {
             error!("failed to cleanup stale null round lookups: {e:#?}");
         }
 
+        self.block_timelines
+            .record_all(head.block_headers(), BlockStage::HeadSwitched);
         let old_head = self.heaviest_tipset.swap(head.shallow_clone().into());
-        if old_head.key() != head.key() && self.head_changes.has_subscribers() {
+        if old_head.key() == head.key() {
+            return Ok(());
+        }
+        if self.head_changes.has_subscribers() {
             let changes = match crate::rpc::chain::chain_get_path(self, old_head.key(), head.key())
             {
                 Ok(changes) => changes,
@@ -261,19 +282,85 @@ impl ChainStore {
                     }
                     // Fallback to single apply
                     PathChanges {
-                        applies: vec![head],
+                        applies: vec![head.shallow_clone()],
                         reverts: vec![],
                     }
                 }
             };
+            if !changes.reverts.is_empty() {
+                self.record_reorg(&old_head, &head, &changes);
+            }
             if !changes.is_empty() {
                 self.head_changes.publish(changes);
             }
+        } else if old_head.epoch() > 0 && head.parents() != old_head.key() {
+            // A head that doesn't extend the previous one by a single tipset may be a reorg.
+            // Without subscribers the path is only needed for the reorg log, so the walk is kept
+            // short.
+            match crate::rpc::chain::chain_get_path_bounded(
+                self,
+                old_head.key(),
+                head.key(),
+                REORG_DETECTION_MAX_DEPTH,
+            ) {
+                Ok(changes) if !changes.reverts.is_empty() => {
+                    self.record_reorg(&old_head, &head, &changes)
+                }
+                Ok(_) => {}
+                Err(e) => debug!("skipped chain reorg detection: {e:#}"),
+            }
         }
 
         Ok(())
     }
 
+    fn record_reorg(&self, old_head: &Tipset, head: &Tipset, changes: &HeadChanges) {
+        let reorg = changes
+            .reverts
+            .last()
+            .context("no reverted tipset")
+            .and_then(|oldest| Ok(self.chain_index.load_required_tipset(oldest.parents())?))
+            .and_then(|common_ancestor| {
+                ChainReorg::new(
+                    old_head,
+                    head,
+                    &common_ancestor,
+                    &changes.reverts,
+                    &changes.applies,
+                    |ts| {
+                        Ok(self
+                            .messages_for_tipset(ts)?
+                            .iter()
+                            .map(ChainMessage::cid)
+                            .collect())
+                    },
+                )
+            });
+        match reorg {
+            Ok(reorg) => {
+                info!(
+                    "Chain reorg of depth {} from epoch {} to epoch {}, {} message(s) dropped",
+                    reorg.depth,
+                    reorg.old_head_epoch,
+                    reorg.new_head_epoch,
+                    reorg.dropped_messages.len()
+                );
+                self.reorg_log.record(reorg);
+            }
+            Err(e) => warn!("failed to record chain reorg: {e:#}"),
+        }
+    }
+
+    /// Returns the log of the latest chain reorgs.
+    pub fn reorg_log(&self) -> &ReorgLog {
+        &self.reorg_log
+    }
+
+    /// Returns the propagation timelines of the latest blocks.
+    pub fn block_timelines(&self) -> &BlockTimelines {
+        &self.block_timelines
+    }
+
     /// Adds a block header to the tipset tracker, which tracks valid headers.
     pub fn add_to_tipset_tracker(&self, header: &CachingBlockHeader) {
         self.tipset_tracker.add(header);
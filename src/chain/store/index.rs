@@ -82,6 +82,15 @@ impl ChainIndex {
         self
     }
 
+    /// Returns an index over another view of the same chain, e.g. an
+    /// [`OverlayBlockstore`](crate::db::OverlayBlockstore), sharing the tipset cache.
+    pub fn with_db(&self, db: impl Into<DbImpl>) -> Self {
+        Self {
+            db: db.into(),
+            ..self.shallow_clone()
+        }
+    }
+
     pub fn db(&self) -> &DbImpl {
         &self.db
     }
@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod base_fee;
+pub mod block_timeline;
 mod chain_store;
 mod errors;
 pub mod index;
+pub mod reorg_log;
 mod tipset_tracker;
 mod weighted_quick_select;
 
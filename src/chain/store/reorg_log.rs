@@ -0,0 +1,118 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A bounded in-memory log of the chain reorganizations observed by the node, i.e. head changes
+//! that revert tipsets of the previous head.
+
+use crate::blocks::{Tipset, TipsetKey};
+use crate::lotus_json::{LotusJson, lotus_json_with_self};
+use crate::prelude::*;
+use ahash::HashSet;
+use parking_lot::Mutex;
+use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+
+/// Maximum number of reorgs kept in the log; the oldest ones are dropped first.
+pub const REORG_LOG_CAPACITY: usize = 256;
+
+static CHAIN_REORG_DEPTH: LazyLock<Histogram> = LazyLock::new(|| {
+    // Buckets from 1 to 512 epochs.
+    let metric = Histogram::new(exponential_buckets(1., 2., 10));
+    crate::metrics::default_registry().register(
+        "chain_reorg_depth",
+        "Number of epochs reverted by chain reorganizations",
+        metric.clone(),
+    );
+    metric
+});
+
+/// A chain reorganization, from `old_head` to `new_head` through `common_ancestor`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainReorg {
+    /// Unix timestamp of the reorg, in seconds.
+    pub timestamp: i64,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TipsetKey>")]
+    pub old_head: TipsetKey,
+    pub old_head_epoch: ChainEpoch,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TipsetKey>")]
+    pub new_head: TipsetKey,
+    pub new_head_epoch: ChainEpoch,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TipsetKey>")]
+    pub common_ancestor: TipsetKey,
+    pub common_ancestor_epoch: ChainEpoch,
+    /// Number of epochs of the old head reverted, `old_head_epoch - common_ancestor_epoch`.
+    pub depth: ChainEpoch,
+    /// Messages of the reverted tipsets that the new head doesn't include.
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<Vec<Cid>>")]
+    pub dropped_messages: Vec<Cid>,
+}
+lotus_json_with_self!(ChainReorg);
+
+impl ChainReorg {
+    /// Describes the reorg between `old_head` and `new_head`, given the tipsets reverted and
+    /// applied by the head change, newest first and oldest first respectively. `messages`
+    /// returns the message CIDs of a tipset.
+    pub fn new(
+        old_head: &Tipset,
+        new_head: &Tipset,
+        common_ancestor: &Tipset,
+        reverts: &[Tipset],
+        applies: &[Tipset],
+        messages: impl Fn(&Tipset) -> anyhow::Result<Vec<Cid>>,
+    ) -> anyhow::Result<Self> {
+        let mut applied = HashSet::default();
+        for ts in applies {
+            applied.extend(messages(ts)?);
+        }
+        let mut dropped_messages = vec![];
+        for ts in reverts.iter().rev() {
+            for cid in messages(ts)? {
+                if !applied.contains(&cid) && !dropped_messages.contains(&cid) {
+                    dropped_messages.push(cid);
+                }
+            }
+        }
+        Ok(Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            old_head: old_head.key().clone(),
+            old_head_epoch: old_head.epoch(),
+            new_head: new_head.key().clone(),
+            new_head_epoch: new_head.epoch(),
+            common_ancestor: common_ancestor.key().clone(),
+            common_ancestor_epoch: common_ancestor.epoch(),
+            depth: old_head.epoch() - common_ancestor.epoch(),
+            dropped_messages,
+        })
+    }
+}
+
+/// A ring of the latest [`REORG_LOG_CAPACITY`] chain reorganizations.
+#[derive(Debug, Default)]
+pub struct ReorgLog {
+    reorgs: Mutex<VecDeque<ChainReorg>>,
+}
+
+impl ReorgLog {
+    /// Records `reorg`, dropping the oldest one when the log is full.
+    pub fn record(&self, reorg: ChainReorg) {
+        CHAIN_REORG_DEPTH.observe(reorg.depth as f64);
+        let mut reorgs = self.reorgs.lock();
+        if reorgs.len() >= REORG_LOG_CAPACITY {
+            reorgs.pop_front();
+        }
+        reorgs.push_back(reorg);
+    }
+
+    /// Returns the recorded reorgs, oldest first.
+    pub fn reorgs(&self) -> Vec<ChainReorg> {
+        self.reorgs.lock().iter().cloned().collect()
+    }
+}
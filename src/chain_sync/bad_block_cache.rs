@@ -1,23 +1,93 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::num::NonZeroUsize;
+use std::{num::NonZeroUsize, sync::LazyLock, time::Duration};
 
+use get_size2::GetSize;
 use nonzero_ext::nonzero;
+use serde::{Deserialize, Serialize};
 
+use crate::db::{
+    DbImpl, SettingsStore as _, SettingsStoreExt as _, setting_keys::BAD_BLOCKS_KEY_PREFIX,
+};
 use crate::prelude::*;
 use crate::utils::cache::SizeTrackingCache;
+use crate::utils::misc::env::env_or_default_logged;
 
 /// Default capacity for CID caches (32768 entries).
 /// That's about 4 MiB.
 const DEFAULT_CID_CACHE_CAPACITY: NonZeroUsize = nonzero!(1usize << 15);
 
+/// How long blocks marked bad by validation stay bad. Blocks marked bad by the operator never
+/// expire.
+static BAD_BLOCK_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_or_default_logged(
+        "FOREST_BAD_BLOCK_TTL_SECS",
+        24 * 60 * 60,
+    ))
+});
+
+/// Who marked a block bad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, GetSize, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum BadBlockOrigin {
+    /// The block failed validation.
+    Validation,
+    /// The operator marked the block bad, e.g. with `SyncMarkBad`.
+    Operator,
+}
+
+/// Why and when a block was marked bad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, GetSize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadBlock {
+    pub reason: String,
+    /// Epoch of the chain head when the block was marked bad.
+    pub epoch: ChainEpoch,
+    pub origin: BadBlockOrigin,
+    /// Unix timestamp of the marking, in seconds.
+    pub marked_at: i64,
+}
+
+impl BadBlock {
+    pub fn new(reason: impl Into<String>, epoch: ChainEpoch, origin: BadBlockOrigin) -> Self {
+        Self {
+            reason: reason.into(),
+            epoch,
+            origin,
+            marked_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    fn is_expired(&self, ttl: Duration, now: i64) -> bool {
+        self.origin == BadBlockOrigin::Validation
+            && now.saturating_sub(self.marked_at)
+                >= i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX)
+    }
+}
+
+impl std::fmt::Display for BadBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (marked by {} at epoch {})",
+            self.reason, self.origin, self.epoch
+        )
+    }
+}
+
 /// Thread-safe cache for tracking bad blocks.
 /// This cache is checked before validating a block, to ensure no duplicate
 /// work.
-#[derive(Debug, derive_more::Deref)]
+///
+/// When backed by a database, each change is written through to the settings store, under
+/// `{BAD_BLOCKS_KEY_PREFIX}/{cid}`, so that the cache survives restarts.
+#[derive(derive_more::Debug)]
 pub struct BadBlockCache {
-    cache: SizeTrackingCache<CidWrapper, ()>,
+    cache: SizeTrackingCache<CidWrapper, BadBlock>,
+    #[debug(skip)]
+    db: Option<DbImpl>,
+    ttl: Duration,
 }
 
 impl Default for BadBlockCache {
@@ -30,6 +100,8 @@ impl ShallowClone for BadBlockCache {
     fn shallow_clone(&self) -> Self {
         Self {
             cache: self.cache.shallow_clone(),
+            db: self.db.shallow_clone(),
+            ttl: self.ttl,
         }
     }
 }
@@ -38,12 +110,102 @@ impl BadBlockCache {
     pub fn new(cap: NonZeroUsize) -> Self {
         Self {
             cache: SizeTrackingCache::new_with_metrics("bad_block", cap),
+            db: None,
+            ttl: *BAD_BLOCK_TTL,
+        }
+    }
+
+    /// Loads the bad blocks persisted in `db`, dropping the expired ones and the ones over
+    /// capacity, and persists the future changes there.
+    pub fn load(db: DbImpl) -> anyhow::Result<Self> {
+        let mut cache = Self::new(DEFAULT_CID_CACHE_CAPACITY);
+        let now = chrono::Utc::now().timestamp();
+        let mut loaded = vec![];
+        for (cid, key) in Self::persisted_keys(&db)? {
+            match db.read_obj::<BadBlock>(&key)? {
+                Some(block) if !block.is_expired(cache.ttl, now) => {
+                    cache.cache.insert(cid.into(), block);
+                    loaded.push((cid, key));
+                }
+                _ => db.delete_setting(&key)?,
+            }
+        }
+        for (cid, key) in loaded {
+            if cache.cache.peek(&cid).is_none() {
+                db.delete_setting(&key)?;
+            }
+        }
+        cache.db = Some(db);
+        Ok(cache)
+    }
+
+    fn key(cid: &Cid) -> String {
+        format!("{BAD_BLOCKS_KEY_PREFIX}/{cid}")
+    }
+
+    /// Returns the CIDs of the bad blocks persisted in `db`, with their keys.
+    fn persisted_keys(db: &DbImpl) -> anyhow::Result<Vec<(Cid, String)>> {
+        let prefix = format!("{BAD_BLOCKS_KEY_PREFIX}/");
+        Ok(db
+            .setting_keys(&prefix)?
+            .into_iter()
+            .filter_map(|key| Some((key.strip_prefix(&prefix)?.parse().ok()?, key)))
+            .collect())
+    }
+
+    /// Marks the block `cid` as bad.
+    pub fn mark(&self, cid: Cid, block: BadBlock) {
+        tracing::warn!("Marked bad block: {cid}, {block}");
+        if let Some(db) = &self.db
+            && let Err(e) = db.write_obj(&Self::key(&cid), &block)
+        {
+            tracing::warn!("Failed to persist bad block {cid}: {e:#}");
+        }
+        self.cache.insert(cid.into(), block);
+    }
+
+    /// Unmarks the block `cid`. Returns whether it was marked bad.
+    pub fn unmark(&self, cid: &Cid) -> bool {
+        let removed = self.cache.remove(cid).is_some();
+        if removed {
+            tracing::info!("Unmarked bad block: {cid}");
+            self.forget(cid);
+        }
+        removed
+    }
+
+    /// Returns why the block `cid` is marked bad, if it is. Expired marks are dropped.
+    pub fn get(&self, cid: &Cid) -> Option<BadBlock> {
+        let block = self.cache.get(cid)?;
+        if block.is_expired(self.ttl, chrono::Utc::now().timestamp()) {
+            self.cache.remove(cid);
+            self.forget(cid);
+            return None;
+        }
+        Some(block)
+    }
+
+    /// Unmarks all the blocks.
+    pub fn clear(&self) {
+        self.cache.clear();
+        if let Some(db) = &self.db {
+            let result = Self::persisted_keys(db).and_then(|keys| {
+                keys.into_iter()
+                    .try_for_each(|(_, key)| db.delete_setting(&key))
+            });
+            if let Err(e) = result {
+                tracing::warn!("Failed to delete persisted bad blocks: {e:#}");
+            }
         }
     }
 
-    pub fn push(&self, c: Cid) {
-        self.cache.insert(c.into(), ());
-        tracing::warn!("Marked bad block: {c}");
+    /// Deletes the persisted mark of the block `cid`.
+    fn forget(&self, cid: &Cid) {
+        if let Some(db) = &self.db
+            && let Err(e) = db.delete_setting(&Self::key(cid))
+        {
+            tracing::warn!("Failed to delete persisted bad block {cid}: {e:#}");
+        }
     }
 }
 
@@ -81,3 +243,85 @@ impl SeenBlockCache {
         self.cache.push_and_get_prev((*c).into(), ()).is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::utils::cid::CidCborExt as _;
+
+    #[test]
+    fn bad_blocks_are_persisted() {
+        let db = DbImpl::from(Arc::new(MemoryDB::default()));
+        let (cthulhu, dagon, hydra) = (
+            Cid::default(),
+            Cid::from_cbor_blake2b256(&"Dagon").unwrap(),
+            Cid::from_cbor_blake2b256(&"Hydra").unwrap(),
+        );
+
+        let cache = BadBlockCache::load(db.shallow_clone()).unwrap();
+        cache.mark(
+            cthulhu,
+            BadBlock::new("R'lyeh rises", 42, BadBlockOrigin::Validation),
+        );
+        cache.mark(
+            dagon,
+            BadBlock::new("Innsmouth", 43, BadBlockOrigin::Operator),
+        );
+        cache.mark(
+            hydra,
+            BadBlock::new("Y'ha-nthlei", 44, BadBlockOrigin::Operator),
+        );
+        assert!(cache.unmark(&hydra));
+        assert!(!cache.unmark(&hydra));
+        // One key per marked block.
+        assert_eq!(
+            db.setting_keys("")
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect_vec(),
+            [cthulhu, dagon]
+                .iter()
+                .map(BadBlockCache::key)
+                .sorted()
+                .collect_vec()
+        );
+
+        let reloaded = BadBlockCache::load(db.shallow_clone()).unwrap();
+        let block = reloaded.get(&cthulhu).unwrap();
+        assert_eq!(block.reason, "R'lyeh rises");
+        assert_eq!(block.epoch, 42);
+        assert_eq!(block.origin, BadBlockOrigin::Validation);
+        assert_eq!(
+            reloaded.get(&dagon).unwrap().to_string(),
+            "Innsmouth (marked by operator at epoch 43)"
+        );
+        assert!(reloaded.get(&hydra).is_none());
+
+        reloaded.clear();
+        assert!(db.setting_keys("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn validation_marks_expire() {
+        let db = DbImpl::from(Arc::new(MemoryDB::default()));
+        let cache = BadBlockCache::load(db.shallow_clone()).unwrap();
+        let (cthulhu, dagon) = (Cid::default(), Cid::from_cbor_blake2b256(&"Dagon").unwrap());
+        let ancient = |origin| BadBlock {
+            marked_at: chrono::Utc::now().timestamp() - cache.ttl.as_secs() as i64,
+            ..BadBlock::new("aeons old", 0, origin)
+        };
+        cache.mark(cthulhu, ancient(BadBlockOrigin::Validation));
+        cache.mark(dagon, ancient(BadBlockOrigin::Operator));
+
+        assert!(cache.get(&cthulhu).is_none());
+        assert!(cache.get(&dagon).is_some());
+        // Only the expired mark is deleted.
+        assert!(!db.exists(&BadBlockCache::key(&cthulhu)).unwrap());
+        assert!(db.exists(&BadBlockCache::key(&dagon)).unwrap());
+        let reloaded = BadBlockCache::load(db).unwrap();
+        assert!(reloaded.get(&cthulhu).is_none());
+        assert!(reloaded.get(&dagon).is_some());
+    }
+}
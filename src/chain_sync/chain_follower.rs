@@ -19,7 +19,7 @@
 use super::network_context::SyncNetworkContext;
 use crate::{
     blocks::{Block, FullTipset, Tipset, TipsetKey},
-    chain::{ChainStore, index::ResolveNullTipset},
+    chain::{ChainStore, block_timeline::BlockStage, index::ResolveNullTipset},
     chain_sync::{
         ForkSyncInfo, ForkSyncStage, SyncStatus, SyncStatusReport, TipsetValidator,
         bad_block_cache::{BadBlockCache, SeenBlockCache},
@@ -43,6 +43,7 @@ use nonzero_ext::nonzero;
 use parking_lot::Mutex;
 use std::{
     borrow::Cow,
+    num::NonZeroU64,
     sync::LazyLock,
     time::{Duration, Instant},
 };
@@ -130,7 +131,13 @@ impl ChainFollower {
             tracing::warn!("bad block cache is disabled by `FOREST_DISABLE_BAD_BLOCK_CACHE`");
             None
         } else {
-            Some(Default::default())
+            match BadBlockCache::load(state_manager.db().shallow_clone()) {
+                Ok(bad_blocks) => Some(bad_blocks),
+                Err(e) => {
+                    tracing::warn!("failed to load the persisted bad blocks: {e:#}");
+                    Some(Default::default())
+                }
+            }
         };
         let state_machine = Arc::new(Mutex::new(SyncStateMachine::new(
             state_manager.chain_store().shallow_clone(),
@@ -355,6 +362,7 @@ async fn chain_follower(
                     let new_status_report = old_status_report.update(
                         &state_manager,
                         current_active_forks,
+                        network.range_sync_progress().peers(),
                         stateless_mode,
                     );
                     sync_status.store(new_status_report.into());
@@ -632,6 +640,7 @@ pub async fn get_full_tipset(
         .chain_exchange_full_tipset(peer_id, tipset_keys)
         .await
         .map_err(|e| anyhow::anyhow!(e))?;
+    record_fetched(chain_store, std::slice::from_ref(&tipset));
     tipset.persist(chain_store.db())?;
 
     Ok(tipset)
@@ -652,6 +661,39 @@ async fn get_full_tipset_batch(
         .chain_exchange_full_tipsets(peer_id, tipset_keys)
         .await
         .map_err(|e| anyhow::anyhow!(e))?;
+    record_fetched(chain_store, &tipsets);
+
+    for tipset in tipsets.iter() {
+        tipset.persist(chain_store.db())?;
+    }
+
+    Ok(tipsets)
+}
+
+/// Tipsets at least this many epochs above the local head have their ancestors fetched with a
+/// range sync, split across several peers.
+const RANGE_SYNC_MIN_EPOCHS: ChainEpoch = 64;
+
+/// Maximum number of tipsets fetched by a single range sync.
+const RANGE_SYNC_MAX_TIPSETS: u64 = 512;
+
+/// Fetches up to `len` tipsets from `tipset_keys` downwards, with the headers from one peer and
+/// the messages from several peers in parallel.
+async fn get_full_tipset_range(
+    network: &SyncNetworkContext,
+    chain_store: &ChainStore,
+    tipset_keys: &TipsetKey,
+    len: NonZeroU64,
+) -> anyhow::Result<Vec<FullTipset>> {
+    // Attempt to load from the store
+    if let Ok(full_tipset) = load_full_tipset(chain_store, tipset_keys) {
+        return Ok(vec![full_tipset]);
+    }
+    let headers = network
+        .chain_exchange_headers(None, tipset_keys, len)
+        .await?;
+    let tipsets = network.chain_exchange_full_tipset_range(&headers).await?;
+    record_fetched(chain_store, &tipsets);
 
     for tipset in tipsets.iter() {
         tipset.persist(chain_store.db())?;
@@ -660,6 +702,16 @@ async fn get_full_tipset_batch(
     Ok(tipsets)
 }
 
+/// Records the blocks of `tipsets` as fetched from the network.
+fn record_fetched(chain_store: &ChainStore, tipsets: &[FullTipset]) {
+    for tipset in tipsets {
+        chain_store.block_timelines().record_all(
+            tipset.blocks().iter().map(Block::header),
+            BlockStage::Fetched,
+        );
+    }
+}
+
 pub fn load_full_tipset(
     chain_store: &ChainStore,
     tipset_keys: &TipsetKey,
@@ -1123,8 +1175,22 @@ impl SyncTask {
                 }
             },
             SyncTask::FetchTipset(key, epoch) => {
-                match get_full_tipset_batch(&network, state_manager.chain_store(), None, &key).await
+                let chain_store = state_manager.chain_store();
+                let behind = epoch - chain_store.heaviest_tipset().epoch();
+                let tipsets = if behind >= RANGE_SYNC_MIN_EPOCHS
+                    && let Some(len) = NonZeroU64::new((behind as u64).min(RANGE_SYNC_MAX_TIPSETS))
                 {
+                    match get_full_tipset_range(&network, chain_store, &key, len).await {
+                        Ok(tipsets) => Ok(tipsets),
+                        Err(e) => {
+                            tracing::debug!(%key, %epoch, "range sync failed, falling back to a single peer: {e:#}");
+                            get_full_tipset_batch(&network, chain_store, None, &key).await
+                        }
+                    }
+                } else {
+                    get_full_tipset_batch(&network, chain_store, None, &key).await
+                };
+                match tipsets {
                     Ok(parents) => Some(SyncEvent::NewFullTipsets(parents)),
                     Err(e) => {
                         // It's not a massive error; could be a transient network issue or a fork.
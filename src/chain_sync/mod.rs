@@ -10,12 +10,14 @@ pub mod network_context;
 mod sync_status;
 pub(crate) mod tipset_syncer;
 mod validation;
+mod validation_report;
 
 pub use self::{
-    bad_block_cache::BadBlockCache,
+    bad_block_cache::{BadBlock, BadBlockCache, BadBlockOrigin},
     chain_follower::{ChainFollower, get_full_tipset, load_full_tipset},
     chain_muxer::SyncConfig,
     consensus::collect_errs,
     sync_status::{ForkSyncInfo, ForkSyncStage, NodeSyncStatus, SyncStatus, SyncStatusReport},
     validation::{TipsetValidationError, TipsetValidator},
+    validation_report::{BlockValidationCheck, BlockValidationReport, explain_block},
 };
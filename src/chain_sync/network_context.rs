@@ -36,6 +36,10 @@ use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 use tracing::{debug, trace};
 
+mod range_sync;
+
+pub use range_sync::{PeerRangeSyncProgress, RangeSyncProgress};
+
 /// Timeout milliseconds for response from an RPC request
 // This value is automatically adapted in the range of [5, 60] for different network conditions,
 // being decreased on success and increased on failure
@@ -57,7 +61,6 @@ static MAX_CONCURRENT_CHAIN_EXCHANGE_REQUESTS: LazyLock<NonZeroUsize> = LazyLock
 /// Context used in chain sync to handle network requests.
 /// This contains the peer manager, P2P service interface, and [`Blockstore`]
 /// required to make network requests.
-#[derive(derive_more::Constructor)]
 pub struct SyncNetworkContext {
     /// Channel to send network messages through P2P service
     network_send: flume::Sender<NetworkMessage>,
@@ -65,6 +68,8 @@ pub struct SyncNetworkContext {
     /// respective peers.
     peer_manager: Arc<PeerManager>,
     db: DbImpl,
+    /// Per-peer progress of the range syncs in flight.
+    range_sync_progress: Arc<RangeSyncProgress>,
 }
 
 impl ShallowClone for SyncNetworkContext {
@@ -73,6 +78,7 @@ impl ShallowClone for SyncNetworkContext {
             network_send: self.network_send.clone(),
             peer_manager: self.peer_manager.shallow_clone(),
             db: self.db.shallow_clone(),
+            range_sync_progress: self.range_sync_progress.clone(),
         }
     }
 }
@@ -126,6 +132,19 @@ where
 }
 
 impl SyncNetworkContext {
+    pub fn new(
+        network_send: flume::Sender<NetworkMessage>,
+        peer_manager: Arc<PeerManager>,
+        db: DbImpl,
+    ) -> Self {
+        Self {
+            network_send,
+            peer_manager,
+            db,
+            range_sync_progress: Default::default(),
+        }
+    }
+
     /// Returns a reference to the peer manager of the network context.
     pub fn peer_manager(&self) -> &PeerManager {
         self.peer_manager.as_ref()
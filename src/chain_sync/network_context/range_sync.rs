@@ -0,0 +1,471 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Range sync: fetches the messages of a long run of tipset headers from several peers in
+//! parallel. The run is split into segments, which are assigned to the peers in proportion to
+//! their throughput, assembled in order as they complete and retried on another peer on failure.
+
+use super::SyncNetworkContext;
+use crate::blocks::{FullTipset, Tipset};
+use crate::libp2p::{
+    PeerId,
+    chain_exchange::{MESSAGES, TipsetBundle},
+};
+use crate::prelude::*;
+use ahash::HashMap;
+use nonzero_ext::nonzero;
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::ops::Range;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Maximum number of tipsets in a segment, i.e. in a single chain exchange request.
+const RANGE_SYNC_SEGMENT_LEN: NonZeroUsize = nonzero!(16_usize);
+
+/// Maximum number of peers a range is split across.
+const RANGE_SYNC_MAX_PEERS: usize = 8;
+
+/// Range sync progress of a peer.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerRangeSyncProgress {
+    pub peer_id: String,
+    /// Number of tipsets requested from the peer, including the retries of segments failed by
+    /// other peers.
+    pub assigned_tipsets: u64,
+    /// Number of tipsets fetched from the peer.
+    pub fetched_tipsets: u64,
+    /// Number of segment requests the peer failed.
+    pub failed_requests: u64,
+}
+
+/// Per-peer progress of the range syncs in flight, cleared once the last one completes.
+#[derive(Debug, Default)]
+pub struct RangeSyncProgress {
+    inner: Mutex<RangeSyncProgressInner>,
+}
+
+#[derive(Debug, Default)]
+struct RangeSyncProgressInner {
+    /// Number of range syncs in flight.
+    active: usize,
+    peers: HashMap<PeerId, PeerRangeSyncProgress>,
+}
+
+impl RangeSyncProgress {
+    /// Returns the progress of the peers involved in the range syncs in flight, ordered by peer
+    /// ID.
+    pub fn peers(&self) -> Vec<PeerRangeSyncProgress> {
+        self.inner
+            .lock()
+            .peers
+            .values()
+            .cloned()
+            .sorted_by(|a, b| a.peer_id.cmp(&b.peer_id))
+            .collect()
+    }
+
+    fn begin(self: &Arc<Self>) -> RangeSyncGuard {
+        self.inner.lock().active += 1;
+        RangeSyncGuard(self.clone())
+    }
+
+    fn update(&self, peer_id: PeerId, f: impl FnOnce(&mut PeerRangeSyncProgress)) {
+        f(self
+            .inner
+            .lock()
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| PeerRangeSyncProgress {
+                peer_id: peer_id.to_string(),
+                ..Default::default()
+            }))
+    }
+}
+
+/// Marks a range sync in flight until dropped.
+struct RangeSyncGuard(Arc<RangeSyncProgress>);
+
+impl Drop for RangeSyncGuard {
+    fn drop(&mut self) {
+        let mut inner = self.0.inner.lock();
+        inner.active = inner.active.saturating_sub(1);
+        if inner.active == 0 {
+            inner.peers.clear();
+        }
+    }
+}
+
+/// Splits `len` tipsets into segments of at most `segment_len` tipsets and assigns them to the
+/// peers with the given `throughputs`, in proportion to their throughput. Assignment follows a
+/// smooth weighted round-robin so that every peer gets work early in the range. Returns the
+/// segments in order, with the index of their peer.
+fn assign_segments(
+    len: usize,
+    segment_len: NonZeroUsize,
+    throughputs: &[f64],
+) -> Vec<(Range<usize>, usize)> {
+    let total: f64 = throughputs.iter().sum();
+    let mut current = vec![0.; throughputs.len()];
+    (0..len)
+        .step_by(segment_len.get())
+        .filter_map(|start| {
+            for (current, throughput) in current.iter_mut().zip(throughputs) {
+                *current += throughput;
+            }
+            let (peer, current) = current
+                .iter_mut()
+                .enumerate()
+                // Ties go to the first, i.e. best, peer.
+                .rev()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+            *current -= total;
+            Some((start..(start + segment_len.get()).min(len), peer))
+        })
+        .collect()
+}
+
+/// A run of consecutive tipsets of a range, ordered from the newest.
+struct Segment {
+    /// Position of the segment in the range.
+    index: usize,
+    headers: Vec<Tipset>,
+}
+
+/// Outcome of a segment request, with the peers that were tried for it.
+struct SegmentResult {
+    segment: Segment,
+    tried: Vec<PeerId>,
+    result: anyhow::Result<Vec<FullTipset>>,
+}
+
+impl SyncNetworkContext {
+    /// Returns the per-peer progress of the range syncs in flight.
+    pub fn range_sync_progress(&self) -> &RangeSyncProgress {
+        &self.range_sync_progress
+    }
+
+    /// Fetches the messages of `headers`, a chain of tipsets ordered from the newest, from the
+    /// best peers in parallel and assembles them into full tipsets. Segments that fail are
+    /// retried on the peers not tried yet. Returns the full tipsets of the longest run of
+    /// `headers`, from the newest, whose messages could be fetched.
+    pub async fn chain_exchange_full_tipset_range(
+        &self,
+        headers: &[Tipset],
+    ) -> anyhow::Result<Vec<FullTipset>> {
+        let peers = self
+            .peer_manager
+            .top_peers_with_throughput(RANGE_SYNC_MAX_PEERS);
+        anyhow::ensure!(
+            !peers.is_empty(),
+            "range sync failed: no peers are available"
+        );
+        let throughputs = peers
+            .iter()
+            .map(|(_, throughput)| *throughput)
+            .collect_vec();
+        // Requests to the same peer are serialized, so that a range doesn't flood a single peer.
+        let peers = peers
+            .into_iter()
+            .map(|(peer_id, _)| (peer_id, Arc::new(Semaphore::new(1))))
+            .collect_vec();
+
+        let _guard = self.range_sync_progress.begin();
+        let mut tasks = JoinSet::new();
+        let segments = assign_segments(headers.len(), RANGE_SYNC_SEGMENT_LEN, &throughputs);
+        let n_segments = segments.len();
+        for (index, (range, peer)) in segments.into_iter().enumerate() {
+            let headers = headers.get(range).context("segment out of range")?.to_vec();
+            let peer = peers.get(peer).context("peer out of range")?;
+            self.spawn_segment(&mut tasks, peer, Segment { index, headers }, vec![]);
+        }
+
+        let mut fetched = BTreeMap::new();
+        let mut last_error = None;
+        while let Some(joined) = tasks.join_next().await {
+            let SegmentResult {
+                segment,
+                tried,
+                result,
+            } = joined?;
+            let Some(&peer_id) = tried.last() else {
+                continue;
+            };
+            match result {
+                Ok(tipsets) => {
+                    self.range_sync_progress.update(peer_id, |progress| {
+                        progress.fetched_tipsets += tipsets.len() as u64;
+                    });
+                    fetched.insert(segment.index, tipsets);
+                }
+                Err(e) => {
+                    self.range_sync_progress.update(peer_id, |progress| {
+                        progress.failed_requests += 1;
+                    });
+                    debug!(%peer_id, segment = segment.index, "range sync segment failed: {e:#}");
+                    last_error = Some(e);
+                    if let Some(peer) = peers.iter().find(|(peer_id, _)| !tried.contains(peer_id)) {
+                        self.spawn_segment(&mut tasks, peer, segment, tried);
+                    }
+                }
+            }
+        }
+        debug!(
+            "range sync of {} tipsets fetched {}/{n_segments} segments",
+            headers.len(),
+            fetched.len()
+        );
+
+        // Segments complete out of order, only keep the ones connected to the newest tipset.
+        let tipsets = (0..n_segments)
+            .map_while(|index| fetched.remove(&index))
+            .flatten()
+            .collect_vec();
+        match last_error {
+            Some(e) if tipsets.is_empty() => Err(e.context("range sync failed")),
+            _ => Ok(tipsets),
+        }
+    }
+
+    /// Requests the messages of `segment` from `peer`, whose ID is pushed to `tried`.
+    fn spawn_segment(
+        &self,
+        tasks: &mut JoinSet<SegmentResult>,
+        (peer_id, peer_lock): &(PeerId, Arc<Semaphore>),
+        segment: Segment,
+        mut tried: Vec<PeerId>,
+    ) {
+        let peer_id = *peer_id;
+        tried.push(peer_id);
+        self.range_sync_progress.update(peer_id, |progress| {
+            progress.assigned_tipsets += segment.headers.len() as u64;
+        });
+        let network = self.shallow_clone();
+        let peer_lock = peer_lock.clone();
+        tasks.spawn(async move {
+            let result = match peer_lock.acquire_owned().await {
+                Ok(_permit) => {
+                    network
+                        .chain_exchange_segment(peer_id, &segment.headers)
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            };
+            SegmentResult {
+                segment,
+                tried,
+                result,
+            }
+        });
+    }
+
+    /// Sends a `chain_exchange` request for the messages of `headers` to `peer_id` and assembles
+    /// them into full tipsets.
+    async fn chain_exchange_segment(
+        &self,
+        peer_id: PeerId,
+        headers: &[Tipset],
+    ) -> anyhow::Result<Vec<FullTipset>> {
+        let start = headers.first().context("empty segment")?;
+        let request_len = NonZeroU64::new(headers.len() as u64).context("empty segment")?;
+        let bundles: Vec<TipsetBundle> = self
+            .handle_chain_exchange_request(
+                Some(peer_id),
+                start.key(),
+                request_len,
+                MESSAGES,
+                |_| true,
+            )
+            .await?;
+        anyhow::ensure!(
+            bundles.len() == headers.len(),
+            "chain exchange request returned {} tipsets, {} expected.",
+            bundles.len(),
+            headers.len()
+        );
+        headers
+            .iter()
+            .zip(bundles)
+            .map(|(ts, mut bundle)| {
+                bundle.blocks = ts.block_headers().to_vec();
+                bundle.try_into()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::db::MemoryDB;
+    use crate::libp2p::chain_exchange::{
+        ChainExchangeResponse, ChainExchangeResponseStatus, CompactedMessages,
+    };
+    use crate::libp2p::{NetworkMessage, PeerManager};
+
+    #[test]
+    fn segments_cover_the_range() {
+        let segments = assign_segments(40, nonzero!(16_usize), &[1., 1.]);
+        assert_eq!(segments, vec![(0..16, 0), (16..32, 1), (32..40, 0)]);
+        assert!(assign_segments(0, nonzero!(16_usize), &[1.]).is_empty());
+        assert!(assign_segments(40, nonzero!(16_usize), &[]).is_empty());
+    }
+
+    #[test]
+    fn segments_follow_peer_throughput() {
+        let segments = assign_segments(80, nonzero!(10_usize), &[3., 1.]);
+        let peers = segments.iter().map(|(_, peer)| *peer).collect_vec();
+        assert_eq!(peers.iter().filter(|&&peer| peer == 0).count(), 6);
+        assert_eq!(peers.iter().filter(|&&peer| peer == 1).count(), 2);
+        // Slow peers get work early in the range rather than only at its end.
+        assert!(peers.iter().take(4).any(|&peer| peer == 1));
+    }
+
+    #[test]
+    fn progress_is_cleared_after_the_last_range() {
+        let progress = Arc::new(RangeSyncProgress::default());
+        let peer_id = PeerId::random();
+        let first = progress.begin();
+        let second = progress.begin();
+        progress.update(peer_id, |p| p.assigned_tipsets += 16);
+        drop(first);
+        assert_eq!(progress.peers().len(), 1);
+        drop(second);
+        assert!(progress.peers().is_empty());
+    }
+
+    /// Headers of `len` tipsets, ordered from the newest.
+    fn headers(len: i64) -> Vec<Tipset> {
+        (0..len)
+            .rev()
+            .map(|epoch| {
+                Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+                    epoch,
+                    ticket: Some(Default::default()),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+
+    /// Successful requests, as `(peer, start)`.
+    type Completed = Arc<Mutex<Vec<(usize, usize)>>>;
+
+    /// Sets up a network of `n_peers` peers serving the messages of `headers`, where a peer
+    /// fails the requests for which `fails(peer, start)` holds, with `start` the position of
+    /// the first tipset of the request in `headers`. Returns the context and the successful
+    /// requests, in the order they complete.
+    fn serve(
+        headers: &[Tipset],
+        n_peers: usize,
+        fails: impl Fn(usize, usize) -> bool + Send + 'static,
+    ) -> (SyncNetworkContext, Completed) {
+        let (network_send, network_receive) = flume::unbounded();
+        let peer_manager = Arc::new(PeerManager::default());
+        let peers = (0..n_peers).map(|_| PeerId::random()).collect_vec();
+        for peer in &peers {
+            peer_manager.touch_peer(peer);
+        }
+        let completed = Arc::new(Mutex::new(vec![]));
+        tokio::spawn({
+            let headers = headers.to_vec();
+            let completed = completed.clone();
+            async move {
+                while let Ok(message) = network_receive.recv_async().await {
+                    let NetworkMessage::ChainExchangeRequest {
+                        peer_id,
+                        request,
+                        response_channel,
+                    } = message
+                    else {
+                        continue;
+                    };
+                    let peer = peers.iter().position(|it| *it == peer_id).unwrap();
+                    let start = headers
+                        .iter()
+                        .position(|ts| ts.key().to_cids() == request.start)
+                        .unwrap();
+                    let response = if fails(peer, start) {
+                        ChainExchangeResponse {
+                            status: ChainExchangeResponseStatus::InternalError,
+                            message: "the stars are not right".into(),
+                            chain: vec![],
+                        }
+                    } else {
+                        completed.lock().push((peer, start));
+                        let bundle = TipsetBundle {
+                            blocks: vec![],
+                            messages: Some(CompactedMessages {
+                                bls_msgs: vec![],
+                                bls_msg_includes: vec![vec![]],
+                                secp_msgs: vec![],
+                                secp_msg_includes: vec![vec![]],
+                            }),
+                        };
+                        ChainExchangeResponse {
+                            status: ChainExchangeResponseStatus::Success,
+                            message: String::new(),
+                            chain: vec![bundle; request.request_len as usize],
+                        }
+                    };
+                    let _ = response_channel.send(Ok(response));
+                }
+            }
+        });
+        let db = Arc::new(MemoryDB::default()).into();
+        (
+            SyncNetworkContext::new(network_send, peer_manager, db),
+            completed,
+        )
+    }
+
+    fn epochs(tipsets: &[FullTipset]) -> Vec<ChainEpoch> {
+        tipsets.iter().map(FullTipset::epoch).collect()
+    }
+
+    #[tokio::test]
+    async fn failed_segments_are_retried_on_another_peer() {
+        let headers = headers(48);
+        // The second peer fails everything.
+        let (network, completed) = serve(&headers, 2, |peer, _| peer == 1);
+        let tipsets = network
+            .chain_exchange_full_tipset_range(&headers)
+            .await
+            .unwrap();
+        assert_eq!(epochs(&tipsets), (0..48).rev().collect_vec());
+
+        let completed = completed.lock().clone();
+        assert!(completed.iter().all(|(peer, _)| *peer == 0));
+        // The retried segments complete after the ones that follow them.
+        let starts = completed.iter().map(|(_, start)| *start).collect_vec();
+        assert_eq!(starts.iter().sorted().copied().collect_vec(), [0, 16, 32]);
+        assert!(!starts.is_sorted());
+        assert!(network.range_sync_progress().peers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn range_is_truncated_to_the_connected_prefix() {
+        let headers = headers(48);
+        // No peer serves the second segment.
+        let (network, _) = serve(&headers, 2, |_, start| start == 16);
+        let tipsets = network
+            .chain_exchange_full_tipset_range(&headers)
+            .await
+            .unwrap();
+        assert_eq!(epochs(&tipsets), (32..48).rev().collect_vec());
+
+        // Nothing is connected to the newest tipset.
+        let (network, _) = serve(&headers, 2, |_, start| start == 0);
+        assert!(
+            network
+                .chain_exchange_full_tipset_range(&headers)
+                .await
+                .is_err()
+        );
+    }
+}
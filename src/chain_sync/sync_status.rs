@@ -1,6 +1,7 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 use crate::blocks::TipsetKey;
+use crate::chain_sync::network_context::PeerRangeSyncProgress;
 use crate::lotus_json::lotus_json_with_self;
 use crate::networks::calculate_expected_epoch;
 use crate::shim::clock::ChainEpoch;
@@ -123,6 +124,9 @@ pub struct SyncStatusReport {
     pub(crate) epochs_behind: i64,
     /// List of active fork synchronization tasks the node is currently handling.
     pub(crate) active_forks: Vec<ForkSyncInfo>,
+    /// Per-peer progress of the range syncs splitting long backfills across peers.
+    #[serde(default)]
+    pub(crate) range_sync_peers: Vec<PeerRangeSyncProgress>,
     /// When the node process started.
     pub(crate) node_start_time: DateTime<Utc>,
     /// Last time this status report was generated.
@@ -145,6 +149,7 @@ impl SyncStatusReport {
         &self,
         state_manager: &StateManager,
         active_forks: Vec<ForkSyncInfo>,
+        range_sync_peers: Vec<PeerRangeSyncProgress>,
         stateless_mode: bool,
     ) -> Self {
         let heaviest = state_manager.chain_store().heaviest_tipset();
@@ -188,6 +193,7 @@ impl SyncStatusReport {
             epochs_behind,
             status,
             active_forks,
+            range_sync_peers,
             last_updated,
         }
     }
@@ -1,7 +1,9 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::chain_sync::BadBlockCache;
+use crate::beacon::BeaconEntryStore;
+use crate::chain::block_timeline::BlockStage;
+use crate::chain_sync::{BadBlock, BadBlockCache, BadBlockOrigin};
 use crate::db::DbImpl;
 use crate::networks::Height;
 use crate::prelude::*;
@@ -124,6 +126,12 @@ pub async fn validate_tipset(
     let tipset_key = full_tipset.key();
     trace!("Tipset keys: {tipset_key}");
     let blocks = full_tipset.into_blocks();
+    let timelines = state_manager.chain_store().block_timelines();
+    let beacon_entries = BeaconEntryStore::new(state_manager.db());
+    timelines.record_all(
+        blocks.iter().map(Block::header),
+        BlockStage::ValidationStarted,
+    );
     let mut validations = JoinSet::new();
     for b in blocks {
         validations.spawn(validate_block(state_manager.shallow_clone(), Arc::new(b)));
@@ -132,6 +140,15 @@ pub async fn validate_tipset(
     while let Some(result) = validations.join_next().await {
         match result? {
             Ok(block) => {
+                timelines.record(block.header(), BlockStage::ValidationFinished);
+                if let Err(e) =
+                    beacon_entries.put_from_header(state_manager.beacon_schedule(), block.header())
+                {
+                    warn!(
+                        "Failed to store the beacon entries of block {}: {e:#}",
+                        block.cid()
+                    );
+                }
                 state_manager
                     .chain_store()
                     .add_to_tipset_tracker(block.header());
@@ -150,7 +167,14 @@ pub async fn validate_tipset(
                         if StateTree::new_from_root(state_manager.db(), &parent_state).is_ok()
                             && let Some(bad_block_cache) = bad_block_cache
                         {
-                            bad_block_cache.push(cid);
+                            bad_block_cache.mark(
+                                cid,
+                                BadBlock::new(
+                                    why.to_string(),
+                                    state_manager.chain_store().heaviest_tipset().epoch(),
+                                    BadBlockOrigin::Validation,
+                                ),
+                            );
                         }
                     }
                 };
@@ -246,6 +270,13 @@ async fn validate_block(
         base_tipset.shallow_clone(),
     ));
 
+    // Check block message root
+    validations.spawn_blocking({
+        let state_manager = state_manager.shallow_clone();
+        let block = block.shallow_clone();
+        move || check_block_message_root(&state_manager, &block)
+    });
+
     // Base fee check
     validations.spawn_blocking({
         let smoke_height = state_manager.chain_config().epoch(Height::Smoke);
@@ -372,11 +403,10 @@ async fn validate_block(
 /// * signature checks
 /// * gas limits, and prices
 /// * account nonce values
-/// * the message root in the header
 ///
 /// NB: This loads/computes the state resulting from the execution of the parent
 /// tipset.
-async fn check_block_messages(
+pub(super) async fn check_block_messages(
     state_manager: StateManager,
     block: Arc<Block>,
     base_tipset: Tipset,
@@ -506,7 +536,14 @@ async fn check_block_messages(
             .map_err(|e| TipsetSyncerError::MessageSignatureInvalid(e.to_string()))?;
     }
 
-    // Validate message root from header matches message root
+    Ok(())
+}
+
+/// Validate that the message root in the header matches the messages of the block.
+fn check_block_message_root(
+    state_manager: &StateManager,
+    block: &Block,
+) -> Result<(), TipsetSyncerError> {
     let msg_root =
         TipsetValidator::compute_msg_root(state_manager.db(), block.bls_msgs(), block.secp_msgs())
             .map_err(|err| TipsetSyncerError::ComputingMessageRoot(err.to_string()))?;
@@ -516,14 +553,13 @@ async fn check_block_messages(
             format!("{msg_root:?}"),
         ));
     }
-
     Ok(())
 }
 
 /// Checks optional values in header.
 ///
 /// It only looks for fields which are common to all consensus types.
-fn block_sanity_checks(header: &CachingBlockHeader) -> Result<(), TipsetSyncerError> {
+pub(super) fn block_sanity_checks(header: &CachingBlockHeader) -> Result<(), TipsetSyncerError> {
     if header.signature.is_none() {
         return Err(TipsetSyncerError::BlockWithoutSignature);
     }
@@ -534,7 +570,7 @@ fn block_sanity_checks(header: &CachingBlockHeader) -> Result<(), TipsetSyncerEr
 }
 
 /// Check the clock drift.
-fn block_timestamp_checks(header: &CachingBlockHeader) -> Result<(), TipsetSyncerError> {
+pub(super) fn block_timestamp_checks(header: &CachingBlockHeader) -> Result<(), TipsetSyncerError> {
     let time_now = chrono::Utc::now().timestamp() as u64;
     if header.timestamp > time_now.saturating_add(ALLOWABLE_CLOCK_DRIFT) {
         return Err(TipsetSyncerError::TimeTravellingBlock(
@@ -350,7 +350,9 @@ mod tests {
     use std::convert::TryFrom;
 
     use crate::blocks::{CachingBlockHeader, ElectionProof, GossipBlock, RawBlockHeader, Tipset};
-    use crate::chain_sync::bad_block_cache::{BadBlockCache, SeenBlockCache};
+    use crate::chain_sync::bad_block_cache::{
+        BadBlock, BadBlockCache, BadBlockOrigin, SeenBlockCache,
+    };
     use crate::db::MemoryDB;
     use crate::message::SignedMessage;
     use crate::shim::crypto::{Signature, SignatureType};
@@ -485,7 +487,10 @@ mod tests {
         let genesis = make_genesis();
         let seen = SeenBlockCache::default();
         let bad_cache = BadBlockCache::default();
-        bad_cache.push(*block.header.cid());
+        bad_cache.mark(
+            *block.header.cid(),
+            BadBlock::new("Azathoth", 0, BadBlockOrigin::Operator),
+        );
 
         let err = GossipBlockValidator::new(&block)
             .validate_pre_fetch(&genesis, 30, 0, Some(&bad_cache), &seen)
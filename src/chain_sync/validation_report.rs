@@ -0,0 +1,285 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Explains the validation of a block: every rule of the block validation is checked against
+//! the stored parent state, without stopping at the first failure, and reported with the value
+//! found in the block header and the one computed locally.
+
+use super::tipset_syncer::{block_sanity_checks, block_timestamp_checks, check_block_messages};
+use crate::blocks::{Block, Tipset};
+use crate::chain::ChainStore;
+use crate::chain_sync::TipsetValidator;
+use crate::fil_cns::{self, FilecoinConsensus};
+use crate::lotus_json::lotus_json_with_self;
+use crate::networks::Height;
+use crate::prelude::*;
+use crate::state_manager::{ExecutedTipset, StateManager};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Outcome of a single validation rule.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockValidationCheck {
+    pub name: String,
+    pub passed: bool,
+    /// Value found in the block header, for the rules comparing it to a computed one.
+    pub expected: Option<String>,
+    /// Value computed from the parent state, for the rules comparing it to the block header.
+    pub computed: Option<String>,
+    /// Why the rule failed, or couldn't be checked.
+    pub error: Option<String>,
+}
+
+impl BlockValidationCheck {
+    /// Reports the outcome of the rule `name`.
+    pub fn new<E: Into<anyhow::Error>>(name: &str, result: Result<(), E>) -> Self {
+        Self {
+            name: name.into(),
+            passed: result.is_ok(),
+            expected: None,
+            computed: None,
+            error: result.err().map(|e| format!("{:#}", e.into())),
+        }
+    }
+
+    /// Reports the rule `name`, which passes if the `computed` value matches the `expected` one
+    /// from the block header.
+    pub fn compare<T: PartialEq + Display>(
+        name: &str,
+        expected: &T,
+        computed: anyhow::Result<T>,
+    ) -> Self {
+        match computed {
+            Ok(computed) => {
+                let passed = &computed == expected;
+                Self {
+                    name: name.into(),
+                    passed,
+                    expected: Some(expected.to_string()),
+                    computed: Some(computed.to_string()),
+                    error: (!passed).then(|| "header and computed values differ".into()),
+                }
+            }
+            Err(e) => Self {
+                expected: Some(expected.to_string()),
+                ..Self::new(name, Err(e))
+            },
+        }
+    }
+
+    /// Attaches the `expected` and `computed` values of the rule.
+    pub fn with_values(self, expected: impl Display, computed: impl Display) -> Self {
+        Self {
+            expected: Some(expected.to_string()),
+            computed: Some(computed.to_string()),
+            ..self
+        }
+    }
+}
+lotus_json_with_self!(BlockValidationCheck);
+
+/// Outcome of every validation rule checked for a block.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockValidationReport {
+    pub epoch: ChainEpoch,
+    /// Whether all the rules passed.
+    pub valid: bool,
+    pub checks: Vec<BlockValidationCheck>,
+}
+lotus_json_with_self!(BlockValidationReport);
+
+impl BlockValidationReport {
+    fn new(epoch: ChainEpoch, checks: Vec<BlockValidationCheck>) -> Self {
+        Self {
+            epoch,
+            valid: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+/// Checks `block` against every rule of the block validation, i.e. the common rules of the
+/// synchronizer followed by the consensus ones, and reports the outcome of each. Unlike the
+/// validation itself, this neither short-circuits nor consults the validated block cache.
+///
+/// NB: This loads/computes the state resulting from the execution of the parent tipset, which
+/// must be in the store.
+pub async fn explain_block(
+    state_manager: &StateManager,
+    block: Arc<Block>,
+) -> anyhow::Result<BlockValidationReport> {
+    let header = block.header();
+    let base_tipset = state_manager
+        .chain_store()
+        .chain_index()
+        .load_required_tipset(&header.parents)
+        .context("failed to load the parent tipset")?;
+
+    let mut checks = vec![
+        BlockValidationCheck::new("sanity", block_sanity_checks(header)),
+        BlockValidationCheck::new("clock_drift", block_timestamp_checks(header)),
+    ];
+
+    let work_addr = async {
+        let (_, lookback_state) = ChainStore::get_lookback_tipset_for_round(
+            state_manager.chain_store().chain_index().shallow_clone(),
+            state_manager.chain_config().shallow_clone(),
+            base_tipset.shallow_clone(),
+            header.epoch,
+        )
+        .await?;
+        anyhow::Ok(state_manager.get_miner_work_addr(lookback_state, &header.miner_address)?)
+    }
+    .await;
+    checks.push(BlockValidationCheck::new(
+        "signature",
+        work_addr.and_then(|work_addr| Ok(header.verify_signature_against(&work_addr)?)),
+    ));
+
+    checks.push(BlockValidationCheck::new(
+        "messages",
+        check_block_messages(
+            state_manager.shallow_clone(),
+            block.shallow_clone(),
+            base_tipset.shallow_clone(),
+        )
+        .await,
+    ));
+    checks.push(BlockValidationCheck::compare(
+        "message_root",
+        &header.messages,
+        TipsetValidator::compute_msg_root(state_manager.db(), block.bls_msgs(), block.secp_msgs())
+            .map_err(anyhow::Error::from),
+    ));
+
+    checks.extend(explain_parent(state_manager, &block, &base_tipset).await);
+
+    checks.extend(
+        FilecoinConsensus::new(state_manager.beacon_schedule().clone())
+            .explain_block(
+                state_manager.shallow_clone(),
+                block.shallow_clone(),
+                base_tipset,
+            )
+            .await,
+    );
+    Ok(BlockValidationReport::new(header.epoch, checks))
+}
+
+/// Checks the fields of the block header derived from the parent tipset.
+async fn explain_parent(
+    state_manager: &StateManager,
+    block: &Block,
+    base_tipset: &Tipset,
+) -> Vec<BlockValidationCheck> {
+    let header = block.header();
+    let base_fee = crate::chain::compute_base_fee(
+        state_manager.db(),
+        base_tipset,
+        state_manager.chain_config().epoch(Height::Smoke),
+        state_manager.chain_config().epoch(Height::FireHorse),
+    );
+    let weight = fil_cns::weight(state_manager.db(), base_tipset);
+    let (state_root, receipt_root) = match state_manager.load_executed_tipset(base_tipset).await {
+        Ok(ExecutedTipset {
+            state_root,
+            receipt_root,
+            ..
+        }) => (Ok(state_root), Ok(receipt_root)),
+        Err(e) => {
+            let e = format!("failed to calculate state: {e:#}");
+            (Err(anyhow::anyhow!("{e}")), Err(anyhow::anyhow!("{e}")))
+        }
+    };
+    vec![
+        BlockValidationCheck::compare(
+            "base_fee",
+            &header.parent_base_fee,
+            base_fee.map_err(anyhow::Error::from),
+        ),
+        BlockValidationCheck::compare("parent_weight", &header.weight, weight),
+        BlockValidationCheck::compare("state_root", &header.state_root, state_root),
+        BlockValidationCheck::compare("receipt_root", &header.message_receipts, receipt_root),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, Chain4U, HeaderBuilder, chain4u};
+    use crate::db::MemoryDB;
+
+    #[tokio::test]
+    async fn every_rule_is_reported() {
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_timestamp(7777)] -> [block]
+        };
+        let cs = ChainStore::new(db, Default::default(), genesis.clone()).unwrap();
+        let state_manager = StateManager::new(cs.shallow_clone()).unwrap();
+        let block = Block {
+            header: CachingBlockHeader::new(block.clone()),
+            bls_messages: vec![],
+            secp_messages: vec![],
+        };
+
+        let report = explain_block(&state_manager, Arc::new(block))
+            .await
+            .unwrap();
+        assert!(!report.valid);
+        let names = report
+            .checks
+            .iter()
+            .map(|check| check.name.as_str())
+            .collect_vec();
+        for name in [
+            "sanity",
+            "signature",
+            "message_root",
+            "base_fee",
+            "parent_weight",
+            "state_root",
+            "timestamp",
+            "election_proof",
+            "ticket",
+            "winning_post",
+        ] {
+            assert!(names.contains(&name), "{name} is not reported");
+        }
+        // The block has neither a signature nor an election proof.
+        let sanity = report.checks.iter().find(|check| check.name == "sanity");
+        assert!(sanity.is_some_and(|check| !check.passed && check.error.is_some()));
+    }
+
+    #[test]
+    fn compare_reports_both_values() {
+        let check = BlockValidationCheck::compare("weight", &1, Ok(1));
+        assert!(check.passed);
+        assert_eq!(check.expected.as_deref(), Some("1"));
+        assert_eq!(check.computed.as_deref(), Some("1"));
+        assert_eq!(check.error, None);
+
+        let check = BlockValidationCheck::compare("weight", &1, Ok(2));
+        assert!(!check.passed);
+        assert_eq!(check.expected.as_deref(), Some("1"));
+        assert_eq!(check.computed.as_deref(), Some("2"));
+
+        let check = BlockValidationCheck::compare("weight", &1, Err(anyhow::anyhow!("no state")));
+        assert!(!check.passed);
+        assert_eq!(check.computed, None);
+        assert_eq!(check.error.as_deref(), Some("no state"));
+    }
+
+    #[test]
+    fn report_is_valid_only_if_all_checks_pass() {
+        let passed = BlockValidationCheck::new::<anyhow::Error>("sanity", Ok(()));
+        let failed = BlockValidationCheck::new("ticket", Err(anyhow::anyhow!("invalid VRF")));
+        assert!(BlockValidationReport::new(1, vec![passed.clone()]).valid);
+        assert!(!BlockValidationReport::new(1, vec![passed, failed]).valid);
+    }
+}
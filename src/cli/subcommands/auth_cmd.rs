@@ -1,10 +1,12 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::auth::TokenScope;
 use crate::rpc::{self, auth::AuthNewParams, prelude::*};
 use chrono::Duration;
 use clap::Subcommand;
 use libp2p::multiaddr;
+use std::num::NonZeroU32;
 
 use super::print_rpc_res_bytes;
 
@@ -18,6 +20,14 @@ pub enum AuthCommands {
         /// Token is revoked after this duration
         #[arg(long, default_value = "2 months")]
         expire_in: humantime::Duration,
+        /// Restrict the token to the given methods. Accepts method names with or without
+        /// their namespace (e.g. `ChainHead` or `Filecoin.ChainHead`) and prefix patterns
+        /// ending in `*` (e.g. `Eth*`). Can be repeated or comma-separated
+        #[arg(long, value_delimiter = ',')]
+        method: Vec<String>,
+        /// Maximum number of requests per minute accepted with the token
+        #[arg(long)]
+        rate_limit: Option<NonZeroU32>,
     },
     /// Get RPC API Information
     ApiInfo {
@@ -28,16 +38,47 @@ pub enum AuthCommands {
         #[arg(long, default_value = "2 months")]
         expire_in: humantime::Duration,
     },
+    /// Revoke an Authentication token, so that the node rejects it from now on
+    Revoke {
+        /// The token to revoke
+        token: String,
+    },
+    /// Replace the key signing Authentication tokens and print a new admin token. The
+    /// default admin token file is updated if present.
+    RotateKey {
+        /// Tokens signed with the previous key remain valid for this duration
+        #[arg(long, default_value = "1 day")]
+        grace_period: humantime::Duration,
+    },
 }
 
 impl AuthCommands {
     pub async fn run(self, client: rpc::Client) -> anyhow::Result<()> {
         match self {
-            Self::CreateToken { perm, expire_in } => {
+            Self::CreateToken {
+                perm,
+                expire_in,
+                method,
+                rate_limit,
+            } => {
                 let perm: String = perm.parse()?;
                 let perms = AuthNewParams::process_perms(perm)?;
                 let token_exp = Duration::from_std(expire_in.into())?;
-                let res = AuthNew::call(&client, AuthNewParams { perms, token_exp }.into()).await?;
+                let scope = TokenScope {
+                    methods: (!method.is_empty()).then_some(method),
+                    rate_limit,
+                };
+                scope.validate()?;
+                let res = AuthNew::call(
+                    &client,
+                    AuthNewParams {
+                        perms,
+                        token_exp,
+                        scope,
+                    }
+                    .into(),
+                )
+                .await?;
                 print_rpc_res_bytes(res)
             }
             Self::ApiInfo { perm, expire_in } => {
@@ -45,12 +86,43 @@ impl AuthCommands {
                 let perms = AuthNewParams::process_perms(perm)?;
                 let token_exp = Duration::from_std(expire_in.into())?;
                 let token = String::from_utf8(
-                    AuthNew::call(&client, AuthNewParams { perms, token_exp }.into()).await?,
+                    AuthNew::call(
+                        &client,
+                        AuthNewParams {
+                            perms,
+                            token_exp,
+                            scope: TokenScope::default(),
+                        }
+                        .into(),
+                    )
+                    .await?,
                 )?;
                 let addr = multiaddr::from_url(client.base_url().as_str())?;
                 println!("FULLNODE_API_INFO=\"{token}:{addr}\"");
                 Ok(())
             }
+            Self::Revoke { token } => {
+                AuthRevoke::call(&client, (token,)).await?;
+                println!("Token revoked");
+                Ok(())
+            }
+            Self::RotateKey { grace_period } => {
+                let grace_period = Duration::from_std(grace_period.into())?;
+                let token = AuthRotateKey::call(&client, (grace_period.num_seconds(),)).await?;
+                let default_token_path = crate::cli_shared::default_token_path();
+                if default_token_path.is_file() {
+                    crate::utils::io::write_new_sensitive_file(
+                        token.as_bytes(),
+                        &default_token_path,
+                    )?;
+                    eprintln!(
+                        "Default admin token updated at {}",
+                        default_token_path.display()
+                    );
+                }
+                println!("{token}");
+                Ok(())
+            }
         }
     }
 }
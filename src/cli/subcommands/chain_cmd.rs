@@ -1,6 +1,9 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod finality;
+use finality::ChainFinalityCommand;
+
 mod list;
 use crate::utils::encoding::hex;
 use list::ChainListCommand;
@@ -77,6 +80,13 @@ pub enum ChainCommands {
     #[command(subcommand)]
     Prune(ChainPruneCommands),
     List(ChainListCommand),
+    Finality(ChainFinalityCommand),
+    /// Prints out the latest chain reorganizations observed by the node, oldest first
+    Reorgs {
+        /// Format of the output. `json` or `text`.
+        #[arg(long, default_value = "text")]
+        format: Format,
+    },
 }
 
 impl ChainCommands {
@@ -134,6 +144,37 @@ impl ChainCommands {
             }
             Self::Prune(cmd) => cmd.run(client).await,
             Self::List(cmd) => cmd.run(client).await,
+            Self::Finality(cmd) => cmd.run(client).await,
+            Self::Reorgs { format } => {
+                let reorgs = ChainReorgs::call(&client, ()).await?;
+                match format {
+                    Format::Json => println!("{}", serde_json::to_string_pretty(&reorgs)?),
+                    Format::Text => {
+                        if reorgs.is_empty() {
+                            println!("No chain reorg observed");
+                        }
+                        for reorg in reorgs {
+                            let time = chrono::DateTime::from_timestamp(reorg.timestamp, 0)
+                                .map(|t| t.to_rfc3339())
+                                .unwrap_or_default();
+                            println!(
+                                "{time}: depth {}, epoch {} -> {} (common ancestor at {}), {} dropped message(s)",
+                                reorg.depth,
+                                reorg.old_head_epoch,
+                                reorg.new_head_epoch,
+                                reorg.common_ancestor_epoch,
+                                reorg.dropped_messages.len()
+                            );
+                            println!("  old head: {}", reorg.old_head);
+                            println!("  new head: {}", reorg.new_head);
+                            for cid in &reorg.dropped_messages {
+                                println!("  dropped:  {cid}");
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,84 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::Format;
+use crate::{
+    rpc::{
+        self, RpcMethodExt as _,
+        chain::{ChainFinalityProbability, ChainHead},
+    },
+    shim::clock::ChainEpoch,
+};
+
+/// Depths below the chain head shown when no epoch is given.
+const DEFAULT_DEPTHS: [ChainEpoch; 8] = [1, 5, 10, 20, 30, 60, 120, 900];
+
+/// View the estimated EC reorg probability of epochs, from the observed block production
+#[derive(Debug, clap::Args)]
+pub struct ChainFinalityCommand {
+    /// Epochs to compute the reorg probability of (default: a range of depths below the head).
+    /// Negative numbers specify decrements from the current head.
+    #[arg(allow_hyphen_values = true)]
+    epochs: Vec<ChainEpoch>,
+    /// Format of the output. `json` or `text`.
+    #[arg(long, default_value = "text")]
+    format: Format,
+}
+
+impl ChainFinalityCommand {
+    pub async fn run(self, client: rpc::Client) -> anyhow::Result<()> {
+        let head = ChainHead::call(&client, ()).await?;
+        let epochs = if self.epochs.is_empty() {
+            DEFAULT_DEPTHS
+                .iter()
+                .map(|depth| head.epoch() - depth)
+                .filter(|epoch| *epoch >= 0)
+                .collect()
+        } else {
+            self.epochs
+                .iter()
+                .map(|epoch| {
+                    if *epoch < 0 {
+                        head.epoch() + epoch
+                    } else {
+                        *epoch
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut probabilities = Vec::with_capacity(epochs.len());
+        for epoch in epochs {
+            probabilities.push(ChainFinalityProbability::call(&client, (epoch,)).await?);
+        }
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&probabilities)?),
+            Format::Text => {
+                let Some(first) = probabilities.first() else {
+                    return Ok(());
+                };
+                println!("Head epoch: {}", first.head_epoch);
+                match first.ec_finality_threshold_depth {
+                    -1 => println!("2^-30 reorg probability: not met within the search range"),
+                    depth => println!("2^-30 reorg probability: {depth} epochs below the head"),
+                }
+                println!();
+                println!(
+                    "{:>10} {:>6} {:>18} {:>18}",
+                    "Epoch", "Depth", "Reorg probability", "Epochs until final"
+                );
+                for p in &probabilities {
+                    println!(
+                        "{:>10} {:>6} {:>18.3e} {:>18}",
+                        p.epoch,
+                        p.head_epoch - p.epoch,
+                        p.reorg_probability,
+                        p.epochs_until_final
+                            .map(|n| n.to_string())
+                            .unwrap_or_else(|| "-".into())
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -255,6 +255,13 @@ pub enum F3CertsCommands {
         #[arg(long, default_value_t = false)]
         reverse: bool,
     },
+    /// Verifies a range of F3 finality certificates natively, without relying on the F3 sidecar.
+    Verify {
+        /// Inclusive range of `from` and `to` instances in following notation:
+        /// `<from>..<to>`. `<from>` defaults to the initial instance, `<to>` to the latest one.
+        /// The power table of a `<from>` other than the initial instance is trusted from the node.
+        range: Option<String>,
+    },
 }
 
 impl F3CertsCommands {
@@ -319,6 +326,73 @@ impl F3CertsCommands {
                     println!();
                 }
             }
+            Self::Verify { range } => {
+                let manifest = F3GetManifest::call(&client, ()).await?;
+                let (from_opt, to_opt) = match range {
+                    Some(range) => Self::parse_range_unvalidated(&range)?,
+                    None => (None, None),
+                };
+                let from = from_opt.unwrap_or(manifest.initial_instance);
+                let to = match to_opt {
+                    Some(i) => i,
+                    None => F3GetLatestCertificate::call(&client, ()).await?.instance,
+                };
+                anyhow::ensure!(
+                    from <= to && from >= manifest.initial_instance,
+                    "ERROR: invalid range: {from}..{to}"
+                );
+                // The power table of the first instance is anchored on the manifest, or on the
+                // certificate of the previous instance. The latter comes from the same node and
+                // is not verified, so only a range starting at the initial instance is verified
+                // end to end.
+                let power_table = F3GetF3PowerTableByInstance::call(&client, (from,)).await?;
+                let expected_power_table_cid = if from == manifest.initial_instance {
+                    manifest.initial_power_table
+                } else {
+                    Some(
+                        F3GetCertificate::call(&client, (from - 1,))
+                            .await?
+                            .supplemental_data
+                            .power_table,
+                    )
+                };
+                let power_table_cid = crate::f3::certs::power_table_cid(&power_table)?;
+                match expected_power_table_cid {
+                    Some(expected) => anyhow::ensure!(
+                        power_table_cid == expected,
+                        "power table {power_table_cid} of instance {from} is not the expected {expected}"
+                    ),
+                    None => println!(
+                        "WARNING: the manifest has no initial power table, trusting {power_table_cid} from the node"
+                    ),
+                }
+                if from != manifest.initial_instance {
+                    println!(
+                        "WARNING: instance {from} is not the initial instance {}, trusting power table {power_table_cid} from the certificate of instance {} without verification",
+                        manifest.initial_instance,
+                        from - 1
+                    );
+                }
+                let mut verifier = crate::f3::certs::CertificateVerifier::new(
+                    &manifest.network_name,
+                    from,
+                    power_table,
+                );
+                for i in from..=to {
+                    let cert = F3GetCertificate::call(&client, (i,)).await?;
+                    verifier
+                        .verify(&cert)
+                        .with_context(|| format!("invalid certificate at instance {i}"))?;
+                }
+                if let Some(head) = verifier.finalized() {
+                    println!("Verified F3 certificates {from} to {to}");
+                    println!("Finalized tipset: epoch {}, key {}", head.epoch, head.key);
+                    println!(
+                        "Next power table: {} participants",
+                        verifier.power_table().len()
+                    );
+                }
+            }
         }
 
         Ok(())
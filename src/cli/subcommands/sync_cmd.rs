@@ -1,6 +1,7 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use super::chain_cmd::Format;
 use crate::blocks::TipsetKey;
 use crate::chain_sync::{NodeSyncStatus, SyncStatusReport};
 use crate::rpc::sync::{SnapshotProgressState, SyncStatus};
@@ -38,6 +39,21 @@ pub enum SyncCommands {
         #[arg(short)]
         cid: Cid,
     },
+    /// Unmark a given block as bad
+    UnmarkBad {
+        /// The block CID to unmark as a bad block
+        #[arg(short)]
+        cid: Cid,
+    },
+    /// Re-validate a given block against its parent state and report every rule checked
+    ValidateBlock {
+        /// The block CID to validate
+        #[arg(short)]
+        cid: Cid,
+        /// Format of the output. `json` or `text`.
+        #[arg(long, default_value = "text")]
+        format: Format,
+    },
 }
 
 impl SyncCommands {
@@ -117,6 +133,40 @@ impl SyncCommands {
                 println!("OK");
                 Ok(())
             }
+            Self::UnmarkBad { cid } => {
+                SyncUnmarkBad::call(&client, (cid,)).await?;
+                println!("OK");
+                Ok(())
+            }
+            Self::ValidateBlock { cid, format } => {
+                let report = SyncValidateBlock::call(&client, (cid,))
+                    .await
+                    .context("Failed to validate block")?;
+                match format {
+                    Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                    Format::Text => {
+                        println!(
+                            "Block \"{cid}\" at epoch {} is {}",
+                            report.epoch,
+                            if report.valid { "valid" } else { "invalid" }
+                        );
+                        for check in report.checks {
+                            let status = if check.passed { "PASS" } else { "FAIL" };
+                            println!("  [{status}] {}", check.name);
+                            if let (Some(expected), Some(computed)) =
+                                (&check.expected, &check.computed)
+                            {
+                                println!("         header:   {expected}");
+                                println!("         computed: {computed}");
+                            }
+                            if let Some(error) = &check.error {
+                                println!("         error:    {error}");
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -176,6 +226,17 @@ fn print_sync_report_details(
         }
     }
 
+    // Print per-peer progress of range syncs
+    if !report.range_sync_peers.is_empty() {
+        lines.push("Range Sync Peers:".into());
+        for peer in &report.range_sync_peers {
+            lines.push(format!(
+                "  - Peer: {}, Fetched: {}/{} tipsets, Failed Requests: {}",
+                peer.peer_id, peer.fetched_tipsets, peer.assigned_tipsets, peer.failed_requests
+            ));
+        }
+    }
+
     let mut rows = 0;
     for line in &lines {
         writeln!(out, "{line}")?;
@@ -33,6 +33,21 @@ impl Default for BufferSize {
     }
 }
 
+/// TLS settings of a listener
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+pub struct TlsConfig {
+    /// PEM file holding the certificate chain, leaf certificate first. Reloaded when it
+    /// changes on disk.
+    pub cert_path: PathBuf,
+    /// PEM file holding the private key of the certificate.
+    pub key_path: PathBuf,
+    /// PEM file holding the CA certificates client certificates must be issued by. If set,
+    /// clients must present a certificate.
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
@@ -47,6 +62,9 @@ pub struct Client {
     pub snapshot_head: Option<i64>,
     pub snapshot_path: Option<PathBuf>,
     pub import_mode: ImportMode,
+    /// Verifies imported snapshots against their F3 finality certificates, from the initial F3
+    /// power table of the network, and refuses snapshots that don't match.
+    pub verify_f3: bool,
     /// Skips loading import CAR file and assumes it's already been loaded.
     /// Will use the CIDs in the header of the file to index the chain.
     pub skip_load: bool,
@@ -65,6 +83,12 @@ pub struct Client {
     pub rpc_filter_list: Option<PathBuf>,
     /// Healthcheck bind, e.g. 127.0.0.1:2346
     pub healthcheck_address: SocketAddr,
+    /// Serve RPC over TLS
+    pub rpc_tls: Option<TlsConfig>,
+    /// Serve metrics over TLS
+    pub metrics_tls: Option<TlsConfig>,
+    /// Serve health checks over TLS
+    pub healthcheck_tls: Option<TlsConfig>,
     /// Load actors from the bundle file (possibly generating it if it doesn't exist)
     pub load_actors: bool,
 }
@@ -80,6 +104,7 @@ impl Default for Client {
             enable_health_check: true,
             snapshot_path: None,
             import_mode: ImportMode::default(),
+            verify_f3: false,
             snapshot_height: None,
             snapshot_head: None,
             skip_load: false,
@@ -94,6 +119,9 @@ impl Default for Client {
                 IpAddr::V4(Ipv4Addr::LOCALHOST),
                 crate::health::DEFAULT_HEALTHCHECK_PORT,
             ),
+            rpc_tls: None,
+            metrics_tls: None,
+            healthcheck_tls: None,
             load_actors: true,
         }
     }
@@ -108,7 +136,12 @@ impl Client {
     }
 
     pub fn rpc_v1_endpoint(&self) -> Result<url::Url, url::ParseError> {
-        format!("http://{}/rpc/v1", self.rpc_address)
+        let scheme = if self.rpc_tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        format!("{scheme}://{}/rpc/v1", self.rpc_address)
             .as_str()
             .parse()
     }
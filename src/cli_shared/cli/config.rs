@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::client::Client;
+use crate::chain_sync::chain_muxer::DEFAULT_RECENT_STATE_ROOTS;
 use crate::db::db_engine::DbConfig;
 use crate::libp2p::Libp2pConfig;
 use crate::shim::clock::ChainEpoch;
@@ -9,6 +10,7 @@ use crate::shim::econ::TokenAmount;
 use crate::utils::misc::env::is_env_set_and_truthy;
 use crate::{chain_sync::SyncConfig, networks::NetworkChain};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 const FOREST_CHAIN_INDEXER_ENABLED: &str = "FOREST_CHAIN_INDEXER_ENABLED";
@@ -99,6 +101,10 @@ pub struct ChainIndexerConfig {
     pub enable_indexer: bool,
     /// Number of retention epochs for indexed entries. Set to `None` to disable garbage collection.
     pub gc_retention_epochs: Option<u32>,
+    /// Persist tipset execution traces on disk, to serve trace and replay RPC methods
+    /// without re-executing tipsets. Traces are pruned with `gc_retention_epochs`.
+    #[serde(default)]
+    pub enable_trace_store: bool,
 }
 
 impl Default for ChainIndexerConfig {
@@ -106,6 +112,7 @@ impl Default for ChainIndexerConfig {
         Self {
             enable_indexer: is_env_set_and_truthy(FOREST_CHAIN_INDEXER_ENABLED).unwrap_or(true),
             gc_retention_epochs: None,
+            enable_trace_store: false,
         }
     }
 }
@@ -127,6 +134,132 @@ impl Default for FeeConfig {
     }
 }
 
+/// How RPC clients are told apart by the rate limiter
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub enum RpcRateLimitKey {
+    /// One budget per authentication token. Requests without a token are keyed by IP address.
+    #[default]
+    Token,
+    /// One budget per client IP address.
+    Ip,
+    /// One budget per method, shared by all clients.
+    Method,
+}
+
+/// Structure that defines the RPC rate limiter configuration
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct RpcRateLimitConfig {
+    pub enabled: bool,
+    pub key: RpcRateLimitKey,
+    /// Maximum cost a client can spend in a burst.
+    pub burst: u32,
+    /// Cost refilled into a client's budget every second.
+    pub refill_per_sec: u32,
+    /// Cost of a call, by method name (e.g. `Filecoin.EthGetLogs`). Calls to methods that
+    /// are not listed cost `1`. Costs may not exceed `burst`.
+    pub method_costs: BTreeMap<String, u32>,
+}
+
+impl Default for RpcRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: RpcRateLimitKey::default(),
+            burst: 200,
+            refill_per_sec: 50,
+            method_costs: BTreeMap::from_iter([
+                ("Filecoin.EthGetLogs".to_owned(), 20),
+                ("Filecoin.StateMarketDeals".to_owned(), 100),
+            ]),
+        }
+    }
+}
+
+/// Structure that defines the RPC response cache configuration
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct RpcCacheConfig {
+    pub enabled: bool,
+    /// Maximum total size of the cached responses, in bytes.
+    pub max_size_bytes: u64,
+}
+
+impl Default for RpcCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Structure that defines the RPC server configuration
+#[derive(Deserialize, Serialize, PartialEq, Eq, Default, Debug, Clone)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct RpcConfig {
+    pub rate_limit: RpcRateLimitConfig,
+    pub cache: RpcCacheConfig,
+    /// Permissions granted to clients that authenticate with a TLS client certificate instead
+    /// of a token, keyed by certificate subject (e.g. `O=Miskatonic, CN=alice`). Requires
+    /// `client.rpc_tls.client_ca_path` to be set.
+    pub client_cert_permissions: BTreeMap<String, Vec<String>>,
+}
+
+/// What the snapshot export scheduler writes
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotExportKind {
+    /// Self-contained lite snapshots.
+    #[default]
+    Lite,
+    /// A lite base snapshot followed by diff snapshots, listed in a snapshot manifest.
+    Diff,
+}
+
+/// Structure that defines the scheduled snapshot export configuration
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct SnapshotExportConfig {
+    pub enabled: bool,
+    /// Directory the snapshots are written to. Defaults to `snapshots` in the chain data
+    /// directory.
+    pub directory: Option<PathBuf>,
+    pub kind: SnapshotExportKind,
+    /// Export once the chain head is this many epochs ahead of the last export.
+    pub interval_epochs: Option<ChainEpoch>,
+    /// Export once this many seconds have passed since the last export. When neither interval
+    /// is set, a snapshot is exported every day worth of epochs.
+    pub interval_secs: Option<u64>,
+    /// Number of recent state roots included in the snapshots.
+    pub recent_state_roots: ChainEpoch,
+    /// Number of snapshot files to keep, older ones are deleted. With the `diff` kind, a new
+    /// base snapshot is started once the base and its diffs reach this number of files.
+    #[cfg_attr(test, arbitrary(gen(|g| u32::arbitrary(g) as _)))]
+    pub keep: usize,
+}
+
+impl Default for SnapshotExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            kind: SnapshotExportKind::default(),
+            interval_epochs: None,
+            interval_secs: None,
+            recent_state_roots: DEFAULT_RECENT_STATE_ROOTS,
+            keep: 7,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Default, Debug, Clone)]
 #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
 #[serde(default)]
@@ -141,6 +274,8 @@ pub struct Config {
     pub fevm: FevmConfig,
     pub fee: FeeConfig,
     pub chain_indexer: ChainIndexerConfig,
+    pub rpc: RpcConfig,
+    pub snapshot_export: SnapshotExportConfig,
 }
 
 impl Config {
@@ -151,6 +286,34 @@ impl Config {
     pub fn chain(&self) -> &NetworkChain {
         &self.chain
     }
+
+    /// Checks that `rpc.client_cert_permissions` only grants known permissions, and that
+    /// client certificates are requested when it grants any.
+    pub fn validate_client_cert_permissions(&self) -> anyhow::Result<()> {
+        let permissions = &self.rpc.client_cert_permissions;
+        if permissions.is_empty() {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            self.client
+                .rpc_tls
+                .as_ref()
+                .is_some_and(|tls| tls.client_ca_path.is_some()),
+            "rpc.client_cert_permissions requires client.rpc_tls.client_ca_path to be set"
+        );
+        for (subject, granted) in permissions {
+            if let Some(unknown) = granted
+                .iter()
+                .find(|it| !crate::auth::ADMIN.contains(&it.as_str()))
+            {
+                anyhow::bail!(
+                    "unknown permission `{unknown}` granted to `{subject}` in rpc.client_cert_permissions, expected one of {}",
+                    crate::auth::ADMIN.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -172,4 +335,28 @@ mod test {
             '['
         )
     }
+
+    #[test]
+    fn client_cert_permissions_are_validated() {
+        let mut config = Config::default();
+        config
+            .rpc
+            .client_cert_permissions
+            .insert("CN=alice".into(), vec!["read".into(), "sign".into()]);
+        // Client certificates are not requested.
+        assert!(config.validate_client_cert_permissions().is_err());
+
+        config.client.rpc_tls = Some(crate::cli_shared::cli::TlsConfig {
+            cert_path: "cert.pem".into(),
+            key_path: "key.pem".into(),
+            client_ca_path: Some("ca.pem".into()),
+        });
+        config.validate_client_cert_permissions().unwrap();
+
+        config
+            .rpc
+            .client_cert_permissions
+            .insert("CN=bob".into(), vec!["root".into()]);
+        assert!(config.validate_client_cert_permissions().is_err());
+    }
 }
@@ -86,12 +86,16 @@ pub struct CliOpts {
     /// is unspecified.
     #[arg(long)]
     pub head: Option<u64>,
-    /// Import a snapshot from a local CAR file or URL
+    /// Import a snapshot from a local CAR file, URL or snapshot manifest (`.manifest.json`)
     #[arg(long)]
     pub import_snapshot: Option<String>,
     /// Snapshot import mode. Available modes are `auto`, `copy`, `move`, `symlink` and `hardlink`.
     #[arg(long, default_value = "auto")]
     pub import_mode: ImportMode,
+    /// Verify the imported snapshot against its F3 finality certificates, from the initial F3 power
+    /// table of the network, and refuse it if its head doesn't match a certified tipset
+    #[arg(long)]
+    pub verify_f3: bool,
     /// Halt with exit code 0 after successfully importing a snapshot
     #[arg(long)]
     pub halt_after_import: bool,
@@ -199,6 +203,7 @@ impl CliOpts {
         if let Some(snapshot_path) = &self.import_snapshot {
             cfg.client.snapshot_path = Some(snapshot_path.into());
             cfg.client.import_mode = self.import_mode;
+            cfg.client.verify_f3 |= self.verify_f3;
         }
 
         cfg.client.snapshot_height = self.height;
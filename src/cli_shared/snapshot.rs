@@ -56,6 +56,14 @@ pub fn filename(
     .to_string()
 }
 
+/// Returns the chain and height of a file name in the "full" format, see [`filename`].
+pub fn chain_and_height(filename: &str) -> Option<(&str, ChainEpoch)> {
+    match ParsedFilename::parse_str(filename).ok()? {
+        ParsedFilename::Full { chain, height, .. } => Some((chain, height)),
+        ParsedFilename::Short { .. } => None,
+    }
+}
+
 /// Returns the path to the downloaded file.
 pub async fn fetch(
     directory: &Path,
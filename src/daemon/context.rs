@@ -19,6 +19,7 @@ use crate::prelude::*;
 use crate::rpc::sync::SnapshotProgressTracker;
 use crate::shim::address::CurrentNetwork;
 use crate::state_manager::StateManager;
+use crate::state_manager::trace_store::{TRACE_STORE_DIR_NAME, TraceStore};
 use crate::{
     Config, ENCRYPTED_KEYSTORE_NAME, FOREST_KEYSTORE_PHRASE_ENV, JWT_IDENTIFIER, KeyStore,
     KeyStoreConfig,
@@ -251,7 +252,11 @@ async fn create_state_manager(
     )?;
 
     // Initialize StateManager
-    let state_manager = StateManager::new(chain_store)?;
+    let mut state_manager = StateManager::new(chain_store)?;
+    if config.chain_indexer.enable_trace_store {
+        let store = TraceStore::open(chain_path(config).join(TRACE_STORE_DIR_NAME))?;
+        state_manager = state_manager.with_trace_store(Arc::new(store));
+    }
     Ok(state_manager)
 }
 
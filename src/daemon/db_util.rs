@@ -6,20 +6,26 @@ use crate::db::SettingsStoreExt;
 use crate::db::car::forest::{
     FOREST_CAR_FILE_EXTENSION, TEMP_FOREST_CAR_FILE_EXTENSION, new_forest_car_temp_path_in,
 };
-use crate::db::car::{ForestCar, ManyCar};
+use crate::db::car::{ForestCar, ManyCar, SnapshotManifest};
+use crate::f3::snapshot::F3CheckpointVerification;
 use crate::ipld::ChainExportState;
 use crate::message::SignedMessage;
 use crate::networks::ChainConfig;
 use crate::prelude::*;
+use crate::rpc::eth::types::CallSource;
 use crate::rpc::sync::SnapshotProgressTracker;
 use crate::shim::clock::ChainEpoch;
 use crate::state_manager::StateManager;
 use crate::utils::db::car_stream::CarStream;
-use crate::utils::io::EitherMmapOrRandomAccessFile;
-use crate::utils::net::{DownloadFileOption, download_to};
+use crate::utils::encoding::hex;
+use crate::utils::io::{EitherMmapOrRandomAccessFile, WithProgress};
+use crate::utils::misc::env::is_env_truthy;
+use crate::utils::net::{DownloadFileOption, download_to, http_get};
+use crate::utils::reqwest_resume;
 use anyhow::{Context, bail};
 use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use std::sync::LazyLock;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::{
@@ -132,36 +138,44 @@ pub enum ImportMode {
 
 /// This function validates and stores the CAR binary from `from_path`(either local path or URL) into the `{DB_ROOT}/car_db/`
 /// (automatically trans-code into `.forest.car.zst` format when needed), and returns its final file path and the heaviest tipset.
+/// The F3 data of the snapshot is imported separately, with [`import_f3_data`].
 pub async fn import_chain_as_forest_car(
     from_path: &Path,
     forest_car_db_dir: &Path,
     import_mode: ImportMode,
-    rpc_endpoint: Url,
-    f3_root: &Path,
-    chain_config: &ChainConfig,
     snapshot_progress_tracker: &SnapshotProgressTracker,
 ) -> anyhow::Result<(PathBuf, Tipset)> {
     info!("Importing chain from snapshot at: {}", from_path.display());
 
     let stopwatch = time::Instant::now();
 
-    let forest_car_db_path = forest_car_db_dir.join(format!(
-        "{}{FOREST_CAR_FILE_EXTENSION}",
-        chrono::Utc::now().timestamp_millis()
-    ));
+    // Snapshots imported in quick succession, e.g. from a manifest, must not share a file name.
+    let forest_car_db_path = (chrono::Utc::now().timestamp_millis()..)
+        .map(|millis| forest_car_db_dir.join(format!("{millis}{FOREST_CAR_FILE_EXTENSION}")))
+        .find(|path| !path.exists())
+        .context("no file name available for the imported snapshot")?;
 
     let move_or_copy = |mode: ImportMode| {
         let forest_car_db_path = forest_car_db_path.clone();
         async move {
             let downloaded_car_temp_path = new_forest_car_temp_path_in(forest_car_db_dir)?;
             if let Ok(url) = Url::parse(&from_path.display().to_string()) {
-                download_to(
-                    &url,
-                    &downloaded_car_temp_path,
-                    DownloadFileOption::Resumable,
-                    snapshot_progress_tracker.create_callback(),
-                )
-                .await?;
+                if is_env_truthy("FOREST_SNAPSHOT_IMPORT_DISABLE_STREAMING") {
+                    download_to(
+                        &url,
+                        &downloaded_car_temp_path,
+                        DownloadFileOption::Resumable,
+                        snapshot_progress_tracker.create_callback(),
+                    )
+                    .await?;
+                } else {
+                    stream_into_forest_car(
+                        &url,
+                        &downloaded_car_temp_path,
+                        snapshot_progress_tracker.create_callback(),
+                    )
+                    .await?;
+                }
 
                 snapshot_progress_tracker.completed();
             } else {
@@ -247,7 +261,29 @@ pub async fn import_chain_as_forest_car(
     };
 
     let forest_car = ForestCar::try_from(forest_car_db_path.as_path())?;
+    let ts = forest_car.heaviest_tipset()?;
+    info!(
+        "Imported snapshot in: {}s, heaviest tipset epoch: {}, key: {}",
+        stopwatch.elapsed().as_secs(),
+        ts.epoch(),
+        ts.key()
+    );
+
+    Ok((forest_car_db_path, ts))
+}
 
+/// Imports the F3 data of the snapshot imported at `forest_car_db_path`, if any, into the F3
+/// sidecar. Anything wrong with the F3 data itself is only logged.
+pub fn import_f3_data(
+    forest_car_db_path: &Path,
+    rpc_endpoint: Url,
+    f3_root: &Path,
+    chain_config: &ChainConfig,
+) -> anyhow::Result<()> {
+    let forest_car_db_dir = forest_car_db_path
+        .parent()
+        .context("the imported snapshot has no parent directory")?;
+    let forest_car = ForestCar::try_from(forest_car_db_path)?;
     if let Some(f3_cid) = forest_car.metadata().and_then(|m| m.f3_data) {
         if crate::f3::get_f3_sidecar_params(chain_config)
             .initial_power_table
@@ -280,16 +316,126 @@ pub async fn import_chain_as_forest_car(
             }
         }
     }
+    Ok(())
+}
 
-    let ts = forest_car.heaviest_tipset()?;
+/// Verifies the snapshot imported at `car_db_paths`, with head `head`, against its F3
+/// certificates, starting from the initial F3 power table configured for the network.
+pub fn verify_f3_checkpoint(
+    car_db_paths: &[PathBuf],
+    chain_config: &ChainConfig,
+    head: &Tipset,
+) -> anyhow::Result<F3CheckpointVerification> {
+    let initial_power_table = crate::f3::get_f3_sidecar_params(chain_config)
+        .initial_power_table
+        .context("the initial F3 power table is not configured for this network")?;
+    let manifest = crate::f3::get_f3_predefined_manifest(&chain_config.network)?;
+    let store = ManyCar::new(crate::db::MemoryDB::default());
+    store.read_only_files(car_db_paths.iter().cloned())?;
+    // The F3 data of the latest snapshot covers the most recent head.
+    let (car, f3_cid) = car_db_paths
+        .iter()
+        .rev()
+        .map(|path| ForestCar::try_from(path.as_path()))
+        .map_ok(|car| car.metadata().and_then(|m| m.f3_data).map(|cid| (car, cid)))
+        .flatten_ok()
+        .next()
+        .context("the snapshot has no F3 data")??;
+    let f3_data = car
+        .get_reader(f3_cid)?
+        .with_context(|| format!("f3 data not found, cid: {f3_cid}"))?;
+    crate::f3::snapshot::verify_f3_checkpoint(
+        std::io::BufReader::new(f3_data),
+        &manifest.network_name,
+        manifest.initial_instance,
+        initial_power_table,
+        head,
+        &store,
+    )
+}
+
+/// Imports the snapshots listed in the [`SnapshotManifest`] at `manifest_path`, the base first,
+/// like [`import_chain_as_forest_car`]. Returns the final file paths and the heaviest tipset.
+///
+/// The manifest is verified before anything is imported. If importing a snapshot fails, the ones
+/// already imported are removed again. With [`ImportMode::Move`], the snapshots are copied, and
+/// the originals are only removed once all of them are imported.
+pub async fn import_manifest_as_forest_cars(
+    manifest_path: &Path,
+    forest_car_db_dir: &Path,
+    import_mode: ImportMode,
+    snapshot_progress_tracker: &SnapshotProgressTracker,
+) -> anyhow::Result<(Vec<PathBuf>, Tipset)> {
+    let manifest = SnapshotManifest::load(manifest_path)?;
     info!(
-        "Imported snapshot in: {}s, heaviest tipset epoch: {}, key: {}",
-        stopwatch.elapsed().as_secs(),
-        ts.epoch(),
-        ts.key()
+        "Importing snapshot manifest at: {}, {} diff(s) on top of the base snapshot at epoch {}",
+        manifest_path.display(),
+        manifest.diffs.len(),
+        manifest.base.epoch
     );
+    manifest
+        .verify_and_record(manifest_path)
+        .with_context(|| format!("invalid snapshot manifest {}", manifest_path.display()))?;
+    let file_import_mode = match import_mode {
+        ImportMode::Move => ImportMode::Copy,
+        mode => mode,
+    };
+    let mut paths = Vec::with_capacity(manifest.diffs.len() + 1);
+    let result = async {
+        let mut heaviest = None;
+        for file in manifest.files() {
+            let (path, ts) = import_chain_as_forest_car(
+                file,
+                forest_car_db_dir,
+                file_import_mode,
+                snapshot_progress_tracker,
+            )
+            .await?;
+            paths.push(path);
+            heaviest = Some(ts);
+        }
+        let heaviest = heaviest.context("empty snapshot manifest")?;
+        anyhow::ensure!(
+            heaviest.epoch() == manifest.head_epoch(),
+            "unexpected head epoch {} of the last snapshot, expected {}",
+            heaviest.epoch(),
+            manifest.head_epoch()
+        );
+        Ok(heaviest)
+    }
+    .await;
+    let heaviest = match result {
+        Ok(heaviest) => heaviest,
+        Err(e) => {
+            for path in &paths {
+                if let Err(e) = fs::remove_file(path) {
+                    warn!("failed to remove imported snapshot {}: {e}", path.display());
+                }
+            }
+            return Err(e);
+        }
+    };
+    if import_mode == ImportMode::Move {
+        for file in manifest.files() {
+            fs::remove_file(file)
+                .with_context(|| format!("Error removing original file {}", file.display()))?;
+        }
+    }
+    Ok((paths, heaviest))
+}
 
-    Ok((forest_car_db_path, ts))
+/// Removes the local snapshot files imported from `from_path`, a snapshot or a snapshot manifest,
+/// as [`ImportMode::Move`] does. Snapshots downloaded from a URL have nothing to remove.
+pub fn remove_snapshot_originals(from_path: &Path) -> anyhow::Result<()> {
+    if SnapshotManifest::is_manifest(from_path) {
+        for file in SnapshotManifest::load(from_path)?.files() {
+            fs::remove_file(file)
+                .with_context(|| format!("Error removing original file {}", file.display()))?;
+        }
+    } else if Url::parse(&from_path.display().to_string()).is_err() {
+        fs::remove_file(from_path).context("Error removing original file")?;
+    }
+    Ok(())
 }
 
 fn move_or_copy_file(from: &Path, to: &Path, import_mode: ImportMode) -> anyhow::Result<()> {
@@ -333,6 +479,108 @@ async fn transcode_into_forest_car(from: &Path, to: &Path) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Streams the snapshot at `url` into `to` while it downloads, resuming interrupted connections.
+/// `.forest.car.zst` snapshots are written as-is, other formats are transcoded into a
+/// `.forest.car.zst` on the fly, so no intermediate file is needed. When a `.sha256sum` file is
+/// published next to the snapshot, the downloaded bytes are verified against it.
+///
+/// Note that zstd compressed CARv2 snapshots are only supported on a best effort basis, see
+/// [`CarStream::new_unsafe`].
+async fn stream_into_forest_car(
+    url: &Url,
+    to: &Path,
+    callback: Option<Arc<dyn Fn(String) + Sync + Send>>,
+) -> anyhow::Result<()> {
+    const DOWNLOAD_BUFFER_SIZE: usize = 512 * 1024;
+
+    let response = reqwest_resume::get(url.clone()).await?;
+    let resp = response.response().error_for_status_ref()?;
+    let content_length = resp.content_length().unwrap_or_default();
+    // Stable snapshot URLs redirect to the actual file.
+    let url = resp.url().clone();
+    let is_forest_car = url.path().ends_with(FOREST_CAR_FILE_EXTENSION);
+    tracing::info!(
+        %url,
+        to = %to.display(),
+        transcode = !is_forest_car,
+        "streaming snapshot"
+    );
+    let expected_checksum = fetch_sha256sum(&url).await;
+
+    let hasher = Arc::new(parking_lot::Mutex::new(Sha256::new()));
+    let stream = response
+        .bytes_stream()
+        .inspect_ok({
+            let hasher = hasher.clone();
+            move |bytes| hasher.lock().update(bytes)
+        })
+        .map_err(std::io::Error::other);
+    let mut reader = tokio::io::BufReader::with_capacity(
+        DOWNLOAD_BUFFER_SIZE,
+        WithProgress::wrap_sync_read_with_callback(
+            "Importing",
+            tokio_util::io::StreamReader::new(stream),
+            content_length,
+            callback,
+        )
+        .bytes(),
+    );
+
+    let mut writer = tokio::io::BufWriter::new(tokio::fs::File::create(to).await?);
+    if is_forest_car {
+        tokio::io::copy_buf(&mut reader, &mut writer).await?;
+    } else {
+        let car_stream = CarStream::new_unsafe(&mut reader).await?;
+        let roots = car_stream.header_v1.roots.clone();
+        let frames = crate::db::car::forest::Encoder::compress_stream_default(
+            car_stream.map_err(anyhow::Error::from),
+        );
+        crate::db::car::forest::Encoder::write(&mut writer, roots, frames).await?;
+        // Consume trailing bytes that are not part of the CAR data, so they are checksummed too.
+        tokio::io::copy_buf(&mut reader, &mut tokio::io::sink()).await?;
+    }
+    writer.shutdown().await?;
+
+    if let Some(expected) = expected_checksum {
+        let actual = std::mem::take(&mut *hasher.lock()).finalize();
+        anyhow::ensure!(
+            actual.as_slice() == expected.as_slice(),
+            "checksum mismatch for {url}, expected: {}, actual: {}",
+            hex::encode(&expected),
+            hex::encode(actual)
+        );
+        tracing::info!(%url, "snapshot checksum verified");
+    }
+
+    Ok(())
+}
+
+/// Fetches the SHA-256 checksum published next to the snapshot at `url`, in the format written by
+/// `forest-cli snapshot export`. Returns `None` if there is no (valid) checksum file.
+async fn fetch_sha256sum(url: &Url) -> Option<Vec<u8>> {
+    let mut checksum_url = url.clone();
+    checksum_url.set_path(&format!("{}.sha256sum", url.path()));
+    let checksum = async {
+        let content = http_get(&checksum_url).await?.text().await?;
+        let checksum = hex::decode(
+            content
+                .split_whitespace()
+                .next()
+                .context("empty checksum file")?,
+        )?;
+        anyhow::ensure!(checksum.len() == 32, "invalid SHA-256 checksum length");
+        anyhow::Ok(checksum)
+    }
+    .await;
+    match checksum {
+        Ok(checksum) => Some(checksum),
+        Err(e) => {
+            warn!("skipping checksum verification, failed to fetch {checksum_url}: {e:#}");
+            None
+        }
+    }
+}
+
 /// Settings-store key under which index backfill persists the epoch of the last committed
 /// batch, so an interrupted backfill can be resumed from where it left off.
 pub const BACKFILL_CHECKPOINT_KEY: &str = "/index/backfill/checkpoint";
@@ -716,6 +964,39 @@ pub async fn backfill_db(
     Ok(())
 }
 
+/// Fills the trace store of `state_manager` with the execution traces of the tipsets in the
+/// range, skipping the tipsets already stored. Returns the number of traced tipsets.
+pub async fn backfill_traces(
+    state_manager: &StateManager,
+    from_ts: &Tipset,
+    spec: RangeSpec,
+) -> anyhow::Result<usize> {
+    let store = state_manager
+        .trace_store()
+        .context("trace store is not enabled")?;
+    let mut traced = 0;
+    for (count, ts) in from_ts
+        .shallow_clone()
+        .chain(&state_manager.chain_store().db())
+        .enumerate()
+    {
+        match spec {
+            RangeSpec::To(to_epoch) if ts.epoch() < to_epoch => break,
+            RangeSpec::NumTipsets(n) if count >= n => break,
+            _ => {}
+        }
+        if store.get(&ts)?.is_some() {
+            continue;
+        }
+        info!("Tracing tipset at epoch {}", ts.epoch());
+        state_manager
+            .execution_trace(&ts, CallSource::Internal)
+            .await?;
+        traced += 1;
+    }
+    Ok(traced)
+}
+
 /// Hardened index backfill core shared by the offline `forest-tool index backfill` command and the
 /// online `Forest.IndexBackfill` RPC method.
 ///
@@ -862,6 +1143,7 @@ pub async fn run_backfill(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::db::car::{DiffChain, export_diff_chain};
 
     // The backfill guard shares the chain-export single-flight slot, so serialize with the export
     // tests that also touch it.
@@ -1057,6 +1339,168 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn import_snapshot_from_url_streaming() {
+        use axum::{Router, http::StatusCode, routing::get};
+
+        let car = fs::read("test-snapshots/chain4.car.zst").unwrap();
+        let forest_car = fs::read("test-snapshots/chain4.forest.car.zst").unwrap();
+        let checksum = |data: &[u8]| format!("{} chain4\n", hex::encode(Sha256::digest(data)));
+        let app = Router::new()
+            .route(
+                "/chain4.car.zst",
+                get({
+                    let car = car.clone();
+                    move || async move { car }
+                }),
+            )
+            .route(
+                "/chain4.car.zst.sha256sum",
+                get({
+                    let checksum = checksum(&car);
+                    move || async move { checksum }
+                }),
+            )
+            .route(
+                "/chain4.forest.car.zst",
+                get({
+                    let forest_car = forest_car.clone();
+                    move || async move { forest_car }
+                }),
+            )
+            .route(
+                "/chain4.forest.car.zst.sha256sum",
+                get({
+                    let checksum = checksum(&forest_car);
+                    move || async move { checksum }
+                }),
+            )
+            .route("/corrupted.car.zst", get(move || async move { car }))
+            .route(
+                "/corrupted.car.zst.sha256sum",
+                get({
+                    let checksum = checksum(b"Yog-Sothoth");
+                    move || async move { checksum }
+                }),
+            )
+            .route(
+                "/unverified.car.zst.sha256sum",
+                get(|| async { StatusCode::NOT_FOUND }),
+            )
+            .route(
+                "/unverified.car.zst",
+                get(move || async move { forest_car }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        for (path, valid) in [
+            ("chain4.car.zst", true),
+            ("chain4.forest.car.zst", true),
+            ("unverified.car.zst", true),
+            ("corrupted.car.zst", false),
+        ] {
+            let result =
+                import_snapshot_from_url(&format!("http://{addr}/{path}"), ImportMode::Auto).await;
+            assert_eq!(result.is_ok(), valid, "{path}: {result:?}");
+        }
+    }
+
+    #[tokio::test]
+    #[serial_test::serial(chain_export)]
+    async fn import_snapshot_from_manifest() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let DiffChain { base, diff, .. } = export_diff_chain(snapshot_dir.path()).await;
+        let manifest_path = snapshot_dir.path().join("chain.manifest.json");
+        let manifest =
+            SnapshotManifest::create(&base, std::slice::from_ref(&diff), &manifest_path).unwrap();
+        manifest.save(&manifest_path).unwrap();
+
+        let temp_db_dir = tempfile::tempdir().unwrap();
+        let (paths, ts) = import_manifest(&manifest_path, temp_db_dir.path(), ImportMode::Move)
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.is_file()));
+        assert_eq!(ts.epoch(), manifest.head_epoch());
+        // The originals are only removed once both snapshots are imported.
+        assert!(!base.exists() && !diff.exists());
+    }
+
+    #[tokio::test]
+    #[serial_test::serial(chain_export)]
+    async fn originals_are_removed_after_copying() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let DiffChain { base, diff, .. } = export_diff_chain(snapshot_dir.path()).await;
+        let manifest_path = snapshot_dir.path().join("chain.manifest.json");
+        SnapshotManifest::create(&base, std::slice::from_ref(&diff), &manifest_path)
+            .unwrap()
+            .save(&manifest_path)
+            .unwrap();
+
+        // As the daemon imports snapshots to verify with F3 in `Move` mode.
+        let temp_db_dir = tempfile::tempdir().unwrap();
+        let (paths, _) = import_manifest(&manifest_path, temp_db_dir.path(), ImportMode::Copy)
+            .await
+            .unwrap();
+        assert!(base.exists() && diff.exists());
+        remove_snapshot_originals(&manifest_path).unwrap();
+        assert!(!base.exists() && !diff.exists());
+        assert!(paths.iter().all(|p| p.is_file()));
+        // Nothing to remove for downloads.
+        remove_snapshot_originals(Path::new("https://example.com/snapshot.car.zst")).unwrap();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial(chain_export)]
+    async fn manifest_is_verified_before_import() {
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        let DiffChain { base, diff, .. } = export_diff_chain(snapshot_dir.path()).await;
+        let manifest_path = snapshot_dir.path().join("chain.manifest.json");
+        let mut manifest =
+            SnapshotManifest::create(&base, std::slice::from_ref(&diff), &manifest_path).unwrap();
+        manifest.diffs[0].sha256 = crate::utils::encoding::hex::encode([0; 32]);
+        manifest.save(&manifest_path).unwrap();
+
+        let temp_db_dir = tempfile::tempdir().unwrap();
+        import_manifest(&manifest_path, temp_db_dir.path(), ImportMode::Move)
+            .await
+            .unwrap_err();
+        assert_eq!(fs::read_dir(temp_db_dir.path()).unwrap().count(), 0);
+        assert!(base.exists() && diff.exists());
+    }
+
+    async fn import_manifest(
+        manifest_path: &Path,
+        forest_car_db_dir: &Path,
+        import_mode: ImportMode,
+    ) -> anyhow::Result<(Vec<PathBuf>, Tipset)> {
+        import_manifest_as_forest_cars(
+            manifest_path,
+            forest_car_db_dir,
+            import_mode,
+            &SnapshotProgressTracker::default(),
+        )
+        .await
+    }
+
+    async fn import_snapshot_from_url(url: &str, import_mode: ImportMode) -> anyhow::Result<()> {
+        let temp_db_dir = tempfile::Builder::new().tempdir()?;
+        let (path, ts) = import_chain_as_forest_car(
+            Path::new(url),
+            temp_db_dir.path(),
+            import_mode,
+            &SnapshotProgressTracker::default(),
+        )
+        .await?;
+        assert!(ForestCar::is_valid(&EitherMmapOrRandomAccessFile::open(
+            &path
+        )?));
+        assert!(ts.epoch() > 0);
+        Ok(())
+    }
+
     async fn import_snapshot_from_file(
         file_path: &str,
         import_mode: ImportMode,
@@ -1072,9 +1516,6 @@ mod test {
             file_path,
             temp_db_dir.path(),
             import_mode,
-            "http://127.0.0.1:2345/rpc/v1".parse().unwrap(),
-            Path::new("test"),
-            &ChainConfig::devnet(),
             &SnapshotProgressTracker::default(),
         )
         .await?;
@@ -5,7 +5,9 @@ pub mod bundle;
 mod context;
 pub mod db_util;
 pub mod main;
+mod snapshot_export;
 
+use crate::auth::TokenRevocations;
 use crate::blocks::TipsetKey;
 use crate::chain::ChainStore;
 use crate::chain_sync::ChainFollower;
@@ -16,7 +18,15 @@ use crate::cli_shared::{
     cli::{CliOpts, Config},
     delete_chain_data,
 };
-use crate::daemon::{context::AppContext, db_util::import_chain_as_forest_car};
+use crate::daemon::{
+    context::AppContext,
+    db_util::{
+        ImportMode, import_chain_as_forest_car, import_f3_data, import_manifest_as_forest_cars,
+        remove_snapshot_originals, verify_f3_checkpoint,
+    },
+    snapshot_export::SnapshotExportScheduler,
+};
+use crate::db::car::SnapshotManifest;
 use crate::db::gc::SnapshotGarbageCollector;
 use crate::db::ttl::EthMappingCollector;
 use crate::libp2p::{Libp2pService, PeerManager};
@@ -28,11 +38,12 @@ use crate::rpc::eth::filter::EthEventHandler;
 use crate::rpc::eth::types::CallSource;
 use crate::rpc::start_rpc;
 use crate::shim::address::Address;
-use crate::shim::clock::ChainEpoch;
+use crate::shim::clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
 use crate::shim::state_tree::StateTree;
 use crate::shim::version::NetworkVersion;
 use crate::state_manager::StateManager;
 use crate::utils::misc::env::is_env_truthy;
+use crate::utils::net::tls::{MaybeTlsListener, TlsAcceptor};
 use crate::utils::{self};
 use crate::utils::{proofs_api::ensure_proof_params_downloaded, version::FOREST_VERSION_STRING};
 use anyhow::{Context as _, bail};
@@ -144,25 +155,66 @@ async fn maybe_import_snapshot(
     if !opts.skip_load.unwrap_or_default()
         && let Some(path) = &config.client.snapshot_path
     {
-        let (car_db_path, ts) = import_chain_as_forest_car(
-            path,
-            &ctx.db_meta_data.get_forest_car_db_dir(),
-            config.client.import_mode,
-            config.client.rpc_v1_endpoint()?,
-            &crate::f3::get_f3_root(config),
-            ctx.chain_config(),
-            &snapshot_tracker,
-        )
-        .await?;
-        ctx.db
-            .read_only_files(std::iter::once(car_db_path.clone()))?;
+        // Snapshots to verify with F3 are copied rather than moved, so that refusing them leaves
+        // the originals in place. The originals are removed once verified.
+        let import_mode = match config.client.import_mode {
+            ImportMode::Move if config.client.verify_f3 => ImportMode::Copy,
+            mode => mode,
+        };
+        let (car_db_paths, ts) = if SnapshotManifest::is_manifest(path) {
+            import_manifest_as_forest_cars(
+                path,
+                &ctx.db_meta_data.get_forest_car_db_dir(),
+                import_mode,
+                &snapshot_tracker,
+            )
+            .await?
+        } else {
+            let (car_db_path, ts) = import_chain_as_forest_car(
+                path,
+                &ctx.db_meta_data.get_forest_car_db_dir(),
+                import_mode,
+                &snapshot_tracker,
+            )
+            .await?;
+            (vec![car_db_path], ts)
+        };
+        if config.client.verify_f3 {
+            match verify_f3_checkpoint(&car_db_paths, ctx.chain_config(), &ts) {
+                Ok(verification) => {
+                    info!("Verified the snapshot with F3:\n{verification}");
+                    if config.client.import_mode == ImportMode::Move {
+                        remove_snapshot_originals(path)?;
+                    }
+                }
+                Err(e) => {
+                    // These are copies, downloads or links made by this import.
+                    for path in &car_db_paths {
+                        std::fs::remove_file(path)?;
+                    }
+                    return Err(e.context(format!(
+                        "refusing the snapshot at {}, it doesn't match the F3 certificates",
+                        path.display()
+                    )));
+                }
+            }
+        }
+        for car_db_path in &car_db_paths {
+            import_f3_data(
+                car_db_path,
+                config.client.rpc_v1_endpoint()?,
+                &crate::f3::get_f3_root(config),
+                ctx.chain_config(),
+            )?;
+        }
+        ctx.db.read_only_files(car_db_paths.iter().cloned())?;
         let ts_epoch = ts.epoch();
         // Explicitly set heaviest tipset here in case HEAD_KEY has already been set
         // in the current setting store
         ctx.state_manager.chain_store().set_heaviest_tipset(ts)?;
         debug!(
             "Loaded car DB at {} and set current head to epoch {ts_epoch}",
-            car_db_path.display(),
+            car_db_paths.iter().map(|p| p.display()).join(", "),
         );
     }
 
@@ -230,8 +282,12 @@ async fn maybe_start_metrics_service(
     ctx: &AppContext,
 ) -> anyhow::Result<()> {
     if config.client.enable_metrics_endpoint {
-        let prometheus_listener =
-            crate::utils::net::bind_tcp_listener(config.client.metrics_address, 0).await?;
+        let prometheus_listener = MaybeTlsListener::bind(
+            config.client.metrics_address,
+            0,
+            config.client.metrics_tls.as_ref(),
+        )
+        .await?;
         info!(
             "Prometheus server started at {}",
             config.client.metrics_address
@@ -284,6 +340,18 @@ async fn maybe_start_metrics_service(
                 get_chain_head_height,
             ),
         ));
+        crate::metrics::register_collector(Box::new(
+            crate::chain::ec_finality::metrics::EcFinalityCollector::new(Arc::new({
+                let cs = ctx.state_manager.chain_store().shallow_clone();
+                move || {
+                    crate::rpc::chain::ChainGetTipSetFinalityStatus::get_ec_finality_threshold_depth_with_cache(
+                        cs.chain_index(),
+                        cs.chain_config(),
+                        &cs.heaviest_tipset(),
+                    )
+                }
+            })),
+        ));
     }
     Ok(())
 }
@@ -512,7 +580,12 @@ async fn maybe_start_health_check_service(
         };
         let healthcheck_address = forest_state.config.client.healthcheck_address;
         info!("Healthcheck endpoint will listen at {healthcheck_address}");
-        let listener = crate::utils::net::bind_tcp_listener(healthcheck_address, 0).await?;
+        let listener = MaybeTlsListener::bind(
+            healthcheck_address,
+            0,
+            forest_state.config.client.healthcheck_tls.as_ref(),
+        )
+        .await?;
         services.spawn(async move {
             crate::health::init_healthcheck_server(forest_state, listener)
                 .await
@@ -558,6 +631,20 @@ fn maybe_start_gc_service(
     Ok(())
 }
 
+fn maybe_start_snapshot_export_service(
+    services: &mut JoinSet<anyhow::Result<()>>,
+    opts: &CliOpts,
+    config: &Config,
+    chain_follower: ChainFollower,
+) -> anyhow::Result<()> {
+    if !config.snapshot_export.enabled || opts.stateless {
+        return Ok(());
+    }
+    let scheduler = SnapshotExportScheduler::new(chain_follower, config)?;
+    services.spawn(async move { scheduler.scheduler_loop().await });
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn maybe_start_rpc_service(
     services: &mut JoinSet<anyhow::Result<()>>,
@@ -570,6 +657,7 @@ fn maybe_start_rpc_service(
     ctx: &AppContext,
 ) -> anyhow::Result<()> {
     if config.client.enable_rpc {
+        config.validate_client_cert_permissions()?;
         let rpc_address = config.client.rpc_address;
         let metrics_mode = crate::rpc::MetricsMode::from(config.client.enable_metrics_endpoint);
         let filter_list = config
@@ -578,6 +666,19 @@ fn maybe_start_rpc_service(
             .as_ref()
             .map(|path| crate::rpc::FilterList::new_from_file(path).map(Arc::new))
             .transpose()?;
+        let rate_limiter = crate::rpc::RateLimiter::new(&config.rpc.rate_limit)?.map(Arc::new);
+        let response_cache = crate::rpc::ResponseCache::new(&config.rpc.cache).map(Arc::new);
+        let tls = config
+            .client
+            .rpc_tls
+            .clone()
+            .map(|tls| {
+                anyhow::Ok(crate::rpc::RpcTls {
+                    acceptor: Arc::new(TlsAcceptor::new(tls)?),
+                    client_permissions: config.rpc.client_cert_permissions.clone(),
+                })
+            })
+            .transpose()?;
         info!("JSON-RPC endpoint will listen at {rpc_address}");
         let eth_event_handler = Arc::new(EthEventHandler::from_config(
             &config.events,
@@ -602,6 +703,7 @@ fn maybe_start_rpc_service(
             let sync_network_context = chain_follower.network.shallow_clone();
             let tipset_send = chain_follower.tipset_sender.clone();
             let keystore = ctx.keystore.shallow_clone();
+            let token_revocations = Arc::new(TokenRevocations::load(state_manager.db())?);
             let snapshot_progress_tracker = ctx.snapshot_progress_tracker.clone();
             let nonce_tracker = NonceTracker::new();
             let mpool_locker = MpoolLocker::new();
@@ -616,6 +718,7 @@ fn maybe_start_rpc_service(
                     RPCState {
                         state_manager,
                         keystore,
+                        token_revocations,
                         mpool,
                         bad_blocks,
                         sync_status,
@@ -631,8 +734,11 @@ fn maybe_start_rpc_service(
                         temp_dir,
                     },
                     rpc_listener,
+                    tls,
                     rpc_stop_handle,
                     filter_list,
+                    rate_limiter,
+                    response_cache,
                     metrics_mode,
                 )
                 .await
@@ -734,6 +840,34 @@ fn maybe_start_f3_service(opts: &CliOpts, config: &Config, ctx: &AppContext) ->
     Ok(())
 }
 
+/// Prunes the traces stored beyond the retention of the chain indexer, see
+/// [`crate::state_manager::trace_store::TraceStore`].
+fn maybe_start_trace_store_collector(
+    services: &mut JoinSet<anyhow::Result<()>>,
+    config: &Config,
+    ctx: &AppContext,
+) {
+    let (Some(store), Some(retention_epochs)) = (
+        ctx.state_manager.trace_store().cloned(),
+        config.chain_indexer.gc_retention_epochs,
+    ) else {
+        return;
+    };
+    let chain_store = ctx.state_manager.chain_store().shallow_clone();
+    services.spawn(async move {
+        tracing::info!("Starting collector for stored traces");
+        let period =
+            Duration::from_secs(EPOCH_DURATION_SECONDS as u64 * u64::from(retention_epochs));
+        loop {
+            tokio::time::sleep(period).await;
+            let cutoff = chain_store.heaviest_tipset().epoch() - ChainEpoch::from(retention_epochs);
+            let store = store.clone();
+            let pruned = tokio::task::spawn_blocking(move || store.prune_before(cutoff)).await??;
+            tracing::debug!("Deleted {pruned} stored traces older than epoch {cutoff}");
+        }
+    });
+}
+
 fn maybe_start_indexer_service(
     services: &mut JoinSet<anyhow::Result<()>>,
     opts: &CliOpts,
@@ -851,11 +985,18 @@ pub(super) async fn start_services(
 
     warmup_in_background(&ctx);
     maybe_start_gc_service(&mut services, opts, &config, chain_follower.shallow_clone())?;
+    maybe_start_snapshot_export_service(
+        &mut services,
+        opts,
+        &config,
+        chain_follower.shallow_clone(),
+    )?;
     maybe_start_metrics_service(&mut services, &config, &ctx).await?;
     maybe_start_f3_service(opts, &config, &ctx)?;
     maybe_start_health_check_service(&mut services, &config, &p2p_service, &chain_follower, &ctx)
         .await?;
     maybe_start_indexer_service(&mut services, opts, &config, &ctx);
+    maybe_start_trace_store_collector(&mut services, &config, &ctx);
     if !opts.stateless {
         ensure_proof_params_downloaded().await?;
     }
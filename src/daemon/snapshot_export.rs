@@ -0,0 +1,490 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Scheduled snapshot exports, see [`SnapshotExportConfig`].
+//!
+//! Snapshots are exported at the chain head in the v2 format, with FRC-0108 metadata and the
+//! latest F3 snapshot when available, and a `.sha256sum` checksum file next to each of them.
+//!
+//! - With the `lite` kind, every export is a standalone lite snapshot, and only the `keep` most
+//!   recent ones are kept.
+//! - With the `diff` kind, the first export is a lite base snapshot, and the following ones are
+//!   diff snapshots on top of the previous export. The chain of snapshots is listed in the
+//!   `forest_snapshot_<chain>.manifest.json` [`SnapshotManifest`], which can be passed to
+//!   `--import-snapshot`. Once the chain reaches `keep` files, a new base snapshot is exported
+//!   and the files of the previous chain are deleted.
+//!
+//! Exports hold the chain-export slot, so they never overlap with the snapshot GC or with
+//! user-requested exports. An export that finds the slot busy is retried at the next check.
+
+use crate::blocks::Tipset;
+use crate::chain::{ExportOptions, ExportResult, index::ResolveNullTipset};
+use crate::chain_sync::ChainFollower;
+use crate::cid_collections::FileBackedCidHashSet;
+use crate::cli_shared::cli::{Config, SnapshotExportConfig, SnapshotExportKind};
+use crate::cli_shared::{chain_path, snapshot};
+use crate::db::DbImpl;
+use crate::db::car::forest::{
+    ASYNC_OPS_TIMEOUT, forest_car_sha256sum_path, tmp_exporting_forest_car_path,
+};
+use crate::db::car::{
+    BaseSnapshot, DiffSnapshot, SNAPSHOT_MANIFEST_FILE_EXTENSION, SnapshotManifest,
+};
+use crate::ipld::{ChainExportGuard, ChainExportKind, stream_chain};
+use crate::networks::NetworkChain;
+use crate::prelude::*;
+use crate::rpc::chain::{export_latest_f3_snapshot, save_checksum};
+use crate::shim::clock::{EPOCH_DURATION_SECONDS, EPOCHS_IN_DAY};
+use crate::utils::encoding::hex;
+use crate::utils::spawn_blocking_with_timeout;
+use chrono::DateTime;
+use futures::TryStreamExt as _;
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct SnapshotExportScheduler {
+    config: SnapshotExportConfig,
+    directory: PathBuf,
+    chain_tmp_root: PathBuf,
+    chain_follower: ChainFollower,
+}
+
+impl SnapshotExportScheduler {
+    pub fn new(chain_follower: ChainFollower, config: &Config) -> anyhow::Result<Self> {
+        let directory = config
+            .snapshot_export
+            .directory
+            .clone()
+            .unwrap_or_else(|| chain_path(config).join("snapshots"));
+        std::fs::create_dir_all(&directory).with_context(|| {
+            format!(
+                "failed to create the snapshot export directory {}",
+                directory.display()
+            )
+        })?;
+        Ok(Self {
+            config: config.snapshot_export.clone(),
+            directory,
+            chain_tmp_root: chain_path(config).join("tmp"),
+            chain_follower,
+        })
+    }
+
+    pub async fn scheduler_loop(&self) -> ! {
+        tracing::info!(
+            "Running snapshot export scheduler, exporting {:?} snapshots to {}",
+            self.config.kind,
+            self.directory.display()
+        );
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = self.export_if_due().await {
+                tracing::warn!("scheduled snapshot export failed: {e:#}");
+            }
+        }
+    }
+
+    async fn export_if_due(&self) -> anyhow::Result<()> {
+        let sync_status = (*self.chain_follower.sync_status.load()).shallow_clone();
+        if sync_status.current_head_epoch <= 0
+            || !sync_status.is_synced()
+            || !sync_status.active_forks.is_empty()
+        {
+            return Ok(());
+        }
+        let head = self.chain_follower.state_manager.heaviest_tipset();
+        let last_export = match self.config.kind {
+            SnapshotExportKind::Lite => lite_snapshots(&self.directory, self.network())?
+                .last()
+                .map(|(epoch, path)| anyhow::Ok((*epoch, modified(path)?)))
+                .transpose()?,
+            SnapshotExportKind::Diff => self
+                .load_manifest()?
+                .map(|manifest| {
+                    anyhow::Ok((manifest.head_epoch(), modified(&self.manifest_path())?))
+                })
+                .transpose()?,
+        };
+        if !is_export_due(&self.config, head.epoch(), last_export, SystemTime::now()) {
+            return Ok(());
+        }
+        let chain_export_guard =
+            match ChainExportGuard::try_start_export(ChainExportKind::ScheduledSnapshot) {
+                Ok(guard) => guard,
+                Err(e) => {
+                    tracing::debug!("scheduled snapshot export postponed: {e:#}");
+                    return Ok(());
+                }
+            };
+        let result = match self.config.kind {
+            SnapshotExportKind::Lite => self.export_lite(&chain_export_guard, &head).await,
+            SnapshotExportKind::Diff => self.export_diff(&chain_export_guard, &head).await,
+        };
+        chain_export_guard.finish(result)
+    }
+
+    async fn export_lite(
+        &self,
+        chain_export_guard: &ChainExportGuard,
+        head: &Tipset,
+    ) -> anyhow::Result<()> {
+        let output_path = self.directory.join(self.lite_filename(head.epoch()));
+        if self
+            .export(chain_export_guard, head, None, &output_path)
+            .await?
+            .is_some()
+        {
+            rotate_lite_snapshots(&self.directory, self.network(), self.config.keep)?;
+        }
+        Ok(())
+    }
+
+    async fn export_diff(
+        &self,
+        chain_export_guard: &ChainExportGuard,
+        head: &Tipset,
+    ) -> anyhow::Result<()> {
+        let manifest_path = self.manifest_path();
+        let current = self.load_manifest()?;
+        if let Some(mut manifest) = current
+            .clone()
+            .filter(|manifest| manifest.diffs.len() + 1 < self.config.keep)
+        {
+            let from = manifest.head_epoch();
+            let output_path = self.directory.join(self.diff_filename(from, head.epoch()));
+            let diff = async {
+                let base = self
+                    .chain_follower
+                    .state_manager
+                    .chain_index()
+                    .load_required_tipset_by_height(
+                        from,
+                        head.shallow_clone(),
+                        ResolveNullTipset::TakeOlder,
+                    )
+                    .await?;
+                self.export(chain_export_guard, head, Some(&base), &output_path)
+                    .await
+            };
+            match diff.await {
+                Ok(Some(sha256)) => {
+                    manifest.base.path = file_name(&manifest.base.path)?;
+                    for diff in &mut manifest.diffs {
+                        diff.path = file_name(&diff.path)?;
+                    }
+                    manifest.diffs.push(DiffSnapshot {
+                        path: file_name(&output_path)?,
+                        from,
+                        to: head.epoch(),
+                        sha256,
+                    });
+                    return manifest.save(&manifest_path);
+                }
+                Ok(None) => return Ok(()),
+                Err(e) => {
+                    tracing::warn!(
+                        "failed to export a diff snapshot from epoch {from}, starting a new base snapshot: {e:#}"
+                    );
+                }
+            }
+        }
+
+        let output_path = self.directory.join(self.lite_filename(head.epoch()));
+        let Some(sha256) = self
+            .export(chain_export_guard, head, None, &output_path)
+            .await?
+        else {
+            return Ok(());
+        };
+        SnapshotManifest::new(BaseSnapshot {
+            path: file_name(&output_path)?,
+            epoch: head.epoch(),
+            sha256,
+        })
+        .save(&manifest_path)?;
+        if let Some(previous) = current {
+            for path in previous.files().filter(|path| *path != output_path) {
+                remove_snapshot(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Exports a snapshot at `head` to `output_path`, or a diff snapshot on top of `base`.
+    /// Returns the hex encoded checksum of the snapshot, or `None` if the export was cancelled.
+    async fn export(
+        &self,
+        chain_export_guard: &ChainExportGuard,
+        head: &Tipset,
+        base: Option<&Tipset>,
+        output_path: &Path,
+    ) -> anyhow::Result<Option<String>> {
+        let db = self.db();
+        let recent_state_roots = self.config.recent_state_roots;
+        tracing::info!(
+            "exporting scheduled snapshot at epoch {} to {}",
+            head.epoch(),
+            output_path.display()
+        );
+        let tmp_path =
+            tempfile::TempPath::try_from_path(tmp_exporting_forest_car_path(output_path))?;
+        let export = async {
+            let seen = FileBackedCidHashSet::new(&self.chain_tmp_root)?;
+            let (_f3_snap_tmp_path, f3_snap, seen) = match base {
+                Some(base) => {
+                    let mut stream = stream_chain(
+                        db.shallow_clone(),
+                        base.shallow_clone().chain_owned(db.shallow_clone()),
+                        base.epoch() - recent_state_roots,
+                        seen,
+                    );
+                    while stream.try_next().await?.is_some() {}
+                    (None, None, stream.into_seen())
+                }
+                None => {
+                    let (f3_snap_tmp_path, f3_snap) =
+                        export_latest_f3_snapshot(output_path).await?;
+                    (Some(f3_snap_tmp_path), f3_snap, seen)
+                }
+            };
+            crate::chain::export_v2::<Sha256, _, _>(
+                db,
+                f3_snap,
+                head,
+                recent_state_roots,
+                tokio::fs::File::create(&tmp_path).await?,
+                ExportOptions {
+                    skip_checksum: false,
+                    include_receipts: false,
+                    include_events: false,
+                    include_tipset_keys: false,
+                    include_tipset_lookup: false,
+                    seen,
+                },
+            )
+            .await
+        };
+        let Some(result) = chain_export_guard.run_cancellable(export).await else {
+            tracing::warn!("scheduled snapshot export was cancelled");
+            return Ok(None);
+        };
+        let ExportResult { checksum, .. } = result?;
+        let checksum = checksum.context("snapshot export did not compute a checksum")?;
+        let output_path = output_path.to_owned();
+        spawn_blocking_with_timeout(ASYNC_OPS_TIMEOUT, move || {
+            tmp_path.persist(&output_path)?;
+            save_checksum(checksum, &output_path)
+        })
+        .await
+        .context("failed to persist the exported snapshot")?;
+        Ok(Some(hex::encode(checksum)))
+    }
+
+    fn load_manifest(&self) -> anyhow::Result<Option<SnapshotManifest>> {
+        let path = self.manifest_path();
+        path.is_file()
+            .then(|| SnapshotManifest::load(&path))
+            .transpose()
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.directory.join(format!(
+            "forest_snapshot_{}{SNAPSHOT_MANIFEST_FILE_EXTENSION}",
+            self.network()
+        ))
+    }
+
+    fn lite_filename(&self, epoch: ChainEpoch) -> String {
+        snapshot::filename(
+            snapshot::TrustedVendor::Forest,
+            self.network(),
+            self.epoch_date(epoch),
+            epoch,
+            true,
+        )
+    }
+
+    /// Same naming as the diff snapshots of `forest-tool archive sync-bucket`.
+    fn diff_filename(&self, from: ChainEpoch, to: ChainEpoch) -> String {
+        format!(
+            "forest_diff_{}_{}_height_{from}+{}.forest.car.zst",
+            self.network(),
+            self.epoch_date(to).format("%Y-%m-%d"),
+            to - from
+        )
+    }
+
+    fn epoch_date(&self, epoch: ChainEpoch) -> chrono::NaiveDate {
+        let genesis_timestamp = self
+            .chain_follower
+            .state_manager
+            .chain_store()
+            .genesis_block_header()
+            .timestamp as i64;
+        DateTime::from_timestamp(genesis_timestamp + epoch * EPOCH_DURATION_SECONDS, 0)
+            .unwrap_or_default()
+            .naive_utc()
+            .date()
+    }
+
+    fn network(&self) -> &NetworkChain {
+        &self.chain_follower.state_manager.chain_config().network
+    }
+
+    fn db(&self) -> &DbImpl {
+        self.chain_follower.state_manager.db()
+    }
+}
+
+/// Whether a snapshot is due at `head_epoch`, given the epoch and time of the last export.
+fn is_export_due(
+    config: &SnapshotExportConfig,
+    head_epoch: ChainEpoch,
+    last_export: Option<(ChainEpoch, SystemTime)>,
+    now: SystemTime,
+) -> bool {
+    let Some((last_epoch, last_time)) = last_export else {
+        return true;
+    };
+    if head_epoch <= last_epoch {
+        return false;
+    }
+    let (interval_epochs, interval_secs) = match (config.interval_epochs, config.interval_secs) {
+        (None, None) => (Some(EPOCHS_IN_DAY), None),
+        intervals => intervals,
+    };
+    interval_epochs.is_some_and(|interval| head_epoch - last_epoch >= interval)
+        || interval_secs.is_some_and(|interval| {
+            now.duration_since(last_time).unwrap_or_default() >= Duration::from_secs(interval)
+        })
+}
+
+/// Lite snapshots of `network` in `directory`, sorted by epoch.
+fn lite_snapshots(
+    directory: &Path,
+    network: &NetworkChain,
+) -> anyhow::Result<Vec<(ChainEpoch, PathBuf)>> {
+    let network = network.to_string();
+    let mut snapshots = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if let Some((chain, epoch)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(snapshot::chain_and_height)
+            && chain == network
+            && path.is_file()
+        {
+            snapshots.push((epoch, path));
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Deletes all but the `keep` most recent lite snapshots of `network` in `directory`.
+fn rotate_lite_snapshots(
+    directory: &Path,
+    network: &NetworkChain,
+    keep: usize,
+) -> anyhow::Result<()> {
+    for (_, path) in lite_snapshots(directory, network)?
+        .iter()
+        .rev()
+        .skip(keep.max(1))
+    {
+        remove_snapshot(path);
+    }
+    Ok(())
+}
+
+/// Deletes a snapshot and its checksum file.
+fn remove_snapshot(path: &Path) {
+    tracing::info!("deleting old snapshot {}", path.display());
+    if let Err(e) = std::fs::remove_file(path) {
+        tracing::warn!("failed to delete {}: {e}", path.display());
+    }
+    let checksum_path = forest_car_sha256sum_path(path);
+    if checksum_path.is_file()
+        && let Err(e) = std::fs::remove_file(&checksum_path)
+    {
+        tracing::warn!("failed to delete {}: {e}", checksum_path.display());
+    }
+}
+
+fn modified(path: &Path) -> anyhow::Result<SystemTime> {
+    Ok(std::fs::metadata(path)?.modified()?)
+}
+
+fn file_name(path: &Path) -> anyhow::Result<PathBuf> {
+    Ok(path
+        .file_name()
+        .with_context(|| format!("{} has no file name", path.display()))?
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_is_due_after_either_interval() {
+        let now = SystemTime::now();
+        let hour_ago = now - Duration::from_secs(3600);
+        let mut config = SnapshotExportConfig::default();
+        assert!(is_export_due(&config, 100, None, now));
+        assert!(!is_export_due(&config, 100, Some((100, hour_ago)), now));
+        assert!(!is_export_due(&config, 100, Some((50, hour_ago)), now));
+        assert!(is_export_due(
+            &config,
+            100 + EPOCHS_IN_DAY,
+            Some((100, hour_ago)),
+            now
+        ));
+
+        config.interval_secs = Some(1800);
+        assert!(is_export_due(&config, 101, Some((100, hour_ago)), now));
+        assert!(!is_export_due(&config, 101, Some((100, now)), now));
+        assert!(!is_export_due(&config, 100, Some((100, hour_ago)), now));
+
+        config.interval_epochs = Some(10);
+        assert!(is_export_due(&config, 110, Some((100, now)), now));
+        assert!(!is_export_due(&config, 109, Some((100, now)), now));
+    }
+
+    #[test]
+    fn rotation_keeps_the_most_recent_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let network = NetworkChain::Calibnet;
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let snapshots = [100, 300, 200, 400].map(|epoch| {
+            dir.path()
+                .join(snapshot::filename("forest", &network, date, epoch, true))
+        });
+        for path in &snapshots {
+            std::fs::write(path, b"yog-sothoth").unwrap();
+            std::fs::write(forest_car_sha256sum_path(path), b"").unwrap();
+        }
+        let mainnet = dir.path().join(snapshot::filename(
+            "forest",
+            NetworkChain::Mainnet,
+            date,
+            1,
+            true,
+        ));
+        std::fs::write(&mainnet, b"").unwrap();
+
+        rotate_lite_snapshots(dir.path(), &network, 2).unwrap();
+        let kept = lite_snapshots(dir.path(), &network).unwrap();
+        assert_eq!(
+            kept.iter().map(|(epoch, _)| *epoch).collect_vec(),
+            vec![300, 400]
+        );
+        assert!(!forest_car_sha256sum_path(&snapshots[0]).exists());
+        assert!(forest_car_sha256sum_path(&snapshots[1]).exists());
+        assert!(mainnet.exists());
+    }
+}
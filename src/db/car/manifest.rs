@@ -0,0 +1,517 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A snapshot manifest describes an incremental chain of snapshots: a base snapshot followed by
+//! diff snapshots, each one covering the epochs after the previous one. Loading all the listed
+//! archives together (e.g. with [`ManyCar`](super::ManyCar)) yields a store equivalent to a
+//! snapshot at the head of the last diff.
+//!
+//! Manifests are JSON files with the [`SNAPSHOT_MANIFEST_FILE_EXTENSION`] extension, for example:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "base": {
+//!     "path": "forest_snapshot_calibnet_2026-03-04_height_3510000.forest.car.zst",
+//!     "epoch": 3510000,
+//!     "sha256": "c0ffee..."
+//!   },
+//!   "diffs": [
+//!     {
+//!       "path": "forest_diff_calibnet_2026-03-05_height_3510000+3000.forest.car.zst",
+//!       "from": 3510000,
+//!       "to": 3513000,
+//!       "sha256": "decaf..."
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Relative paths are resolved against the directory of the manifest.
+//!
+//! Verifying a manifest hashes all its snapshots, so the sizes and modification times of the
+//! verified files are recorded next to it, with the [`VERIFIED_STAMP_FILE_EXTENSION`]
+//! extension, and the manifest is only verified again once they change.
+
+use super::{AnyCar, ManyCar};
+use crate::blocks::{Tipset, TipsetKey};
+use crate::prelude::*;
+use crate::utils::encoding::hex;
+use crate::utils::io::EitherMmapOrRandomAccessFile;
+use ahash::HashMap;
+use digest_io::IoWrapper;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const SNAPSHOT_MANIFEST_FILE_EXTENSION: &str = ".manifest.json";
+
+/// Extension appended to the path of a manifest to record that it was verified.
+pub const VERIFIED_STAMP_FILE_EXTENSION: &str = ".verified";
+
+const SNAPSHOT_MANIFEST_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotManifest {
+    pub version: u64,
+    pub base: BaseSnapshot,
+    /// Diff snapshots, in the order they have to be applied.
+    #[serde(default)]
+    pub diffs: Vec<DiffSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseSnapshot {
+    pub path: PathBuf,
+    /// Epoch of the heaviest tipset of the snapshot.
+    pub epoch: ChainEpoch,
+    /// Hex encoded SHA-256 checksum of the snapshot file.
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSnapshot {
+    pub path: PathBuf,
+    /// Head epoch of the snapshot the diff applies to.
+    pub from: ChainEpoch,
+    /// Epoch of the heaviest tipset of the diff snapshot.
+    pub to: ChainEpoch,
+    /// Hex encoded SHA-256 checksum of the snapshot file.
+    pub sha256: String,
+}
+
+impl SnapshotManifest {
+    pub fn is_manifest(path: &Path) -> bool {
+        path.to_str()
+            .is_some_and(|p| p.ends_with(SNAPSHOT_MANIFEST_FILE_EXTENSION))
+    }
+
+    /// A manifest listing only the `base` snapshot.
+    pub fn new(base: BaseSnapshot) -> Self {
+        Self {
+            version: SNAPSHOT_MANIFEST_VERSION,
+            base,
+            diffs: vec![],
+        }
+    }
+
+    /// Creates a manifest for `base` and the ordered `diffs`, to be saved at `manifest_path`.
+    /// Epochs and checksums are read from the snapshot files.
+    pub fn create(base: &Path, diffs: &[PathBuf], manifest_path: &Path) -> anyhow::Result<Self> {
+        let dir = manifest_dir(manifest_path);
+        let relative = |path: &Path| {
+            std::path::absolute(path)
+                .ok()
+                .and_then(|path| Some(path.strip_prefix(&dir).ok()?.to_owned()))
+                .unwrap_or_else(|| path.to_owned())
+        };
+        let base = BaseSnapshot {
+            path: relative(base),
+            epoch: heaviest_tipset(base)?.epoch(),
+            sha256: file_sha256(base)?,
+        };
+        let mut from = base.epoch;
+        let diffs = diffs
+            .iter()
+            .map(|diff| {
+                let to = heaviest_tipset(diff)?.epoch();
+                let entry = DiffSnapshot {
+                    path: relative(diff),
+                    from,
+                    to,
+                    sha256: file_sha256(diff)?,
+                };
+                from = to;
+                anyhow::Ok(entry)
+            })
+            .try_collect()?;
+        let manifest = Self {
+            version: SNAPSHOT_MANIFEST_VERSION,
+            base,
+            diffs,
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Loads and validates the manifest at `path`, resolving relative snapshot paths.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut manifest: Self = serde_json::from_slice(&std::fs::read(path)?)
+            .with_context(|| format!("invalid snapshot manifest {}", path.display()))?;
+        manifest.validate()?;
+        let dir = manifest_dir(path);
+        manifest.base.path = dir.join(&manifest.base.path);
+        for diff in &mut manifest.diffs {
+            diff.path = dir.join(&diff.path);
+        }
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Checks that the diffs form a chain starting at the base snapshot.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.version == SNAPSHOT_MANIFEST_VERSION,
+            "unsupported snapshot manifest version {}",
+            self.version
+        );
+        let mut head = self.base.epoch;
+        for diff in &self.diffs {
+            anyhow::ensure!(
+                diff.from == head,
+                "diff {} starts at epoch {}, expected {head}",
+                diff.path.display(),
+                diff.from,
+            );
+            anyhow::ensure!(
+                diff.to > diff.from,
+                "diff {} ends at epoch {}, which is not after its start epoch {}",
+                diff.path.display(),
+                diff.to,
+                diff.from,
+            );
+            head = diff.to;
+        }
+        Ok(())
+    }
+
+    /// Epoch of the heaviest tipset once all the diffs are applied.
+    pub fn head_epoch(&self) -> ChainEpoch {
+        self.diffs.last().map_or(self.base.epoch, |diff| diff.to)
+    }
+
+    /// Snapshot files, the base first.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.base.path.as_path()).chain(self.diffs.iter().map(|d| d.path.as_path()))
+    }
+
+    /// Verifies the checksums and head epochs of all the snapshots, and that each diff builds on
+    /// the head of the previous snapshot, with all the tipsets from the head of the last diff down
+    /// to the base snapshot available.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let entries = std::iter::once((&self.base.path, self.base.epoch, &self.base.sha256)).chain(
+            self.diffs
+                .iter()
+                .map(|diff| (&diff.path, diff.to, &diff.sha256)),
+        );
+        let mut heads = Vec::with_capacity(self.diffs.len() + 1);
+        for (path, epoch, sha256) in entries {
+            let actual = file_sha256(path)?;
+            anyhow::ensure!(
+                &actual == sha256,
+                "checksum mismatch for {}, expected: {sha256}, actual: {actual}",
+                path.display()
+            );
+            let head = heaviest_tipset(path)?;
+            anyhow::ensure!(
+                head.epoch() == epoch,
+                "unexpected head epoch of {}, expected: {epoch}, actual: {}",
+                path.display(),
+                head.epoch()
+            );
+            heads.push((path, head));
+        }
+
+        let store = ManyCar::try_from(self.files().map(Path::to_owned).collect_vec())?;
+        let chain: HashMap<ChainEpoch, TipsetKey> = store
+            .heaviest_tipset()?
+            .chain(&store)
+            .take_while(|ts| ts.epoch() >= self.base.epoch)
+            .map(|ts| (ts.epoch(), ts.key().clone()))
+            .collect();
+        let lowest = chain.keys().min().copied().unwrap_or(ChainEpoch::MAX);
+        anyhow::ensure!(
+            lowest == self.base.epoch,
+            "the tipsets are only available down to epoch {lowest}, the chain of diffs is broken"
+        );
+        for (path, head) in heads {
+            anyhow::ensure!(
+                chain.get(&head.epoch()) == Some(head.key()),
+                "the chain of diffs doesn't go through the head of {}",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotManifest {
+    /// Verifies the manifest loaded from `path` like [`Self::verify`], and records it as
+    /// verified.
+    pub fn verify_and_record(&self, path: &Path) -> anyhow::Result<()> {
+        self.verify()?;
+        let stamp_path = verified_stamp_path(path);
+        let result = self
+            .file_stamps(path)
+            .and_then(|stamps| Ok(std::fs::write(&stamp_path, serde_json::to_vec(&stamps)?)?));
+        if let Err(e) = result {
+            tracing::warn!(
+                "failed to record the verification of {} at {}: {e:#}",
+                path.display(),
+                stamp_path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Verifies the manifest loaded from `path`, unless it was verified with
+    /// [`Self::verify_and_record`] and neither it nor its snapshots changed since.
+    pub fn verify_once(&self, path: &Path) -> anyhow::Result<()> {
+        let recorded = std::fs::read(verified_stamp_path(path))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Vec<FileStamp>>(&bytes).ok());
+        if recorded.is_some() && recorded == self.file_stamps(path).ok() {
+            return Ok(());
+        }
+        self.verify_and_record(path)
+    }
+
+    /// Stamps of the manifest at `path` and of its snapshots.
+    fn file_stamps(&self, path: &Path) -> anyhow::Result<Vec<FileStamp>> {
+        std::iter::once(path)
+            .chain(self.files())
+            .map(FileStamp::new)
+            .collect()
+    }
+}
+
+/// Size and modification time of a file, to tell whether it changed.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl FileStamp {
+    fn new(path: &Path) -> anyhow::Result<Self> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("failed to read the metadata of {}", path.display()))?;
+        Ok(Self {
+            path: std::path::absolute(path)?,
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+fn verified_stamp_path(manifest_path: &Path) -> PathBuf {
+    let mut path = manifest_path.as_os_str().to_owned();
+    path.push(VERIFIED_STAMP_FILE_EXTENSION);
+    path.into()
+}
+
+fn manifest_dir(manifest_path: &Path) -> PathBuf {
+    std::path::absolute(manifest_path)
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_owned))
+        .unwrap_or_default()
+}
+
+fn heaviest_tipset(path: &Path) -> anyhow::Result<Tipset> {
+    let car = AnyCar::new(EitherMmapOrRandomAccessFile::open(path)?)
+        .with_context(|| format!("failed to load CAR at {}", path.display()))?;
+    car.heaviest_tipset()
+}
+
+fn file_sha256(path: &Path) -> anyhow::Result<String> {
+    let mut hasher = IoWrapper(Sha256::new());
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(hex::encode(hasher.0.finalize()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::blocks::{Chain4U, chain4u};
+    use crate::db::MemoryDB;
+    use crate::tool::subcommands::archive_cmd::do_export;
+
+    const SNAPSHOT: &str = "test-snapshots/chain4.forest.car.zst";
+
+    /// Snapshots of a chain of 6 tipsets, see [`export_diff_chain`].
+    pub struct DiffChain {
+        /// Base snapshot at epoch 4.
+        pub base: PathBuf,
+        /// Diff snapshot from epoch 4 to 6.
+        pub diff: PathBuf,
+        /// Base snapshot at epoch 4 of a fork of the chain.
+        pub fork_base: PathBuf,
+    }
+
+    /// Exports a base snapshot and a diff snapshot of a chain to `dir`. Callers must be
+    /// `#[serial_test::serial(chain_export)]`.
+    pub async fn export_diff_chain(dir: &Path) -> DiffChain {
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            genesis @ [_genesis_header]
+            -> [_b1] -> [_b2] -> [_b3]
+            -> [_b4] -> [_b5]
+            -> head @ [_b6]
+        };
+        chain4u! {
+            from [_b3] in c4u;
+            fork_head @ [_fork_b4]
+        };
+        let export = |root: &Tipset, path: &str, epoch, diff| {
+            let path = dir.join(path);
+            let (db, root, genesis) = (db.clone(), root.clone(), genesis.clone());
+            async move {
+                do_export(
+                    &db,
+                    root,
+                    Some(genesis),
+                    path.clone(),
+                    epoch,
+                    6,
+                    diff,
+                    diff.map(|_| 2),
+                    true,
+                )
+                .await
+                .unwrap();
+                path
+            }
+        };
+        DiffChain {
+            base: export(head, "base.forest.car.zst", Some(4), None).await,
+            diff: export(head, "diff.forest.car.zst", None, Some(4)).await,
+            fork_base: export(fork_head, "fork.forest.car.zst", None, None).await,
+        }
+    }
+
+    #[test]
+    fn diffs_must_be_contiguous() {
+        let diff = |from, to| DiffSnapshot {
+            path: "shoggoth.forest.car.zst".into(),
+            from,
+            to,
+            sha256: String::new(),
+        };
+        let mut manifest = SnapshotManifest {
+            version: SNAPSHOT_MANIFEST_VERSION,
+            base: BaseSnapshot {
+                path: "azathoth.forest.car.zst".into(),
+                epoch: 100,
+                sha256: String::new(),
+            },
+            diffs: vec![diff(100, 200), diff(200, 300)],
+        };
+        manifest.validate().unwrap();
+        assert_eq!(manifest.head_epoch(), 300);
+
+        manifest.diffs[1].from = 150;
+        manifest.validate().unwrap_err();
+        manifest.diffs[1] = diff(200, 200);
+        manifest.validate().unwrap_err();
+    }
+
+    #[test]
+    fn create_load_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("base.forest.car.zst");
+        std::fs::copy(SNAPSHOT, &snapshot).unwrap();
+        let manifest_path = dir
+            .path()
+            .join(format!("chain4{SNAPSHOT_MANIFEST_FILE_EXTENSION}"));
+        assert!(SnapshotManifest::is_manifest(&manifest_path));
+
+        let manifest = SnapshotManifest::create(&snapshot, &[], &manifest_path).unwrap();
+        assert_eq!(manifest.base.path, Path::new("base.forest.car.zst"));
+        manifest.save(&manifest_path).unwrap();
+
+        let mut loaded = SnapshotManifest::load(&manifest_path).unwrap();
+        assert_eq!(loaded.base.path, snapshot);
+        loaded.verify().unwrap();
+        let store = ManyCar::try_from(vec![manifest_path]).unwrap();
+        assert_eq!(
+            store.heaviest_tipset().unwrap().epoch(),
+            loaded.head_epoch()
+        );
+
+        loaded.base.sha256 = hex::encode([0; 32]);
+        loaded.verify().unwrap_err();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial(chain_export)]
+    async fn diffs_are_chained_and_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let DiffChain {
+            base,
+            diff,
+            fork_base,
+        } = export_diff_chain(dir.path()).await;
+
+        let manifest_path = dir
+            .path()
+            .join(format!("chain{SNAPSHOT_MANIFEST_FILE_EXTENSION}"));
+        let manifest =
+            SnapshotManifest::create(&base, std::slice::from_ref(&diff), &manifest_path).unwrap();
+        assert_eq!((manifest.base.epoch, manifest.head_epoch()), (4, 6));
+        manifest.save(&manifest_path).unwrap();
+        let mut loaded = SnapshotManifest::load(&manifest_path).unwrap();
+        loaded.verify().unwrap();
+        let store = ManyCar::try_from(vec![manifest_path]).unwrap();
+        let head = store.heaviest_tipset().unwrap();
+        assert_eq!(head.epoch(), 6);
+        assert_eq!(head.chain(&store).last().unwrap().epoch(), 0);
+
+        loaded.diffs[0].sha256 = hex::encode([0; 32]);
+        loaded.verify().unwrap_err();
+
+        // The diff doesn't build on the fork, even though the epochs line up.
+        let fork_manifest_path = dir
+            .path()
+            .join(format!("fork{SNAPSHOT_MANIFEST_FILE_EXTENSION}"));
+        let fork_manifest =
+            SnapshotManifest::create(&fork_base, &[diff], &fork_manifest_path).unwrap();
+        fork_manifest.save(&fork_manifest_path).unwrap();
+        SnapshotManifest::load(&fork_manifest_path)
+            .unwrap()
+            .verify()
+            .unwrap_err();
+        assert!(ManyCar::try_from(vec![fork_manifest_path]).is_err());
+    }
+
+    #[test]
+    fn verification_is_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("base.forest.car.zst");
+        std::fs::copy(SNAPSHOT, &snapshot).unwrap();
+        let manifest_path = dir
+            .path()
+            .join(format!("chain4{SNAPSHOT_MANIFEST_FILE_EXTENSION}"));
+        SnapshotManifest::create(&snapshot, &[], &manifest_path)
+            .unwrap()
+            .save(&manifest_path)
+            .unwrap();
+        let mut manifest = SnapshotManifest::load(&manifest_path).unwrap();
+        manifest.verify_once(&manifest_path).unwrap();
+        assert!(verified_stamp_path(&manifest_path).exists());
+
+        // A recorded verification is not done again...
+        manifest.base.sha256 = hex::encode([0; 32]);
+        manifest.verify_once(&manifest_path).unwrap();
+
+        // ...unless a snapshot changed.
+        std::fs::File::options()
+            .write(true)
+            .open(&snapshot)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        manifest.verify_once(&manifest_path).unwrap_err();
+    }
+}
@@ -8,7 +8,7 @@
 //!
 //! A single z-frame cache is shared between all read-only stores.
 
-use super::{AnyCar, ZstdFrameCache};
+use super::{AnyCar, SnapshotManifest, ZstdFrameCache};
 use crate::blocks::TipsetKey;
 use crate::db::parity_db::GarbageCollectableDb;
 use crate::db::{
@@ -145,7 +145,16 @@ impl<WriterT> ManyCar<WriterT> {
         Ok(())
     }
 
+    /// Loads a CAR file, or all the snapshots listed in a [`SnapshotManifest`] once they are
+    /// verified. A manifest is only verified again if its files changed since it last was.
     pub fn read_only_file(&self, file: impl AsRef<Path>) -> anyhow::Result<()> {
+        if SnapshotManifest::is_manifest(file.as_ref()) {
+            let manifest = SnapshotManifest::load(file.as_ref())?;
+            manifest.verify_once(file.as_ref()).with_context(|| {
+                format!("invalid snapshot manifest {}", file.as_ref().display())
+            })?;
+            return self.read_only_files(manifest.files().map(Path::to_owned));
+        }
         (|| {
             self.read_only(AnyCar::new(EitherMmapOrRandomAccessFile::open(
                 file.as_ref(),
@@ -280,8 +289,12 @@ impl<WriterT: SettingsStore> SettingsStore for ManyCar<WriterT> {
         SettingsStore::exists(self.writer(), key)
     }
 
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
-        SettingsStore::setting_keys(self.writer())
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        SettingsStore::delete_setting(self.writer(), key)
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        SettingsStore::setting_keys(self.writer(), prefix)
     }
 }
 
@@ -2,12 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod any;
 pub mod forest;
+mod manifest;
 mod many;
 pub mod plain;
 
 pub use any::AnyCar;
 pub use forest::ForestCar;
 use get_size2::GetSize as _;
+#[cfg(test)]
+pub(crate) use manifest::tests::{DiffChain, export_diff_chain};
+pub use manifest::{
+    BaseSnapshot, DiffSnapshot, SNAPSHOT_MANIFEST_FILE_EXTENSION, SnapshotManifest,
+};
 pub use many::{ManyCar, ReloadableManyCar};
 pub use plain::PlainCar;
 
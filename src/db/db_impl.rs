@@ -28,6 +28,7 @@ pub enum DbImpl {
     ManyCarParityDb(Arc<ManyCar<ParityDb>>),
     Memory(Arc<MemoryDB>),
     ReadOpsTrackingManyCarParityDb(Arc<ReadOpsTrackingStore<ManyCar<ParityDb>>>),
+    Overlay(Arc<OverlayBlockstore>),
 }
 
 impl ShallowClone for DbImpl {
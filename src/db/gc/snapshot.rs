@@ -36,6 +36,7 @@
 //! where V is the number of vertices(state-trees and messages) and E is the number of edges(block headers).
 //!
 
+use crate::beacon::BeaconEntryStore;
 use crate::blocks::{Tipset, TipsetKey};
 use crate::chain::{ChainStore, ExportOptions};
 use crate::chain_sync::ChainFollower;
@@ -378,12 +379,22 @@ impl SnapshotGarbageCollector {
                     .unwrap_or_default()
             );
 
-            // Prune blooms whose events are no longer retained by the lite snapshot.
+            // Prune blooms whose events are no longer retained by the lite snapshot, and the
+            // beacon entries of the same epochs.
             if let Ok(head) = db.heaviest_car_tipset() {
                 let cutoff = head.epoch() - self.recent_state_roots;
                 if let Err(e) = db.delete_blooms_before_height(cutoff) {
                     tracing::warn!("failed to prune stale block blooms: {e:#}");
                 }
+                let state_manager = &self.chain_follower.state_manager;
+                match BeaconEntryStore::new(db).prune(
+                    state_manager.beacon_schedule(),
+                    state_manager.get_network_version(cutoff),
+                    cutoff,
+                ) {
+                    Ok(pruned) => tracing::info!("pruned {pruned} stale beacon entries"),
+                    Err(e) => tracing::warn!("failed to prune stale beacon entries: {e:#}"),
+                }
             }
 
             // Reset chain head. Note that `self.exported_head_key` is guaranteed to be present,
@@ -97,8 +97,20 @@ impl SettingsStore for MemoryDB {
         Ok(self.settings_db.read().contains_key(key))
     }
 
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
-        Ok(self.settings_db.read().keys().cloned().collect_vec())
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        self.settings_db.write().remove(key);
+        Ok(())
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .settings_db
+            .read()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .sorted()
+            .collect_vec())
     }
 }
 
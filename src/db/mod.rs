@@ -10,6 +10,7 @@ mod either;
 pub mod gc;
 mod memory;
 pub mod migration;
+mod overlay;
 pub mod parity_db;
 pub mod parity_db_config;
 pub mod ttl;
@@ -20,6 +21,7 @@ pub use db_impl::DbImpl;
 pub use either::Either;
 pub use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 pub use memory::{IndexMapBlockstore, MemoryDB};
+pub use overlay::OverlayBlockstore;
 
 use crate::blocks::{Tipset, TipsetKey};
 use crate::prelude::*;
@@ -36,6 +38,14 @@ pub mod setting_keys {
     pub const HEAD_KEY: &str = "head";
     /// Key used to store the memory pool configuration in the settings store.
     pub const MPOOL_CONFIG_KEY: &str = "/mpool/config";
+    /// Key used to store the revoked JWT tokens and the JWT key rotation state in the settings store.
+    pub const JWT_REVOCATIONS_KEY: &str = "/auth/revocations";
+    /// Prefix of the keys used to store the blocks marked bad, by CID, with the reason of each,
+    /// in the settings store.
+    pub const BAD_BLOCKS_KEY_PREFIX: &str = "/sync/bad_blocks";
+    /// Prefix of the keys used to store the `drand` beacon entries, by network and round, in the
+    /// settings store.
+    pub const BEACON_ENTRIES_KEY_PREFIX: &str = "/beacon/entries";
 }
 
 /// Interface used to store and retrieve settings from the database.
@@ -54,9 +64,11 @@ pub trait SettingsStore {
     /// Returns `Ok(true)` if key exists in store.
     fn exists(&self, key: &str) -> anyhow::Result<bool>;
 
-    /// Returns all setting keys.
-    #[allow(dead_code)]
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>>;
+    /// Deletes `key`, if it exists in store.
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Returns the setting keys starting with `prefix`, in ascending order.
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
 }
 
 /// Extension trait for the [`SettingsStore`] trait. It is implemented for all types that implement
@@ -0,0 +1,301 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::blocks::TipsetKey;
+use crate::libp2p_bitswap::*;
+use crate::prelude::*;
+use crate::rpc::eth::types::EthHash;
+use crate::utils::multihash::MultihashCode;
+use ahash::HashSet;
+use parking_lot::Mutex;
+
+/// A database that keeps all writes in memory on top of a read-only base database.
+///
+/// Used to simulate messages against a modified state tree, e.g. with `stateOverride`s,
+/// without persisting the modified state. Blocks, settings, Ethereum mappings and blooms
+/// written or deleted through the overlay never reach the base.
+pub struct OverlayBlockstore {
+    base: DbImpl,
+    overlay: MemoryDB,
+    deleted: Mutex<Deleted>,
+}
+
+/// Keys deleted through the overlay, which are hidden from the base.
+#[derive(Default)]
+struct Deleted {
+    settings: HashSet<String>,
+    eth_mappings: HashSet<EthHash>,
+    tipset_keys: HashSet<ChainEpoch>,
+}
+
+impl OverlayBlockstore {
+    pub fn new(base: DbImpl) -> Self {
+        Self {
+            base,
+            overlay: MemoryDB::default(),
+            deleted: Default::default(),
+        }
+    }
+}
+
+impl Blockstore for OverlayBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        match Blockstore::get(&self.overlay, k)? {
+            Some(v) => Ok(Some(v)),
+            None => Blockstore::get(&self.base, k),
+        }
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        Ok(self.overlay.has(k)? || self.base.has(k)?)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.overlay.put_keyed(k, block)
+    }
+}
+
+impl BitswapStoreRead for OverlayBlockstore {
+    fn contains(&self, cid: &Cid) -> anyhow::Result<bool> {
+        Blockstore::has(self, cid)
+    }
+
+    fn get(&self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Blockstore::get(self, cid)
+    }
+}
+
+impl BitswapStoreReadWrite for OverlayBlockstore {
+    type Hashes = MultihashCode;
+
+    fn insert(&self, block: &Block64<Self::Hashes>) -> anyhow::Result<()> {
+        self.put_keyed(block.cid(), block.data())
+    }
+}
+
+impl SettingsStore for OverlayBlockstore {
+    fn read_bin(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(value) = SettingsStore::read_bin(&self.overlay, key)? {
+            return Ok(Some(value));
+        }
+        if self.deleted.lock().settings.contains(key) {
+            return Ok(None);
+        }
+        SettingsStore::read_bin(&self.base, key)
+    }
+
+    fn write_bin(&self, key: &str, value: &[u8]) -> anyhow::Result<()> {
+        self.deleted.lock().settings.remove(key);
+        SettingsStore::write_bin(&self.overlay, key, value)
+    }
+
+    fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(SettingsStore::exists(&self.overlay, key)?
+            || (!self.deleted.lock().settings.contains(key)
+                && SettingsStore::exists(&self.base, key)?))
+    }
+
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        self.deleted.lock().settings.insert(key.to_owned());
+        self.overlay.delete_setting(key)
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let deleted = &self.deleted.lock().settings;
+        Ok(self
+            .base
+            .setting_keys(prefix)?
+            .into_iter()
+            .filter(|key| !deleted.contains(key))
+            .chain(self.overlay.setting_keys(prefix)?)
+            .sorted()
+            .dedup()
+            .collect())
+    }
+}
+
+impl EthMappingsStore for OverlayBlockstore {
+    fn read_bin(&self, key: &EthHash) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(value) = EthMappingsStore::read_bin(&self.overlay, key)? {
+            return Ok(Some(value));
+        }
+        if self.deleted.lock().eth_mappings.contains(key) {
+            return Ok(None);
+        }
+        EthMappingsStore::read_bin(&self.base, key)
+    }
+
+    fn write_bin(&self, key: &EthHash, value: &[u8]) -> anyhow::Result<()> {
+        self.deleted.lock().eth_mappings.remove(key);
+        EthMappingsStore::write_bin(&self.overlay, key, value)
+    }
+
+    fn exists(&self, key: &EthHash) -> anyhow::Result<bool> {
+        Ok(EthMappingsStore::exists(&self.overlay, key)?
+            || (!self.deleted.lock().eth_mappings.contains(key)
+                && EthMappingsStore::exists(&self.base, key)?))
+    }
+
+    fn get_message_cids(&self) -> anyhow::Result<Vec<(Cid, u64)>> {
+        // The base only lists values, so hide the values of the keys deleted or overwritten
+        // through the overlay.
+        let hidden: HashSet<Vec<u8>> = {
+            let deleted = &self.deleted.lock().eth_mappings;
+            let overwritten = self
+                .overlay
+                .eth_mappings_db
+                .read()
+                .keys()
+                .copied()
+                .collect_vec();
+            deleted
+                .iter()
+                .chain(&overwritten)
+                .map(|key| EthMappingsStore::read_bin(&self.base, key))
+                .filter_map_ok(|value| value)
+                .try_collect()?
+        };
+        let hidden: HashSet<(Cid, u64)> = hidden
+            .iter()
+            .filter_map(|value| fvm_ipld_encoding::from_slice(value).ok())
+            .collect();
+        Ok(self
+            .base
+            .get_message_cids()?
+            .into_iter()
+            .filter(|it| !hidden.contains(it))
+            .chain(self.overlay.get_message_cids()?)
+            .collect())
+    }
+
+    fn delete(&self, keys: Vec<EthHash>) -> anyhow::Result<()> {
+        self.deleted
+            .lock()
+            .eth_mappings
+            .extend(keys.iter().cloned());
+        self.overlay.delete(keys)
+    }
+
+    fn tipset_key_by_epoch(&self, epoch: ChainEpoch) -> anyhow::Result<Option<TipsetKey>> {
+        if let Some(tsk) = self.overlay.tipset_key_by_epoch(epoch)? {
+            return Ok(Some(tsk));
+        }
+        if self.deleted.lock().tipset_keys.contains(&epoch) {
+            return Ok(None);
+        }
+        self.base.tipset_key_by_epoch(epoch)
+    }
+
+    fn delete_tipset_key_at_epoch(&self, epoch: ChainEpoch) -> anyhow::Result<()> {
+        self.deleted.lock().tipset_keys.insert(epoch);
+        self.overlay.delete_tipset_key_at_epoch(epoch)
+    }
+
+    fn set_tipset_key_at_epoch_raw(
+        &self,
+        epoch: ChainEpoch,
+        tsk: &TipsetKey,
+    ) -> anyhow::Result<()> {
+        self.deleted.lock().tipset_keys.remove(&epoch);
+        self.overlay.set_tipset_key_at_epoch_raw(epoch, tsk)
+    }
+}
+
+impl EthBlockBloomStore for OverlayBlockstore {
+    fn read_bloom(&self, key: &Cid) -> anyhow::Result<Option<[u8; BLOCK_BLOOM_LEN]>> {
+        match self.overlay.read_bloom(key)? {
+            Some(bloom) => Ok(Some(bloom)),
+            None => self.base.read_bloom(key),
+        }
+    }
+
+    fn write_bloom(
+        &self,
+        key: &Cid,
+        height: ChainEpoch,
+        bloom: &[u8; BLOCK_BLOOM_LEN],
+    ) -> anyhow::Result<()> {
+        self.overlay.write_bloom(key, height, bloom)
+    }
+
+    fn delete_blooms_before_height(&self, _height: ChainEpoch) -> anyhow::Result<()> {
+        anyhow::bail!("blooms can't be pruned through an overlay")
+    }
+}
+
+impl HeaviestTipsetKeyProvider for OverlayBlockstore {
+    fn heaviest_tipset_key(&self) -> anyhow::Result<Option<TipsetKey>> {
+        SettingsStoreExt::read_obj(self, setting_keys::HEAD_KEY)
+    }
+
+    fn set_heaviest_tipset_key(&self, tsk: &TipsetKey) -> anyhow::Result<()> {
+        SettingsStoreExt::write_obj(self, setting_keys::HEAD_KEY, tsk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::db::CborStoreExt as _;
+
+    #[test]
+    fn writes_stay_in_overlay() {
+        let base = Arc::new(MemoryDB::default());
+        let shared = base.put_cbor_default(&"Azathoth").unwrap();
+        let store = OverlayBlockstore::new(base.shallow_clone().into());
+        let written = store.put_cbor_default(&"Nyarlathotep").unwrap();
+
+        assert!(store.has(&shared).unwrap());
+        assert!(store.has(&written).unwrap());
+        assert!(!base.has(&written).unwrap());
+    }
+
+    #[test]
+    fn base_is_unchanged_by_a_simulation() {
+        let base = Arc::new(MemoryDB::default());
+        let (head, other) = (
+            TipsetKey::from(nunny::vec![Cid::default()]),
+            TipsetKey::from(nunny::vec![base.put_cbor_default(&"Dagon").unwrap()]),
+        );
+        let hash = EthHash::default();
+        base.set_heaviest_tipset_key(&head).unwrap();
+        SettingsStore::write_bin(&*base, "R'lyeh", b"sunken").unwrap();
+        EthMappingsStore::write_bin(&*base, &hash, b"Innsmouth").unwrap();
+        base.set_tipset_key_at_epoch_raw(42, &head).unwrap();
+
+        let store = OverlayBlockstore::new(base.shallow_clone().into());
+        store.set_heaviest_tipset_key(&other).unwrap();
+        SettingsStore::write_bin(&store, "Kadath", b"unknown").unwrap();
+        store.delete_setting("R'lyeh").unwrap();
+        store.delete(vec![hash]).unwrap();
+        store.set_tipset_key_at_epoch_raw(42, &other).unwrap();
+        store.set_tipset_key_at_epoch_raw(43, &other).unwrap();
+        store
+            .write_bloom(&Cid::default(), 42, &[0; BLOCK_BLOOM_LEN])
+            .unwrap();
+        assert!(store.delete_blooms_before_height(42).is_err());
+
+        // The overlay sees its own writes...
+        assert_eq!(store.heaviest_tipset_key().unwrap(), Some(other.clone()));
+        assert!(SettingsStore::exists(&store, "Kadath").unwrap());
+        assert_eq!(SettingsStore::read_bin(&store, "R'lyeh").unwrap(), None);
+        assert!(!EthMappingsStore::exists(&store, &hash).unwrap());
+        assert_eq!(store.tipset_key_by_epoch(42).unwrap(), Some(other));
+        assert!(store.read_bloom(&Cid::default()).unwrap().is_some());
+
+        // ...which never reach the base.
+        assert_eq!(base.heaviest_tipset_key().unwrap(), Some(head.clone()));
+        assert!(!SettingsStore::exists(&*base, "Kadath").unwrap());
+        assert_eq!(
+            SettingsStore::read_bin(&*base, "R'lyeh")
+                .unwrap()
+                .as_deref(),
+            Some(&b"sunken"[..])
+        );
+        assert!(EthMappingsStore::exists(&*base, &hash).unwrap());
+        assert_eq!(base.tipset_key_by_epoch(42).unwrap(), Some(head));
+        assert_eq!(base.tipset_key_by_epoch(43).unwrap(), None);
+        assert!(base.read_bloom(&Cid::default()).unwrap().is_none());
+    }
+}
@@ -179,10 +179,21 @@ impl SettingsStore for ParityDb {
             .context("error checking if key exists")
     }
 
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        let tx = [(DbColumn::Settings as u8, key.as_bytes(), None)];
+        self.db
+            .commit(tx)
+            .with_context(|| format!("error deleting from column {}", DbColumn::Settings))
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
         let mut iter = self.db.iter(DbColumn::Settings as u8)?;
+        iter.seek(prefix.as_bytes())?;
         let mut keys = vec![];
         while let Some((key, _)) = iter.next()? {
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
             keys.push(String::from_utf8(key)?);
         }
         Ok(keys)
@@ -126,8 +126,12 @@ impl SettingsStore for GarbageCollectableParityDb {
         SettingsStore::exists(&*self.db.read(), key)
     }
 
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
-        SettingsStore::setting_keys(&*self.db.read())
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        SettingsStore::delete_setting(&*self.db.read(), key)
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        SettingsStore::setting_keys(&*self.db.read(), prefix)
     }
 }
 
@@ -29,6 +29,18 @@ fn mem_db_does_not_exist() {
     subtests::does_not_exist(&db);
 }
 
+#[test]
+fn mem_db_delete_setting() {
+    let db = MemoryDB::default();
+    subtests::delete_setting(&db);
+}
+
+#[test]
+fn mem_db_setting_keys_with_prefix() {
+    let db = MemoryDB::default();
+    subtests::setting_keys_with_prefix(&db);
+}
+
 #[test]
 fn mem_write_read_obj() {
     let db = MemoryDB::default();
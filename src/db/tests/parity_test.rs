@@ -27,6 +27,18 @@ fn db_does_not_exist() {
     subtests::does_not_exist(&*db);
 }
 
+#[test]
+fn db_delete_setting() {
+    let db = TempParityDB::new();
+    subtests::delete_setting(&*db);
+}
+
+#[test]
+fn db_setting_keys_with_prefix() {
+    let db = TempParityDB::new();
+    subtests::setting_keys_with_prefix(&*db);
+}
+
 #[test]
 fn db_write_read_obj() {
     let db = TempParityDB::new();
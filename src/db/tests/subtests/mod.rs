@@ -72,6 +72,33 @@ where
     assert!(db.require_obj::<i32>(key).is_err());
 }
 
+pub fn delete_setting<DB>(db: &DB)
+where
+    DB: SettingsStore,
+{
+    let (key, other) = ("Cthulhu", "Dagon");
+    db.write_bin(key, &[1]).unwrap();
+    db.write_bin(other, &[2]).unwrap();
+    db.delete_setting(key).unwrap();
+    assert!(!db.exists(key).unwrap());
+    assert!(db.exists(other).unwrap());
+    // Deleting a missing key is fine.
+    db.delete_setting(key).unwrap();
+}
+
+pub fn setting_keys_with_prefix<DB>(db: &DB)
+where
+    DB: SettingsStore,
+{
+    for key in ["/b/2", "/a", "/b/1", "/c", "/b"] {
+        db.write_bin(key, &[1]).unwrap();
+    }
+    assert_eq!(db.setting_keys("/b/").unwrap(), ["/b/1", "/b/2"]);
+    assert_eq!(db.setting_keys("/b").unwrap(), ["/b", "/b/1", "/b/2"]);
+    assert_eq!(db.setting_keys("/d").unwrap(), Vec::<String>::new());
+    assert_eq!(db.setting_keys("").unwrap().len(), 5);
+}
+
 pub fn tipset_lookup_read_write_delete<DB>(db: &DB)
 where
     DB: EthMappingsStore,
@@ -176,7 +176,7 @@ async fn estimate(
         ..Default::default()
     };
     let gas = client
-        .call(EthEstimateGas::request((msg, Some(block)))?)
+        .call(EthEstimateGas::request((msg, Some(block), None))?)
         .await?;
     Ok(gas.0)
 }
@@ -0,0 +1,544 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Native verification of F3 finality certificates, without the Go sidecar.
+//!
+//! Ported from the `certs`, `gpbft` and `merkle` packages of
+//! [`go-f3`](https://github.com/filecoin-project/go-f3). A certificate is valid when the
+//! participants in its signer set hold a strong quorum of the power table of its instance, and
+//! their BDN-aggregated BLS signature over the `DECIDE` payload verifies. Applying the power table
+//! delta of the certificate then yields the power table of the next instance, whose CID must match
+//! the one committed to in the certificate.
+//!
+//! It backs `forest-cli f3 certs verify` and the verification of F3 snapshots. The daemon still
+//! follows F3 finality through the sidecar.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use crate::prelude::*;
+use crate::{
+    blocks::TipsetKey,
+    rpc::f3::{ECTipSet, F3PowerEntry, FinalityCertificate, PowerTableDelta, SupplementalData},
+    shim::fvm_shared_latest::bigint::bigint_ser,
+    utils::multihash::prelude::*,
+};
+use anyhow::{Context as _, bail, ensure};
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use fil_actors_shared::fvm_ipld_bitfield::BitField;
+use fvm_ipld_encoding::{DAG_CBOR, strict_bytes, tuple::*};
+use fvm_shared4::ActorID;
+use group::{Curve as _, Group as _, prime::PrimeCurveAffine as _};
+use num::{Signed as _, Zero as _};
+
+/// Domain separation tag of signed `GossipPBFT` payloads.
+const DOMAIN_SEPARATION_TAG: &str = "GPBFT";
+/// The phase of `GossipPBFT` that certificates are signed in.
+const DECIDE_PHASE: u8 = 5;
+/// Power is scaled to `[0, 0xffff]` before computing quorums.
+const MAX_SCALED_POWER: i64 = 0xffff;
+const CSUITE_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Verifies a chain of finality certificates, tracking the power table as it goes.
+pub struct CertificateVerifier {
+    network_name: String,
+    next_instance: u64,
+    power_table: Vec<F3PowerEntry>,
+    finalized: Option<ECTipSet>,
+}
+
+impl CertificateVerifier {
+    /// Starts verifying certificates from `initial_instance`, whose committee is `power_table`.
+    pub fn new(
+        network_name: impl Into<String>,
+        initial_instance: u64,
+        mut power_table: Vec<F3PowerEntry>,
+    ) -> Self {
+        power_table.sort();
+        Self {
+            network_name: network_name.into(),
+            next_instance: initial_instance,
+            power_table,
+            finalized: None,
+        }
+    }
+
+    /// The instance of the next certificate to verify.
+    pub fn next_instance(&self) -> u64 {
+        self.next_instance
+    }
+
+    /// The power table of the next instance.
+    pub fn power_table(&self) -> &[F3PowerEntry] {
+        &self.power_table
+    }
+
+    /// The head of the last verified certificate.
+    pub fn finalized(&self) -> Option<&ECTipSet> {
+        self.finalized.as_ref()
+    }
+
+    /// Verifies the next certificate of the chain, and advances to the following instance.
+    pub fn verify(&mut self, cert: &FinalityCertificate) -> anyhow::Result<()> {
+        ensure!(
+            cert.instance == self.next_instance,
+            "expected a certificate for instance {}, got instance {}",
+            self.next_instance,
+            cert.instance
+        );
+        validate_ec_chain(cert)?;
+        if let Some(finalized) = &self.finalized {
+            ensure!(
+                cert.chain_base() == finalized,
+                "the base of certificate {} is not the head of the previous certificate",
+                cert.instance
+            );
+        }
+        verify_signature(&self.network_name, &self.power_table, cert)
+            .with_context(|| format!("invalid signature on certificate {}", cert.instance))?;
+        let power_table = apply_power_table_delta(&self.power_table, &cert.power_table_delta)
+            .with_context(|| {
+                format!("invalid power table delta in certificate {}", cert.instance)
+            })?;
+        let power_table_cid = power_table_cid(&power_table)?;
+        ensure!(
+            power_table_cid == cert.supplemental_data.power_table,
+            "certificate {} commits to power table {}, but applying its delta yields {power_table_cid}",
+            cert.instance,
+            cert.supplemental_data.power_table,
+        );
+        self.power_table = power_table;
+        self.next_instance += 1;
+        self.finalized = Some(cert.chain_head().clone());
+        Ok(())
+    }
+}
+
+fn validate_ec_chain(cert: &FinalityCertificate) -> anyhow::Result<()> {
+    for (parent, child) in cert.ec_chain.iter().tuple_windows() {
+        ensure!(
+            child.epoch > parent.epoch,
+            "tipset epochs of certificate {} are not increasing",
+            cert.instance
+        );
+    }
+    for tipset in cert.ec_chain.iter() {
+        ensure!(
+            tipset.commitments.len() == 32,
+            "invalid tipset commitments length {}",
+            tipset.commitments.len()
+        );
+    }
+    ensure!(
+        cert.supplemental_data.commitments.len() == 32,
+        "invalid commitments length {}",
+        cert.supplemental_data.commitments.len()
+    );
+    Ok(())
+}
+
+/// Computes the CID of a power table, a `Blake2b-256` `DAG-CBOR` CID of its entries.
+pub fn power_table_cid(power_table: &[F3PowerEntry]) -> anyhow::Result<Cid> {
+    let entries = power_table
+        .iter()
+        .map(|entry| PowerEntryCbor {
+            id: entry.id,
+            power: entry.power.clone(),
+            pub_key: entry.pub_key.clone(),
+        })
+        .collect_vec();
+    let bytes = fvm_ipld_encoding::to_vec(&entries)?;
+    Ok(Cid::new_v1(
+        DAG_CBOR,
+        MultihashCode::Blake2b256.digest(&bytes),
+    ))
+}
+
+/// Applies a power table delta, returning the sorted power table of the next instance.
+pub fn apply_power_table_delta(
+    power_table: &[F3PowerEntry],
+    delta: &[PowerTableDelta],
+) -> anyhow::Result<Vec<F3PowerEntry>> {
+    let mut entries: ahash::HashMap<ActorID, F3PowerEntry> = power_table
+        .iter()
+        .map(|entry| (entry.id, entry.clone()))
+        .collect();
+    let mut last_id = None;
+    for d in delta {
+        // Deltas must be sorted, so that the delta of a certificate is canonical.
+        ensure!(
+            last_id.is_none_or(|last_id| d.participant_id > last_id),
+            "power table delta is not sorted by participant ID"
+        );
+        last_id = Some(d.participant_id);
+        ensure!(
+            !d.power_delta.is_zero() || !d.signing_key.is_empty(),
+            "empty power table delta for participant {}",
+            d.participant_id
+        );
+        let mut entry = match entries.remove(&d.participant_id) {
+            Some(entry) => {
+                ensure!(
+                    entry.pub_key != d.signing_key,
+                    "power table delta for participant {} includes an unchanged key",
+                    d.participant_id
+                );
+                entry
+            }
+            None => {
+                ensure!(
+                    d.power_delta.is_positive() && !d.signing_key.is_empty(),
+                    "new participant {} must have a key and positive power",
+                    d.participant_id
+                );
+                F3PowerEntry {
+                    id: d.participant_id,
+                    power: num::BigInt::zero(),
+                    pub_key: vec![],
+                }
+            }
+        };
+        entry.power += &d.power_delta;
+        if !d.signing_key.is_empty() {
+            entry.pub_key = d.signing_key.clone();
+        }
+        ensure!(
+            !entry.power.is_negative(),
+            "participant {} has negative power after applying the delta",
+            d.participant_id
+        );
+        if entry.power.is_positive() {
+            entries.insert(d.participant_id, entry);
+        }
+    }
+    Ok(entries.into_values().sorted().collect())
+}
+
+/// Scales the power of each participant to `[0, 0xffff]`, returning the scaled powers and their
+/// sum.
+fn scaled_power(power_table: &[F3PowerEntry]) -> anyhow::Result<(Vec<i64>, i64)> {
+    let total: num::BigInt = power_table.iter().map(|entry| &entry.power).sum();
+    ensure!(total.is_positive(), "power table has no power");
+    let scaled = power_table
+        .iter()
+        .map(|entry| i64::try_from(MAX_SCALED_POWER * &entry.power / &total))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scaled_total = scaled.iter().sum();
+    Ok((scaled, scaled_total))
+}
+
+/// Whether `part` of the scaled power is at least two thirds of `total`, as `go-f3` checks
+/// strong quorums.
+fn is_strong_quorum(part: i64, total: i64) -> bool {
+    3 * part >= 2 * total
+}
+
+/// Checks that the signers of a certificate hold a strong quorum of `power_table`, and that their
+/// aggregated signature over the `DECIDE` payload is valid.
+pub fn verify_signature(
+    network_name: &str,
+    power_table: &[F3PowerEntry],
+    cert: &FinalityCertificate,
+) -> anyhow::Result<()> {
+    let (scaled, scaled_total) = scaled_power(power_table)?;
+    let mut signers = vec![];
+    let mut signing_power = 0;
+    for i in cert.signers.iter() {
+        let i = usize::try_from(i)?;
+        let power = *scaled
+            .get(i)
+            .with_context(|| format!("signer index {i} is out of the power table bounds"))?;
+        ensure!(power > 0, "signer at index {i} has no power");
+        signing_power += power;
+        signers.push(i);
+    }
+    ensure!(
+        is_strong_quorum(signing_power, scaled_total),
+        "signers hold {signing_power} of {scaled_total} scaled power, which is not a strong quorum"
+    );
+
+    let public_keys = power_table
+        .iter()
+        .map(|entry| entry.pub_key.as_slice())
+        .collect_vec();
+    let aggregate = aggregate_public_keys(&public_keys, &signers)?;
+    let signature: [u8; 96] = cert
+        .signature
+        .as_slice()
+        .try_into()
+        .context("invalid signature length")?;
+    let signature = Option::<G2Affine>::from(G2Affine::from_compressed(&signature))
+        .context("invalid signature")?;
+    let message = G2Projective::hash_to_curve(&signing_payload(network_name, cert), CSUITE_G2, &[]);
+    ensure!(
+        blstrs::pairing(&aggregate.to_affine(), &message.to_affine())
+            == blstrs::pairing(&G1Affine::generator(), &signature),
+        "signature verification failed"
+    );
+    Ok(())
+}
+
+/// Aggregates the public keys at the `signers` indices with the `BDN` scheme, where each key is
+/// weighted by a coefficient derived from the whole committee, to prevent rogue-key attacks.
+pub(crate) fn aggregate_public_keys(
+    public_keys: &[&[u8]],
+    signers: &[usize],
+) -> anyhow::Result<G1Projective> {
+    let coefficients = bdn_coefficients(public_keys);
+    let mut aggregate = G1Projective::identity();
+    for &i in signers {
+        let (public_key, coefficient) = public_keys
+            .get(i)
+            .zip(coefficients.get(i))
+            .with_context(|| format!("signer index {i} is out of bounds"))?;
+        let public_key: [u8; 48] = (*public_key)
+            .try_into()
+            .with_context(|| format!("invalid public key length at index {i}"))?;
+        let public_key = Option::<G1Affine>::from(G1Affine::from_compressed(&public_key))
+            .with_context(|| format!("invalid public key at index {i}"))?;
+        aggregate += G1Projective::from(public_key) * (*coefficient + Scalar::from(1u64));
+    }
+    Ok(aggregate)
+}
+
+/// The 128-bit `BDN` coefficients of a committee, read from a `BLAKE2Xs` stream over its public
+/// keys, as `kyber`'s `bdn` package derives them. Each 16-byte chunk of the stream is a
+/// big-endian integer, like the `SetBytes` of `kyber`'s scalars reads it.
+pub(crate) fn bdn_coefficients(public_keys: &[&[u8]]) -> Vec<Scalar> {
+    const UNKNOWN_OUTPUT_LENGTH: u64 = 0xffff;
+    let mut root = blake2s_simd::Params::new();
+    root.hash_length(32)
+        .node_offset(UNKNOWN_OUTPUT_LENGTH << 32);
+    let mut state = root.to_state();
+    for public_key in public_keys {
+        state.update(public_key);
+    }
+    let root = state.finalize();
+
+    let mut output = Vec::with_capacity(public_keys.len() * 16 + 32);
+    for block in 0u64.. {
+        if output.len() >= public_keys.len() * 16 {
+            break;
+        }
+        let hash = blake2s_simd::Params::new()
+            .hash_length(32)
+            .fanout(0)
+            .max_depth(0)
+            .max_leaf_length(32)
+            .node_offset((UNKNOWN_OUTPUT_LENGTH << 32) | block)
+            .inner_hash_length(32)
+            .hash(root.as_bytes());
+        output.extend_from_slice(hash.as_bytes());
+    }
+    output
+        .chunks_exact(16)
+        .take(public_keys.len())
+        .map(|chunk| {
+            let mut bytes = [0; 32];
+            bytes.get_mut(16..).expect("32 > 16").copy_from_slice(chunk);
+            Option::from(Scalar::from_bytes_be(&bytes)).expect("128-bit values are valid scalars")
+        })
+        .collect()
+}
+
+/// The bytes signed by the committee to decide on the chain of a certificate.
+pub fn signing_payload(network_name: &str, cert: &FinalityCertificate) -> Vec<u8> {
+    let SupplementalData {
+        commitments,
+        power_table,
+    } = &cert.supplemental_data;
+    let mut buf = format!("{DOMAIN_SEPARATION_TAG}:{network_name}:").into_bytes();
+    buf.push(DECIDE_PHASE);
+    // The round is always zero for certificates.
+    buf.extend_from_slice(&0u64.to_be_bytes());
+    buf.extend_from_slice(&cert.instance.to_be_bytes());
+    buf.extend_from_slice(commitments);
+    let tipsets = cert.ec_chain.iter().map(tipset_for_signing).collect_vec();
+    buf.extend_from_slice(&merkle_root(&tipsets));
+    buf.extend_from_slice(&power_table.to_bytes());
+    buf
+}
+
+/// Serializes a tipset as it is committed to in the `ECChain` merkle tree.
+fn tipset_for_signing(tipset: &ECTipSet) -> Vec<u8> {
+    let mut buf = vec![];
+    write_cbor_byte_string(&mut buf, &tipset.key.bytes());
+    buf.extend_from_slice(&tipset.epoch.to_be_bytes());
+    buf.extend_from_slice(&tipset.commitments);
+    write_cbor_byte_string(&mut buf, &tipset.power_table.to_bytes());
+    buf
+}
+
+fn write_cbor_byte_string(buf: &mut Vec<u8>, bytes: &[u8]) {
+    const MAJOR_BYTES: u8 = 2 << 5;
+    let len = bytes.len();
+    if len < 24 {
+        buf.push(MAJOR_BYTES | len as u8);
+    } else if let Ok(len) = u8::try_from(len) {
+        buf.extend_from_slice(&[MAJOR_BYTES | 24, len]);
+    } else if let Ok(len) = u16::try_from(len) {
+        buf.push(MAJOR_BYTES | 25);
+        buf.extend_from_slice(&len.to_be_bytes());
+    } else {
+        buf.push(MAJOR_BYTES | 26);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    buf.extend_from_slice(bytes);
+}
+
+/// The root of the `Keccak-256` merkle tree over `values`, padded to a power of two with zero
+/// digests, with `0x00`/`0x01` prefixes to separate internal nodes from leaves.
+pub(crate) fn merkle_root(values: &[Vec<u8>]) -> [u8; 32] {
+    fn build(depth: u32, values: &[Vec<u8>]) -> [u8; 32] {
+        match values {
+            [] => [0; 32],
+            [value, ..] if depth == 0 => keccak_hash::keccak([&[1], value.as_slice()].concat()).0,
+            _ => {
+                let split = values.len().min(1 << (depth - 1));
+                let (left, right) = values.split_at(split);
+                keccak_hash::keccak(
+                    [&[0][..], &build(depth - 1, left), &build(depth - 1, right)].concat(),
+                )
+                .0
+            }
+        }
+    }
+    let depth = values
+        .len()
+        .saturating_sub(1)
+        .checked_ilog2()
+        .map_or(0, |log| log + 1);
+    build(depth, values)
+}
+
+/// Decodes a `CBOR`-encoded finality certificate, as found in F3 snapshots.
+pub fn decode_certificate(bytes: &[u8]) -> anyhow::Result<FinalityCertificate> {
+    let cert: CertificateCbor = fvm_ipld_encoding::from_slice(bytes)?;
+    cert.try_into()
+}
+
+/// Encodes a finality certificate to `CBOR`.
+#[cfg(test)]
+pub fn encode_certificate(cert: &FinalityCertificate) -> anyhow::Result<Vec<u8>> {
+    Ok(fvm_ipld_encoding::to_vec(&CertificateCbor::from(cert))?)
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct PowerEntryCbor {
+    id: ActorID,
+    #[serde(with = "bigint_ser")]
+    power: num::BigInt,
+    #[serde(with = "strict_bytes")]
+    pub_key: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct TipSetCbor {
+    epoch: i64,
+    #[serde(with = "strict_bytes")]
+    key: Vec<u8>,
+    power_table: Cid,
+    #[serde(with = "strict_bytes")]
+    commitments: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct SupplementalDataCbor {
+    #[serde(with = "strict_bytes")]
+    commitments: Vec<u8>,
+    power_table: Cid,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct PowerTableDeltaCbor {
+    participant_id: ActorID,
+    #[serde(with = "bigint_ser")]
+    power_delta: num::BigInt,
+    #[serde(with = "strict_bytes")]
+    signing_key: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct CertificateCbor {
+    instance: u64,
+    ec_chain: Vec<TipSetCbor>,
+    supplemental_data: SupplementalDataCbor,
+    signers: BitField,
+    #[serde(with = "strict_bytes")]
+    signature: Vec<u8>,
+    power_table_delta: Vec<PowerTableDeltaCbor>,
+}
+
+impl TryFrom<CertificateCbor> for FinalityCertificate {
+    type Error = anyhow::Error;
+
+    fn try_from(cert: CertificateCbor) -> anyhow::Result<Self> {
+        let ec_chain = cert
+            .ec_chain
+            .into_iter()
+            .map(|tipset| {
+                anyhow::Ok(ECTipSet {
+                    key: TipsetKey::from_bytes(tipset.key.into())?,
+                    epoch: tipset.epoch,
+                    power_table: tipset.power_table,
+                    commitments: tipset.commitments,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let Ok(ec_chain) = nunny::Vec::new(ec_chain) else {
+            bail!("certificate {} has an empty chain", cert.instance);
+        };
+        Ok(Self {
+            instance: cert.instance,
+            ec_chain,
+            supplemental_data: SupplementalData {
+                commitments: cert.supplemental_data.commitments,
+                power_table: cert.supplemental_data.power_table,
+            },
+            signers: cert.signers,
+            signature: cert.signature,
+            power_table_delta: cert
+                .power_table_delta
+                .into_iter()
+                .map(|d| PowerTableDelta {
+                    participant_id: d.participant_id,
+                    power_delta: d.power_delta,
+                    signing_key: d.signing_key,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl From<&FinalityCertificate> for CertificateCbor {
+    fn from(cert: &FinalityCertificate) -> Self {
+        Self {
+            instance: cert.instance,
+            ec_chain: cert
+                .ec_chain
+                .iter()
+                .map(|tipset| TipSetCbor {
+                    epoch: tipset.epoch,
+                    key: tipset.key.bytes().into(),
+                    power_table: tipset.power_table,
+                    commitments: tipset.commitments.clone(),
+                })
+                .collect(),
+            supplemental_data: SupplementalDataCbor {
+                commitments: cert.supplemental_data.commitments.clone(),
+                power_table: cert.supplemental_data.power_table,
+            },
+            signers: cert.signers.clone(),
+            signature: cert.signature.clone(),
+            power_table_delta: cert
+                .power_table_delta
+                .iter()
+                .map(|d| PowerTableDeltaCbor {
+                    participant_id: d.participant_id,
+                    power_delta: d.power_delta.clone(),
+                    signing_key: d.signing_key.clone(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::f3::snapshot::F3SnapshotHeader;
+use crate::utils::encoding::hex;
+use bls_signatures::{PrivateKey, Serialize as _};
+use std::str::FromStr as _;
+
+pub(crate) const NETWORK_NAME: &str = "r'lyeh";
+
+pub(crate) struct Committee {
+    keys: Vec<(ActorID, PrivateKey)>,
+}
+
+impl Committee {
+    pub(crate) fn new(powers: &[(ActorID, u64)]) -> (Self, Vec<F3PowerEntry>) {
+        let mut rng = crate::utils::rand::forest_rng();
+        let keys = powers
+            .iter()
+            .map(|(id, _)| (*id, PrivateKey::generate(&mut rng)))
+            .collect_vec();
+        let power_table = powers
+            .iter()
+            .zip(&keys)
+            .map(|((id, power), (_, key))| F3PowerEntry {
+                id: *id,
+                power: (*power).into(),
+                pub_key: key.public_key().as_bytes(),
+            })
+            .sorted()
+            .collect();
+        (Self { keys }, power_table)
+    }
+
+    /// Signs `cert` with the participants `signers`, and aggregates their signatures as `go-f3`
+    /// does.
+    pub(crate) fn sign(
+        &self,
+        power_table: &[F3PowerEntry],
+        cert: &mut FinalityCertificate,
+        signers: &[ActorID],
+    ) {
+        let payload = signing_payload(NETWORK_NAME, cert);
+        let public_keys = power_table
+            .iter()
+            .map(|entry| entry.pub_key.as_slice())
+            .collect_vec();
+        let coefficients = bdn_coefficients(&public_keys);
+        let mut signature = G2Projective::identity();
+        let mut indices = vec![];
+        for (i, entry) in power_table.iter().enumerate() {
+            if !signers.contains(&entry.id) {
+                continue;
+            }
+            let (_, key) = self.keys.iter().find(|(id, _)| *id == entry.id).unwrap();
+            let signed = G2Projective::from(G2Affine::from(key.sign(&payload)));
+            signature += signed * (coefficients[i] + Scalar::from(1u64));
+            indices.push(i as u64);
+        }
+        cert.signature = signature.to_affine().to_compressed().to_vec();
+        cert.signers = BitField::try_from_bits(indices).unwrap();
+    }
+}
+
+fn tipset(epoch: ChainEpoch, power_table: Cid) -> ECTipSet {
+    let cid = Cid::new_v1(
+        DAG_CBOR,
+        MultihashCode::Blake2b256.digest(&epoch.to_be_bytes()),
+    );
+    ECTipSet {
+        key: nunny::vec![cid].into(),
+        epoch,
+        power_table,
+        commitments: vec![0; 32],
+    }
+}
+
+fn certificate(
+    instance: u64,
+    base: ECTipSet,
+    len: i64,
+    power_table: &[F3PowerEntry],
+    delta: Vec<PowerTableDelta>,
+) -> FinalityCertificate {
+    let next_power_table = apply_power_table_delta(power_table, &delta).unwrap();
+    let next_power_table_cid = power_table_cid(&next_power_table).unwrap();
+    let mut ec_chain = nunny::vec![base.clone()];
+    for i in 1..=len {
+        ec_chain.push(tipset(base.epoch + i, base.power_table));
+    }
+    FinalityCertificate {
+        instance,
+        ec_chain,
+        supplemental_data: SupplementalData {
+            commitments: vec![0; 32],
+            power_table: next_power_table_cid,
+        },
+        signers: BitField::new(),
+        signature: vec![],
+        power_table_delta: delta,
+    }
+}
+
+#[test]
+fn verify_certificate_chain() {
+    let (committee, power_table) =
+        Committee::new(&[(1000, 40), (1001, 30), (1002, 20), (1003, 10)]);
+    let mut verifier = CertificateVerifier::new(NETWORK_NAME, 0, power_table.clone());
+    let base = tipset(100, power_table_cid(&power_table).unwrap());
+
+    // 90% of the power signs.
+    let mut first = certificate(0, base, 3, &power_table, vec![]);
+    committee.sign(&power_table, &mut first, &[1000, 1001, 1002]);
+    verifier.verify(&first).unwrap();
+    assert_eq!(verifier.next_instance(), 1);
+    assert_eq!(verifier.finalized(), Some(first.chain_head()));
+
+    // The next certificate removes a participant, and adds power to another.
+    let delta = vec![
+        PowerTableDelta {
+            participant_id: 1001,
+            power_delta: 5.into(),
+            signing_key: vec![],
+        },
+        PowerTableDelta {
+            participant_id: 1003,
+            power_delta: (-10).into(),
+            signing_key: vec![],
+        },
+    ];
+    let mut second = certificate(1, first.chain_head().clone(), 2, &power_table, delta);
+    // 70% of the power is a strong quorum.
+    committee.sign(&power_table, &mut second, &[1000, 1001]);
+    verifier.verify(&second).unwrap();
+    assert_eq!(verifier.power_table().len(), 3);
+
+    // 60% of the power is not.
+    let power_table = verifier.power_table().to_vec();
+    let mut third = certificate(2, second.chain_head().clone(), 1, &power_table, vec![]);
+    committee.sign(&power_table, &mut third, &[1000, 1002]);
+    let error = verifier.verify(&third).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("not a strong quorum"),
+        "{error:#}"
+    );
+    assert_eq!(verifier.next_instance(), 2);
+}
+
+#[test]
+fn reject_invalid_certificates() {
+    let (committee, power_table) =
+        Committee::new(&[(1000, 40), (1001, 30), (1002, 20), (1003, 10)]);
+    let base = tipset(100, power_table_cid(&power_table).unwrap());
+    let mut cert = certificate(0, base.clone(), 3, &power_table, vec![]);
+    committee.sign(&power_table, &mut cert, &[1000, 1001, 1002]);
+
+    // Signed for another network.
+    let mut verifier = CertificateVerifier::new("innsmouth", 0, power_table.clone());
+    verifier.verify(&cert).unwrap_err();
+
+    // Unexpected instance.
+    let mut verifier = CertificateVerifier::new(NETWORK_NAME, 1, power_table.clone());
+    verifier.verify(&cert).unwrap_err();
+
+    // Claims a signer that didn't sign.
+    let mut forged = cert.clone();
+    forged.signers = BitField::try_from_bits([0, 1, 2, 3]).unwrap();
+    let mut verifier = CertificateVerifier::new(NETWORK_NAME, 0, power_table.clone());
+    verifier.verify(&forged).unwrap_err();
+
+    // Commits to another power table.
+    let mut forged = cert.clone();
+    forged.supplemental_data.power_table = base.key.cid().unwrap();
+    committee.sign(&power_table, &mut forged, &[1000, 1001, 1002]);
+    let mut verifier = CertificateVerifier::new(NETWORK_NAME, 0, power_table.clone());
+    let error = verifier.verify(&forged).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("commits to power table"),
+        "{error:#}"
+    );
+
+    // A chain that doesn't extend the previous certificate.
+    let mut verifier = CertificateVerifier::new(NETWORK_NAME, 0, power_table.clone());
+    verifier.verify(&cert).unwrap();
+    let mut next = certificate(1, base, 1, &power_table, vec![]);
+    committee.sign(&power_table, &mut next, &[1000, 1001, 1002]);
+    verifier.verify(&next).unwrap_err();
+}
+
+#[test]
+fn power_table_delta_rules() {
+    let entry = |id, power: i64, key: u8| F3PowerEntry {
+        id,
+        power: power.into(),
+        pub_key: vec![key; 48],
+    };
+    let delta = |participant_id, power: i64, key: Option<u8>| PowerTableDelta {
+        participant_id,
+        power_delta: power.into(),
+        signing_key: key.map(|key| vec![key; 48]).unwrap_or_default(),
+    };
+    let power_table = vec![entry(1, 20, 1), entry(2, 10, 2)];
+
+    assert_eq!(
+        apply_power_table_delta(
+            &power_table,
+            &[
+                delta(1, -20, None),
+                delta(2, 0, Some(9)),
+                delta(3, 30, Some(3))
+            ]
+        )
+        .unwrap(),
+        vec![entry(3, 30, 3), entry(2, 10, 9)]
+    );
+    // Unsorted
+    apply_power_table_delta(&power_table, &[delta(2, 1, None), delta(1, 1, None)]).unwrap_err();
+    // Empty
+    apply_power_table_delta(&power_table, &[delta(1, 0, None)]).unwrap_err();
+    // Unchanged key
+    apply_power_table_delta(&power_table, &[delta(1, 0, Some(1))]).unwrap_err();
+    // New participant without a key
+    apply_power_table_delta(&power_table, &[delta(3, 1, None)]).unwrap_err();
+    // Negative power
+    apply_power_table_delta(&power_table, &[delta(2, -11, None)]).unwrap_err();
+}
+
+#[test]
+fn certificate_cbor_roundtrip() {
+    let (committee, power_table) = Committee::new(&[(1000, 1)]);
+    let base = tipset(7, power_table_cid(&power_table).unwrap());
+    let delta = vec![PowerTableDelta {
+        participant_id: 1001,
+        power_delta: 2.into(),
+        signing_key: vec![7; 48],
+    }];
+    let mut cert = certificate(3, base, 2, &power_table, delta);
+    committee.sign(&power_table, &mut cert, &[1000]);
+    let encoded = encode_certificate(&cert).unwrap();
+    assert_eq!(decode_certificate(&encoded).unwrap(), cert);
+}
+
+#[test]
+fn merkle_root_of_a_single_value_is_its_leaf_hash() {
+    assert_eq!(
+        merkle_root(&[b"dagon".to_vec()]),
+        keccak_hash::keccak(b"\x01dagon").0
+    );
+    // Missing leaves are zero digests.
+    let values = [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+    let leaf = |v: &[u8]| keccak_hash::keccak([&[1], v].concat()).0;
+    let node = |l: [u8; 32], r: [u8; 32]| keccak_hash::keccak([&[0][..], &l, &r].concat()).0;
+    assert_eq!(
+        merkle_root(&values),
+        node(node(leaf(b"a"), leaf(b"b")), node(leaf(b"c"), [0; 32]))
+    );
+}
+
+#[test]
+fn calibnet_initial_power_table_cid() {
+    // The initial power table of the calibnet F3 snapshot header in `snapshot/tests.rs`
+    let header: F3SnapshotHeader = fvm_ipld_encoding::from_slice(
+        &hex::decode(crate::f3::snapshot::tests::ENCODED_F3_SNAP_HEADER_HEX).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        power_table_cid(&header.initial_power_table).unwrap(),
+        Cid::from_str("bafy2bzaceab236vmmb3n4q4tkvua2n4dphcbzzxerxuey3mot4g3cov5j3r2c").unwrap()
+    );
+}
+
+#[test]
+fn two_thirds_is_a_strong_quorum() {
+    assert!(is_strong_quorum(2, 3));
+    assert!(is_strong_quorum(43690, 65535));
+    assert!(!is_strong_quorum(43689, 65535));
+}
+
+/// Verifies the latest calibnet certificates against the power table served by a public
+/// calibnet node, which checks the `BDN` coefficients and the signing payload against the
+/// signatures of `go-f3`.
+#[tokio::test]
+async fn verify_calibnet_certificates() {
+    use crate::rpc::{
+        Client, RpcMethodExt as _,
+        f3::{F3GetCertificate, F3GetF3PowerTableByInstance, F3GetLatestCertificate},
+    };
+
+    let client = Client::from_url("https://api.calibration.node.glif.io/".parse().unwrap());
+    let latest = F3GetLatestCertificate::call(&client, ()).await.unwrap();
+    let first = latest.instance.saturating_sub(2);
+    let power_table = F3GetF3PowerTableByInstance::call(&client, (first,))
+        .await
+        .unwrap();
+    let mut verifier = CertificateVerifier::new("calibrationnet2", first, power_table);
+    for instance in first..=latest.instance {
+        let cert = F3GetCertificate::call(&client, (instance,)).await.unwrap();
+        verifier.verify(&cert).unwrap();
+    }
+    assert_eq!(verifier.finalized(), Some(latest.chain_head()));
+}
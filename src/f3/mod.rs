@@ -13,11 +13,16 @@ use std::{
 #[cfg(all(f3sidecar, not(feature = "no-f3-sidecar")))]
 use go_ffi::*;
 
+pub mod certs;
 pub mod snapshot;
 
 use crate::prelude::*;
 
-use crate::{networks::ChainConfig, utils::misc::env::is_env_set_and_truthy};
+use crate::{
+    networks::{ChainConfig, NetworkChain},
+    utils::misc::env::is_env_set_and_truthy,
+};
+use serde::Deserialize;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct F3Options {
@@ -91,6 +96,27 @@ pub fn get_f3_sidecar_params(chain_config: &ChainConfig) -> F3Options {
     }
 }
 
+/// The fields of the F3 manifests embedded in the sidecar that native certificate verification
+/// needs.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct F3PredefinedManifest {
+    /// The network name signed payloads are bound to. It differs from the chain name.
+    pub network_name: String,
+    pub initial_instance: u64,
+}
+
+/// Returns the predefined F3 manifest of `chain`, as used by the sidecar.
+pub fn get_f3_predefined_manifest(chain: &NetworkChain) -> anyhow::Result<F3PredefinedManifest> {
+    let json = match chain {
+        NetworkChain::Mainnet => include_str!("../../f3-sidecar/f3manifest_mainnet.json"),
+        NetworkChain::Calibnet => include_str!("../../f3-sidecar/f3manifest_calibnet.json"),
+        NetworkChain::Butterflynet => include_str!("../../f3-sidecar/f3manifest_butterfly.json"),
+        NetworkChain::Devnet(_) => include_str!("../../f3-sidecar/f3manifest_2k.json"),
+    };
+    Ok(serde_json::from_str(json)?)
+}
+
 #[allow(unused_variables)]
 pub fn run_f3_sidecar_if_enabled(
     chain_config: &ChainConfig,
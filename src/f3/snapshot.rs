@@ -2,13 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
-use crate::{rpc::f3::F3PowerEntry, utils::multihash::MultihashCode};
+use crate::{
+    blocks::{Tipset, TipsetKey},
+    f3::certs::{CertificateVerifier, decode_certificate, power_table_cid},
+    rpc::f3::{ECTipSet, F3PowerEntry, FinalityCertificate},
+    shim::clock::ChainEpoch,
+    utils::multihash::MultihashCode,
+};
+use anyhow::Context as _;
 use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{IPLD_RAW, tuple::*};
 use integer_encoding::VarIntReader as _;
-use std::io::Read;
+use std::io::{BufRead, Read};
 
 pub fn get_f3_snapshot_cid(f3_data: &mut impl Read) -> anyhow::Result<Cid> {
     Ok(Cid::new_v1(
@@ -31,17 +39,55 @@ impl F3SnapshotHeader {
         // Reasonable upper bound for snapshot header size (100MiB)
         const MAX_HEADER_SIZE: usize = 100 * 1024 * 1024;
 
-        let data_len = f3_snapshot.read_varint::<usize>()?;
-        anyhow::ensure!(
-            data_len <= MAX_HEADER_SIZE,
-            "F3 snapshot header size {data_len} exceeds maximum allowed size {MAX_HEADER_SIZE}"
-        );
-        let mut data_bytes = vec![0; data_len];
-        f3_snapshot.read_exact(&mut data_bytes)?;
+        let data_bytes = read_block(f3_snapshot, MAX_HEADER_SIZE)
+            .context("failed to read F3 snapshot header")?;
         Ok(fvm_ipld_encoding::from_slice(&data_bytes)?)
     }
 }
 
+/// Reads a varint length-prefixed block of at most `max_size` bytes.
+fn read_block(f3_snapshot: &mut impl Read, max_size: usize) -> anyhow::Result<Vec<u8>> {
+    let data_len = f3_snapshot.read_varint::<usize>()?;
+    anyhow::ensure!(
+        data_len <= max_size,
+        "F3 snapshot block size {data_len} exceeds maximum allowed size {max_size}"
+    );
+    let mut data_bytes = vec![0; data_len];
+    f3_snapshot.read_exact(&mut data_bytes)?;
+    Ok(data_bytes)
+}
+
+/// Iterates over the finality certificates that follow the header of an F3 snapshot, in order.
+pub struct F3SnapshotCertificates<R> {
+    reader: R,
+}
+
+impl<R: BufRead> F3SnapshotCertificates<R> {
+    /// `reader` must be positioned right after the header, see
+    /// [`F3SnapshotHeader::decode_from_snapshot`].
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: BufRead> Iterator for F3SnapshotCertificates<R> {
+    type Item = anyhow::Result<FinalityCertificate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Certificates are small, this bounds allocations on corrupted input.
+        const MAX_CERTIFICATE_SIZE: usize = 16 * 1024 * 1024;
+
+        match self.reader.fill_buf() {
+            Ok([]) => None,
+            Ok(_) => Some(
+                read_block(&mut self.reader, MAX_CERTIFICATE_SIZE)
+                    .and_then(|bytes| decode_certificate(&bytes)),
+            ),
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
 impl std::fmt::Display for F3SnapshotHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f, "F3 snapshot version:        {}", self.version)?;
@@ -49,3 +95,206 @@ impl std::fmt::Display for F3SnapshotHeader {
         write!(f, "F3 snapshot last instance:  {}", self.latest_instance)
     }
 }
+
+/// The outcome of verifying the certificate chain of an F3 snapshot.
+#[derive(Debug, Clone)]
+pub struct F3SnapshotVerification {
+    pub first_instance: u64,
+    pub latest_instance: u64,
+    pub initial_power_table: Cid,
+    /// Whether the initial power table is the configured one, which anchors the chain of trust.
+    /// Otherwise, the certificates are only consistent with the power table in the header.
+    pub anchored: bool,
+    /// The head of the latest certificate, the latest tipset finalized by F3.
+    pub finalized: ECTipSet,
+}
+
+impl std::fmt::Display for F3SnapshotVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Verified F3 certificates:  {} to {}",
+            self.first_instance, self.latest_instance
+        )?;
+        writeln!(
+            f,
+            "Initial power table:       {} ({})",
+            self.initial_power_table,
+            if self.anchored {
+                "matches the network configuration"
+            } else {
+                "NOT checked against the network configuration"
+            }
+        )?;
+        write!(
+            f,
+            "Finalized tipset:          epoch {}, key {}",
+            self.finalized.epoch, self.finalized.key
+        )
+    }
+}
+
+/// Verifies every finality certificate of an F3 snapshot against the power table in its header.
+/// `expected_initial_power_table` is checked when the snapshot starts at the initial instance of
+/// the network.
+pub fn verify_f3_snapshot(
+    f3_snapshot: impl BufRead,
+    network_name: &str,
+    initial_instance: u64,
+    expected_initial_power_table: Option<Cid>,
+) -> anyhow::Result<F3SnapshotVerification> {
+    verify_f3_snapshot_with(
+        f3_snapshot,
+        network_name,
+        initial_instance,
+        expected_initial_power_table,
+        |_| {},
+    )
+}
+
+/// Like [`verify_f3_snapshot`], calling `inspect` with every verified certificate.
+fn verify_f3_snapshot_with(
+    mut f3_snapshot: impl BufRead,
+    network_name: &str,
+    initial_instance: u64,
+    expected_initial_power_table: Option<Cid>,
+    mut inspect: impl FnMut(&FinalityCertificate),
+) -> anyhow::Result<F3SnapshotVerification> {
+    let header = F3SnapshotHeader::decode_from_snapshot(&mut f3_snapshot)?;
+    let initial_power_table = power_table_cid(&header.initial_power_table)?;
+    let anchored = match expected_initial_power_table {
+        Some(expected) if header.first_instance == initial_instance => {
+            anyhow::ensure!(
+                initial_power_table == expected,
+                "the initial power table {initial_power_table} of the F3 snapshot is not the configured {expected}"
+            );
+            true
+        }
+        _ => false,
+    };
+    let mut verifier = CertificateVerifier::new(
+        network_name,
+        header.first_instance,
+        header.initial_power_table.clone(),
+    );
+    for cert in F3SnapshotCertificates::new(f3_snapshot) {
+        let cert = cert?;
+        verifier.verify(&cert)?;
+        inspect(&cert);
+    }
+    let finalized = verifier
+        .finalized()
+        .cloned()
+        .context("the F3 snapshot has no certificate")?;
+    let latest_instance = verifier.next_instance() - 1;
+    anyhow::ensure!(
+        latest_instance == header.latest_instance,
+        "the F3 snapshot header claims certificates up to instance {}, but the last one is {latest_instance}",
+        header.latest_instance
+    );
+    Ok(F3SnapshotVerification {
+        first_instance: header.first_instance,
+        latest_instance,
+        initial_power_table,
+        anchored,
+        finalized,
+    })
+}
+
+/// The outcome of checking a snapshot head against the F3 certificates of the snapshot.
+#[derive(Debug, Clone)]
+pub struct F3CheckpointVerification {
+    pub certificates: F3SnapshotVerification,
+    pub head: TipsetKey,
+    pub head_epoch: ChainEpoch,
+}
+
+impl F3CheckpointVerification {
+    /// The number of epochs of the snapshot past the latest tipset finalized by F3, which are only
+    /// secured by expected consensus.
+    pub fn uncertified_epochs(&self) -> ChainEpoch {
+        (self.head_epoch - self.certificates.finalized.epoch).max(0)
+    }
+}
+
+impl std::fmt::Display for F3CheckpointVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.certificates)?;
+        write!(
+            f,
+            "Snapshot head:             epoch {}, key {}, ",
+            self.head_epoch, self.head
+        )?;
+        match self.uncertified_epochs() {
+            0 => write!(f, "finalized by F3"),
+            n => write!(
+                f,
+                "{n} epoch(s) past the latest tipset finalized by F3, which it extends"
+            ),
+        }
+    }
+}
+
+/// Verifies the F3 certificate chain of a snapshot from the configured initial power table, and
+/// that the snapshot `head` is, or extends, a tipset certified by it. `store` must contain the
+/// snapshot chain.
+pub fn verify_f3_checkpoint(
+    f3_snapshot: impl BufRead,
+    network_name: &str,
+    initial_instance: u64,
+    initial_power_table: Cid,
+    head: &Tipset,
+    store: &impl Blockstore,
+) -> anyhow::Result<F3CheckpointVerification> {
+    let mut certified_head = None;
+    let certificates = verify_f3_snapshot_with(
+        f3_snapshot,
+        network_name,
+        initial_instance,
+        Some(initial_power_table),
+        |cert| {
+            if let Some(ts) = cert.ec_chain.iter().find(|ts| ts.epoch == head.epoch()) {
+                certified_head = Some(ts.key.clone());
+            }
+        },
+    )?;
+    anyhow::ensure!(
+        certificates.anchored,
+        "the F3 snapshot starts at instance {}, not at the initial instance {initial_instance}",
+        certificates.first_instance
+    );
+    let finalized = &certificates.finalized;
+    if head.epoch() <= finalized.epoch {
+        let certified_head = certified_head.with_context(|| {
+            format!(
+                "no tipset is finalized by F3 at the head epoch {}",
+                head.epoch()
+            )
+        })?;
+        anyhow::ensure!(
+            &certified_head == head.key(),
+            "the snapshot head {} at epoch {} is not the tipset {certified_head} finalized by F3",
+            head.key(),
+            head.epoch()
+        );
+    } else {
+        let ancestor = head
+            .clone()
+            .chain(store)
+            .find(|ts| ts.epoch() <= finalized.epoch)
+            .context("the snapshot doesn't reach the latest tipset finalized by F3")?;
+        anyhow::ensure!(
+            ancestor.key() == &finalized.key,
+            "the snapshot chain has {} at epoch {}, but F3 finalized {} at epoch {}",
+            ancestor.key(),
+            ancestor.epoch(),
+            finalized.key,
+            finalized.epoch
+        );
+    }
+    Ok(F3CheckpointVerification {
+        certificates,
+        head: head.key().clone(),
+        head_epoch: head.epoch(),
+    })
+}
@@ -5,6 +5,7 @@ use std::fmt::Debug;
 use crate::beacon::BeaconSchedule;
 use crate::blocks::{Block, Tipset};
 use crate::chain::{Error as ChainStoreError, Weight};
+use crate::chain_sync::BlockValidationCheck;
 use crate::prelude::*;
 use crate::state_manager::{Error as StateManagerError, StateManager};
 use anyhow::anyhow;
@@ -75,6 +76,17 @@ impl FilecoinConsensus {
     ) -> Result<(), NonEmpty<FilecoinConsensusError>> {
         validation::validate_block(state_manager, self.beacon.clone(), block).await
     }
+
+    /// Reports the outcome of every consensus rule checked by [`Self::validate_block`] for a
+    /// block on top of `base_tipset`.
+    pub async fn explain_block(
+        &self,
+        state_manager: StateManager,
+        block: Arc<Block>,
+        base_tipset: Tipset,
+    ) -> Vec<BlockValidationCheck> {
+        validation::explain_block(state_manager, self.beacon.clone(), block, base_tipset).await
+    }
 }
 
 impl Debug for FilecoinConsensus {
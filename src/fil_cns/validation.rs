@@ -3,10 +3,10 @@
 
 use std::collections::BTreeMap;
 
-use crate::beacon::{BeaconEntry, BeaconSchedule, IGNORE_DRAND};
-use crate::blocks::{Block, CachingBlockHeader, Tipset};
+use crate::beacon::{Beacon as _, BeaconEntry, BeaconSchedule, IGNORE_DRAND};
+use crate::blocks::{Block, CachingBlockHeader, ElectionProof, Tipset};
 use crate::chain::ChainStore;
-use crate::chain_sync::collect_errs;
+use crate::chain_sync::{BlockValidationCheck, collect_errs};
 use crate::networks::{ChainConfig, Height};
 use crate::prelude::*;
 use crate::shim::actors::PowerActorStateLoad as _;
@@ -172,6 +172,161 @@ pub(in crate::fil_cns) async fn validate_block(
     collect_errs(validations).await
 }
 
+/// Blocks are elected against the state at their lookback tipset and the latest beacon entry of
+/// their parent tipset.
+struct ElectionBase {
+    lookback_tipset: Tipset,
+    lookback_state: Cid,
+    prev_beacon: BeaconEntry,
+    work_addr: Address,
+}
+
+/// Checks the rules of [`validate_block`] one after another, without stopping at the first
+/// failure, and reports the outcome of each.
+pub(in crate::fil_cns) async fn explain_block(
+    state_manager: StateManager,
+    beacon_schedule: Arc<BeaconSchedule>,
+    block: Arc<Block>,
+    base_tipset: Tipset,
+) -> Vec<BlockValidationCheck> {
+    let chain_store = state_manager.chain_store().shallow_clone();
+    let header = block.header();
+    let election_base = async {
+        let (lookback_tipset, lookback_state) = ChainStore::get_lookback_tipset_for_round(
+            chain_store.chain_index().shallow_clone(),
+            state_manager.chain_config().shallow_clone(),
+            base_tipset.shallow_clone(),
+            header.epoch,
+        )
+        .await?;
+        let prev_beacon = chain_store
+            .chain_index()
+            .latest_beacon_entry(base_tipset.shallow_clone())?;
+        let work_addr = state_manager.get_miner_work_addr(lookback_state, &header.miner_address)?;
+        anyhow::Ok(ElectionBase {
+            lookback_tipset,
+            lookback_state,
+            prev_beacon,
+            work_addr,
+        })
+    }
+    .await
+    .map_err(|e| format!("failed to load the election base: {e:#}"));
+
+    // Proofs are expensive to verify
+    tokio::task::spawn_blocking(move || {
+        let header = block.header();
+        let chain_config = state_manager.chain_config();
+        let with_base = |check: &dyn Fn(&ElectionBase) -> Result<(), FilecoinConsensusError>| {
+            match &election_base {
+                Ok(base) => check(base).map_err(anyhow::Error::from),
+                Err(e) => Err(anyhow::anyhow!("{e}")),
+            }
+        };
+
+        let mut checks = vec![
+            BlockValidationCheck::new("consensus_sanity", block_sanity_checks(header)),
+            BlockValidationCheck::new(
+                "timestamp",
+                block_timestamp_checks(header, &base_tipset, chain_config),
+            )
+            .with_values(
+                header.timestamp,
+                target_timestamp(header.epoch, &base_tipset, chain_config),
+            ),
+            BlockValidationCheck::new(
+                "miner",
+                validate_miner(
+                    &state_manager,
+                    &header.miner_address,
+                    base_tipset.parent_state(),
+                ),
+            ),
+        ];
+
+        let mut election_proof = BlockValidationCheck::new(
+            "election_proof",
+            with_base(&|base| {
+                validate_winner_election(
+                    header,
+                    &base_tipset,
+                    &base.lookback_tipset,
+                    &base.lookback_state,
+                    &base.prev_beacon,
+                    &base.work_addr,
+                    &state_manager,
+                )
+            }),
+        );
+        if let (Some(proof), Ok(base)) = (&header.election_proof, &election_base)
+            && let Ok(win_count) = win_count(&state_manager, header, proof, &base.lookback_state)
+        {
+            election_proof = election_proof.with_values(proof.win_count, win_count);
+        }
+        checks.push(election_proof);
+
+        checks.push(BlockValidationCheck::new(
+            "ticket",
+            with_base(&|base| {
+                validate_ticket_election(
+                    header,
+                    &base_tipset,
+                    &base.prev_beacon,
+                    &base.work_addr,
+                    chain_config,
+                )
+            }),
+        ));
+
+        checks.push(BlockValidationCheck::new(
+            "winning_post",
+            with_base(&|base| {
+                verify_winning_post_proof(
+                    &state_manager,
+                    state_manager.get_network_version(base_tipset.epoch()),
+                    header,
+                    &base.prev_beacon,
+                    &base.lookback_state,
+                )
+            }),
+        ));
+
+        // Not checked by the validation either when drand is ignored
+        if !*IGNORE_DRAND {
+            let nv = state_manager.get_network_version(header.epoch);
+            let mut beacon_entries = BlockValidationCheck::new(
+                "beacon_entries",
+                with_base(&|base| {
+                    header
+                        .validate_block_drand(
+                            nv,
+                            &beacon_schedule,
+                            base_tipset.epoch(),
+                            &base.prev_beacon,
+                        )
+                        .map_err(|e| FilecoinConsensusError::BeaconValidation(e.to_string()))
+                }),
+            );
+            if let Some(last) = header.beacon_entries.last()
+                && let Ok(max_round) = beacon_schedule
+                    .beacon_for_epoch(header.epoch)
+                    .and_then(|(_, beacon)| beacon.max_beacon_round_for_epoch(nv, header.epoch))
+            {
+                beacon_entries = beacon_entries.with_values(last.round(), max_round);
+            }
+            checks.push(beacon_entries);
+        }
+        checks
+    })
+    .await
+    .unwrap_or_else(|e| {
+        vec![BlockValidationCheck::new(
+            "consensus",
+            Err(anyhow::anyhow!("consensus checks failed: {e}")),
+        )]
+    })
+}
+
 /// Checks optional values in header.
 ///
 /// In particular it looks for an election proof and a ticket,
@@ -202,10 +357,7 @@ fn block_timestamp_checks(
         );
     }
     // Timestamp checks
-    let block_delay = chain_config.block_delay_secs;
-    let nulls = header.epoch - (base_tipset.epoch() + 1);
-    let target_timestamp =
-        base_tipset.min_timestamp() + u64::from(block_delay) * (nulls + 1) as u64;
+    let target_timestamp = target_timestamp(header.epoch, base_tipset, chain_config);
     if target_timestamp != header.timestamp {
         return Err(FilecoinConsensusError::UnequalBlockTimestamps(
             header.timestamp,
@@ -215,6 +367,13 @@ fn block_timestamp_checks(
     Ok(())
 }
 
+/// Timestamp of a block at `epoch` on top of `base_tipset`.
+fn target_timestamp(epoch: ChainEpoch, base_tipset: &Tipset, chain_config: &ChainConfig) -> u64 {
+    let block_delay = chain_config.block_delay_secs;
+    let nulls = epoch - (base_tipset.epoch() + 1);
+    base_tipset.min_timestamp() + u64::from(block_delay) * (nulls + 1) as u64
+}
+
 // Check that the miner power can be loaded.
 // Doesn't check that the miner actually has any power.
 fn validate_miner(
@@ -276,12 +435,7 @@ fn validate_winner_election(
         return Err(FilecoinConsensusError::InvalidOrSlashedMiner);
     }
 
-    let (mpow, tpow) = state_manager
-        .get_power(lookback_state, Some(&header.miner_address))?
-        .ok_or(FilecoinConsensusError::MinerPowerNotAvailable)?;
-
-    let j = election_proof.compute_win_count(&mpow.quality_adj_power, &tpow.quality_adj_power);
-
+    let j = win_count(state_manager, header, election_proof, lookback_state)?;
     if election_proof.win_count != j {
         return Err(FilecoinConsensusError::MinerWinClaimsIncorrect(
             election_proof.win_count,
@@ -292,6 +446,19 @@ fn validate_winner_election(
     Ok(())
 }
 
+/// Number of wins of the block miner, given its power at the lookback state.
+fn win_count(
+    state_manager: &StateManager,
+    header: &CachingBlockHeader,
+    election_proof: &ElectionProof,
+    lookback_state: &Cid,
+) -> Result<i64, FilecoinConsensusError> {
+    let (mpow, tpow) = state_manager
+        .get_power(lookback_state, Some(&header.miner_address))?
+        .ok_or(FilecoinConsensusError::MinerPowerNotAvailable)?;
+    Ok(election_proof.compute_win_count(&mpow.quality_adj_power, &tpow.quality_adj_power))
+}
+
 fn validate_ticket_election(
     header: &CachingBlockHeader,
     base_tipset: &Tipset,
@@ -33,7 +33,7 @@ pub(crate) struct ForestState {
 /// All endpoints accept an optional `verbose` query parameter. If present, the response will include detailed information about the checks performed.
 pub(crate) async fn init_healthcheck_server(
     forest_state: ForestState,
-    tcp_listener: tokio::net::TcpListener,
+    listener: impl axum::serve::Listener<Addr = std::net::SocketAddr>,
 ) -> anyhow::Result<()> {
     let healthcheck_service = Router::new()
         .route("/healthz", get(endpoints::healthz))
@@ -41,7 +41,7 @@ pub(crate) async fn init_healthcheck_server(
         .route("/livez", get(endpoints::livez))
         .with_state(forest_state.into());
 
-    axum::serve(tcp_listener, healthcheck_service).await?;
+    axum::serve(listener, healthcheck_service).await?;
     Ok(())
 }
 
@@ -77,6 +77,7 @@ mod test {
                 chain_indexer: ChainIndexerConfig {
                     enable_indexer: true,
                     gc_retention_epochs: None,
+                    enable_trace_store: false,
                 },
                 client: Client {
                     healthcheck_address,
@@ -30,6 +30,8 @@ pub enum ChainExportKind {
     DiffSnapshot,
     /// A lite snapshot export performed by the automatic snapshot GC.
     SnapshotGc,
+    /// A snapshot export performed by the snapshot export scheduler.
+    ScheduledSnapshot,
     /// An index backfill requested via `Forest.IndexBackfill`. It holds the same single-flight
     /// slot as exports and the snapshot GC so that these heavy DB operations never overlap.
     IndexBackfill,
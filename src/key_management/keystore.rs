@@ -341,6 +341,24 @@ impl KeyStore {
         Ok(())
     }
 
+    /// Insert or replace a key/`KeyInfo` pair in the `KeyStore`, and return the replaced
+    /// `KeyInfo`. The `KeyStore` is left unchanged if it can't be persisted.
+    pub fn replace(&mut self, key: &str, key_info: KeyInfo) -> anyhow::Result<Option<KeyInfo>> {
+        let replaced = self.key_info.insert(key.to_string(), key_info);
+
+        if self.persistence.is_some()
+            && let Err(e) = self.flush()
+        {
+            match replaced {
+                Some(replaced) => self.key_info.insert(key.to_string(), replaced),
+                None => self.key_info.remove(key),
+            };
+            return Err(e);
+        }
+
+        Ok(replaced)
+    }
+
     /// Remove the key and corresponding `KeyInfo` from the `KeyStore`
     pub fn remove(&mut self, key: &str) -> anyhow::Result<KeyInfo> {
         let key_out = self.key_info.remove(key).ok_or(Error::KeyInfo)?;
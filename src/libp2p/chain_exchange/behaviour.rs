@@ -4,6 +4,7 @@
 use std::{
     num::NonZeroUsize,
     sync::{Arc, LazyLock},
+    time::{Duration, Instant},
 };
 
 use ahash::HashMap;
@@ -46,6 +47,10 @@ static MAX_CONCURRENT_INBOUND_CHAIN_EXCHANGE_REQUESTS_PER_PEER: LazyLock<NonZero
         )
     });
 
+/// Peers we have sent a chain exchange request to within this duration are considered peers we
+/// are syncing from, and get a larger response budget.
+const SYNC_PEER_TIMEOUT: Duration = Duration::from_mins(5);
+
 pub struct ChainExchangeBehaviour {
     inner: InnerBehaviour,
     response_channels: HashMap<
@@ -54,6 +59,9 @@ pub struct ChainExchangeBehaviour {
     >,
     request_limiter: Arc<Semaphore>,
     per_peer_limiters: HashMap<PeerId, Arc<Semaphore>>,
+    budgets: PeerBudgets,
+    /// Time of the last chain exchange request we sent to each peer.
+    sync_peers: HashMap<PeerId, Instant>,
 }
 
 impl ChainExchangeBehaviour {
@@ -68,6 +76,8 @@ impl ChainExchangeBehaviour {
                 MAX_CONCURRENT_INBOUND_CHAIN_EXCHANGE_REQUESTS.get(),
             )),
             per_peer_limiters: Default::default(),
+            budgets: Default::default(),
+            sync_peers: Default::default(),
         }
     }
 
@@ -89,9 +99,30 @@ impl ChainExchangeBehaviour {
             .ok()
     }
 
+    /// Reserves the budget of a response to `request` for `peer`, or returns `None` if the peer
+    /// has spent its budget. Peers we are syncing from get a larger budget.
+    pub fn try_reserve_budget(
+        &self,
+        peer: PeerId,
+        request: &ChainExchangeRequest,
+    ) -> Option<BudgetLease> {
+        let is_sync_peer = self
+            .sync_peers
+            .get(&peer)
+            .is_some_and(|last_request| last_request.elapsed() < SYNC_PEER_TIMEOUT);
+        self.budgets.reserve(
+            peer,
+            request.request_len,
+            MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES.get(),
+            is_sync_peer,
+        )
+    }
+
     fn on_peer_connection_closed(&mut self, peer: PeerId, remaining_established: usize) {
         if remaining_established == 0 {
             self.per_peer_limiters.remove(&peer);
+            self.sync_peers.remove(&peer);
+            self.budgets.prune();
         }
     }
 
@@ -103,6 +134,7 @@ impl ChainExchangeBehaviour {
     ) -> OutboundRequestId {
         let request_id = self.inner.send_request(peer, request);
         self.response_channels.insert(request_id, response_channel);
+        self.sync_peers.insert(*peer, Instant::now());
         self.track_metrics();
         request_id
     }
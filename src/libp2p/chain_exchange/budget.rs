@@ -0,0 +1,278 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Per-peer budgets for the chain exchange responses we serve. Every peer may be served a number
+//! of tipsets and bytes per [`BUDGET_WINDOW`]. Peers we are syncing from get
+//! [`SYNC_PEER_BUDGET_MULTIPLIER`] times more.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use ahash::HashMap;
+use libp2p::PeerId;
+use nonzero_ext::nonzero;
+use parking_lot::Mutex;
+
+use crate::utils::misc::env::env_or_default_logged;
+
+/// Length of the window the per-peer budgets are accounted over.
+pub const BUDGET_WINDOW: Duration = Duration::from_mins(1);
+
+/// Budget multiplier of the peers we are syncing from.
+pub const SYNC_PEER_BUDGET_MULTIPLIER: usize = 4;
+
+/// Number of tipsets served to a single peer per [`BUDGET_WINDOW`].
+static CHAIN_EXCHANGE_PEER_TIPSET_BUDGET: LazyLock<NonZeroUsize> = LazyLock::new(|| {
+    env_or_default_logged(
+        "FOREST_CHAIN_EXCHANGE_PEER_TIPSET_BUDGET",
+        nonzero!(2_000_usize),
+    )
+});
+
+/// Encoded bytes served to a single peer per [`BUDGET_WINDOW`].
+static CHAIN_EXCHANGE_PEER_BYTE_BUDGET: LazyLock<NonZeroUsize> = LazyLock::new(|| {
+    env_or_default_logged(
+        "FOREST_CHAIN_EXCHANGE_PEER_BYTE_BUDGET",
+        nonzero!(100 * 1024 * 1024_usize),
+    )
+});
+
+/// Upper bounds of a chain exchange response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseLimits {
+    pub max_tipsets: u64,
+    pub max_bytes: usize,
+}
+
+/// Tipsets and bytes a peer may be served per window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Budget {
+    tipsets: u64,
+    bytes: usize,
+}
+
+impl Budget {
+    fn from_env() -> Self {
+        Self {
+            tipsets: CHAIN_EXCHANGE_PEER_TIPSET_BUDGET.get() as u64,
+            bytes: CHAIN_EXCHANGE_PEER_BYTE_BUDGET.get(),
+        }
+    }
+}
+
+/// What a peer was served in the current window.
+#[derive(Debug)]
+struct PeerUsage {
+    window_start: Instant,
+    tipsets: u64,
+    bytes: usize,
+}
+
+/// Tracks what every peer was served in the current window.
+#[derive(Debug, Clone)]
+pub struct PeerBudgets {
+    budget: Budget,
+    usage: Arc<Mutex<HashMap<PeerId, PeerUsage>>>,
+}
+
+impl Default for PeerBudgets {
+    fn default() -> Self {
+        Self::new(Budget::from_env())
+    }
+}
+
+impl PeerBudgets {
+    fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            usage: Default::default(),
+        }
+    }
+
+    /// Reserves the budget of a response to `request_len` tipsets of at most `max_bytes` for
+    /// `peer`, or returns `None` if the peer's budget for the window is spent.
+    pub fn reserve(
+        &self,
+        peer: PeerId,
+        request_len: u64,
+        max_bytes: usize,
+        is_sync_peer: bool,
+    ) -> Option<BudgetLease> {
+        self.reserve_at(peer, request_len, max_bytes, is_sync_peer, Instant::now())
+    }
+
+    fn reserve_at(
+        &self,
+        peer: PeerId,
+        request_len: u64,
+        max_bytes: usize,
+        is_sync_peer: bool,
+        now: Instant,
+    ) -> Option<BudgetLease> {
+        let multiplier = if is_sync_peer {
+            SYNC_PEER_BUDGET_MULTIPLIER
+        } else {
+            1
+        };
+        let mut usage = self.usage.lock();
+        let usage = usage.entry(peer).or_insert_with(|| PeerUsage {
+            window_start: now,
+            tipsets: 0,
+            bytes: 0,
+        });
+        if now.duration_since(usage.window_start) >= BUDGET_WINDOW {
+            *usage = PeerUsage {
+                window_start: now,
+                tipsets: 0,
+                bytes: 0,
+            };
+        }
+        let tipsets = (self.budget.tipsets * multiplier as u64)
+            .saturating_sub(usage.tipsets)
+            .min(request_len);
+        let bytes = (self.budget.bytes * multiplier)
+            .saturating_sub(usage.bytes)
+            .min(max_bytes);
+        if tipsets == 0 || bytes == 0 {
+            return None;
+        }
+        usage.tipsets += tipsets;
+        usage.bytes += bytes;
+        Some(BudgetLease {
+            budgets: self.clone(),
+            peer,
+            limits: ResponseLimits {
+                max_tipsets: tipsets,
+                max_bytes: bytes,
+            },
+        })
+    }
+
+    /// Forgets the usage of the peers whose window is over. Usage is kept across reconnections
+    /// until then, so that reconnecting doesn't renew a budget.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        self.usage
+            .lock()
+            .retain(|_, usage| now.duration_since(usage.window_start) < BUDGET_WINDOW);
+    }
+}
+
+/// Budget reserved for a response. The part left unused is given back with
+/// [`BudgetLease::settle`].
+#[derive(Debug)]
+pub struct BudgetLease {
+    budgets: PeerBudgets,
+    peer: PeerId,
+    limits: ResponseLimits,
+}
+
+impl BudgetLease {
+    /// Limits the response must fit in.
+    pub fn limits(&self) -> ResponseLimits {
+        self.limits
+    }
+
+    /// Replaces the reserved budget with the one used by a response of `tipsets` tipsets and
+    /// `bytes` bytes. The latter may exceed the reservation, as the first tipset of a response is
+    /// always included.
+    pub fn settle(self, tipsets: u64, bytes: usize) {
+        if let Some(usage) = self.budgets.usage.lock().get_mut(&self.peer) {
+            usage.tipsets = (usage.tipsets + tipsets).saturating_sub(self.limits.max_tipsets);
+            usage.bytes = (usage.bytes + bytes).saturating_sub(self.limits.max_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Budget = Budget {
+        tipsets: 100,
+        bytes: 1000,
+    };
+
+    #[test]
+    fn budget_is_spent_and_renewed() {
+        let budgets = PeerBudgets::new(BUDGET);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        let lease = budgets.reserve_at(peer, 60, 800, false, now).unwrap();
+        assert_eq!(
+            lease.limits(),
+            ResponseLimits {
+                max_tipsets: 60,
+                max_bytes: 800
+            }
+        );
+        lease.settle(60, 800);
+        // Truncated to what is left of the budget.
+        let lease = budgets.reserve_at(peer, 60, 800, false, now).unwrap();
+        assert_eq!(
+            lease.limits(),
+            ResponseLimits {
+                max_tipsets: 40,
+                max_bytes: 200
+            }
+        );
+        lease.settle(40, 200);
+        assert!(budgets.reserve_at(peer, 1, 1, false, now).is_none());
+        // Other peers have their own budget.
+        assert!(
+            budgets
+                .reserve_at(PeerId::random(), 1, 1, false, now)
+                .is_some()
+        );
+        // The budget is renewed with the window.
+        assert!(
+            budgets
+                .reserve_at(peer, 1, 1, false, now + BUDGET_WINDOW)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn unused_budget_is_given_back() {
+        let budgets = PeerBudgets::new(BUDGET);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        budgets
+            .reserve_at(peer, 100, 1000, false, now)
+            .unwrap()
+            .settle(10, 100);
+        let lease = budgets.reserve_at(peer, 100, 1000, false, now).unwrap();
+        assert_eq!(
+            lease.limits(),
+            ResponseLimits {
+                max_tipsets: 90,
+                max_bytes: 900
+            }
+        );
+        // An oversized first tipset is charged in full.
+        lease.settle(1, 1000);
+        assert!(budgets.reserve_at(peer, 1, 1, false, now).is_none());
+    }
+
+    #[test]
+    fn sync_peers_get_a_larger_budget() {
+        let budgets = PeerBudgets::new(BUDGET);
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        let lease = budgets.reserve_at(peer, 1000, 10_000, true, now).unwrap();
+        assert_eq!(
+            lease.limits().max_tipsets,
+            BUDGET.tipsets * SYNC_PEER_BUDGET_MULTIPLIER as u64
+        );
+        assert_eq!(
+            lease.limits().max_bytes,
+            BUDGET.bytes * SYNC_PEER_BUDGET_MULTIPLIER
+        );
+    }
+}
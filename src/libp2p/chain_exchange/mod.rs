@@ -2,13 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod behaviour;
+mod budget;
 mod message;
 mod provider;
 use std::time::Duration;
 
 pub use behaviour::*;
 
-pub use self::{message::*, provider::*};
+pub use self::{budget::*, message::*, provider::*};
 use super::rpc::{CborRequestResponse, CodecConfig};
 
 /// Libp2p protocol name for `ChainExchange`.
@@ -7,12 +7,13 @@ use ahash::{HashMap, HashMapExt};
 use nonzero_ext::nonzero;
 
 use super::{
-    ChainExchangeRequest, ChainExchangeResponse, ChainExchangeResponseStatus, CompactedMessages,
-    TipsetBundle,
+    BudgetLease, ChainExchangeRequest, ChainExchangeResponse, ChainExchangeResponseStatus,
+    CompactedMessages, ResponseLimits, TipsetBundle,
 };
 use crate::{
     blocks::{Tipset, TipsetKey},
     chain::{ChainStore, Error as ChainError},
+    libp2p::metrics,
     prelude::*,
     utils::misc::env::env_or_default_logged,
 };
@@ -21,12 +22,13 @@ use crate::{
 /// Building stops as soon as the running encoded size would exceed this cap;
 /// the response is returned with status
 /// [`ChainExchangeResponseStatus::PartialResponse`].
-static MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES: LazyLock<NonZeroUsize> = LazyLock::new(|| {
-    env_or_default_logged(
-        "FOREST_MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES",
-        nonzero!(10 * 1024 * 1024_usize),
-    )
-});
+pub(super) static MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES: LazyLock<NonZeroUsize> =
+    LazyLock::new(|| {
+        env_or_default_logged(
+            "FOREST_MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES",
+            nonzero!(10 * 1024 * 1024_usize),
+        )
+    });
 
 /// `io::Write` that discards the bytes and only tracks how many were written.
 struct CountingSink(usize);
@@ -49,28 +51,61 @@ fn encoded_size<T: serde::Serialize>(value: &T) -> Result<usize, ChainError> {
 }
 
 /// Builds chain exchange response out of chain data.
+#[cfg(test)]
 pub fn make_chain_exchange_response(
     cs: &ChainStore,
     request: &ChainExchangeRequest,
 ) -> ChainExchangeResponse {
-    make_chain_exchange_response_with_cap(
+    let (response, _) = make_chain_exchange_response_with_limits(
         cs,
         request,
-        MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES.get(),
-    )
+        ResponseLimits {
+            max_tipsets: u64::MAX,
+            max_bytes: MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES.get(),
+        },
+    );
+    response
 }
 
-fn make_chain_exchange_response_with_cap(
+/// Builds chain exchange response out of chain data, within the budget reserved for the peer.
+/// The budget left unused is given back.
+pub fn serve_chain_exchange_request(
     cs: &ChainStore,
     request: &ChainExchangeRequest,
-    max_bytes: usize,
+    lease: BudgetLease,
 ) -> ChainExchangeResponse {
+    let limits = lease.limits();
+    let (response, bytes) = make_chain_exchange_response_with_limits(cs, request, limits);
+    let tipsets = response.chain.len() as u64;
+    lease.settle(tipsets, bytes);
+
+    metrics::CHAIN_EXCHANGE_SERVED_REQUESTS.inc();
+    metrics::CHAIN_EXCHANGE_SERVED_TIPSETS.inc_by(tipsets);
+    metrics::CHAIN_EXCHANGE_SERVED_BYTES.inc_by(bytes as u64);
+    let is_budget_truncated = limits.max_tipsets < request.request_len
+        || limits.max_bytes < MAX_OUTBOUND_CHAIN_EXCHANGE_RESPONSE_BYTES.get();
+    if response.status == ChainExchangeResponseStatus::PartialResponse && is_budget_truncated {
+        metrics::CHAIN_EXCHANGE_THROTTLED_REQUESTS
+            .get_or_create(&metrics::values::PEER_BUDGET_TRUNCATED)
+            .inc();
+    }
+    response
+}
+
+/// Builds chain exchange response out of chain data, of at most `limits.max_tipsets` tipsets and
+/// `limits.max_bytes` encoded bytes. Returns it with its encoded size.
+fn make_chain_exchange_response_with_limits(
+    cs: &ChainStore,
+    request: &ChainExchangeRequest,
+    limits: ResponseLimits,
+) -> (ChainExchangeResponse, usize) {
     if !request.is_options_valid() || !request.is_request_len_valid() {
-        return ChainExchangeResponse {
+        let response = ChainExchangeResponse {
             chain: Default::default(),
             status: ChainExchangeResponseStatus::BadRequest,
             message: format!("Invalid chain exchange request {request:?}"),
         };
+        return (response, 0);
     }
 
     let inner = move || {
@@ -80,18 +115,20 @@ fn make_chain_exchange_response_with_cap(
         {
             Some(tipset) => tipset,
             None => {
-                return Ok(ChainExchangeResponse {
+                let response = ChainExchangeResponse {
                     status: ChainExchangeResponseStatus::BlockNotFound,
                     chain: Default::default(),
                     message: "Start tipset was not found in the database".into(),
-                });
+                };
+                return Ok((response, 0));
             }
         };
 
-        let mut chain: Vec<TipsetBundle> = Vec::with_capacity(request.request_len as usize);
+        let len = request.request_len.min(limits.max_tipsets);
+        let mut chain: Vec<TipsetBundle> = Vec::with_capacity(len as usize);
         let mut accumulated: usize = 0;
 
-        for tipset in root.chain(cs.db()).take(request.request_len as _) {
+        for tipset in root.chain(cs.db()).take(len as _) {
             let mut tipset_bundle: TipsetBundle = TipsetBundle::default();
             if request.include_messages() {
                 tipset_bundle.messages = Some(compact_messages(cs.db(), &tipset)?);
@@ -103,14 +140,14 @@ fn make_chain_exchange_response_with_cap(
             let bundle_bytes = encoded_size(&tipset_bundle)?;
             // Always include the first bundle so a peer can make forward
             // progress even if a single tipset exceeds the cap.
-            if !chain.is_empty() && accumulated + bundle_bytes > max_bytes {
+            if !chain.is_empty() && accumulated + bundle_bytes > limits.max_bytes {
                 break;
             }
             accumulated += bundle_bytes;
             chain.push(tipset_bundle);
         }
 
-        anyhow::Ok(ChainExchangeResponse {
+        let response = ChainExchangeResponse {
             status: if request.request_len > chain.len() as u64 {
                 ChainExchangeResponseStatus::PartialResponse
             } else {
@@ -118,16 +155,20 @@ fn make_chain_exchange_response_with_cap(
             },
             chain,
             message: "Success".into(),
-        })
+        };
+        anyhow::Ok((response, accumulated))
     };
 
     match inner() {
         Ok(r) => r,
-        Err(e) => ChainExchangeResponse {
-            chain: Default::default(),
-            status: ChainExchangeResponseStatus::InternalError,
-            message: e.to_string(),
-        },
+        Err(e) => {
+            let response = ChainExchangeResponse {
+                chain: Default::default(),
+                status: ChainExchangeResponseStatus::InternalError,
+                message: e.to_string(),
+            };
+            (response, 0)
+        }
     }
 }
 
@@ -286,14 +327,41 @@ mod tests {
         let (cids, cs) = populate_chain_store().await;
 
         // A 1-byte cap exercises the always-include-first invariant.
-        let response = make_chain_exchange_response_with_cap(
+        let (response, _) = make_chain_exchange_response_with_limits(
             &cs,
             &ChainExchangeRequest {
                 start: cids,
                 request_len: 5,
                 options: HEADERS | MESSAGES,
             },
-            1,
+            ResponseLimits {
+                max_tipsets: u64::MAX,
+                max_bytes: 1,
+            },
+        );
+
+        assert_eq!(response.chain.len(), 1);
+        assert_eq!(
+            response.status,
+            ChainExchangeResponseStatus::PartialResponse
+        );
+    }
+
+    #[tokio::test]
+    async fn response_tipset_limit_truncates_to_partial() {
+        let (cids, cs) = populate_chain_store().await;
+
+        let (response, bytes) = make_chain_exchange_response_with_limits(
+            &cs,
+            &ChainExchangeRequest {
+                start: cids,
+                request_len: 2,
+                options: HEADERS | MESSAGES,
+            },
+            ResponseLimits {
+                max_tipsets: 1,
+                max_bytes: usize::MAX,
+            },
         );
 
         assert_eq!(response.chain.len(), 1);
@@ -301,6 +369,7 @@ mod tests {
             response.status,
             ChainExchangeResponseStatus::PartialResponse
         );
+        assert_eq!(bytes, encoded_size(&response.chain[0]).unwrap());
     }
 
     #[tokio::test]
@@ -1,7 +1,8 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use prometheus_client::metrics::{counter::Counter, gauge::Gauge};
+use crate::metrics::KindLabel;
+use prometheus_client::metrics::{counter::Counter, family::Family, gauge::Gauge};
 use std::sync::LazyLock;
 
 pub static PEER_FAILURE_TOTAL: LazyLock<Counter> = LazyLock::new(|| {
@@ -33,3 +34,57 @@ pub static BAD_PEERS: LazyLock<Gauge> = LazyLock::new(|| {
     );
     metric
 });
+
+pub static CHAIN_EXCHANGE_SERVED_REQUESTS: LazyLock<Counter> = LazyLock::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "chain_exchange_served_requests",
+        "Total number of chain exchange requests served to peers",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static CHAIN_EXCHANGE_SERVED_TIPSETS: LazyLock<Counter> = LazyLock::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "chain_exchange_served_tipsets",
+        "Total number of tipsets served to peers in chain exchange responses",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static CHAIN_EXCHANGE_SERVED_BYTES: LazyLock<Counter> = LazyLock::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "chain_exchange_served_bytes",
+        "Total encoded size of the chain exchange responses served to peers",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static CHAIN_EXCHANGE_THROTTLED_REQUESTS: LazyLock<Family<KindLabel, Counter>> =
+    LazyLock::new(|| {
+        let metric = Family::default();
+        crate::metrics::default_registry().register(
+            "chain_exchange_throttled_requests",
+            "Total number of inbound chain exchange requests rejected or truncated by rate limits",
+            metric.clone(),
+        );
+        metric
+    });
+
+pub mod values {
+    use crate::metrics::KindLabel;
+
+    /// Rejected, the peer has reached its cap of concurrent requests.
+    pub const PEER_CONCURRENCY: KindLabel = KindLabel::new("peer_concurrency");
+    /// Rejected, the node has reached its cap of concurrent requests.
+    pub const GLOBAL_CONCURRENCY: KindLabel = KindLabel::new("global_concurrency");
+    /// Rejected, the peer has spent its response budget.
+    pub const PEER_BUDGET_EXHAUSTED: KindLabel = KindLabel::new("peer_budget_exhausted");
+    /// Served partially, the response didn't fit in the peer's remaining budget.
+    pub const PEER_BUDGET_TRUNCATED: KindLabel = KindLabel::new("peer_budget_truncated");
+}
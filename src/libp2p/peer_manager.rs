@@ -25,6 +25,10 @@ const LOCAL_INV_ALPHA: u32 = 5;
 /// Global duration multiplier, affects duration delta change.
 const GLOBAL_INV_ALPHA: u32 = 20;
 
+/// Lower bound of the request cost used to estimate peer throughput, so that peers without
+/// latency samples yet don't get an infinite throughput.
+const MIN_PEER_COST_SECS: f64 = 0.001;
+
 #[derive(Debug, Default)]
 /// Contains info about the peer's head [Tipset], as well as the request stats.
 struct PeerInfo {
@@ -92,9 +96,9 @@ impl PeerManager {
         peers.full_peers.entry(*peer_id).or_default();
     }
 
-    /// Sort peers based on a score function with the success rate and latency
-    /// of requests.
-    pub(in crate::libp2p) fn sorted_peers(&self) -> Vec<PeerId> {
+    /// Scores peers by the cost of a request, in seconds, estimated from the success rate and
+    /// latency of their past requests. Returns them cheapest first.
+    fn scored_peers(&self) -> Vec<(PeerId, f64)> {
         let peer_lk = self.peers.read();
         let average_time = self.avg_global_time.read();
         let mut peers: Vec<_> = peer_lk
@@ -116,8 +120,27 @@ impl PeerManager {
 
         // Unstable sort because hashmap iter order doesn't need to be preserved.
         peers.sort_unstable_by(|(_, v1), (_, v2)| v1.total_cmp(v2));
+        peers
+    }
+
+    /// Sort peers based on a score function with the success rate and latency
+    /// of requests.
+    pub(in crate::libp2p) fn sorted_peers(&self) -> Vec<PeerId> {
+        self.scored_peers()
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .collect()
+    }
 
-        peers.into_iter().map(|(peer, _)| peer).collect()
+    /// Returns up to `n` of the best peers with their expected throughput, in requests per
+    /// second, best first. Peers whose requests have all failed are left out.
+    pub fn top_peers_with_throughput(&self, n: usize) -> Vec<(PeerId, f64)> {
+        self.scored_peers()
+            .into_iter()
+            .filter(|(_, cost)| cost.is_finite())
+            .take(n)
+            .map(|(peer, cost)| (peer, 1. / cost.max(MIN_PEER_COST_SECS)))
+            .collect()
     }
 
     /// Return shuffled slice of ordered peers from the peer manager. Ordering
@@ -371,4 +394,24 @@ mod tests {
         assert!(!is_crawler("lotus"));
         assert!(!is_crawler("venus"));
     }
+
+    #[test]
+    fn top_peers_with_throughput_favours_fast_peers() {
+        let manager = PeerManager::default();
+        let (fast, slow, failing) = (PeerId::random(), PeerId::random(), PeerId::random());
+        manager.log_success(&fast, Duration::from_millis(100));
+        manager.log_success(&slow, Duration::from_millis(400));
+        manager.log_failure(&failing, Duration::from_millis(100));
+
+        let peers = manager.top_peers_with_throughput(10);
+        assert_eq!(
+            peers.iter().map(|(peer, _)| *peer).collect::<Vec<_>>(),
+            vec![fast, slow]
+        );
+        let (_, fast_throughput) = peers[0];
+        let (_, slow_throughput) = peers[1];
+        assert!((fast_throughput / slow_throughput - 4.).abs() < 1e-9);
+
+        assert_eq!(manager.top_peers_with_throughput(1).len(), 1);
+    }
 }
@@ -5,7 +5,10 @@ use std::time::{Duration, UNIX_EPOCH};
 
 use crate::prelude::*;
 use crate::{blocks::GossipBlock, rpc::net::NetInfoResult};
-use crate::{chain::ChainStore, utils::encoding::from_slice_with_fallback};
+use crate::{
+    chain::{ChainStore, block_timeline::BlockStage},
+    utils::encoding::from_slice_with_fallback,
+};
 use crate::{
     libp2p_bitswap::{BitswapStoreReadWrite, request_manager::BitswapRequestManager},
     utils::flume::FlumeSenderExt as _,
@@ -35,7 +38,7 @@ use tracing::{debug, error, info, trace, warn};
 
 use super::{
     ForestBehaviour, ForestBehaviourEvent, Libp2pConfig,
-    chain_exchange::{ChainExchangeRequest, ChainExchangeResponse, make_chain_exchange_response},
+    chain_exchange::{ChainExchangeRequest, ChainExchangeResponse, serve_chain_exchange_request},
     discovery::{DerivedDiscoveryBehaviourEvent, PeerInfo},
 };
 use crate::libp2p::{
@@ -653,6 +656,7 @@ async fn handle_discovery_event(
 
 async fn handle_gossip_event(
     e: gossipsub::Event,
+    cs: &ChainStore,
     network_sender_out: &Sender<NetworkEvent>,
     pubsub_block_str: &str,
     pubsub_msg_str: &str,
@@ -669,6 +673,8 @@ async fn handle_gossip_event(
         if topic == pubsub_block_str {
             match from_slice_with_fallback::<GossipBlock>(&message) {
                 Ok(b) => {
+                    cs.block_timelines()
+                        .record(&b.header, BlockStage::GossipReceived);
                     emit_event(
                         network_sender_out,
                         NetworkEvent::PubsubMessage {
@@ -831,6 +837,9 @@ async fn handle_chain_exchange_event(
             } => {
                 let Some(per_peer_permit) = chain_exchange.try_acquire_peer_permit(peer) else {
                     debug!("Rejecting chain_exchange request from {peer}: per-peer cap reached");
+                    crate::libp2p::metrics::CHAIN_EXCHANGE_THROTTLED_REQUESTS
+                        .get_or_create(&crate::libp2p::metrics::values::PEER_CONCURRENCY)
+                        .inc();
                     let _ = chain_exchange.send_response(
                         channel,
                         ChainExchangeResponse::go_away("per-peer concurrent request cap reached"),
@@ -839,12 +848,26 @@ async fn handle_chain_exchange_event(
                 };
                 let Some(global_permit) = chain_exchange.try_acquire_request_permit() else {
                     debug!("Rejecting chain_exchange request from {peer}: global cap reached");
+                    crate::libp2p::metrics::CHAIN_EXCHANGE_THROTTLED_REQUESTS
+                        .get_or_create(&crate::libp2p::metrics::values::GLOBAL_CONCURRENCY)
+                        .inc();
                     let _ = chain_exchange.send_response(
                         channel,
                         ChainExchangeResponse::go_away("global concurrent request cap reached"),
                     );
                     return;
                 };
+                let Some(budget) = chain_exchange.try_reserve_budget(peer, &request) else {
+                    debug!("Rejecting chain_exchange request from {peer}: response budget spent");
+                    crate::libp2p::metrics::CHAIN_EXCHANGE_THROTTLED_REQUESTS
+                        .get_or_create(&crate::libp2p::metrics::values::PEER_BUDGET_EXHAUSTED)
+                        .inc();
+                    let _ = chain_exchange.send_response(
+                        channel,
+                        ChainExchangeResponse::go_away("per-peer response budget spent"),
+                    );
+                    return;
+                };
 
                 trace!(
                     "Received chain_exchange request (request_id:{request_id}, peer_id: {peer:?})",
@@ -865,7 +888,7 @@ async fn handle_chain_exchange_event(
                             .send_async((
                                 request_id,
                                 channel,
-                                make_chain_exchange_response(&db, &request),
+                                serve_chain_exchange_request(&db, &request, budget),
                             ))
                             .await
                         {
@@ -937,7 +960,7 @@ async fn handle_forest_behaviour_event(
             .await
         }
         ForestBehaviourEvent::Gossipsub(e) => {
-            handle_gossip_event(e, network_sender_out, pubsub_block_str, pubsub_msg_str).await
+            handle_gossip_event(e, db, network_sender_out, pubsub_block_str, pubsub_msg_str).await
         }
         ForestBehaviourEvent::Hello(rr_event) => {
             let behaviour_mut = swarm.behaviour_mut();
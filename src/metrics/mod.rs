@@ -18,7 +18,6 @@ use prometheus_client::{
 };
 use std::sync::{Arc, LazyLock};
 use std::{path::PathBuf, time::Instant};
-use tokio::net::TcpListener;
 use tower_http::compression::CompressionLayer;
 use tracing::warn;
 
@@ -55,6 +54,36 @@ pub static RPC_METHOD_FAILURE: LazyLock<Family<RpcMethodLabel, Counter>> = LazyL
     metric
 });
 
+pub static RPC_RATE_LIMITED: LazyLock<Family<RpcMethodLabel, Counter>> = LazyLock::new(|| {
+    let metric = Family::default();
+    DEFAULT_REGISTRY.write().register(
+        "rpc_rate_limited",
+        "Number of RPC calls rejected by the rate limiter",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static RPC_CACHE_HITS: LazyLock<Family<RpcMethodLabel, Counter>> = LazyLock::new(|| {
+    let metric = Family::default();
+    DEFAULT_REGISTRY.write().register(
+        "rpc_cache_hits",
+        "Number of cacheable RPC calls served from the response cache",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static RPC_CACHE_MISSES: LazyLock<Family<RpcMethodLabel, Counter>> = LazyLock::new(|| {
+    let metric = Family::default();
+    DEFAULT_REGISTRY.write().register(
+        "rpc_cache_misses",
+        "Number of cacheable RPC calls not found in the response cache",
+        metric.clone(),
+    );
+    metric
+});
+
 pub static RPC_IN_FLIGHT: LazyLock<Gauge> = LazyLock::new(|| {
     let metric = Gauge::default();
     DEFAULT_REGISTRY.write().register(
@@ -79,7 +108,7 @@ pub static RPC_METHOD_TIME: LazyLock<Family<RpcMethodLabel, Histogram>> = LazyLo
 });
 
 pub async fn init_prometheus<DB>(
-    prometheus_listener: TcpListener,
+    prometheus_listener: impl axum::serve::Listener<Addr = std::net::SocketAddr>,
     db_directory: PathBuf,
     db: Arc<DB>,
     chain_config: Arc<ChainConfig>,
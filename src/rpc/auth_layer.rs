@@ -1,11 +1,14 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::auth::{JWT_IDENTIFIER, verify_token};
-use crate::key_management::KeyStore;
+use crate::auth::{
+    Claims, TokenRevocations, TokenScope, is_token_still_valid, verify_token_signing_key,
+};
+use crate::key_management::{KeyInfo, KeyStore};
 use crate::prelude::*;
-use crate::rpc::error::implementation_defined_errors::INSUFFICIENT_PERMISSIONS;
+use crate::rpc::error::implementation_defined_errors::{INSUFFICIENT_PERMISSIONS, RATE_LIMITED};
 use crate::rpc::{CANCEL_METHOD_NAME, Permission, RpcMethod as _, chain};
+use crate::utils::rate_limit::TokenBucket;
 use ahash::HashMap;
 use futures::future::Either;
 use http::header::HeaderValue;
@@ -14,31 +17,57 @@ use jsonrpsee::core::middleware::{Batch, BatchEntry, BatchEntryErr, Notification
 use jsonrpsee::server::middleware::rpc::RpcServiceT;
 use jsonrpsee::types::Id;
 use jsonrpsee::types::{ErrorObject, ErrorObjectOwned, error::ErrorCode};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use quick_cache::sync::Cache;
+use std::convert::Infallible;
 use std::sync::LazyLock;
 use tower::Layer;
 use tracing::debug;
 
-static METHOD_NAME2REQUIRED_PERMISSION: LazyLock<HashMap<&str, Permission>> = LazyLock::new(|| {
+/// What it takes to invoke a method, keyed by both its name and its alias.
+#[derive(Debug, Clone, Copy)]
+struct MethodAccess {
+    /// The canonical method name, which token scopes are matched against.
+    name: &'static str,
+    required: Permission,
+}
+
+static METHOD_NAME2ACCESS: LazyLock<HashMap<&str, MethodAccess>> = LazyLock::new(|| {
     let mut access = HashMap::new();
 
     macro_rules! insert {
         ($ty:ty) => {
-            access.insert(<$ty>::NAME, <$ty>::PERMISSION);
+            let entry = MethodAccess {
+                name: <$ty>::NAME,
+                required: <$ty>::PERMISSION,
+            };
+            access.insert(<$ty>::NAME, entry);
 
             if let Some(alias) = <$ty>::NAME_ALIAS {
-                access.insert(alias, <$ty>::PERMISSION);
+                access.insert(alias, entry);
             }
         };
     }
     super::for_each_rpc_method!(insert);
 
-    access.insert(chain::CHAIN_NOTIFY, Permission::Read);
-    access.insert(CANCEL_METHOD_NAME, Permission::Read);
+    for name in [chain::CHAIN_NOTIFY, CANCEL_METHOD_NAME] {
+        access.insert(
+            name,
+            MethodAccess {
+                name,
+                required: Permission::Read,
+            },
+        );
+    }
 
     access
 });
 
+/// Request budgets of rate-limited tokens, shared by every connection authenticated with the
+/// same token.
+static TOKEN_RATE_LIMITERS: LazyLock<Cache<String, Arc<Mutex<TokenBucket>>>> =
+    LazyLock::new(|| Cache::new(10_000));
+
 /// The lowercase wire string for a permission, as it appears in a JWT `Allow`
 /// claim list and in Lotus's permission errors.
 fn permission_str(permission: Permission) -> &'static str {
@@ -55,17 +84,81 @@ fn is_allowed(required_by_method: Permission, claimed_by_user: &[String]) -> boo
     claimed_by_user.iter().any(|haystack| haystack == needle)
 }
 
+/// Authorization state of a connection, resolved from its token by [`resolve_claims`].
+#[derive(Debug, Default)]
+pub struct ConnectionClaims {
+    permissions: Vec<String>,
+    scope: TokenScope,
+    /// Present if the token carries a rate limit.
+    rate_limiter: Option<Arc<Mutex<TokenBucket>>>,
+    /// Present if the connection is authenticated with a token.
+    token: Option<TokenValidity>,
+}
+
+/// What it takes to re-check, on every call, that the token of a connection has neither been
+/// revoked nor had its signing key rotated out since the connection was set up.
+struct TokenValidity {
+    jti: Option<String>,
+    /// The key the token is signed with.
+    key: KeyInfo,
+    keystore: Arc<RwLock<KeyStore>>,
+    revocations: Arc<TokenRevocations>,
+}
+
+impl std::fmt::Debug for TokenValidity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Skip the key.
+        f.debug_struct("TokenValidity")
+            .field("jti", &self.jti)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenValidity {
+    fn is_valid(&self) -> bool {
+        is_token_still_valid(
+            self.jti.as_deref(),
+            &self.key,
+            &self.keystore.read(),
+            &self.revocations,
+        )
+    }
+}
+
+impl ConnectionClaims {
+    fn new(token: &str, claims: Claims) -> Self {
+        let rate_limiter = claims.scope.rate_limit.map(|limit| {
+            match TOKEN_RATE_LIMITERS.get_or_insert_with(token, || {
+                Ok::<_, Infallible>(Arc::new(Mutex::new(TokenBucket::per_minute(limit))))
+            }) {
+                Ok(limiter) => limiter,
+                Err(infallible) => match infallible {},
+            }
+        });
+        Self {
+            permissions: claims.allow,
+            scope: claims.scope,
+            rate_limiter,
+            token: None,
+        }
+    }
+
+    fn permissions(&self) -> &[String] {
+        &self.permissions
+    }
+}
+
 #[derive(Clone)]
 pub struct AuthLayer {
-    /// Permission claims resolved once for this connection (via [`resolve_claims`]
+    /// Claims resolved once for this connection (via [`resolve_claims`]
     /// at the HTTP request / WebSocket upgrade). Token-verification failures are
     /// rejected with an HTTP `401` before this layer is built, so the claims are
     /// always present here.
-    claims: Arc<[String]>,
+    claims: Arc<ConnectionClaims>,
 }
 
 impl AuthLayer {
-    pub fn new(claims: Arc<[String]>) -> Self {
+    pub fn new(claims: Arc<ConnectionClaims>) -> Self {
         Self { claims }
     }
 }
@@ -83,26 +176,43 @@ impl<S> Layer<S> for AuthLayer {
 
 #[derive(Clone)]
 pub struct Auth<S> {
-    claims: Arc<[String]>,
+    claims: Arc<ConnectionClaims>,
     service: S,
 }
 
 impl<S> Auth<S> {
-    /// Authorize a single method call against this connection's permission claims.
+    /// Authorize a single method call against this connection's claims.
     ///
-    /// Returns a JSON-RPC error for an unknown method ([`ErrorCode::MethodNotFound`])
-    /// or one the claims don't permit ([`INSUFFICIENT_PERMISSIONS`]). Token-level
+    /// Returns a JSON-RPC error for an unknown method ([`ErrorCode::MethodNotFound`]),
+    /// one the claims or the token scope don't permit ([`INSUFFICIENT_PERMISSIONS`]),
+    /// or when the token's rate limit is exhausted ([`RATE_LIMITED`]). Token-level
     /// auth failures never reach here — they are rejected with an HTTP `401` at the
     /// transport layer before any JSON-RPC dispatch.
     fn authorize(&self, method_name: &str) -> Result<(), ErrorObjectOwned> {
-        match METHOD_NAME2REQUIRED_PERMISSION.get(&method_name) {
-            None => Err(ErrorObject::from(ErrorCode::MethodNotFound)),
-            Some(&required) if is_allowed(required, &self.claims) => Ok(()),
-            Some(&required) => {
-                tracing::warn!("insufficient permissions to invoke method {method_name}");
-                Err(insufficient_permissions(method_name, required))
-            }
+        let Some(access) = METHOD_NAME2ACCESS.get(&method_name) else {
+            return Err(ErrorObject::from(ErrorCode::MethodNotFound));
+        };
+        if let Some(token) = &self.claims.token
+            && !token.is_valid()
+        {
+            tracing::warn!("revoked or rotated-out token used to invoke method {method_name}");
+            return Err(invalid_token());
+        }
+        if !is_allowed(access.required, self.claims.permissions()) {
+            tracing::warn!("insufficient permissions to invoke method {method_name}");
+            return Err(insufficient_permissions(method_name, access.required));
+        }
+        if !self.claims.scope.allows_method(access.name) {
+            tracing::warn!("method {method_name} is outside of the token scope");
+            return Err(out_of_scope(method_name));
         }
+        if let Some(limiter) = &self.claims.rate_limiter
+            && !limiter.lock().try_acquire(1.0)
+        {
+            debug!("token rate limit exceeded when invoking method {method_name}");
+            return Err(rate_limited());
+        }
+        Ok(())
     }
 }
 
@@ -176,25 +286,46 @@ fn insufficient_permissions(method: &str, required: Permission) -> ErrorObjectOw
     )
 }
 
-/// Verify JWT Token and return the token's permissions.
-fn auth_verify(token: &str, keystore: &RwLock<KeyStore>) -> anyhow::Result<Vec<String>> {
-    let key_info = keystore.read().get(JWT_IDENTIFIER)?;
-    Ok(verify_token(token, key_info.private_key())?)
+/// Build the JSON-RPC error returned when a method is permitted by the token's
+/// permissions but not by its method scope.
+fn out_of_scope(method: &str) -> ErrorObjectOwned {
+    ErrorObject::owned(
+        INSUFFICIENT_PERMISSIONS,
+        format!("token scope does not allow invoking '{method}'"),
+        None::<()>,
+    )
 }
 
-/// Resolve the connection's `Authorization` header into permission claims.
+/// Build the JSON-RPC error returned when the token of a connection was revoked, or its signing
+/// key rotated out, after the connection was set up.
+fn invalid_token() -> ErrorObjectOwned {
+    ErrorObject::owned(
+        INSUFFICIENT_PERMISSIONS,
+        "authorization token is no longer valid",
+        None::<()>,
+    )
+}
+
+fn rate_limited() -> ErrorObjectOwned {
+    ErrorObject::owned(RATE_LIMITED, "token rate limit exceeded", None::<()>)
+}
+
+/// Resolve the connection's `Authorization` header into its claims.
 ///
 /// This performs the (relatively expensive) JWT verification and is intended to
 /// be called once per connection (at the HTTP request / WebSocket upgrade), not
-/// once per request. Returns `Err(reason)` when the header is malformed or the
+/// once per request. Revocations and key rotations are still re-checked on every call, by
+/// [`Auth::authorize`]. Returns `Err(reason)` when the header is malformed or the
 /// token fails verification; the caller rejects such connections with a bare
 /// HTTP `401 Unauthorized` before any JSON-RPC dispatch, matching Lotus. The
 /// `reason` is for server-side logging only and is not sent to the client.
 pub(super) fn resolve_claims(
-    keystore: &RwLock<KeyStore>,
+    keystore: &Arc<RwLock<KeyStore>>,
+    revocations: &Arc<TokenRevocations>,
     auth_header: Option<&HeaderValue>,
-) -> Result<Arc<[String]>, &'static str> {
-    let claims: Vec<String> = match auth_header {
+    certificate_permissions: Option<&[String]>,
+) -> Result<Arc<ConnectionClaims>, &'static str> {
+    let claims = match auth_header {
         Some(header) => {
             let token = header
                 .to_str()
@@ -202,12 +333,32 @@ pub(super) fn resolve_claims(
                 .strip_prefix("Bearer ")
                 .ok_or("malformed authorization header")?;
 
-            auth_verify(token, keystore).map_err(|_| "invalid authorization token")?
+            let (claims, key) = verify_token_signing_key(token, &keystore.read(), revocations)
+                .map_err(|_| "invalid authorization token")?;
+            let jti = claims.jti.clone();
+            ConnectionClaims {
+                token: Some(TokenValidity {
+                    jti,
+                    key,
+                    keystore: keystore.shallow_clone(),
+                    revocations: revocations.shallow_clone(),
+                }),
+                ..ConnectionClaims::new(token, claims)
+            }
         }
-        // If no token is passed, assume read behavior.
-        None => vec!["read".to_owned()],
+        // If no token is passed, use the permissions of the client certificate, or assume
+        // read behavior.
+        None => ConnectionClaims {
+            permissions: certificate_permissions
+                .map(<[String]>::to_vec)
+                .unwrap_or_else(|| vec!["read".to_owned()]),
+            ..Default::default()
+        },
     };
-    debug!("Decoded JWT permissions: {}", claims.join(","));
+    debug!(
+        "Decoded JWT permissions: {}",
+        claims.permissions().join(",")
+    );
     Ok(claims.into())
 }
 
@@ -215,7 +366,7 @@ pub(super) fn resolve_claims(
 mod tests {
     use self::chain::ChainHead;
     use super::*;
-    use crate::rpc::wallet;
+    use crate::rpc::{eth, wallet};
     use chrono::Duration;
 
     fn empty_keystore() -> Arc<RwLock<KeyStore>> {
@@ -244,8 +395,19 @@ mod tests {
     }
 
     fn auth_with(claims: &[&str]) -> Auth<()> {
+        auth_with_scope(claims, TokenScope::default())
+    }
+
+    fn auth_with_scope(claims: &[&str], scope: TokenScope) -> Auth<()> {
+        let claims = Claims {
+            allow: claims.iter().map(ToString::to_string).collect(),
+            exp: None,
+            jti: None,
+            scope,
+        };
         Auth {
-            claims: claims.iter().map(ToString::to_string).collect(),
+            claims: ConnectionClaims::new(&crate::utils::rand::new_uuid_v4().to_string(), claims)
+                .into(),
             service: (),
         }
     }
@@ -254,8 +416,32 @@ mod tests {
 
     #[test]
     fn resolve_claims_no_header_defaults_to_read() {
-        let claims = resolve_claims(&empty_keystore(), None).unwrap();
-        assert_eq!(&*claims, &["read".to_owned()]);
+        let claims = resolve_claims(&empty_keystore(), &Default::default(), None, None).unwrap();
+        assert_eq!(claims.permissions(), &["read".to_owned()]);
+    }
+
+    #[test]
+    fn resolve_claims_prefers_token_over_certificate() {
+        let certificate_permissions = ["read".to_owned(), "write".to_owned()];
+        let claims = resolve_claims(
+            &empty_keystore(),
+            &Default::default(),
+            None,
+            Some(&certificate_permissions),
+        )
+        .unwrap();
+        assert_eq!(claims.permissions(), &certificate_permissions);
+
+        let (keystore, token) = keystore_with_token(crate::auth::ADMIN);
+        let header = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+        let claims = resolve_claims(
+            &keystore,
+            &Default::default(),
+            Some(&header),
+            Some(&certificate_permissions),
+        )
+        .unwrap();
+        assert!(claims.permissions().iter().any(|c| c == "admin"));
     }
 
     #[test]
@@ -265,14 +451,14 @@ mod tests {
         // Not valid UTF-8, so it cannot be a JWT.
         let header = HeaderValue::from_bytes(&[0xff, 0xfe]).unwrap();
         assert_eq!(
-            resolve_claims(&keystore, Some(&header)).unwrap_err(),
+            resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap_err(),
             "malformed authorization header"
         );
 
         // No `Bearer ` scheme prefix.
         let header = HeaderValue::from_static("Cthulhu");
         assert_eq!(
-            resolve_claims(&keystore, Some(&header)).unwrap_err(),
+            resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap_err(),
             "malformed authorization header"
         );
     }
@@ -281,7 +467,8 @@ mod tests {
     fn resolve_claims_rejects_invalid_token() {
         let header = HeaderValue::from_static("Bearer Azathoth");
         assert_eq!(
-            resolve_claims(&empty_keystore(), Some(&header)).unwrap_err(),
+            resolve_claims(&empty_keystore(), &Default::default(), Some(&header), None,)
+                .unwrap_err(),
             "invalid authorization token"
         );
     }
@@ -291,14 +478,14 @@ mod tests {
         let (keystore, token) = keystore_with_token(crate::auth::ADMIN);
 
         let header = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
-        let claims = resolve_claims(&keystore, Some(&header)).unwrap();
-        assert!(claims.iter().any(|c| c == "admin"));
+        let claims = resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap();
+        assert!(claims.permissions().iter().any(|c| c == "admin"));
 
         // A bare token without the `Bearer ` scheme is malformed, even though the
         // value itself is a valid token.
         let header = HeaderValue::from_str(&token).unwrap();
         assert_eq!(
-            resolve_claims(&keystore, Some(&header)).unwrap_err(),
+            resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap_err(),
             "malformed authorization header"
         );
 
@@ -306,7 +493,7 @@ mod tests {
         // `Bearer ...` value that is not a valid token.
         let header = HeaderValue::from_str(&format!("Bearer Bearer {token}")).unwrap();
         assert_eq!(
-            resolve_claims(&keystore, Some(&header)).unwrap_err(),
+            resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap_err(),
             "invalid authorization token"
         );
     }
@@ -342,13 +529,96 @@ mod tests {
         assert_eq!(err.code(), ErrorCode::MethodNotFound.code());
     }
 
+    #[test]
+    fn authorize_respects_method_scope() {
+        let auth = auth_with_scope(
+            &["read", "write"],
+            TokenScope {
+                methods: Some(vec!["Eth*".into()]),
+                rate_limit: None,
+            },
+        );
+        assert!(auth.authorize(eth::EthBlockNumber::NAME).is_ok());
+        // Aliases are matched through their canonical names.
+        assert!(auth.authorize("eth_blockNumber").is_ok());
+
+        let err = auth.authorize(ChainHead::NAME).unwrap_err();
+        assert_eq!(err.code(), INSUFFICIENT_PERMISSIONS);
+
+        // The scope narrows down the permissions, it never widens them.
+        let auth = auth_with_scope(
+            &["read"],
+            TokenScope {
+                methods: Some(vec!["Wallet*".into()]),
+                rate_limit: None,
+            },
+        );
+        let err = auth.authorize(wallet::WalletNew::NAME).unwrap_err();
+        assert_eq!(err.code(), INSUFFICIENT_PERMISSIONS);
+    }
+
+    #[test]
+    fn authorize_enforces_token_rate_limit() {
+        let auth = auth_with_scope(
+            &["read"],
+            TokenScope {
+                methods: None,
+                rate_limit: std::num::NonZeroU32::new(2),
+            },
+        );
+        assert!(auth.authorize(ChainHead::NAME).is_ok());
+        assert!(auth.authorize(ChainHead::NAME).is_ok());
+        let err = auth.authorize(ChainHead::NAME).unwrap_err();
+        assert_eq!(err.code(), RATE_LIMITED);
+    }
+
+    #[test]
+    fn authorize_rechecks_revocation_on_every_call() {
+        let db = crate::db::MemoryDB::default();
+        let (keystore, token) = keystore_with_token(crate::auth::ADMIN);
+        let revocations = Arc::new(TokenRevocations::default());
+        let header = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+        let auth =
+            AuthLayer::new(resolve_claims(&keystore, &revocations, Some(&header), None).unwrap())
+                .layer(());
+        assert!(auth.authorize(ChainHead::NAME).is_ok());
+
+        let claims =
+            crate::auth::verify_token_with_keystore(&token, &keystore.read(), &revocations)
+                .unwrap();
+        revocations
+            .revoke(&db, claims.jti.unwrap(), claims.exp)
+            .unwrap();
+        let err = auth.authorize(ChainHead::NAME).unwrap_err();
+        assert_eq!(err.code(), INSUFFICIENT_PERMISSIONS);
+    }
+
+    #[test]
+    fn authorize_rechecks_signing_key_on_every_call() {
+        let db = crate::db::MemoryDB::default();
+        let (keystore, token) = keystore_with_token(crate::auth::ADMIN);
+        let revocations = Arc::new(TokenRevocations::default());
+        let header = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+        let auth =
+            AuthLayer::new(resolve_claims(&keystore, &revocations, Some(&header), None).unwrap())
+                .layer(());
+
+        crate::auth::rotate_key(&mut keystore.write(), &revocations, &db, Duration::hours(1))
+            .unwrap();
+        assert!(auth.authorize(ChainHead::NAME).is_ok());
+        crate::auth::rotate_key(&mut keystore.write(), &revocations, &db, Duration::zero())
+            .unwrap();
+        let err = auth.authorize(ChainHead::NAME).unwrap_err();
+        assert_eq!(err.code(), INSUFFICIENT_PERMISSIONS);
+    }
+
     /// Resolved admin claims, threaded through `AuthLayer::layer`, authorize a
     /// write method without re-touching the token.
     #[test]
     fn layer_propagates_resolved_claims() {
         let (keystore, token) = keystore_with_token(crate::auth::ADMIN);
         let header = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
-        let claims = resolve_claims(&keystore, Some(&header)).unwrap();
+        let claims = resolve_claims(&keystore, &Default::default(), Some(&header), None).unwrap();
 
         let auth = AuthLayer::new(claims).layer(());
         assert!(auth.authorize(wallet::WalletNew::NAME).is_ok());
@@ -44,6 +44,8 @@ pub struct Client {
     /// SHOULD end in a slash, due to our use of [`Url::join`].
     base_url: Url,
     token: Option<String>,
+    /// TLS settings, when the default ones don't do, e.g. to trust a private CA.
+    tls: Option<rustls::ClientConfig>,
     // just having these versions inline is easier than using a map
     v0: tokio::sync::OnceCell<UrlClient>,
     v1: tokio::sync::OnceCell<UrlClient>,
@@ -54,6 +56,9 @@ impl Client {
     /// Use either the URL in the environment or a default.
     ///
     /// If `token` is provided, use that over the token in either of the above.
+    ///
+    /// TLS connections trust the CA in `FOREST_RPC_CA_CERT` and authenticate with the
+    /// certificate in `FOREST_RPC_CLIENT_CERT`, if set.
     pub fn default_or_from_env(token: Option<&str>) -> anyhow::Result<Self> {
         static DEFAULT: LazyLock<Url> = LazyLock::new(|| {
             "http://127.0.0.1:2345/"
@@ -89,7 +94,10 @@ impl Client {
                 }
             }
         }
-        Ok(Self::from_url(base_url))
+        Ok(Self {
+            tls: crate::utils::net::tls::client_config_from_env()?,
+            ..Self::from_url(base_url)
+        })
     }
     pub fn from_url(mut base_url: Url) -> Self {
         let token = base_url.password().map(Into::into);
@@ -97,6 +105,7 @@ impl Client {
         Self {
             token,
             base_url,
+            tls: None,
             v0: Default::default(),
             v1: Default::default(),
             v2: Default::default(),
@@ -175,7 +184,7 @@ impl Client {
             let url = self.base_url.join(path.path()).map_err(|it| {
                 ClientError::Custom(format!("creating url for endpoint failed: {it}"))
             })?;
-            UrlClient::new(url, self.token.clone()).await
+            UrlClient::new(url, self.token.clone(), self.tls.clone()).await
         })
         .await
     }
@@ -207,7 +216,12 @@ impl Debug for UrlClient {
 }
 
 impl UrlClient {
-    pub async fn new(url: Url, token: impl Into<Option<String>>) -> Result<Self, ClientError> {
+    /// Connects to `url`, with custom TLS settings if `tls` is set.
+    pub async fn new(
+        url: Url,
+        token: impl Into<Option<String>>,
+        tls: Option<rustls::ClientConfig>,
+    ) -> Result<Self, ClientError> {
         const ONE_DAY: Duration = Duration::from_secs(24 * 3600); // we handle timeouts ourselves.
         let mut headers = HeaderMap::from_iter([(header::USER_AGENT, USER_AGENT.clone())]);
         if let Some(token) = token.into() {
@@ -216,23 +230,28 @@ impl UrlClient {
             headers.insert(header::AUTHORIZATION, value);
         }
         let inner = match url.scheme() {
-            "ws" | "wss" => UrlClientInner::Ws(
-                jsonrpsee::ws_client::WsClientBuilder::new()
+            "ws" | "wss" => {
+                let mut builder = jsonrpsee::ws_client::WsClientBuilder::new()
                     .set_headers(headers)
                     .max_request_size(MAX_REQUEST_BODY_SIZE)
                     .max_response_size(*MAX_RESPONSE_BODY_SIZE)
-                    .request_timeout(ONE_DAY)
-                    .build(&url)
-                    .await?,
-            ),
-            "http" | "https" => UrlClientInner::Https(
-                jsonrpsee::http_client::HttpClientBuilder::new()
+                    .request_timeout(ONE_DAY);
+                if let Some(tls) = tls {
+                    builder = builder.with_custom_cert_store(tls);
+                }
+                UrlClientInner::Ws(builder.build(&url).await?)
+            }
+            "http" | "https" => {
+                let mut builder = jsonrpsee::http_client::HttpClientBuilder::new()
                     .set_headers(headers)
                     .max_request_size(MAX_REQUEST_BODY_SIZE)
                     .max_response_size(*MAX_RESPONSE_BODY_SIZE)
-                    .request_timeout(ONE_DAY)
-                    .build(&url)?,
-            ),
+                    .request_timeout(ONE_DAY);
+                if let Some(tls) = tls {
+                    builder = builder.with_custom_cert_store(tls);
+                }
+                UrlClientInner::Https(builder.build(&url)?)
+            }
             it => {
                 return Err(ClientError::Custom(format!("Unsupported URL scheme: {it}")));
             }
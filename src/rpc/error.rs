@@ -48,6 +48,8 @@ pub(crate) mod implementation_defined_errors {
     /// The token authenticated, but its permissions do not allow the requested method. (Token
     /// verification failures are rejected earlier with an HTTP `401`, not this code.)
     pub(crate) const INSUFFICIENT_PERMISSIONS: i32 = -32003;
+    /// EIP-1474 "limit exceeded": the caller has exhausted its request budget.
+    pub(crate) const RATE_LIMITED: i32 = -32005;
 }
 
 impl ServerError {
@@ -22,26 +22,31 @@ impl AuthNew {
         keystore: &KeyStore,
         token_exp: Duration,
         permissions: Vec<String>,
+        scope: TokenScope,
     ) -> anyhow::Result<String> {
         let ki = keystore.get(JWT_IDENTIFIER)?;
-        Ok(create_token(permissions, ki.private_key(), token_exp)?)
+        Ok(create_scoped_token(
+            permissions,
+            scope,
+            ki.private_key(),
+            token_exp,
+        )?)
     }
 }
 
-impl RpcMethod<2> for AuthNew {
+impl RpcMethod<3> for AuthNew {
     const NAME: &'static str = "Filecoin.AuthNew";
     const N_REQUIRED_PARAMS: usize = 1;
-    // Note: Lotus does not support the optional `expiration_secs` parameter
-    const PARAM_NAMES: [&'static str; 2] = ["permissions", "expirationSecs"];
+    // Note: Lotus does not support the optional `expiration_secs` and `scope` parameters
+    const PARAM_NAMES: [&'static str; 3] = ["permissions", "expirationSecs", "scope"];
     const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
     const PERMISSION: Permission = Permission::Admin;
-    const DESCRIPTION: &'static str =
-        "Creates a new JWT authentication token with the given permissions.";
-    type Params = (Vec<String>, Option<i64>);
+    const DESCRIPTION: &'static str = "Creates a new JWT authentication token with the given permissions, optionally restricted to a set of methods and a rate limit.";
+    type Params = (Vec<String>, Option<i64>, Option<TokenScope>);
     type Ok = Vec<u8>;
     async fn handle(
         ctx: Ctx,
-        (permissions, expiration_secs): Self::Params,
+        (permissions, expiration_secs, scope): Self::Params,
         _: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
         let ks = ctx.keystore.read();
@@ -53,7 +58,11 @@ impl RpcMethod<2> for AuthNew {
                 .with_context(|| format!("expirationSecs out of range: {secs}"))?,
             None => Duration::days(365 * 100),
         };
-        let token = Self::create_token(&ks, token_exp, permissions)?;
+        let scope = scope.unwrap_or_default();
+        scope
+            .validate()
+            .map_err(|e| ServerError::invalid_params(e.to_string(), None))?;
+        let token = Self::create_token(&ks, token_exp, permissions, scope)?;
         Ok(token.as_bytes().to_vec())
     }
 }
@@ -73,10 +82,62 @@ impl RpcMethod<1> for AuthVerify {
         (token,): Self::Params,
         _: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
-        let ks = ctx.keystore.read();
-        let ki = ks.get(JWT_IDENTIFIER)?;
-        let perms = verify_token(&token, ki.private_key())?;
-        Ok(perms)
+        let claims =
+            verify_token_with_keystore(&token, &ctx.keystore.read(), &ctx.token_revocations)?;
+        Ok(claims.allow)
+    }
+}
+
+pub enum AuthRevoke {}
+impl RpcMethod<1> for AuthRevoke {
+    const NAME: &'static str = "Forest.AuthRevoke";
+    const PARAM_NAMES: [&'static str; 1] = ["token"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Admin;
+    const DESCRIPTION: &'static str = "Revokes a JWT authentication token, so that it is rejected from then on, including after a restart.";
+    type Params = (String,);
+    type Ok = ();
+    async fn handle(
+        ctx: Ctx,
+        (token,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let claims =
+            verify_token_with_keystore(&token, &ctx.keystore.read(), &ctx.token_revocations)?;
+        let jti = claims.jti.context(
+            "token has no ID and cannot be revoked individually, rotate the signing key instead",
+        )?;
+        ctx.token_revocations.revoke(ctx.db(), jti, claims.exp)?;
+        Ok(())
+    }
+}
+
+pub enum AuthRotateKey {}
+impl RpcMethod<1> for AuthRotateKey {
+    const NAME: &'static str = "Forest.AuthRotateKey";
+    const PARAM_NAMES: [&'static str; 1] = ["gracePeriodSecs"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Admin;
+    const DESCRIPTION: &'static str = "Replaces the JWT signing key and returns a new admin token. Tokens signed with the previous key stay valid for the given grace period.";
+    type Params = (i64,);
+    type Ok = String;
+    async fn handle(
+        ctx: Ctx,
+        (grace_period_secs,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let grace_period = Duration::try_seconds(grace_period_secs)
+            .with_context(|| format!("gracePeriodSecs out of range: {grace_period_secs}"))?;
+        let mut ks = ctx.keystore.write();
+        rotate_key(&mut ks, &ctx.token_revocations, ctx.db(), grace_period)?;
+        // Lotus admin tokens do not expire, see `AuthNew`.
+        let token = AuthNew::create_token(
+            &ks,
+            Duration::days(365 * 100),
+            ADMIN.iter().map(ToString::to_string).collect(),
+            TokenScope::default(),
+        )?;
+        Ok(token)
     }
 }
 
@@ -87,8 +148,11 @@ pub struct AuthNewParams {
     #[serde_as(as = "DurationSeconds<i64>")]
     #[schemars(with = "i64")]
     pub token_exp: Duration,
+    #[serde(default)]
+    pub scope: TokenScope,
 }
 lotus_json_with_self!(AuthNewParams);
+lotus_json_with_self!(TokenScope);
 
 impl AuthNewParams {
     pub fn process_perms(perm: String) -> Result<Vec<String>, ServerError> {
@@ -105,8 +169,9 @@ impl AuthNewParams {
     }
 }
 
-impl From<AuthNewParams> for (Vec<String>, Option<i64>) {
+impl From<AuthNewParams> for (Vec<String>, Option<i64>, Option<TokenScope>) {
     fn from(value: AuthNewParams) -> Self {
-        (value.perms, Some(value.token_exp.num_seconds()))
+        let scope = (!value.scope.is_empty()).then_some(value.scope);
+        (value.perms, Some(value.token_exp.num_seconds()), scope)
     }
 }
@@ -7,7 +7,9 @@ use types::*;
 #[cfg(test)]
 use crate::blocks::RawBlockHeader;
 use crate::blocks::{Block, CachingBlockHeader, Tipset, TipsetKey};
+use crate::chain::block_timeline;
 use crate::chain::index::{ChainIndex, ResolveNullTipset};
+use crate::chain::reorg_log::ChainReorg;
 use crate::chain::{ChainStore, ExportOptions, ExportResult, FilecoinSnapshotVersion, HeadChange};
 use crate::chain_sync::{get_full_tipset, load_full_tipset};
 use crate::cid_collections::{CidHashSet, FileBackedCidHashSet};
@@ -305,7 +307,33 @@ impl RpcMethod<1> for ForestChainExport {
     }
 }
 
-fn save_checksum(
+/// Exports the latest F3 snapshot to a temporary file next to `output_path`, to be included in a
+/// v2 snapshot. A failed F3 export is logged and yields no F3 data.
+pub(crate) async fn export_latest_f3_snapshot(
+    output_path: &Path,
+) -> anyhow::Result<(tempfile::TempPath, Option<(Cid, File)>)> {
+    let f3_snap_tmp_path = {
+        let mut f3_snap_dir = output_path.to_owned();
+        let mut builder = tempfile::Builder::new();
+        let with_suffix = builder.suffix(".f3snap.bin");
+        if f3_snap_dir.pop() {
+            with_suffix.tempfile_in(&f3_snap_dir)
+        } else {
+            with_suffix.tempfile_in(".")
+        }?
+        .into_temp_path()
+    };
+    let f3_snap = match F3ExportLatestSnapshot::run(f3_snap_tmp_path.display().to_string()).await {
+        Ok(cid) => Some((cid, File::open(&f3_snap_tmp_path)?)),
+        Err(e) => {
+            tracing::error!("Failed to export F3 snapshot: {e:#}");
+            None
+        }
+    };
+    Ok((f3_snap_tmp_path, f3_snap))
+}
+
+pub(crate) fn save_checksum(
     checksum: digest::Output<Sha256>,
     snapshot_output_path: &Path,
 ) -> anyhow::Result<()> {
@@ -375,26 +403,7 @@ async fn export_chain_inner(
                 .boxed()
         }
         FilecoinSnapshotVersion::V2 => {
-            let f3_snap_tmp_path = {
-                let mut f3_snap_dir = output_path.clone();
-                let mut builder = tempfile::Builder::new();
-                let with_suffix = builder.suffix(".f3snap.bin");
-                if f3_snap_dir.pop() {
-                    with_suffix.tempfile_in(&f3_snap_dir)
-                } else {
-                    with_suffix.tempfile_in(".")
-                }?
-                .into_temp_path()
-            };
-            let f3_snap = {
-                match F3ExportLatestSnapshot::run(f3_snap_tmp_path.display().to_string()).await {
-                    Ok(cid) => Some((cid, File::open(&f3_snap_tmp_path)?)),
-                    Err(e) => {
-                        tracing::error!("Failed to export F3 snapshot: {e:#}");
-                        None
-                    }
-                }
-            };
+            let (_f3_snap_tmp_path, f3_snap) = export_latest_f3_snapshot(&output_path).await?;
             crate::chain::export_v2::<Sha256, _, _>(
                 ctx.db(),
                 f3_snap,
@@ -1133,13 +1142,25 @@ impl RpcMethod<2> for ChainGetPath {
 ///      ~~~~~~~~> then apply B', C'
 /// ```
 ///
-/// Exposes errors from the [`Blockstore`], and returns an error if there is no common ancestor.
+/// Exposes errors from the [`Blockstore`], and returns an error if there is no common ancestor
+/// within chain finality.
 pub fn chain_get_path(
     chain_store: &ChainStore,
     from: &TipsetKey,
     to: &TipsetKey,
 ) -> anyhow::Result<PathChanges> {
     let finality = chain_store.chain_config().policy.chain_finality;
+    chain_get_path_bounded(chain_store, from, to, finality)
+}
+
+/// [`chain_get_path`], returning an error when `from` and `to` are more than `max_depth` epochs
+/// apart, or when their common ancestor is more than `max_depth` epochs below the lower of them.
+pub fn chain_get_path_bounded(
+    chain_store: &ChainStore,
+    from: &TipsetKey,
+    to: &TipsetKey,
+    max_depth: ChainEpoch,
+) -> anyhow::Result<PathChanges> {
     let mut to_revert = chain_store
         .load_required_tipset_or_heaviest(from)
         .context("couldn't load `from`")?;
@@ -1148,17 +1169,16 @@ pub fn chain_get_path(
         .context("couldn't load `to`")?;
 
     anyhow::ensure!(
-        (to_apply.epoch() - to_revert.epoch()).abs() <= finality,
-        "the gap between the new head ({}) and the old head ({}) is larger than chain finality ({finality})",
+        (to_apply.epoch() - to_revert.epoch()).abs() <= max_depth,
+        "the gap between the new head ({}) and the old head ({}) is larger than {max_depth} epochs",
         to_apply.epoch(),
         to_revert.epoch()
     );
+    let lowest_epoch = to_apply.epoch().min(to_revert.epoch()) - max_depth;
 
     let mut reverts = vec![];
     let mut applies = vec![];
 
-    // This loop is guaranteed to terminate if the blockstore contain no cycles.
-    // This is currently computationally infeasible.
     while to_revert != to_apply {
         if to_revert.epoch() > to_apply.epoch() {
             let next = chain_store
@@ -1173,6 +1193,10 @@ pub fn chain_get_path(
             applies.push(to_apply);
             to_apply = next;
         }
+        anyhow::ensure!(
+            to_revert.epoch().min(to_apply.epoch()) >= lowest_epoch,
+            "no common ancestor within {max_depth} epochs"
+        );
     }
     applies.reverse();
     Ok(PathChanges { reverts, applies })
@@ -1499,7 +1523,7 @@ impl ChainGetTipSetFinalityStatus {
         (head.epoch() - depth).max(0)
     }
 
-    fn get_ec_finality_threshold_depth_with_cache(
+    pub fn get_ec_finality_threshold_depth_with_cache(
         chain_index: &ChainIndex,
         chain_config: &ChainConfig,
         head: &Tipset,
@@ -1527,6 +1551,31 @@ impl ChainGetTipSetFinalityStatus {
             find_threshold_depth,
         };
 
+        let chain = Self::get_ec_finality_chain_sample(chain_index, chain_config, head);
+        match find_threshold_depth(
+            &chain,
+            chain_config.policy.chain_finality,
+            DEFAULT_BLOCKS_PER_EPOCH,
+            DEFAULT_BYZANTINE_FRACTION,
+            *DEFAULT_GUARANTEE,
+        ) {
+            Ok(threshold) => threshold,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to calculate EC finality threshold depth: {e:#}, chain: {chain:?}"
+                );
+                -1
+            }
+        }
+    }
+
+    /// Returns the number of blocks of each epoch over the EC finality window up to `head`, in
+    /// chronological order, with zeros for null rounds.
+    fn get_ec_finality_chain_sample(
+        chain_index: &ChainIndex,
+        chain_config: &ChainConfig,
+        head: &Tipset,
+    ) -> Vec<i64> {
         /// Number of extra epochs to fetch beyond [`chain_finality`] when
         /// building the chain sample for [`find_threshold_depth`].
         ///
@@ -1552,21 +1601,7 @@ impl ChainGetTipSetFinalityStatus {
         }
         // Reverse to chronological order (oldest first).
         chain.reverse();
-        match find_threshold_depth(
-            &chain,
-            finality,
-            DEFAULT_BLOCKS_PER_EPOCH,
-            DEFAULT_BYZANTINE_FRACTION,
-            *DEFAULT_GUARANTEE,
-        ) {
-            Ok(threshold) => threshold,
-            Err(e) => {
-                tracing::error!(
-                    "Failed to calculate EC finality threshold depth: {e:#}, chain: {chain:?}"
-                );
-                -1
-            }
-        }
+        chain
     }
 
     async fn get_ec_finality_threshold_depth_and_tipset(
@@ -1608,6 +1643,125 @@ impl RpcMethod<0> for ChainGetTipSetFinalityStatus {
     }
 }
 
+pub enum ChainFinalityProbability {}
+impl ChainFinalityProbability {
+    /// Computes the probability that the tipset at `epoch` is reorganized out of the chain with
+    /// the given `head`, from the block production observed over the EC finality window.
+    pub fn get_finality_probability(
+        chain_index: &ChainIndex,
+        chain_config: &ChainConfig,
+        head: &Tipset,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<FinalityProbability> {
+        use crate::chain::ec_finality::calculator::{
+            DEFAULT_BLOCKS_PER_EPOCH, DEFAULT_BYZANTINE_FRACTION, calc_validator_prob,
+        };
+
+        let finality = chain_config.policy.chain_finality;
+        let depth = head.epoch() - epoch;
+        let chain = ChainGetTipSetFinalityStatus::get_ec_finality_chain_sample(
+            chain_index,
+            chain_config,
+            head,
+        );
+        let current_epoch = chain.len() as i64 - 1;
+        let reorg_probability = if depth <= 0 {
+            1.0
+        } else if depth >= finality || depth > current_epoch {
+            // Beyond the EC finality window, or before genesis.
+            0.0
+        } else {
+            calc_validator_prob(
+                &chain,
+                finality,
+                DEFAULT_BLOCKS_PER_EPOCH,
+                DEFAULT_BYZANTINE_FRACTION,
+                current_epoch,
+                current_epoch - depth,
+            )?
+        };
+        let ec_finality_threshold_depth =
+            ChainGetTipSetFinalityStatus::get_ec_finality_threshold_depth_with_cache(
+                chain_index,
+                chain_config,
+                head,
+            );
+        Ok(FinalityProbability {
+            head_epoch: head.epoch(),
+            epoch,
+            reorg_probability,
+            ec_finality_threshold_depth,
+            epochs_until_final: (ec_finality_threshold_depth >= 0)
+                .then(|| (ec_finality_threshold_depth - depth).max(0)),
+        })
+    }
+}
+
+impl RpcMethod<1> for ChainFinalityProbability {
+    const NAME: &'static str = "Forest.ChainFinalityProbability";
+    const PARAM_NAMES: [&'static str; 1] = ["epoch"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Returns the estimated probability that the tipset at the given epoch is reorganized out of the chain, from the block production observed over the EC finality window.";
+
+    type Params = (ChainEpoch,);
+    type Ok = FinalityProbability;
+
+    async fn handle(
+        ctx: Ctx,
+        (epoch,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let head = ctx.chain_store().heaviest_tipset();
+        Ok(Self::get_finality_probability(
+            ctx.chain_index(),
+            ctx.chain_config(),
+            &head,
+            epoch,
+        )?)
+    }
+}
+
+pub enum ChainReorgs {}
+impl RpcMethod<0> for ChainReorgs {
+    const NAME: &'static str = "Forest.ChainReorgs";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Returns the latest chain reorganizations observed by the node, oldest first, with the messages they dropped.";
+
+    type Params = ();
+    type Ok = Vec<ChainReorg>;
+
+    async fn handle(
+        ctx: Ctx,
+        (): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.chain_store().reorg_log().reorgs())
+    }
+}
+
+pub enum BlockTimeline {}
+impl RpcMethod<1> for BlockTimeline {
+    const NAME: &'static str = "Forest.BlockTimeline";
+    const PARAM_NAMES: [&'static str; 1] = ["cid"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Returns when the node received, fetched, validated and switched its head to the given block, if it is recent enough to be tracked.";
+
+    type Params = (Cid,);
+    type Ok = Option<block_timeline::BlockTimeline>;
+
+    async fn handle(
+        ctx: Ctx,
+        (cid,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.chain_store().block_timelines().get(&cid))
+    }
+}
+
 pub enum ChainSetHead {}
 impl RpcMethod<1> for ChainSetHead {
     const NAME: &'static str = "Filecoin.ChainSetHead";
@@ -2118,6 +2272,100 @@ mod tests {
         assert_eq!(index_backfill_range_spec(&params).ok(), expected);
     }
 
+    #[test]
+    fn finality_probability_decreases_with_depth() {
+        let cs = ChainStore::calibnet();
+        let c4u = Chain4U::with_blockstore(cs.db_owned());
+        c4u.insert(&[], "genesis", cs.genesis_block_header().into());
+        // A healthy chain with 5 blocks per epoch.
+        let mut parents = vec!["genesis".to_string()];
+        for epoch in 1..=60 {
+            let names = (0..5).map(|i| format!("{epoch}-{i}")).collect_vec();
+            let parent_refs = parents.iter().map(String::as_str).collect_vec();
+            for name in &names {
+                c4u.insert(&parent_refs, name.as_str(), HeaderBuilder::new());
+            }
+            parents = names;
+        }
+        let head = c4u.tipset(&parents.iter().map(String::as_str).collect_vec());
+        assert_eq!(head.epoch(), 60);
+        let probability = |epoch| {
+            ChainFinalityProbability::get_finality_probability(
+                cs.chain_index(),
+                cs.chain_config(),
+                &head,
+                epoch,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(probability(60).reorg_probability, 1.0);
+        let shallow = probability(55);
+        let deep = probability(30);
+        assert!(shallow.reorg_probability > deep.reorg_probability);
+        assert!(deep.reorg_probability > 0.0);
+        // Before genesis
+        assert_eq!(probability(-1).reorg_probability, 0.0);
+
+        let threshold = shallow.ec_finality_threshold_depth;
+        assert!(threshold > 5 && threshold < 30, "{threshold}");
+        assert_eq!(shallow.epochs_until_final, Some(threshold - 5));
+        assert_eq!(deep.epochs_until_final, Some(0));
+        assert!(probability(60 - threshold).reorg_probability < 2f64.powi(-30));
+    }
+
+    #[test]
+    fn reorgs_are_recorded() {
+        let cs = ChainStore::calibnet();
+        let db = Chain4U::with_blockstore(cs.db_owned());
+        chain4u! {
+            in db;
+            [_genesis = cs.genesis_block_header()]
+            -> a @ [_a] -> b @ [_b]
+        };
+        chain4u! {
+            from [_a] in db;
+            [_c] -> d @ [_d] -> e @ [_e]
+        };
+
+        cs.set_heaviest_tipset(a.shallow_clone()).unwrap();
+        cs.set_heaviest_tipset(b.shallow_clone()).unwrap();
+        assert!(cs.reorg_log().reorgs().is_empty());
+
+        cs.set_heaviest_tipset(d.shallow_clone()).unwrap();
+        cs.set_heaviest_tipset(e.shallow_clone()).unwrap();
+        let reorgs = cs.reorg_log().reorgs();
+        assert_eq!(reorgs.len(), 1);
+        let reorg = &reorgs[0];
+        assert_eq!(&reorg.old_head, b.key());
+        assert_eq!(&reorg.new_head, d.key());
+        assert_eq!(&reorg.common_ancestor, a.key());
+        assert_eq!(reorg.depth, 1);
+        assert_eq!(reorg.new_head_epoch, 3);
+        assert!(reorg.dropped_messages.is_empty());
+    }
+
+    #[test]
+    fn path_is_bounded() {
+        let cs = ChainStore::calibnet();
+        let db = Chain4U::with_blockstore(cs.db_owned());
+        chain4u! {
+            in db;
+            [_genesis = cs.genesis_block_header()]
+            -> a @ [_a] -> [_b1] -> c1 @ [_c1]
+        };
+        chain4u! {
+            from [_a] in db;
+            [_b2] -> c2 @ [_c2]
+        };
+
+        assert!(chain_get_path_bounded(&cs, c1.key(), c2.key(), 1).is_err());
+        let changes = chain_get_path_bounded(&cs, c1.key(), c2.key(), 2).unwrap();
+        assert_eq!(changes.reverts.len(), 2);
+        assert_eq!(changes.applies.len(), 2);
+        assert!(chain_get_path_bounded(&cs, a.key(), c1.key(), 1).is_err());
+    }
+
     #[test]
     fn revert_to_ancestor_linear() {
         let cs = ChainStore::calibnet();
@@ -48,3 +48,24 @@ pub struct ChainFinalityStatus {
     pub head: Tipset,
 }
 lotus_json_with_self!(ChainFinalityStatus);
+
+/// The estimated probability that the tipset at an epoch is reorganized out of the chain, from
+/// the block production observed over the EC finality window (`FRC-0089`).
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FinalityProbability {
+    /// The epoch of the chain head the probability is computed against.
+    pub head_epoch: ChainEpoch,
+    /// The queried epoch.
+    pub epoch: ChainEpoch,
+    /// An upper bound on the probability that the tipset at `epoch` is reorganized. Epochs
+    /// beyond the EC finality window have a probability of 0.
+    pub reorg_probability: f64,
+    /// The shallowest epoch depth at which the probability of a chain reorganization drops
+    /// below 2^-30. A value of -1 indicates the threshold was not met within the search range.
+    pub ec_finality_threshold_depth: i64,
+    /// The number of epochs until `epoch` reaches the 2^-30 threshold at the observed block
+    /// production, 0 if it already has. [`None`] if the threshold is not met.
+    pub epochs_until_final: Option<i64>,
+}
+lotus_json_with_self!(FinalityProbability);
@@ -7,6 +7,7 @@ mod eth_tx;
 pub mod filter;
 pub mod pubsub;
 pub(crate) mod pubsub_trait;
+pub mod state_override;
 pub mod tipset_resolver;
 pub(crate) mod trace;
 pub mod types;
@@ -21,6 +22,7 @@ use tokio_util::sync::CancellationToken;
 
 use self::eth_tx::*;
 use self::filter::hex_str_to_epoch;
+use self::state_override::StateOverride;
 use self::trace::types::*;
 use self::types::*;
 use super::gas;
@@ -64,7 +66,9 @@ use crate::shim::fvm_shared_latest::address::{Address as VmAddress, DelegatedAdd
 use crate::shim::gas::GasOutputs;
 use crate::shim::message::Message;
 use crate::shim::{clock::ChainEpoch, state_tree::StateTree};
-use crate::state_manager::{ExecutedMessage, ExecutedTipset, StateManager, TipsetState, VMFlush};
+use crate::state_manager::{
+    ExecutedMessage, ExecutedTipset, StateManager, StateOverlay, TipsetState, VMFlush,
+};
 use crate::utils::cache::SizeTrackingCache;
 use crate::utils::db::BlockstoreExt as _;
 use crate::utils::encoding::from_slice_with_fallback;
@@ -377,6 +381,19 @@ impl BlockNumberOrHash {
         })
     }
 
+    /// Whether this selects the same block regardless of the current head, that is, by
+    /// hash without requiring it to be canonical.
+    pub fn is_head_independent(&self) -> bool {
+        matches!(
+            self,
+            Self::BlockHash(_)
+                | Self::BlockHashObject(BlockHash {
+                    require_canonical: false,
+                    ..
+                })
+        )
+    }
+
     pub fn from_str(s: &str) -> Result<Self, Error> {
         if s.starts_with("0x") {
             let epoch = hex_str_to_epoch(s)?;
@@ -1864,22 +1881,26 @@ impl RpcMethod<0> for EthSyncing {
 
 pub enum EthEstimateGas {}
 
-impl RpcMethod<2> for EthEstimateGas {
+impl RpcMethod<3> for EthEstimateGas {
     const NAME: &'static str = "Filecoin.EthEstimateGas";
     const NAME_ALIAS: Option<&'static str> = Some("eth_estimateGas");
     const N_REQUIRED_PARAMS: usize = 1;
-    const PARAM_NAMES: [&'static str; 2] = ["tx", "blockParam"];
+    const PARAM_NAMES: [&'static str; 3] = ["tx", "blockParam", "stateOverride"];
     const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all_with_v2();
     const PERMISSION: Permission = Permission::Read;
     const DESCRIPTION: &'static str =
         "Estimates the amount of gas required to execute the given transaction.";
 
-    type Params = (EthCallMessage, Option<BlockNumberOrHash>);
+    type Params = (
+        EthCallMessage,
+        Option<BlockNumberOrHash>,
+        Option<StateOverride>,
+    );
     type Ok = EthUint64;
 
     async fn handle(
         ctx: Ctx,
-        (tx, block_param): Self::Params,
+        (tx, block_param, state_override): Self::Params,
         ext: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
         let tipset = if let Some(block_param) = block_param {
@@ -1890,7 +1911,7 @@ impl RpcMethod<2> for EthEstimateGas {
         } else {
             ctx.chain_store().heaviest_tipset()
         };
-        eth_estimate_gas(&ctx, tx, tipset).await
+        eth_estimate_gas(&ctx, tx, tipset, state_override).await
     }
 }
 
@@ -1898,13 +1919,23 @@ async fn eth_estimate_gas(
     ctx: &Ctx,
     tx: EthCallMessage,
     tipset: Tipset,
+    state_override: Option<StateOverride>,
 ) -> Result<EthUint64, ServerError> {
     let mut msg = Message::try_from(tx)?;
     // Set the gas limit to the zero sentinel value, which makes
     // gas estimation actually run.
     msg.gas_limit = 0;
+    let overlay = state_overlay(ctx, &tipset, state_override).await?;
 
-    match gas::estimate_message_gas(ctx, msg.clone(), None, tipset.key().clone().into()).await {
+    match gas::estimate_message_gas(
+        ctx,
+        msg.clone(),
+        None,
+        tipset.key().clone().into(),
+        overlay.clone(),
+    )
+    .await
+    {
         Err(server_err) => {
             // On failure, GasEstimateMessageGas doesn't actually return the invocation result,
             // it just returns an error. That means we can't get the revert reason.
@@ -1913,7 +1944,7 @@ async fn eth_estimate_gas(
             // guts of EthCall). This will give us an ethereum specific error with revert
             // information.
             msg.set_gas_limit(BLOCK_GAS_LIMIT);
-            let err = match apply_message(ctx, Some(tipset), msg).await {
+            let err = match apply_message(ctx, Some(tipset), overlay, msg).await {
                 Ok(_) => Error::msg(server_err.to_string()),
                 Err(e)
                     if e.downcast_ref::<EthErrors>().is_some_and(|eth_err| {
@@ -1928,7 +1959,8 @@ async fn eth_estimate_gas(
             Err(err.context("failed to estimate gas").into())
         }
         Ok(gassed_msg) => {
-            let expected_gas = eth_gas_search(ctx, gassed_msg, &tipset.key().into()).await?;
+            let expected_gas =
+                eth_gas_search(ctx, gassed_msg, &tipset.key().into(), overlay).await?;
             Ok(expected_gas.into())
         }
     }
@@ -1945,9 +1977,27 @@ fn execution_reverted_error(
     EthErrors::execution_reverted(exit_code.into(), &reason, vm_error, &data)
 }
 
+/// Returns the parent state of `tipset` with `state_override` applied, if any.
+async fn state_overlay(
+    ctx: &Ctx,
+    tipset: &Tipset,
+    state_override: Option<StateOverride>,
+) -> Result<Option<StateOverlay>> {
+    let Some(state_override) = state_override else {
+        return Ok(None);
+    };
+    let mut overlay = ctx
+        .state_manager
+        .state_overlay(tipset.shallow_clone())
+        .await?;
+    overlay.state_root = state_override.apply(&overlay.store, &overlay.state_root)?;
+    Ok(Some(overlay))
+}
+
 async fn apply_message(
     ctx: &Ctx,
     tipset: Option<Tipset>,
+    overlay: Option<StateOverlay>,
     msg: Message,
 ) -> Result<ApiInvocResult, Error> {
     if let Some(ts) = &tipset
@@ -1961,7 +2011,7 @@ async fn apply_message(
 
     let (invoc_res, _) = ctx
         .state_manager
-        .apply_on_state_with_gas(tipset, msg, VMFlush::Skip, VMTrace::NotTraced)
+        .apply_on_state_with_gas(tipset, overlay, msg, VMFlush::Skip, VMTrace::NotTraced)
         .await
         .context("failed to apply on state with gas")?;
 
@@ -1983,12 +2033,22 @@ async fn apply_message(
     Ok(invoc_res)
 }
 
-pub async fn eth_gas_search(data: &Ctx, msg: Message, tsk: &ApiTipsetKey) -> anyhow::Result<u64> {
+pub async fn eth_gas_search(
+    data: &Ctx,
+    msg: Message,
+    tsk: &ApiTipsetKey,
+    overlay: Option<StateOverlay>,
+) -> anyhow::Result<u64> {
     // Probe the message as the caller specified it: the question is whether *its* limit
     // suffices, which the block maximum would always answer yes to.
-    let (apply_ret, prior_messages, ts, from) =
-        gas::GasEstimateGasLimit::probe_as_specified(data, msg.clone(), tsk, VMTrace::NotTraced)
-            .await?;
+    let (apply_ret, prior_messages, ts, from) = gas::GasEstimateGasLimit::probe_as_specified(
+        data,
+        msg.clone(),
+        tsk,
+        overlay.clone(),
+        VMTrace::NotTraced,
+    )
+    .await?;
     if apply_ret.exit_code().is_success() {
         return Ok(msg.gas_limit());
     }
@@ -2002,6 +2062,7 @@ pub async fn eth_gas_search(data: &Ctx, msg: Message, tsk: &ApiTipsetKey) -> any
             ChainMessage::for_gas_estimation(msg.clone(), from.protocol()),
             prior_messages.shallow_clone(),
             Some(ts.shallow_clone()),
+            overlay.clone(),
             VMFlush::Skip,
             VMTrace::Traced,
         )
@@ -2021,7 +2082,7 @@ pub async fn eth_gas_search(data: &Ctx, msg: Message, tsk: &ApiTipsetKey) -> any
         .into());
     }
 
-    let ret = gas_search(data, &msg, from.protocol(), prior_messages, ts).await?;
+    let ret = gas_search(data, &msg, from.protocol(), prior_messages, ts, overlay).await?;
     Ok(((ret as f64) * data.mpool.gas_limit_overestimation()) as u64)
 }
 
@@ -2035,6 +2096,7 @@ async fn gas_search(
     from_protocol: Protocol,
     prior_messages: Arc<Vec<ChainMessage>>,
     ts: Tipset,
+    overlay: Option<StateOverlay>,
 ) -> anyhow::Result<u64> {
     // `max(1)` keeps the doubling below able to make progress.
     let mut high = msg.gas_limit.max(1);
@@ -2049,6 +2111,7 @@ async fn gas_search(
                 ChainMessage::for_gas_estimation(msg, from_protocol),
                 prior_messages.shallow_clone(),
                 Some(ts.shallow_clone()),
+                overlay.clone(),
                 VMFlush::Skip,
                 VMTrace::NotTraced,
             )
@@ -2750,32 +2813,38 @@ impl RpcMethod<1> for EthGetTransactionHashByCid {
 }
 
 pub enum EthCall {}
-impl RpcMethod<2> for EthCall {
+impl RpcMethod<3> for EthCall {
     const NAME: &'static str = "Filecoin.EthCall";
     const NAME_ALIAS: Option<&'static str> = Some("eth_call");
     const N_REQUIRED_PARAMS: usize = 2;
-    const PARAM_NAMES: [&'static str; 2] = ["tx", "blockParam"];
+    const PARAM_NAMES: [&'static str; 3] = ["tx", "blockParam", "stateOverride"];
     const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all_with_v2();
     const PERMISSION: Permission = Permission::Read;
     const DESCRIPTION: &'static str = "Executes a read-only message call against the given block state without creating an on-chain transaction, returning the call output.";
-    type Params = (EthCallMessage, BlockNumberOrHash);
+    type Params = (EthCallMessage, BlockNumberOrHash, Option<StateOverride>);
     type Ok = EthBytes;
     async fn handle(
         ctx: Ctx,
-        (tx, block_param): Self::Params,
+        (tx, block_param, state_override): Self::Params,
         ext: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
         let resolver = TipsetResolver::new(&ctx, Self::api_path(ext)?);
         let ts = resolver
             .tipset_by_block_number_or_hash(block_param, ResolveNullTipset::TakeOlder)
             .await?;
-        eth_call(&ctx, tx, ts).await
+        eth_call(&ctx, tx, ts, state_override).await
     }
 }
 
-async fn eth_call(ctx: &Ctx, tx: EthCallMessage, ts: Tipset) -> Result<EthBytes, ServerError> {
+async fn eth_call(
+    ctx: &Ctx,
+    tx: EthCallMessage,
+    ts: Tipset,
+    state_override: Option<StateOverride>,
+) -> Result<EthBytes, ServerError> {
     let msg = Message::try_from(tx)?;
-    let invoke_result = apply_message(ctx, Some(ts), msg.clone()).await?;
+    let overlay = state_overlay(ctx, &ts, state_override).await?;
+    let invoke_result = apply_message(ctx, Some(ts), overlay, msg.clone()).await?;
 
     if msg.to() == FilecoinAddress::ETHEREUM_ACCOUNT_MANAGER_ACTOR {
         Ok(EthBytes::default())
@@ -3875,11 +3944,11 @@ async fn debug_trace_transaction(
 }
 
 pub enum EthTraceCall {}
-impl RpcMethod<3> for EthTraceCall {
+impl RpcMethod<4> for EthTraceCall {
     const NAME: &'static str = "Forest.EthTraceCall";
     const NAME_ALIAS: Option<&'static str> = Some("trace_call");
     const N_REQUIRED_PARAMS: usize = 1;
-    const PARAM_NAMES: [&'static str; 3] = ["tx", "traceTypes", "blockParam"];
+    const PARAM_NAMES: [&'static str; 4] = ["tx", "traceTypes", "blockParam", "stateOverride"];
     const API_PATHS: BitFlags<ApiPaths> = make_bitflags!(ApiPaths::{ V1 | V2 });
     const PERMISSION: Permission = Permission::Read;
     const DESCRIPTION: &'static str =
@@ -3889,11 +3958,12 @@ impl RpcMethod<3> for EthTraceCall {
         EthCallMessage,
         NonEmpty<EthTraceType>,
         Option<BlockNumberOrHash>,
+        Option<StateOverride>,
     );
     type Ok = EthTraceResults;
     async fn handle(
         ctx: Ctx,
-        (tx, trace_types, block_param): Self::Params,
+        (tx, trace_types, block_param, state_override): Self::Params,
         ext: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
         let msg = Message::try_from(tx)?;
@@ -3903,20 +3973,24 @@ impl RpcMethod<3> for EthTraceCall {
             .tipset_by_block_number_or_hash(block_param, ResolveNullTipset::TakeOlder)
             .await?;
 
-        let TipsetState {
-            state_root: pre_state_root,
-            ..
-        } = ctx
+        // The post-execution state is only needed for the diff, so it is kept in an overlay
+        // rather than written to the database.
+        let mut overlay = ctx
             .state_manager
-            .load_tipset_state(&ts)
+            .state_overlay(ts.shallow_clone())
             .await
             .context("failed to get tipset state")?;
-        let pre_state = StateTree::new_from_root(ctx.db(), &pre_state_root)?;
+        if let Some(state_override) = &state_override {
+            overlay.state_root = state_override.apply(&overlay.store, &overlay.state_root)?;
+        }
+        let db = DbImpl::from(overlay.store.shallow_clone());
+        let pre_state = StateTree::new_from_root(&db, &overlay.state_root)?;
 
         let (invoke_result, post_state_root) = ctx
             .state_manager
             .apply_on_state_with_gas(
                 Some(ts.shallow_clone()),
+                Some(overlay),
                 msg.clone(),
                 VMFlush::Flush,
                 VMTrace::Traced,
@@ -3925,7 +3999,7 @@ impl RpcMethod<3> for EthTraceCall {
             .context("failed to apply message")?;
         let post_state_root =
             post_state_root.context("post-execution state root required for trace call")?;
-        let post_state = StateTree::new_from_root(ctx.db(), &post_state_root)?;
+        let post_state = StateTree::new_from_root(&db, &post_state_root)?;
 
         let mut trace_results = EthTraceResults {
             output: get_trace_output(&msg, &invoke_result)?,
@@ -3960,8 +4034,7 @@ impl RpcMethod<3> for EthTraceCall {
                 all_touched.insert(to_eth);
             }
 
-            let state_diff =
-                trace::build_state_diff(ctx.db(), &pre_state, &post_state, &all_touched)?;
+            let state_diff = trace::build_state_diff(&db, &pre_state, &post_state, &all_touched)?;
             trace_results.state_diff = Some(state_diff);
         }
 
@@ -3969,6 +4042,78 @@ impl RpcMethod<3> for EthTraceCall {
     }
 }
 
+pub enum EthCallMany {}
+impl RpcMethod<3> for EthCallMany {
+    const NAME: &'static str = "Forest.EthCallMany";
+    const NAME_ALIAS: Option<&'static str> = Some("eth_callMany");
+    const N_REQUIRED_PARAMS: usize = 2;
+    const PARAM_NAMES: [&'static str; 3] = ["txs", "blockParam", "stateOverride"];
+    const API_PATHS: BitFlags<ApiPaths> = make_bitflags!(ApiPaths::{ V1 | V2 });
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Executes the given message calls one after another against the given block state, returning the output, gas used and traces of each call. Each call sees the effects of the previous ones. Bundles are limited to 100 messages and a total of 50 billion gas.";
+
+    type Params = (
+        Vec<EthCallMessage>,
+        BlockNumberOrHash,
+        Option<StateOverride>,
+    );
+    type Ok = Vec<EthCallManyResult>;
+    async fn handle(
+        ctx: Ctx,
+        (txs, block_param, state_override): Self::Params,
+        ext: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let messages = txs
+            .into_iter()
+            .map(Message::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let resolver = TipsetResolver::new(&ctx, Self::api_path(ext)?);
+        let ts = resolver
+            .tipset_by_block_number_or_hash(block_param, ResolveNullTipset::TakeOlder)
+            .await?;
+        let mut overlay = ctx
+            .state_manager
+            .state_overlay(ts)
+            .await
+            .context("failed to get tipset state")?;
+        if let Some(state_override) = &state_override {
+            overlay.state_root = state_override.apply(&overlay.store, &overlay.state_root)?;
+        }
+        let db = DbImpl::from(overlay.store.shallow_clone());
+        let (invoc_results, post_state_root) =
+            ctx.state_manager.call_many(messages, overlay).await?;
+        let post_state = StateTree::new_from_root(&db, &post_state_root)?;
+
+        let mut results = Vec::with_capacity(invoc_results.len());
+        for invoc_result in invoc_results {
+            let receipt = invoc_result
+                .msg_rct
+                .as_ref()
+                .context("no message receipt in execution result")?;
+            let mut result = EthCallManyResult {
+                gas_used: receipt.gas_used().into(),
+                ..Default::default()
+            };
+            if receipt.exit_code().is_success() {
+                result.status = 1.into();
+                result.output = get_trace_output(&invoc_result.msg, &invoc_result)?;
+            } else {
+                let (data, reason) = decode_revert_reason(receipt.return_data());
+                result.output = data.into();
+                result.error = Some(format!("execution reverted: {reason}"));
+            }
+            if let Some(exec_trace) = invoc_result.execution_trace {
+                let mut env = trace::base_environment(&post_state, &invoc_result.msg.from())
+                    .context("failed to create trace environment")?;
+                trace::build_traces(&mut env, &[], exec_trace)?;
+                result.trace = env.traces;
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
 /// Get output bytes from trace execution result.
 fn get_trace_output(msg: &Message, invoke_result: &ApiInvocResult) -> Result<EthBytes> {
     if msg.to() == FilecoinAddress::ETHEREUM_ACCOUNT_MANAGER_ACTOR {
@@ -0,0 +1,256 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! State overrides of `eth_call` and related methods, see [`StateOverride`].
+
+use super::trace::{EvmStorageKamt, evm_kamt_config};
+use super::types::{EthAddress, EthBytes, EthHash};
+use super::{EthBigInt, EthUint64};
+use crate::lotus_json::lotus_json_with_self;
+use crate::prelude::*;
+use crate::shim::actors::{
+    EVMActorStateLoad as _, evm, is_ethaccount_actor, is_evm_actor, is_placeholder_actor,
+};
+use crate::shim::econ::TokenAmount;
+use crate::shim::machine::BuiltinActor;
+use crate::shim::state_tree::{ActorState, StateTree};
+use crate::utils::db::CborStoreExt as _;
+use crate::utils::multihash::prelude::*;
+use fil_actor_evm_state::evm_shared::v17::uints::U256;
+use fvm_ipld_encoding::IPLD_RAW;
+use fvm_ipld_kamt::Kamt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Account state to replace before simulating a message, keyed by account address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct StateOverride(pub BTreeMap<EthAddress, AccountOverride>);
+lotus_json_with_self!(StateOverride);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub balance: Option<EthBigInt>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<EthUint64>,
+    /// Contract bytecode, which turns accounts and placeholders into EVM actors.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub code: Option<EthBytes>,
+    /// Replaces the whole contract storage.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<BTreeMap<EthHash, EthHash>>,
+    /// Replaces the given contract storage slots, keeping the others.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state_diff: Option<BTreeMap<EthHash, EthHash>>,
+}
+
+impl StateOverride {
+    /// Applies the overrides on top of `state_root`, returning the new state root. Accounts that
+    /// don't exist yet are created as placeholders, or as EVM actors if `code` is given.
+    ///
+    /// The modified state is written to `store`, which should be an
+    /// [`OverlayBlockstore`](crate::db::OverlayBlockstore) to keep it out of the database.
+    pub fn apply<DB: Blockstore + ShallowClone>(
+        &self,
+        store: &DB,
+        state_root: &Cid,
+    ) -> anyhow::Result<Cid> {
+        let mut state_tree = StateTree::new_from_root(store, state_root)?;
+        for (address, account) in &self.0 {
+            apply_account(&mut state_tree, address, account)
+                .with_context(|| format!("failed to override state of {:#x}", address.0))?;
+        }
+        state_tree.flush()
+    }
+}
+
+fn apply_account<DB: Blockstore + ShallowClone>(
+    state_tree: &mut StateTree<DB>,
+    address: &EthAddress,
+    account: &AccountOverride,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        account.state.is_none() || account.state_diff.is_none(),
+        "state and stateDiff are mutually exclusive"
+    );
+    let (version, evm_code, placeholder_code) = {
+        let bundle = state_tree.get_actor_bundle_metadata()?;
+        (
+            bundle.actor_major_version()?,
+            bundle.manifest.get(BuiltinActor::EVM)?,
+            bundle.manifest.get(BuiltinActor::Placeholder)?,
+        )
+    };
+    let fil_addr = address.to_filecoin_address()?;
+    let mut actor = match state_tree.get_actor(&fil_addr)? {
+        Some(actor) => actor,
+        None => {
+            anyhow::ensure!(!address.is_masked_id(), "actor not found");
+            state_tree.register_new_address(&fil_addr)?;
+            ActorState::new_empty(placeholder_code, Some(fil_addr))
+        }
+    };
+
+    if let Some(balance) = account.balance {
+        actor.balance = TokenAmount::from(balance).into();
+    }
+
+    let store = state_tree.store();
+    let evm_state = if let Some(EthBytes(code)) = &account.code {
+        let bytecode = Cid::new_v1(IPLD_RAW, MultihashCode::Blake2b256.digest(code));
+        store.put_keyed(&bytecode, code)?;
+        let bytecode_hash = keccak_hash::keccak(code).0;
+        if is_evm_actor(&actor.code) {
+            let mut state = evm::State::load(store, actor.code, actor.state)?;
+            state.set_bytecode(bytecode, bytecode_hash);
+            Some(state)
+        } else {
+            anyhow::ensure!(
+                is_placeholder_actor(&actor.code) || is_ethaccount_actor(&actor.code),
+                "code can only be set on EVM actors, Ethereum accounts and placeholders"
+            );
+            let contract_state =
+                EvmStorageKamt::new_with_config(store, evm_kamt_config()).flush()?;
+            actor.code = evm_code;
+            Some(evm::State::new_for_version(
+                version,
+                bytecode,
+                bytecode_hash,
+                contract_state,
+                actor.sequence,
+            )?)
+        }
+    } else if is_evm_actor(&actor.code) {
+        Some(evm::State::load(store, actor.code, actor.state)?)
+    } else {
+        None
+    };
+
+    match evm_state {
+        Some(mut state) => {
+            if let Some(EthUint64(nonce)) = account.nonce {
+                state.set_nonce(nonce);
+            }
+            if let Some(slots) = &account.state {
+                state.set_contract_state(write_storage(store, None, slots)?);
+            }
+            if let Some(slots) = &account.state_diff {
+                let root = state.contract_state();
+                state.set_contract_state(write_storage(store, Some(&root), slots)?);
+            }
+            actor.state = store.put_cbor_default(&state)?;
+        }
+        None => {
+            if let Some(EthUint64(nonce)) = account.nonce {
+                actor.sequence = nonce;
+            }
+            anyhow::ensure!(
+                account.state.is_none() && account.state_diff.is_none(),
+                "storage can only be set on EVM actors"
+            );
+        }
+    }
+
+    state_tree.set_actor(&fil_addr, actor)
+}
+
+/// Writes storage `slots` to a new KAMT, or on top of an existing one. Zero values delete slots,
+/// like `SSTORE` does.
+fn write_storage<DB: Blockstore>(
+    store: &DB,
+    root: Option<&Cid>,
+    slots: &BTreeMap<EthHash, EthHash>,
+) -> anyhow::Result<Cid> {
+    let mut kamt: EvmStorageKamt<&DB> = match root {
+        Some(root) => Kamt::load_with_config(root, store, evm_kamt_config())?,
+        None => Kamt::new_with_config(store, evm_kamt_config()),
+    };
+    for (key, value) in slots {
+        let key = U256::from_big_endian(key.0.as_bytes());
+        let value = U256::from_big_endian(value.0.as_bytes());
+        if value.is_zero() {
+            kamt.delete(&key)?;
+        } else {
+            kamt.set(key, value)?;
+        }
+    }
+    Ok(kamt.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{MemoryDB, OverlayBlockstore};
+    use crate::networks::ACTOR_BUNDLES_METADATA;
+    use crate::shim::address::Address;
+    use crate::shim::state_tree::StateTreeVersion;
+
+    #[test]
+    fn overrides_contract_code_nonce_and_storage() {
+        let store = Arc::new(MemoryDB::default());
+        let bundle = ACTOR_BUNDLES_METADATA
+            .values()
+            .find(|it| it.actor_major_version().ok() == Some(17))
+            .unwrap();
+        let evm_code = bundle.manifest.get(BuiltinActor::EVM).unwrap();
+        let contract_state = EvmStorageKamt::new_with_config(&store, evm_kamt_config())
+            .flush()
+            .unwrap();
+        let contract =
+            evm::State::new_for_version(17, Cid::default(), [0; 32], contract_state, 1).unwrap();
+        let contract = ActorState::new(
+            evm_code,
+            store.put_cbor_default(&contract).unwrap(),
+            TokenAmount::from_atto(0),
+            0,
+            None,
+        );
+
+        let mut state_tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &Address::SYSTEM_ACTOR,
+                ActorState::new_empty(bundle.manifest.get_system(), None),
+            )
+            .unwrap();
+        state_tree
+            .set_actor(&Address::new_id(1000), contract)
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let (slot, value) = (EthHash::from(ethereum_types::H256::from_low_u64_be(1)), {
+            EthHash::from(ethereum_types::H256::from_low_u64_be(42))
+        });
+        let state_override = StateOverride(BTreeMap::from([(
+            EthAddress::from_actor_id(1000),
+            AccountOverride {
+                balance: Some(EthBigInt::from(7)),
+                nonce: Some(EthUint64(5)),
+                code: Some(EthBytes(b"Cthulhu".to_vec())),
+                state_diff: Some(BTreeMap::from([(slot, value)])),
+                ..Default::default()
+            },
+        )]));
+        // Simulations apply the overrides on an overlay, leaving the database unchanged.
+        let base_len = store.blockstore_len();
+        let overlay = Arc::new(OverlayBlockstore::new(store.shallow_clone().into()));
+        let state_root = state_override.apply(&overlay, &state_root).unwrap();
+        assert_eq!(store.blockstore_len(), base_len);
+        assert!(!store.has(&state_root).unwrap());
+
+        let actor = StateTree::new_from_root(&overlay, &state_root)
+            .unwrap()
+            .get_required_actor(&Address::new_id(1000))
+            .unwrap();
+        assert_eq!(TokenAmount::from(&actor.balance), TokenAmount::from_atto(7));
+        let state = evm::State::load(&overlay, actor.code, actor.state).unwrap();
+        assert_eq!(state.nonce(), 5);
+        assert_eq!(state.bytecode_hash(), keccak_hash::keccak(b"Cthulhu").0);
+        let kamt: EvmStorageKamt<&Arc<OverlayBlockstore>> =
+            Kamt::load_with_config(&state.contract_state(), &overlay, evm_kamt_config()).unwrap();
+        assert_eq!(kamt.get(&U256::from(1)).unwrap(), Some(&U256::from(42)));
+    }
+}
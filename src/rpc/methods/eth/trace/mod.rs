@@ -21,7 +21,7 @@ mod utils;
 
 pub(super) use geth::*;
 pub(super) use parity::*;
-pub(crate) use state_diff::build_state_diff;
+pub(crate) use state_diff::{EvmStorageKamt, build_state_diff, evm_kamt_config};
 
 use super::lookup_eth_address;
 use super::types::EthAddress;
@@ -23,7 +23,7 @@ use tracing::debug;
 
 /// KAMT configuration matching the EVM actor in builtin-actors.
 // Code is taken from: https://github.com/filecoin-project/builtin-actors/blob/v17.0.0/actors/evm/src/interpreter/system.rs#L47
-fn evm_kamt_config() -> KamtConfig {
+pub(crate) fn evm_kamt_config() -> KamtConfig {
     KamtConfig {
         bit_width: 5,       // 32 children per node (2^5)
         min_data_depth: 0,  // Data can be stored at root level
@@ -33,7 +33,7 @@ fn evm_kamt_config() -> KamtConfig {
 
 /// Hash algorithm for EVM storage KAMT.
 // Code taken from: https://github.com/filecoin-project/builtin-actors/blob/v17.0.0/actors/evm/src/interpreter/system.rs#L49.
-struct EvmStateHashAlgorithm;
+pub(crate) struct EvmStateHashAlgorithm;
 
 impl AsHashedKey<U256, 32> for EvmStateHashAlgorithm {
     fn as_hashed_key(key: &U256) -> Cow<'_, HashedKey<32>> {
@@ -42,7 +42,7 @@ impl AsHashedKey<U256, 32> for EvmStateHashAlgorithm {
 }
 
 /// Type alias for EVM storage KAMT with configuration.
-type EvmStorageKamt<BS> = Kamt<BS, U256, U256, EvmStateHashAlgorithm>;
+pub(crate) type EvmStorageKamt<BS> = Kamt<BS, U256, U256, EvmStateHashAlgorithm>;
 
 /// Build state diff by comparing pre and post-execution states for touched addresses.
 pub fn build_state_diff<S: Blockstore, T: Blockstore>(
@@ -376,6 +376,23 @@ impl EthCallMessage {
     }
 }
 
+/// Result of one transaction of `eth_callMany`.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EthCallManyResult {
+    /// Return data of the call, or the revert data if it failed.
+    pub output: EthBytes,
+    pub gas_used: EthUint64,
+    /// `1` if the call succeeded, `0` otherwise.
+    pub status: EthUint64,
+    /// Revert reason of a failed call.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+    /// Parity style traces of the call.
+    pub trace: Vec<EthTrace>,
+}
+lotus_json_with_self!(EthCallManyResult);
+
 impl TryFrom<EthCallMessage> for Message {
     type Error = anyhow::Error;
     fn try_from(tx: EthCallMessage) -> Result<Self, Self::Error> {
@@ -11,7 +11,8 @@ mod types;
 mod util;
 
 pub use self::types::{
-    F3InstanceProgress, F3LeaseManager, F3Manifest, F3PowerEntry, FinalityCertificate,
+    ECTipSet, F3InstanceProgress, F3LeaseManager, F3Manifest, F3PowerEntry, FinalityCertificate,
+    PowerTableDelta, SupplementalData,
 };
 use self::{types::*, util::*};
 use super::wallet::WalletSign;
@@ -13,7 +13,7 @@ use crate::shim::{
     econ::{BLOCK_GAS_LIMIT, TokenAmount},
     message::Message,
 };
-use crate::state_manager::VMFlush;
+use crate::state_manager::{StateOverlay, VMFlush};
 use anyhow::Result;
 use enumflags2::BitFlags;
 use num::BigInt;
@@ -200,7 +200,7 @@ impl RpcMethod<2> for GasEstimateGasLimit {
         (msg, tsk): Self::Params,
         _: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
-        Ok(Self::estimate_gas_limit(&ctx, msg, &tsk).await?)
+        Ok(Self::estimate_gas_limit(&ctx, msg, &tsk, None).await?)
     }
 }
 
@@ -210,21 +210,25 @@ impl GasEstimateGasLimit {
         data: &Ctx,
         mut msg: Message,
         tsk: &ApiTipsetKey,
+        overlay: Option<StateOverlay>,
     ) -> anyhow::Result<(ApplyRet, Arc<Vec<ChainMessage>>, Tipset, Address)> {
         msg.set_gas_limit(BLOCK_GAS_LIMIT);
         msg.set_gas_fee_cap(TokenAmount::from_atto(0));
         msg.set_gas_premium(TokenAmount::from_atto(0));
-        Self::probe_as_specified(data, msg, tsk, VMTrace::NotTraced).await
+        Self::probe_as_specified(data, msg, tsk, overlay, VMTrace::NotTraced).await
     }
 
     /// Runs `msg` exactly as given. The limit and fees are left alone: a gas search compares this
     /// against runs of the same message, so rewriting either here would measure something the
     /// caller never asked about. Also returns the resolved sender so a caller can avoid
     /// resolving it a second time.
+    ///
+    /// With an `overlay`, the message runs on its state alone, without the pending messages.
     pub async fn probe_as_specified(
         data: &Ctx,
         msg: Message,
         ApiTipsetKey(tsk): &ApiTipsetKey,
+        overlay: Option<StateOverlay>,
         vm_trace: VMTrace,
     ) -> anyhow::Result<(ApplyRet, Arc<Vec<ChainMessage>>, Tipset, Address)> {
         let curr_ts = data.chain_store().load_required_tipset_or_heaviest(tsk)?;
@@ -233,13 +237,19 @@ impl GasEstimateGasLimit {
             .resolve_to_deterministic_address(msg.from, &curr_ts)
             .await?;
 
-        let pending = data.mpool.pending_for(&from_a).await;
-        let prior_messages: Arc<Vec<ChainMessage>> = pending
-            .map(|s| s.into_iter().map(Into::into).collect_vec())
-            .unwrap_or_default()
-            .into();
-
-        let ts = data.mpool.current_tipset();
+        let (prior_messages, ts): (Arc<Vec<ChainMessage>>, _) = match &overlay {
+            Some(overlay) => (Default::default(), overlay.tipset.shallow_clone()),
+            None => {
+                let pending = data.mpool.pending_for(&from_a).await;
+                (
+                    pending
+                        .map(|s| s.into_iter().map(Into::into).collect_vec())
+                        .unwrap_or_default()
+                        .into(),
+                    data.mpool.current_tipset(),
+                )
+            }
+        };
         // A zeroed signature, because its length changes the inclusion cost.
         let chain_msg = ChainMessage::for_gas_estimation(msg, from_a.protocol());
 
@@ -249,6 +259,7 @@ impl GasEstimateGasLimit {
                 chain_msg,
                 prior_messages.shallow_clone(),
                 Some(ts.shallow_clone()),
+                overlay,
                 VMFlush::Skip,
                 vm_trace,
             )
@@ -256,8 +267,13 @@ impl GasEstimateGasLimit {
         Ok((apply_ret, prior_messages, ts, from_a))
     }
 
-    pub async fn estimate_gas_limit(data: &Ctx, msg: Message, tsk: &ApiTipsetKey) -> Result<i64> {
-        let (apply_ret, ..) = Self::measure_gas_used(data, msg, tsk)
+    pub async fn estimate_gas_limit(
+        data: &Ctx,
+        msg: Message,
+        tsk: &ApiTipsetKey,
+        overlay: Option<StateOverlay>,
+    ) -> Result<i64> {
+        let (apply_ret, ..) = Self::measure_gas_used(data, msg, tsk, overlay)
             .await
             .context("gas estimation failed")?;
         anyhow::ensure!(
@@ -287,7 +303,7 @@ impl RpcMethod<3> for GasEstimateMessageGas {
         (msg, spec, tsk): Self::Params,
         _: &http::Extensions,
     ) -> Result<Self::Ok, ServerError> {
-        let message = estimate_message_gas(&ctx, msg, spec, tsk).await?;
+        let message = estimate_message_gas(&ctx, msg, spec, tsk, None).await?;
         Ok(message)
     }
 }
@@ -297,9 +313,10 @@ pub async fn estimate_message_gas(
     mut msg: Message,
     msg_spec: Option<MessageSendSpec>,
     tsk: ApiTipsetKey,
+    overlay: Option<StateOverlay>,
 ) -> Result<Message, ServerError> {
     if msg.gas_limit == 0 {
-        let gl = GasEstimateGasLimit::estimate_gas_limit(data, msg.clone(), &tsk).await?;
+        let gl = GasEstimateGasLimit::estimate_gas_limit(data, msg.clone(), &tsk, overlay).await?;
         let gl = gl as f64 * data.mpool.gas_limit_overestimation();
         msg.set_gas_limit((gl as u64).min(BLOCK_GAS_LIMIT));
     }
@@ -313,7 +313,7 @@ impl RpcMethod<2> for MpoolPushMessage {
         let _sender_guard = ctx.mpool_locker.take_lock(key_addr).await;
 
         let mut message =
-            estimate_message_gas(&ctx, message, send_spec, Default::default()).await?;
+            estimate_message_gas(&ctx, message, send_spec, Default::default(), None).await?;
         if message.gas_premium > message.gas_fee_cap {
             return Err(anyhow::anyhow!(
                 "After estimation, gas premium is greater than gas fee cap"
@@ -46,9 +46,10 @@ use crate::shim::{
 };
 use crate::state_manager::{ExecutedTipset, NO_CALLBACK};
 use crate::state_manager::{MarketBalance, StateManager, utils::structured};
+use crate::statediff::{STATE_DIFF_MAX_ACTORS, StateDiffPage, StateDiffPaging, diff_state_roots};
 use crate::utils::db::car_stream::{CarBlock, CarWriter};
 use crate::{
-    beacon::BeaconEntry,
+    beacon::{BeaconEntry, BeaconEntryStore},
     rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError, types::*},
 };
 use ahash::{HashMap, HashSet};
@@ -136,6 +137,30 @@ impl RpcMethod<2> for StateCall {
     }
 }
 
+pub enum ForestStateCallMany {}
+
+impl RpcMethod<2> for ForestStateCallMany {
+    const NAME: &'static str = "Forest.StateCallMany";
+    const PARAM_NAMES: [&'static str; 2] = ["messages", "tipsetKey"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Runs the given messages one after another on the tipset's parent state, without persisting changes. Each message sees the effects of the previous ones. Bundles are limited to 100 messages and a total of 50 billion gas.";
+
+    type Params = (Vec<Message>, ApiTipsetKey);
+    type Ok = Vec<ApiInvocResult>;
+
+    async fn handle(
+        ctx: Ctx,
+        (messages, ApiTipsetKey(tsk)): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let overlay = ctx.state_manager.state_overlay(tipset).await?;
+        let (results, _) = ctx.state_manager.call_many(messages, overlay).await?;
+        Ok(results)
+    }
+}
+
 pub enum StateReplay {}
 impl RpcMethod<2> for StateReplay {
     const NAME: &'static str = "Filecoin.StateReplay";
@@ -1393,6 +1418,43 @@ impl RpcMethod<2> for StateSearchMsgLimited {
     }
 }
 
+pub enum ForestStateDiff {}
+
+impl RpcMethod<4> for ForestStateDiff {
+    const NAME: &'static str = "Forest.StateDiff";
+    const N_REQUIRED_PARAMS: usize = 2;
+    const PARAM_NAMES: [&'static str; 4] = ["rootA", "rootB", "includeFields", "paging"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Returns the actors added, removed or changed from the state tree at rootA to the one at rootB, with their code CIDs and balance and nonce deltas. When includeFields is set, also returns the changed top-level fields of decoded builtin actor states. Actors are returned in pages of at most paging.limit (default and maximum 1000) actors, ordered by the hash of their address; the next page is requested with the nextCursor of the previous one as paging.cursor, which is only set if the page is full.";
+
+    type Params = (Cid, Cid, Option<bool>, Option<StateDiffPaging>);
+    type Ok = StateDiffPage;
+
+    async fn handle(
+        ctx: Ctx,
+        (root_a, root_b, include_fields, paging): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let db = ctx.db_owned();
+        let StateDiffPaging { cursor, limit } = paging.unwrap_or_default();
+        let limit = limit
+            .and_then(|it| usize::try_from(it).ok())
+            .unwrap_or(STATE_DIFF_MAX_ACTORS);
+        Ok(tokio::task::spawn_blocking(move || {
+            diff_state_roots(
+                &db,
+                &root_a,
+                &root_b,
+                include_fields.unwrap_or_default(),
+                cursor.as_ref(),
+                limit,
+            )
+        })
+        .await??)
+    }
+}
+
 // Sample CIDs (useful for testing):
 //   Mainnet:
 //     1,594,681 bafy2bzaceaclaz3jvmbjg3piazaq5dcesoyv26cdpoozlkzdiwnsvdvm2qoqm OhSnap upgrade
@@ -2237,10 +2299,17 @@ impl RpcMethod<1> for StateGetBeaconEntry {
             }
         }
 
-        // Future epoch (or missing locally): wait only until the drand round is produced.
+        // Then from the entries stored while syncing, e.g. for the epochs whose tipsets were
+        // garbage collected.
         let (_, beacon) = ctx.beacon().beacon_for_epoch(epoch)?;
         let network_version = ctx.state_manager.get_network_version(epoch);
         let round = beacon.max_beacon_round_for_epoch(network_version, epoch)?;
+        let store = BeaconEntryStore::new(ctx.db());
+        if let Some(entry) = store.get(beacon.network(), round)? {
+            return Ok(entry);
+        }
+
+        // Future epoch (or missing locally): wait only until the drand round is produced.
         let entry = beacon.entry_when_available(round).await?;
         Ok(entry)
     }
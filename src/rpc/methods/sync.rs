@@ -3,9 +3,12 @@
 
 mod types;
 
-use crate::blocks::{Block, FullTipset, GossipBlock};
+use crate::blocks::{Block, CachingBlockHeader, FullTipset, GossipBlock};
 use crate::chain;
-use crate::chain_sync::{NodeSyncStatus, SyncStatusReport, TipsetValidator};
+use crate::chain_sync::{
+    BadBlock, BadBlockOrigin, BlockValidationReport, NodeSyncStatus, SyncStatusReport,
+    TipsetValidator, explain_block,
+};
 use crate::libp2p::{IdentTopic, NetworkMessage, PUBSUB_BLOCK_STR};
 use crate::prelude::*;
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError};
@@ -36,7 +39,7 @@ impl RpcMethod<1> for SyncCheckBad {
             .as_ref()
             .context("bad block cache is disabled")?
             .get(&cid)
-            .map(|_| "bad".to_string())
+            .map(|block| block.to_string())
             .unwrap_or_default())
     }
 }
@@ -60,11 +63,70 @@ impl RpcMethod<1> for SyncMarkBad {
         ctx.bad_blocks
             .as_ref()
             .context("bad block cache is disabled")?
-            .push(cid);
+            .mark(
+                cid,
+                BadBlock::new(
+                    "marked bad by the operator",
+                    ctx.chain_store().heaviest_tipset().epoch(),
+                    BadBlockOrigin::Operator,
+                ),
+            );
         Ok(())
     }
 }
 
+pub enum SyncUnmarkBad {}
+impl RpcMethod<1> for SyncUnmarkBad {
+    const NAME: &'static str = "Filecoin.SyncUnmarkBad";
+    const PARAM_NAMES: [&'static str; 1] = ["cid"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Admin;
+    const DESCRIPTION: &'static str = "Unmarks the block with the given CID as bad.";
+
+    type Params = (Cid,);
+    type Ok = ();
+
+    async fn handle(
+        ctx: Ctx,
+        (cid,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        ctx.bad_blocks
+            .as_ref()
+            .context("bad block cache is disabled")?
+            .unmark(&cid);
+        Ok(())
+    }
+}
+
+pub enum SyncValidateBlock {}
+impl RpcMethod<1> for SyncValidateBlock {
+    const NAME: &'static str = "Forest.SyncValidateBlock";
+    const PARAM_NAMES: [&'static str; 1] = ["cid"];
+    const API_PATHS: BitFlags<ApiPaths> = ApiPaths::all();
+    const PERMISSION: Permission = Permission::Read;
+    const DESCRIPTION: &'static str = "Re-validates the block with the given CID against its stored parent state and returns the outcome of every rule checked.";
+
+    type Params = (Cid,);
+    type Ok = BlockValidationReport;
+
+    async fn handle(
+        ctx: Ctx,
+        (cid,): Self::Params,
+        _: &http::Extensions,
+    ) -> Result<Self::Ok, ServerError> {
+        let header = CachingBlockHeader::load(ctx.db(), cid)?
+            .with_context(|| format!("block {cid} not found"))?;
+        let (bls_messages, secp_messages) = chain::store::block_messages(ctx.db(), &header)?;
+        let block = Block {
+            header,
+            bls_messages,
+            secp_messages,
+        };
+        Ok(explain_block(&ctx.state_manager, Arc::new(block)).await?)
+    }
+}
+
 pub enum SyncSnapshotProgress {}
 impl RpcMethod<0> for SyncSnapshotProgress {
     const NAME: &'static str = "Forest.SyncSnapshotProgress";
@@ -234,6 +296,7 @@ mod tests {
         let state = Arc::new(RPCState {
             state_manager,
             keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory).unwrap())),
+            token_revocations: Default::default(),
             mpool,
             bad_blocks: Some(Default::default()),
             sync_status: Default::default(),
@@ -272,7 +335,16 @@ mod tests {
         let reason = SyncCheckBad::handle(ctx.clone(), (cid,), &Default::default())
             .await
             .unwrap();
-        assert_eq!(reason, "bad");
+        assert!(reason.starts_with("marked bad by the operator"));
+
+        SyncUnmarkBad::handle(ctx.clone(), (cid,), &Default::default())
+            .await
+            .unwrap();
+
+        let reason = SyncCheckBad::handle(ctx.clone(), (cid,), &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(reason, "");
     }
 
     #[tokio::test]
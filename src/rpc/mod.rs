@@ -12,9 +12,11 @@ pub mod json_validator;
 mod log_layer;
 mod metrics_layer;
 mod parallel_batch_layer;
+mod rate_limit_layer;
 mod reflect;
 mod registry;
 mod request;
+mod response_cache_layer;
 mod segregation_layer;
 mod set_extension_layer;
 pub mod types;
@@ -37,9 +39,13 @@ use jsonrpsee::server::ServerConfig;
 use log_layer::LogLayer;
 pub use metrics_layer::MetricsMode;
 use parallel_batch_layer::ParallelBatchLayer;
+use rate_limit_layer::RateLimitLayer;
+pub use rate_limit_layer::RateLimiter;
 use reflect::Ctx;
 pub use reflect::{ApiPaths, Permission, RpcMethod, RpcMethodExt};
 pub use request::Request;
+pub use response_cache_layer::ResponseCache;
+use response_cache_layer::ResponseCacheLayer;
 use schemars::Schema;
 use segregation_layer::SegregationLayer;
 use set_extension_layer::SetExtensionLayer;
@@ -65,6 +71,8 @@ macro_rules! for_each_rpc_method {
         // auth vertical
         $callback!($crate::rpc::auth::AuthNew);
         $callback!($crate::rpc::auth::AuthVerify);
+        $callback!($crate::rpc::auth::AuthRevoke);
+        $callback!($crate::rpc::auth::AuthRotateKey);
 
         // beacon vertical
         $callback!($crate::rpc::beacon::BeaconGetEntry);
@@ -86,6 +94,9 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::chain::ChainGetTipSet);
         $callback!($crate::rpc::chain::ChainGetTipSetV2);
         $callback!($crate::rpc::chain::ChainGetTipSetFinalityStatus);
+        $callback!($crate::rpc::chain::ChainFinalityProbability);
+        $callback!($crate::rpc::chain::ChainReorgs);
+        $callback!($crate::rpc::chain::BlockTimeline);
         $callback!($crate::rpc::chain::ChainGetTipSetAfterHeight);
         $callback!($crate::rpc::chain::ChainGetTipSetByHeight);
         $callback!($crate::rpc::chain::ChainHasObj);
@@ -153,6 +164,7 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::eth::EthSyncing);
         $callback!($crate::rpc::eth::EthTraceBlock);
         $callback!($crate::rpc::eth::EthTraceCall);
+        $callback!($crate::rpc::eth::EthCallMany);
         $callback!($crate::rpc::eth::EthTraceFilter);
         $callback!($crate::rpc::eth::EthTraceTransaction);
         $callback!($crate::rpc::eth::EthDebugTraceTransaction);
@@ -214,11 +226,13 @@ macro_rules! for_each_rpc_method {
         // state vertical
         $callback!($crate::rpc::state::StateAccountKey);
         $callback!($crate::rpc::state::StateCall);
+        $callback!($crate::rpc::state::ForestStateCallMany);
         $callback!($crate::rpc::state::StateCirculatingSupply);
         $callback!($crate::rpc::state::ForestStateCompute);
         $callback!($crate::rpc::state::StateCompute);
         $callback!($crate::rpc::state::StateDealProviderCollateralBounds);
         $callback!($crate::rpc::state::StateFetchRoot);
+        $callback!($crate::rpc::state::ForestStateDiff);
         $callback!($crate::rpc::state::StateGetActor);
         $callback!($crate::rpc::state::StateGetActorV2);
         $callback!($crate::rpc::state::StateGetID);
@@ -286,9 +300,11 @@ macro_rules! for_each_rpc_method {
         // sync vertical
         $callback!($crate::rpc::sync::SyncCheckBad);
         $callback!($crate::rpc::sync::SyncMarkBad);
+        $callback!($crate::rpc::sync::SyncUnmarkBad);
         $callback!($crate::rpc::sync::SyncSnapshotProgress);
         $callback!($crate::rpc::sync::SyncStatus);
         $callback!($crate::rpc::sync::SyncSubmitBlock);
+        $callback!($crate::rpc::sync::SyncValidateBlock);
 
         // wallet vertical
         $callback!($crate::rpc::wallet::WalletBalance);
@@ -446,12 +462,14 @@ use crate::{chain_sync::network_context::SyncNetworkContext, key_management::Key
 
 use crate::blocks::FullTipset;
 use crate::utils::misc::env::env_or_default;
+use crate::utils::net::tls::{MaybeTlsListener, TlsAcceptor, peer_subject};
 use jsonrpsee::{
     Methods,
     core::middleware::RpcServiceBuilder,
     server::{RpcModule, Server, StopHandle, TowerServiceBuilder},
 };
 use parking_lot::RwLock;
+use std::collections::BTreeMap;
 use std::env;
 use std::sync::{Arc, LazyLock};
 use std::time::Duration;
@@ -501,6 +519,7 @@ static MAX_RESPONSE_BODY_SIZE: LazyLock<u32> =
 /// data.
 pub struct RPCState {
     pub keystore: Arc<RwLock<KeyStore>>,
+    pub token_revocations: Arc<crate::auth::TokenRevocations>,
     pub state_manager: crate::state_manager::StateManager,
     pub mpool: crate::message_pool::MessagePool<crate::chain::ChainStore>,
     pub bad_blocks: Option<crate::chain_sync::BadBlockCache>,
@@ -560,6 +579,7 @@ struct PerConnection<RpcMiddleware, HttpMiddleware> {
     stop_handle: StopHandle,
     svc_builder: Arc<TowerServiceBuilder<RpcMiddleware, HttpMiddleware>>,
     keystore: Arc<RwLock<KeyStore>>,
+    token_revocations: Arc<crate::auth::TokenRevocations>,
 }
 
 /// A bare HTTP response carrying just `status` and an empty body.
@@ -570,17 +590,38 @@ fn bare_http_response<B: Default>(status: http::StatusCode) -> http::Response<B>
         .unwrap_or_else(|_| http::Response::new(B::default()))
 }
 
+/// TLS settings of the RPC server
+pub struct RpcTls {
+    pub acceptor: Arc<TlsAcceptor>,
+    /// Permissions granted to clients that authenticate with a certificate instead of a
+    /// token, keyed by certificate subject.
+    pub client_permissions: BTreeMap<String, Vec<String>>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn start_rpc(
     state: RPCState,
     rpc_listener: tokio::net::TcpListener,
+    tls: Option<RpcTls>,
     stop_handle: StopHandle,
     filter_list: Option<Arc<FilterList>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    response_cache: Option<Arc<ResponseCache>>,
     metrics_mode: MetricsMode,
 ) -> anyhow::Result<()> {
     let filter_list = filter_list.unwrap_or_default();
+    let (acceptor, client_permissions) = match tls {
+        Some(RpcTls {
+            acceptor,
+            client_permissions,
+        }) => (Some(acceptor), client_permissions),
+        None => Default::default(),
+    };
+    let mut rpc_listener = MaybeTlsListener::new(rpc_listener, acceptor)?;
     // `Arc` is needed because we will share the state between two modules
     let state = Arc::new(state);
     let keystore = state.keystore.shallow_clone();
+    let token_revocations = state.token_revocations.shallow_clone();
     let mut modules = create_modules(state.shallow_clone());
 
     let mut pubsub_module = FilRpcModule::default();
@@ -621,29 +662,27 @@ pub async fn start_rpc(
             .to_service_builder()
             .into(),
         keystore,
+        token_revocations,
     };
     tracing::info!("Ready for RPC connections");
     loop {
-        let sock = tokio::select! {
-        res = rpc_listener.accept() => {
-            match res {
-              Ok((stream, _remote_addr)) => {
-                let _ = stream.set_nodelay(true); // Disable Nagle's algorithm
-                stream
-              }
-              Err(e) => {
-                tracing::error!("failed to accept v4 connection: {:?}", e);
-                continue;
-              }
+        let (sock, remote_addr) = tokio::select! {
+            accepted = axum::serve::Listener::accept(&mut rpc_listener) => accepted,
+            _ = per_conn.stop_handle.clone().shutdown() => break,
+        };
+        let certificate_permissions = match &sock {
+            tokio_util::either::Either::Right(stream) => {
+                peer_subject(stream).and_then(|subject| client_permissions.get(&subject).cloned())
             }
-          }
-          _ = per_conn.stop_handle.clone().shutdown() => break,
+            tokio_util::either::Either::Left(_) => None,
         };
 
         let svc = tower::service_fn({
             let methods = methods.shallow_clone();
             let per_conn = per_conn.clone();
             let filter_list = filter_list.shallow_clone();
+            let rate_limiter = rate_limiter.clone();
+            let response_cache = response_cache.clone();
             move |req: http::Request<_>| {
                 let svc_or_result = if let Ok(path) = ApiPaths::from_uri(req.uri()) {
                     let methods = methods.get(&path).cloned().unwrap_or_default();
@@ -651,13 +690,25 @@ pub async fn start_rpc(
                         stop_handle,
                         svc_builder,
                         keystore,
+                        token_revocations,
                     } = per_conn.clone();
                     // Authenticate the connection once, here at the HTTP layer (for a
                     // WebSocket this is the upgrade request), before any JSON-RPC
                     // dispatch.
-                    match resolve_claims(&keystore, req.headers().get(http::header::AUTHORIZATION))
-                    {
+                    match resolve_claims(
+                        &keystore,
+                        &token_revocations,
+                        req.headers().get(http::header::AUTHORIZATION),
+                        certificate_permissions.as_deref(),
+                    ) {
                         Ok(claims) => {
+                            let rate_limit = rate_limiter.as_ref().map(|limiter| {
+                                let client = limiter.client_key(
+                                    remote_addr.ip(),
+                                    req.headers().get(http::header::AUTHORIZATION),
+                                );
+                                RateLimitLayer::new(limiter.shallow_clone(), client)
+                            });
                             // NOTE, the rpc middleware must be initialized here to be able to be created once per connection
                             // with data from the connection such as the headers in this example
                             let rpc_middleware = RpcServiceBuilder::new()
@@ -666,12 +717,15 @@ pub async fn start_rpc(
                                 .layer(FilterLayer::new(filter_list.shallow_clone()))
                                 .layer(validation_layer::JsonValidationLayer)
                                 .layer(AuthLayer::new(claims))
+                                .option_layer(rate_limit)
                                 .layer(LogLayer::default())
                                 // `ParallelBatchLayer` fans a batch out into per-entry `call`s, so it must be
                                 // outer to `MetricsLayer` for batched methods to be measured. Both must stay
                                 // inner to the batch-transforming layers above.
                                 .layer(ParallelBatchLayer::new(max_response_body_size))
-                                .layer(MetricsLayer::new(metrics_mode));
+                                .layer(MetricsLayer::new(metrics_mode))
+                                // Inner to `MetricsLayer`, so that cache hits are measured too.
+                                .option_layer(response_cache.clone().map(ResponseCacheLayer::new));
                             Either::Left(
                                 Arc::unwrap_or_clone(svc_builder)
                                     .set_rpc_middleware(rpc_middleware)
@@ -1101,6 +1155,7 @@ mod tests {
             &state.keystore.read(),
             chrono::Duration::hours(1),
             jwt_read_permissions.clone(),
+            Default::default(),
         )
         .unwrap();
         let rpc_listener =
@@ -1115,8 +1170,11 @@ mod tests {
         let handle = tokio::spawn(start_rpc(
             state,
             rpc_listener,
+            None,
             stop_handle,
             None,
+            None,
+            None,
             MetricsMode::Enabled,
         ));
 
@@ -1200,6 +1258,7 @@ mod tests {
                 .parse()
                 .unwrap(),
             None,
+            None,
         )
         .await
         .unwrap();
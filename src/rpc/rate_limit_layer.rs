@@ -0,0 +1,303 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::cli_shared::cli::{RpcRateLimitConfig, RpcRateLimitKey};
+use crate::metrics;
+use crate::prelude::*;
+use crate::rpc::error::implementation_defined_errors::RATE_LIMITED;
+use crate::rpc::{CANCEL_METHOD_NAME, RpcMethod as _, chain};
+use crate::utils::rate_limit::TokenBucket;
+use ahash::HashMap;
+use anyhow::ensure;
+use futures::future::Either;
+use http::header::HeaderValue;
+use jsonrpsee::MethodResponse;
+use jsonrpsee::core::middleware::{Batch, BatchEntry, BatchEntryErr, Notification};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::{ErrorObject, ErrorObjectOwned, Id};
+use parking_lot::Mutex;
+use quick_cache::sync::Cache;
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use tower::Layer;
+
+/// Canonical method names, keyed by both the name and the alias of every method.
+static METHOD_NAMES: LazyLock<HashMap<&str, &'static str>> = LazyLock::new(|| {
+    let mut names = HashMap::default();
+    macro_rules! insert {
+        ($ty:ty) => {
+            names.insert(<$ty>::NAME, <$ty>::NAME);
+            if let Some(alias) = <$ty>::NAME_ALIAS {
+                names.insert(alias, <$ty>::NAME);
+            }
+        };
+    }
+    super::for_each_rpc_method!(insert);
+    names.insert(chain::CHAIN_NOTIFY, chain::CHAIN_NOTIFY);
+    names.insert(CANCEL_METHOD_NAME, CANCEL_METHOD_NAME);
+    names
+});
+
+/// Request budgets of RPC clients, shared by all connections.
+pub struct RateLimiter {
+    key: RpcRateLimitKey,
+    burst: f64,
+    refill_per_sec: f64,
+    /// Call costs, keyed by canonical method names.
+    costs: HashMap<&'static str, f64>,
+    buckets: Cache<Arc<str>, Arc<Mutex<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    /// Number of clients whose budget is tracked. The least recently seen clients are
+    /// evicted first, and start over with a full budget.
+    const MAX_CLIENTS: usize = 100_000;
+
+    /// Returns [`None`] if rate limiting is disabled.
+    pub fn new(config: &RpcRateLimitConfig) -> anyhow::Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        ensure!(
+            config.burst > 0 && config.refill_per_sec > 0,
+            "RPC rate limit burst and refill rate must be positive"
+        );
+        let mut costs = HashMap::default();
+        for (method, cost) in &config.method_costs {
+            let name = METHOD_NAMES
+                .get(method.as_str())
+                .with_context(|| format!("unknown RPC method in rate limit costs: {method}"))?;
+            // Such a method could never be called.
+            ensure!(
+                *cost <= config.burst,
+                "RPC rate limit cost of {method} ({cost}) exceeds the burst ({})",
+                config.burst
+            );
+            costs.insert(*name, f64::from(*cost));
+        }
+        Ok(Some(Self {
+            key: config.key,
+            burst: f64::from(config.burst),
+            refill_per_sec: f64::from(config.refill_per_sec),
+            costs,
+            buckets: Cache::new(Self::MAX_CLIENTS),
+        }))
+    }
+
+    /// Identifies the client of a connection, as configured by [`RpcRateLimitKey`].
+    pub fn client_key(&self, remote_ip: IpAddr, auth_header: Option<&HeaderValue>) -> Arc<str> {
+        match (self.key, auth_header.and_then(|it| it.to_str().ok())) {
+            (RpcRateLimitKey::Token, Some(token)) => format!("token:{token}").into(),
+            _ => format!("ip:{remote_ip}").into(),
+        }
+    }
+
+    /// Takes the cost of calling `method` out of the budget of `client`, returning `false`
+    /// if the budget is exhausted.
+    fn try_acquire(&self, client: &Arc<str>, method: &str) -> bool {
+        let cost = self.costs.get(method).copied().unwrap_or(1.0);
+        let key = match self.key {
+            RpcRateLimitKey::Method => Arc::from(method),
+            RpcRateLimitKey::Token | RpcRateLimitKey::Ip => Arc::clone(client),
+        };
+        let bucket = match self.buckets.get_or_insert_with(&key, || {
+            Ok::<_, Infallible>(Arc::new(Mutex::new(TokenBucket::new(
+                self.burst,
+                self.refill_per_sec,
+            ))))
+        }) {
+            Ok(bucket) => bucket,
+            Err(infallible) => match infallible {},
+        };
+        bucket.lock().try_acquire(cost)
+    }
+}
+
+/// jsonrpsee layer rejecting calls of clients that exhausted their request budget.
+#[derive(Clone)]
+pub(super) struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    client: Arc<str>,
+}
+
+impl RateLimitLayer {
+    pub(super) fn new(limiter: Arc<RateLimiter>, client: Arc<str>) -> Self {
+        Self { limiter, client }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RateLimit {
+            limiter: self.limiter.shallow_clone(),
+            client: Arc::clone(&self.client),
+            service,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct RateLimit<S> {
+    limiter: Arc<RateLimiter>,
+    client: Arc<str>,
+    service: S,
+}
+
+impl<S> RateLimit<S> {
+    fn check(&self, method_name: &str) -> Result<(), ErrorObjectOwned> {
+        // Unknown methods are rejected by the auth layer before reaching here.
+        let method = METHOD_NAMES.get(method_name).copied().unwrap_or("unknown");
+        if self.limiter.try_acquire(&self.client, method) {
+            Ok(())
+        } else {
+            metrics::RPC_RATE_LIMITED
+                .get_or_create(&metrics::RpcMethodLabel { method })
+                .inc();
+            Err(ErrorObject::owned(
+                RATE_LIMITED,
+                "rate limit exceeded",
+                None::<()>,
+            ))
+        }
+    }
+}
+
+impl<S> RpcServiceT for RateLimit<S>
+where
+    S: RpcServiceT<
+            MethodResponse = MethodResponse,
+            NotificationResponse = MethodResponse,
+            BatchResponse = MethodResponse,
+        > + Send
+        + Sync
+        + Clone
+        + 'static,
+{
+    type MethodResponse = S::MethodResponse;
+    type NotificationResponse = S::NotificationResponse;
+    type BatchResponse = S::BatchResponse;
+
+    fn call<'a>(
+        &self,
+        req: jsonrpsee::types::Request<'a>,
+    ) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+        match self.check(req.method_name()) {
+            Ok(()) => Either::Left(self.service.call(req)),
+            Err(e) => Either::Right(async move { MethodResponse::error(req.id(), e) }),
+        }
+    }
+
+    fn notification<'a>(
+        &self,
+        n: Notification<'a>,
+    ) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+        match self.check(n.method_name()) {
+            Ok(()) => Either::Left(self.service.notification(n)),
+            Err(e) => Either::Right(async move { MethodResponse::error(Id::Null, e) }),
+        }
+    }
+
+    fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+        let entries = batch
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Ok(BatchEntry::Call(req)) => Some(match self.check(req.method_name()) {
+                    Ok(()) => Ok(BatchEntry::Call(req)),
+                    Err(e) => Err(BatchEntryErr::new(req.id(), e)),
+                }),
+                Ok(BatchEntry::Notification(n)) => match self.check(n.method_name()) {
+                    Ok(()) => Some(Ok(BatchEntry::Notification(n))),
+                    Err(_) => None,
+                },
+                Err(err) => Some(Err(err)),
+            })
+            .collect_vec();
+        self.service.batch(Batch::from(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{chain::ChainHead, eth::EthGetLogs};
+    use std::net::Ipv4Addr;
+
+    fn limiter(key: RpcRateLimitKey) -> RateLimiter {
+        RateLimiter::new(&RpcRateLimitConfig {
+            enabled: true,
+            key,
+            burst: 10,
+            refill_per_sec: 1,
+            method_costs: [("eth_getLogs".to_owned(), 10)].into_iter().collect(),
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        assert!(
+            RateLimiter::new(&RpcRateLimitConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn unknown_method_cost_is_rejected() {
+        let config = RpcRateLimitConfig {
+            enabled: true,
+            method_costs: [("Cthulhu.Awaken".to_owned(), 10)].into_iter().collect(),
+            ..Default::default()
+        };
+        assert!(RateLimiter::new(&config).is_err());
+    }
+
+    #[test]
+    fn cost_above_burst_is_rejected() {
+        let config = RpcRateLimitConfig {
+            enabled: true,
+            burst: 10,
+            method_costs: [("eth_getLogs".to_owned(), 11)].into_iter().collect(),
+            ..Default::default()
+        };
+        assert!(RateLimiter::new(&config).is_err());
+    }
+
+    #[test]
+    fn budgets_are_per_client() {
+        let limiter = limiter(RpcRateLimitKey::Ip);
+        let alice = limiter.client_key(Ipv4Addr::new(10, 0, 0, 1).into(), None);
+        let bob = limiter.client_key(Ipv4Addr::new(10, 0, 0, 2).into(), None);
+
+        // Costs configured for an alias apply to the method.
+        assert!(limiter.try_acquire(&alice, EthGetLogs::NAME));
+        assert!(!limiter.try_acquire(&alice, ChainHead::NAME));
+        assert!(limiter.try_acquire(&bob, ChainHead::NAME));
+    }
+
+    #[test]
+    fn token_key_falls_back_to_ip() {
+        let limiter = limiter(RpcRateLimitKey::Token);
+        let ip = Ipv4Addr::LOCALHOST.into();
+        let header = HeaderValue::from_static("Bearer Azathoth");
+        assert_eq!(
+            &*limiter.client_key(ip, Some(&header)),
+            "token:Bearer Azathoth"
+        );
+        assert_eq!(&*limiter.client_key(ip, None), "ip:127.0.0.1");
+    }
+
+    #[test]
+    fn method_key_shares_budget_between_clients() {
+        let limiter = limiter(RpcRateLimitKey::Method);
+        let alice = limiter.client_key(Ipv4Addr::new(10, 0, 0, 1).into(), None);
+        let bob = limiter.client_key(Ipv4Addr::new(10, 0, 0, 2).into(), None);
+        assert!(limiter.try_acquire(&alice, EthGetLogs::NAME));
+        assert!(!limiter.try_acquire(&bob, EthGetLogs::NAME));
+        assert!(limiter.try_acquire(&bob, ChainHead::NAME));
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::cli_shared::cli::RpcCacheConfig;
+use crate::metrics;
+use crate::prelude::*;
+use crate::rpc::ApiPaths;
+use crate::rpc::RpcMethod as _;
+use crate::rpc::chain::{
+    ChainGetBlock, ChainGetBlockMessages, ChainGetMessage, ChainGetParentMessages,
+    ChainGetParentReceipts, ChainGetTipSet, ChainGetTipSetByHeight, ChainGetTipSetV2, ChainReadObj,
+};
+use crate::rpc::eth::{
+    BlockNumberOrHash, EthGetBlockByHash, EthGetBlockReceipts, EthGetTransactionByBlockHashAndIndex,
+};
+use crate::rpc::state::{StateGetActor, StateGetActorV2};
+use crate::rpc::types::TipsetSelector;
+use ahash::HashMap;
+use futures::future::Either;
+use jsonrpsee::core::middleware::{Batch, Notification};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::{MethodResponse, ResponsePayload};
+use quick_cache::{Weighter, sync::Cache};
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::value::RawValue;
+use std::sync::LazyLock;
+use tower::Layer;
+
+/// How the result of a cacheable method is pinned to immutable chain data.
+#[derive(Clone, Copy, Debug)]
+enum Pin {
+    /// All parameters identify immutable objects, e.g. CIDs or block hashes.
+    Content,
+    /// The parameter at the given position is a tipset key. An empty key selects the head.
+    TipsetKey(usize),
+    /// The parameter at the given position is a tipset selector. Only key selectors are
+    /// independent of the head.
+    TipsetSelector(usize),
+    /// The parameter at the given position is an Ethereum block number, tag or hash. Only
+    /// hashes are independent of the head.
+    EthBlock(usize),
+}
+
+impl Pin {
+    /// Whether a call with the given raw parameters always yields the same result.
+    fn is_immutable(self, params: Option<&str>) -> bool {
+        let param = |position: usize| -> Option<Value> {
+            let mut params: Vec<Value> = serde_json::from_str(params?).ok()?;
+            (position < params.len()).then(|| params.swap_remove(position))
+        };
+        match self {
+            Self::Content => true,
+            Self::TipsetKey(position) => {
+                matches!(param(position), Some(Value::Array(cids)) if !cids.is_empty())
+            }
+            Self::TipsetSelector(position) => param(position)
+                .and_then(|it| serde_json::from_value::<TipsetSelector>(it).ok())
+                .is_some_and(|it| it.validate().is_ok() && it.key.0.is_some()),
+            Self::EthBlock(position) => param(position)
+                .and_then(|it| serde_json::from_value::<BlockNumberOrHash>(it).ok())
+                .is_some_and(|it| it.is_head_independent()),
+        }
+    }
+}
+
+struct Cacheable {
+    /// Canonical method name
+    method: &'static str,
+    pin: Pin,
+}
+
+/// Cacheable methods, keyed by API path and by both the name and the alias of each method.
+static CACHEABLE: LazyLock<HashMap<(ApiPaths, &str), Cacheable>> = LazyLock::new(|| {
+    let mut methods = HashMap::default();
+    macro_rules! insert {
+        ($ty:ty, $pin:expr) => {
+            for path in <$ty>::API_PATHS.iter() {
+                let cacheable = || Cacheable {
+                    method: <$ty>::NAME,
+                    pin: $pin,
+                };
+                methods.insert((path, <$ty>::NAME), cacheable());
+                if let Some(alias) = <$ty>::NAME_ALIAS {
+                    methods.insert((path, alias), cacheable());
+                }
+            }
+        };
+    }
+    insert!(ChainGetBlock, Pin::Content);
+    insert!(ChainGetBlockMessages, Pin::Content);
+    insert!(ChainGetMessage, Pin::Content);
+    insert!(ChainGetParentMessages, Pin::Content);
+    insert!(ChainGetParentReceipts, Pin::Content);
+    insert!(ChainReadObj, Pin::Content);
+    insert!(ChainGetTipSet, Pin::TipsetKey(0));
+    insert!(ChainGetTipSetV2, Pin::TipsetSelector(0));
+    insert!(ChainGetTipSetByHeight, Pin::TipsetKey(1));
+    insert!(StateGetActor, Pin::TipsetKey(1));
+    insert!(StateGetActorV2, Pin::TipsetSelector(1));
+    insert!(EthGetBlockByHash, Pin::Content);
+    insert!(EthGetTransactionByBlockHashAndIndex, Pin::Content);
+    insert!(EthGetBlockReceipts, Pin::EthBlock(0));
+    methods
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: ApiPaths,
+    method: &'static str,
+    params: Box<str>,
+}
+
+#[derive(Clone)]
+struct ResponseWeighter;
+
+impl Weighter<CacheKey, Arc<RawValue>> for ResponseWeighter {
+    fn weight(&self, key: &CacheKey, result: &Arc<RawValue>) -> u64 {
+        (key.params.len() + result.get().len()) as u64
+    }
+}
+
+/// Serialized results of RPC calls that do not depend on the current head, shared by all
+/// connections.
+pub struct ResponseCache {
+    results: Cache<CacheKey, Arc<RawValue>, ResponseWeighter>,
+}
+
+impl ResponseCache {
+    /// Rough average size of a cached result, used to size the cache index.
+    const ESTIMATED_ENTRY_SIZE: u64 = 4 * 1024;
+
+    /// Returns [`None`] if response caching is disabled.
+    pub fn new(config: &RpcCacheConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            results: Cache::with_weighter(
+                (config.max_size_bytes / Self::ESTIMATED_ENTRY_SIZE).max(1) as usize,
+                config.max_size_bytes,
+                ResponseWeighter,
+            ),
+        })
+    }
+
+    /// Returns the cache key of a call, or [`None`] if its result may change over time.
+    fn key(path: ApiPaths, method_name: &str, params: Option<&str>) -> Option<CacheKey> {
+        let Cacheable { method, pin } = CACHEABLE.get(&(path, method_name))?;
+        if !pin.is_immutable(params) {
+            return None;
+        }
+        Some(CacheKey {
+            path,
+            method,
+            params: params.unwrap_or_default().into(),
+        })
+    }
+
+    /// Looks up a cached result, counting the hit or miss for `key.method`.
+    fn get(&self, key: &CacheKey) -> Option<Arc<RawValue>> {
+        let label = metrics::RpcMethodLabel { method: key.method };
+        let result = self.results.get(key);
+        if result.is_some() {
+            metrics::RPC_CACHE_HITS.get_or_create(&label).inc();
+        } else {
+            metrics::RPC_CACHE_MISSES.get_or_create(&label).inc();
+        }
+        result
+    }
+
+    /// Caches the result of a successful response. Errors and `null` results (e.g. objects
+    /// that are not available yet) are not cached.
+    fn insert(&self, key: CacheKey, response: &MethodResponse) {
+        #[derive(Deserialize)]
+        struct Success {
+            result: Box<RawValue>,
+        }
+        if !response.is_success() {
+            return;
+        }
+        if let Ok(Success { result }) = serde_json::from_str(response.as_json().get())
+            && result.get() != "null"
+        {
+            self.results.insert(key, result.into());
+        }
+    }
+}
+
+/// jsonrpsee layer serving calls that do not depend on the current head from a
+/// [`ResponseCache`].
+#[derive(Clone)]
+pub(super) struct ResponseCacheLayer {
+    cache: Arc<ResponseCache>,
+}
+
+impl ResponseCacheLayer {
+    pub(super) fn new(cache: Arc<ResponseCache>) -> Self {
+        Self { cache }
+    }
+}
+
+impl<S> Layer<S> for ResponseCacheLayer {
+    type Service = CacheResponses<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        CacheResponses {
+            cache: self.cache.shallow_clone(),
+            service,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct CacheResponses<S> {
+    cache: Arc<ResponseCache>,
+    service: S,
+}
+
+impl<S> RpcServiceT for CacheResponses<S>
+where
+    S: RpcServiceT<MethodResponse = MethodResponse> + Send + Sync + Clone + 'static,
+{
+    type MethodResponse = S::MethodResponse;
+    type NotificationResponse = S::NotificationResponse;
+    type BatchResponse = S::BatchResponse;
+
+    fn call<'a>(
+        &self,
+        req: jsonrpsee::types::Request<'a>,
+    ) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+        let key = req
+            .extensions()
+            .get::<ApiPaths>()
+            .and_then(|path| ResponseCache::key(*path, req.method_name(), req.params().as_str()));
+        let Some(key) = key else {
+            return Either::Left(self.service.call(req));
+        };
+        if let Some(result) = self.cache.get(&key) {
+            // The size of the cached result was checked when it was first served.
+            let response = MethodResponse::response(
+                req.id(),
+                ResponsePayload::success(RawValue::to_owned(&result)),
+                usize::MAX,
+            );
+            return Either::Right(Either::Left(std::future::ready(response)));
+        }
+        let cache = self.cache.shallow_clone();
+        let future = self.service.call(req);
+        Either::Right(Either::Right(async move {
+            let response = future.await;
+            cache.insert(key, &response);
+            response
+        }))
+    }
+
+    fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+        self.service.batch(batch)
+    }
+
+    fn notification<'a>(
+        &self,
+        n: Notification<'a>,
+    ) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+        self.service.notification(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::Id;
+
+    fn cache() -> ResponseCache {
+        ResponseCache::new(&RpcCacheConfig {
+            enabled: true,
+            max_size_bytes: 1024 * 1024,
+        })
+        .unwrap()
+    }
+
+    fn response(result: impl serde::Serialize + Clone) -> MethodResponse {
+        MethodResponse::response(Id::Number(1), ResponsePayload::success(result), usize::MAX)
+    }
+
+    const CID: &str = r#"[{"/":"bafy2bzacedbaliyx3k64d4rxy5q2og3wj3nnkn5mzulqgbuvj7nybfpz7hozi"}]"#;
+
+    #[test]
+    fn disabled_by_default() {
+        assert!(ResponseCache::new(&RpcCacheConfig::default()).is_none());
+    }
+
+    #[test]
+    fn head_relative_calls_are_not_cached() {
+        let key = |path, method, params: &str| ResponseCache::key(path, method, Some(params));
+        let tsk = format!(r#"["f01234", {CID}]"#);
+        assert!(key(ApiPaths::V1, StateGetActor::NAME, &tsk).is_some());
+        assert!(key(ApiPaths::V1, StateGetActor::NAME, r#"["f01234", []]"#).is_none());
+        assert!(key(ApiPaths::V1, StateGetActor::NAME, r#"["f01234", null]"#).is_none());
+        assert!(key(ApiPaths::V1, StateGetActor::NAME, r#"["f01234"]"#).is_none());
+
+        let selector = format!(r#"["f01234", {{"key": {CID}}}]"#);
+        assert!(key(ApiPaths::V2, StateGetActor::NAME, &selector).is_some());
+        let tag = r#"["f01234", {"tag": "finalized"}]"#;
+        assert!(key(ApiPaths::V2, StateGetActor::NAME, tag).is_none());
+
+        let hash = r#"["0x8a3b6dd3b4ae2a5c7cfc7d12c5a3c1c1dd68a4b6d8ad6a3f3a1bf0b2c4e8f1a2"]"#;
+        assert!(key(ApiPaths::V1, "eth_getBlockReceipts", hash).is_some());
+        assert!(key(ApiPaths::V1, "eth_getBlockReceipts", r#"["latest"]"#).is_none());
+        assert!(key(ApiPaths::V1, "eth_getBlockReceipts", r#"["0x10"]"#).is_none());
+
+        // Not cacheable at all
+        assert!(key(ApiPaths::V1, "Filecoin.ChainHead", "[]").is_none());
+    }
+
+    #[test]
+    fn aliases_share_entries() {
+        let params = Some(
+            r#"["0x8a3b6dd3b4ae2a5c7cfc7d12c5a3c1c1dd68a4b6d8ad6a3f3a1bf0b2c4e8f1a2", false]"#,
+        );
+        assert_eq!(
+            ResponseCache::key(ApiPaths::V1, "eth_getBlockByHash", params),
+            ResponseCache::key(ApiPaths::V1, EthGetBlockByHash::NAME, params),
+        );
+        assert_ne!(
+            ResponseCache::key(ApiPaths::V1, EthGetBlockByHash::NAME, params),
+            ResponseCache::key(ApiPaths::V2, EthGetBlockByHash::NAME, params),
+        );
+    }
+
+    #[test]
+    fn only_successful_results_are_cached() {
+        let cache = cache();
+        let key = |params: &str| {
+            ResponseCache::key(ApiPaths::V1, ChainGetBlock::NAME, Some(params)).unwrap()
+        };
+
+        cache.insert(key("[1]"), &response("Cthulhu"));
+        assert_eq!(cache.get(&key("[1]")).unwrap().get(), r#""Cthulhu""#);
+
+        cache.insert(key("[2]"), &response(()));
+        assert!(cache.get(&key("[2]")).is_none());
+
+        cache.insert(
+            key("[3]"),
+            &MethodResponse::error(
+                Id::Number(1),
+                jsonrpsee::types::ErrorObject::owned(1, "Azathoth", None::<()>),
+            ),
+        );
+        assert!(cache.get(&key("[3]")).is_none());
+    }
+}
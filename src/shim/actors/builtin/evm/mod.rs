@@ -46,6 +46,42 @@ impl State {
         })
     }
 
+    /// Creates an empty state of the given actors version, e.g. to turn an account into a
+    /// contract.
+    pub fn new_for_version(
+        version: u64,
+        bytecode: Cid,
+        bytecode_hash: [u8; 32],
+        contract_state: Cid,
+        nonce: u64,
+    ) -> anyhow::Result<Self> {
+        macro_rules! new_state {
+            ($version:ident $(, $field:ident: $value:expr)*) => {
+                fil_actor_evm_state::$version::State {
+                    bytecode,
+                    bytecode_hash: bytecode_hash.into(),
+                    contract_state,
+                    nonce,
+                    tombstone: None,
+                    $($field: $value,)*
+                }
+                .into()
+            };
+        }
+        Ok(match version {
+            10 => new_state!(v10),
+            11 => new_state!(v11),
+            12 => new_state!(v12),
+            13 => new_state!(v13),
+            14 => new_state!(v14),
+            15 => new_state!(v15),
+            16 => new_state!(v16, transient_data: None),
+            17 => new_state!(v17, transient_data: None),
+            18 => new_state!(v18, transient_data: None),
+            _ => anyhow::bail!("EVM actor is not supported in actors v{version}"),
+        })
+    }
+
     pub fn nonce(&self) -> u64 {
         delegate_state!(self.nonce)
     }
@@ -65,6 +101,21 @@ impl State {
     pub fn contract_state(&self) -> Cid {
         delegate_state!(self.contract_state)
     }
+
+    pub fn set_nonce(&mut self, nonce: u64) {
+        delegate_state!(self => |s| s.nonce = nonce)
+    }
+
+    pub fn set_bytecode(&mut self, bytecode: Cid, bytecode_hash: [u8; 32]) {
+        delegate_state!(self => |s| {
+            s.bytecode = bytecode;
+            s.bytecode_hash = bytecode_hash.into();
+        })
+    }
+
+    pub fn set_contract_state(&mut self, contract_state: Cid) {
+        delegate_state!(self => |s| s.contract_state = contract_state)
+    }
 }
 
 #[delegated_enum(impl_conversions)]
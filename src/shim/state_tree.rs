@@ -349,6 +349,18 @@ where
         }
     }
 
+    /// Allocates a new actor ID for `addr` in the init actor, e.g. before creating the actor
+    /// with [`StateTree::set_actor`].
+    pub fn register_new_address(&mut self, addr: &Address) -> anyhow::Result<ActorID> {
+        match self {
+            StateTree::FvmV3(st) => Ok(st.register_new_address(&addr.into())?),
+            StateTree::FvmV4(st) => Ok(st.register_new_address(&addr.into())?),
+            StateTree::FvmV2(_) | StateTree::V0(_) => {
+                bail!("StateTree::register_new_address not supported on old state trees")
+            }
+        }
+    }
+
     /// Set actor state with an actor ID.
     pub fn set_actor(&mut self, addr: &Address, actor: ActorState) -> anyhow::Result<()> {
         match self {
@@ -243,6 +243,27 @@ impl StateManager {
     fn execution_trace_inner_blocking(
         &self,
         tipset: Tipset,
+    ) -> anyhow::Result<(CidWrapper, Vec<Arc<ApiInvocResult>>)> {
+        let Some(store) = self.trace_store() else {
+            return self.compute_execution_trace_blocking(tipset);
+        };
+        match store.get(&tipset) {
+            Ok(Some((state_root, invoc_trace))) => return Ok((state_root.into(), invoc_trace)),
+            Ok(None) => {}
+            Err(e) => warn!("failed to read the stored trace of {}: {e:#}", tipset.key()),
+        }
+        let (state_root, invoc_trace) =
+            self.compute_execution_trace_blocking(tipset.shallow_clone())?;
+        let state_root = Cid::from(state_root);
+        if let Err(e) = store.put(&tipset, state_root, &invoc_trace) {
+            warn!("failed to store the trace of {}: {e:#}", tipset.key());
+        }
+        Ok((state_root.into(), invoc_trace))
+    }
+
+    fn compute_execution_trace_blocking(
+        &self,
+        tipset: Tipset,
     ) -> anyhow::Result<(CidWrapper, Vec<Arc<ApiInvocResult>>)> {
         let mut invoc_trace = vec![];
 
@@ -3,16 +3,47 @@
 
 use super::utils::structured;
 use super::*;
+use crate::db::OverlayBlockstore;
 use crate::interpreter::{ExecutionContext, IMPLICIT_MESSAGE_GAS_LIMIT, VM, VMTrace};
 use crate::message::{MessageRead as _, MessageReadWrite as _};
 use crate::rpc::state::{ApiInvocResult, MessageGasCost};
+use crate::shim::econ::BLOCK_GAS_LIMIT;
 use crate::shim::executor::ApplyRet;
 use crate::shim::message::Message;
 use crate::state_migration::run_state_migrations;
 use std::time::Duration;
 use tracing::instrument;
 
+/// Maximum number of messages of a bundle simulated by [`StateManager::call_many`].
+pub const CALL_MANY_MAX_MESSAGES: usize = 100;
+
+/// Gas available to a bundle simulated by [`StateManager::call_many`], that of a tipset of the
+/// expected size of 5 blocks.
+pub const CALL_MANY_GAS_BUDGET: u64 = 5 * BLOCK_GAS_LIMIT;
+
+/// A state to simulate messages on instead of the parent state of a tipset, e.g. with
+/// `eth_call` state overrides applied. See [`StateManager::state_overlay`].
+#[derive(Clone)]
+pub struct StateOverlay {
+    /// The tipset the messages are simulated on top of.
+    pub tipset: Tipset,
+    pub state_root: Cid,
+    /// Holds the blocks of the modified state, which are never written to the database.
+    pub store: Arc<OverlayBlockstore>,
+}
+
 impl StateManager {
+    /// Returns an overlay over the parent state of `tipset`, to be modified before simulating
+    /// messages on it.
+    pub async fn state_overlay(&self, tipset: Tipset) -> anyhow::Result<StateOverlay> {
+        let TipsetState { state_root, .. } = self.load_tipset_state(&tipset).await?;
+        Ok(StateOverlay {
+            tipset,
+            state_root,
+            store: Arc::new(OverlayBlockstore::new(self.chain_index().db_owned())),
+        })
+    }
+
     #[instrument(skip(self))]
     fn call_raw_blocking(
         &self,
@@ -180,20 +211,32 @@ impl StateManager {
         self.call_raw_blocking(Some(state_cid), message, tipset)
     }
 
+    /// Applies `msg` on the parent state of `tipset`, or on `overlay` if given.
     pub async fn apply_on_state_with_gas(
         &self,
         tipset: Option<Tipset>,
+        overlay: Option<StateOverlay>,
         msg: Message,
         vm_flush: VMFlush,
         vm_trace: VMTrace,
     ) -> anyhow::Result<(ApiInvocResult, Option<Cid>)> {
-        let ts = tipset.unwrap_or_else(|| self.heaviest_tipset());
+        let ts = match &overlay {
+            Some(overlay) => overlay.tipset.shallow_clone(),
+            None => tipset.unwrap_or_else(|| self.heaviest_tipset()),
+        };
 
         let from_a = self.resolve_to_deterministic_address(msg.from, &ts).await?;
         let chain_msg = ChainMessage::for_gas_estimation(msg.clone(), from_a.protocol());
 
         let (apply_ret, duration, state_root) = self
-            .call_with_gas(chain_msg, Default::default(), Some(ts), vm_flush, vm_trace)
+            .call_with_gas(
+                chain_msg,
+                Default::default(),
+                Some(ts),
+                overlay,
+                vm_flush,
+                vm_trace,
+            )
             .await?;
 
         let msg_rct = Some(apply_ret.msg_receipt());
@@ -215,48 +258,39 @@ impl StateManager {
 
     /// Computes message on the given [Tipset] state, after applying other
     /// messages and returns the values computed in the VM.
+    ///
+    /// When `overlay` is given, the messages are applied on its state and tipset instead, and the
+    /// resulting state is only written to its store.
     pub async fn call_with_gas(
         &self,
         mut message: ChainMessage,
         prior_messages: Arc<Vec<ChainMessage>>,
         tipset: Option<Tipset>,
+        overlay: Option<StateOverlay>,
         vm_flush: VMFlush,
         vm_trace: VMTrace,
     ) -> Result<(ApplyRet, Duration, Option<Cid>), Error> {
-        let ts = tipset.unwrap_or_else(|| self.heaviest_tipset());
-        let TipsetState { state_root, .. } = self
-            .load_tipset_state(&ts)
-            .await
-            .map_err(|e| Error::Other(format!("Could not load tipset state: {e:#}")))?;
-        let chain_rand = self.chain_rand(ts.clone());
-
-        // Since we're simulating a future message, pretend we're applying it in the
-        // "next" tipset
-        let epoch = ts.epoch() + 1;
+        let (ts, state_root, chain_index) = match overlay {
+            Some(StateOverlay {
+                tipset,
+                state_root,
+                store,
+            }) => (tipset, state_root, self.chain_index().with_db(store)),
+            None => {
+                let ts = tipset.unwrap_or_else(|| self.heaviest_tipset());
+                let TipsetState { state_root, .. } = self
+                    .load_tipset_state(&ts)
+                    .await
+                    .map_err(|e| Error::Other(format!("Could not load tipset state: {e:#}")))?;
+                (ts, state_root, self.chain_index().shallow_clone())
+            }
+        };
         let this = self.shallow_clone();
         tokio::task::spawn_blocking(move || {
             // FVM requires a stack size of 64MiB. The alternative is to use `ThreadedExecutor` from
             // FVM, but that introduces some constraints, and possible deadlocks.
             let (ret, duration, state_cid) = stacker::grow(64 << 20, || -> anyhow::Result<_> {
-                let mut vm = VM::new(
-                    ExecutionContext {
-                        heaviest_tipset: ts.clone(),
-                        state_tree_root: state_root,
-                        epoch,
-                        rand: Box::new(chain_rand),
-                        base_fee: ts.block_headers().first().parent_base_fee.clone(),
-                        circ_supply: this.genesis_info().get_vm_circulating_supply(
-                            epoch,
-                            this.chain_index().db(),
-                            &state_root,
-                        )?,
-                        chain_config: this.chain_config().shallow_clone(),
-                        chain_index: this.chain_index().shallow_clone(),
-                        timestamp: ts.min_timestamp(),
-                    },
-                    &this.engine,
-                    vm_trace,
-                )?;
+                let mut vm = this.simulation_vm(&ts, state_root, chain_index, vm_trace)?;
 
                 for msg in prior_messages.iter() {
                     vm.apply_message(msg)?;
@@ -280,4 +314,312 @@ impl StateManager {
         })
         .await?
     }
+
+    /// Applies `messages` one after another on the state of `overlay`, as if they were included
+    /// in the tipset after its tipset. Each message gets the current nonce of its sender, so that
+    /// later messages see the effects of the earlier ones. Messages without a gas limit get the
+    /// block gas limit, and no message gets more than what is left of [`CALL_MANY_GAS_BUDGET`]
+    /// once the gas used by the earlier ones is deducted. Bundles of more than
+    /// [`CALL_MANY_MAX_MESSAGES`] messages are refused.
+    ///
+    /// Returns the result of each message, and the state root after all of them, in the store of
+    /// `overlay`.
+    pub async fn call_many(
+        &self,
+        messages: Vec<Message>,
+        overlay: StateOverlay,
+    ) -> Result<(Vec<ApiInvocResult>, Cid), Error> {
+        let StateOverlay {
+            tipset: ts,
+            state_root,
+            store,
+        } = overlay;
+        if messages.len() > CALL_MANY_MAX_MESSAGES {
+            return Err(Error::Other(format!(
+                "bundle of {} messages exceeds the maximum of {CALL_MANY_MAX_MESSAGES}",
+                messages.len()
+            )));
+        }
+        if ts.epoch() > 0
+            && self
+                .chain_config()
+                .has_expensive_fork_between(ts.epoch(), ts.epoch() + 1)
+        {
+            return Err(Error::ExpensiveFork { epoch: ts.epoch() });
+        }
+        let mut chain_messages = Vec::with_capacity(messages.len());
+        for mut msg in messages {
+            if msg.gas_limit == 0 {
+                msg.set_gas_limit(BLOCK_GAS_LIMIT);
+            }
+            // Senders created by earlier messages of the bundle can't be resolved yet, they are
+            // simulated unsigned.
+            let from_protocol = self
+                .resolve_to_deterministic_address(msg.from, &ts)
+                .await
+                .map_or(msg.from.protocol(), |it| it.protocol());
+            chain_messages.push(ChainMessage::for_gas_estimation(msg, from_protocol));
+        }
+
+        let chain_index = self.chain_index().with_db(store);
+        let this = self.shallow_clone();
+        tokio::task::spawn_blocking(move || {
+            stacker::grow(64 << 20, || -> anyhow::Result<_> {
+                let mut vm = this.simulation_vm(&ts, state_root, chain_index, VMTrace::Traced)?;
+                let results = apply_bundle(&mut vm, chain_messages)?;
+                Ok((results, vm.flush()?))
+            })
+            .map_err(Error::from)
+        })
+        .await?
+    }
+
+    /// Creates a VM to simulate messages on `state_root`, as if they were included in the tipset
+    /// after `ts`.
+    fn simulation_vm(
+        &self,
+        ts: &Tipset,
+        state_root: Cid,
+        chain_index: ChainIndex,
+        vm_trace: VMTrace,
+    ) -> anyhow::Result<VM> {
+        // Since we're simulating a future message, pretend we're applying it in the
+        // "next" tipset
+        let epoch = ts.epoch() + 1;
+        VM::new(
+            ExecutionContext {
+                heaviest_tipset: ts.shallow_clone(),
+                state_tree_root: state_root,
+                epoch,
+                rand: Box::new(self.chain_rand(ts.shallow_clone())),
+                base_fee: ts.block_headers().first().parent_base_fee.clone(),
+                circ_supply: self.genesis_info().get_vm_circulating_supply(
+                    epoch,
+                    chain_index.db(),
+                    &state_root,
+                )?,
+                chain_config: self.chain_config().shallow_clone(),
+                chain_index,
+                timestamp: ts.min_timestamp(),
+            },
+            &self.engine,
+            vm_trace,
+        )
+    }
+}
+
+/// The part of a [`VM`] a bundle of messages is applied with.
+trait BundleVm {
+    fn get_actor(&self, addr: &Address) -> anyhow::Result<Option<ActorState>>;
+    fn apply_message(&mut self, msg: &ChainMessage) -> anyhow::Result<(ApplyRet, Duration)>;
+}
+
+impl BundleVm for VM {
+    fn get_actor(&self, addr: &Address) -> anyhow::Result<Option<ActorState>> {
+        VM::get_actor(self, addr)
+    }
+
+    fn apply_message(&mut self, msg: &ChainMessage) -> anyhow::Result<(ApplyRet, Duration)> {
+        VM::apply_message(self, msg)
+    }
+}
+
+/// Applies `messages` in order, see [`StateManager::call_many`].
+fn apply_bundle(
+    vm: &mut impl BundleVm,
+    messages: Vec<ChainMessage>,
+) -> anyhow::Result<Vec<ApiInvocResult>> {
+    let mut gas_left = CALL_MANY_GAS_BUDGET;
+    let mut results = Vec::with_capacity(messages.len());
+    for mut chain_msg in messages {
+        anyhow::ensure!(
+            gas_left > 0,
+            "the bundle used up its gas budget of {CALL_MANY_GAS_BUDGET}"
+        );
+        if chain_msg.gas_limit() > gas_left {
+            chain_msg.set_gas_limit(gas_left);
+        }
+        if let Some(from_actor) = vm.get_actor(&chain_msg.from())? {
+            chain_msg.set_sequence(from_actor.sequence);
+        }
+        let (apply_ret, duration) = vm.apply_message(&chain_msg)?;
+        gas_left = gas_left.saturating_sub(apply_ret.msg_receipt().gas_used());
+        let msg = chain_msg.message().clone();
+        results.push(ApiInvocResult {
+            msg_cid: msg.cid(),
+            msg_rct: Some(apply_ret.msg_receipt()),
+            error: apply_ret.failure_info().unwrap_or_default(),
+            duration: duration.as_nanos().clamp(0, u128::from(u64::MAX)) as u64,
+            gas_cost: MessageGasCost::new(&msg, &apply_ret)?,
+            execution_trace: structured::parse_events(apply_ret.into_exec_trace())
+                .unwrap_or_default(),
+            msg,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shim::address::Protocol;
+    use crate::shim::error::ExitCode;
+    use ahash::HashMap;
+    use fvm_shared4::error::ExitCode as ExitCode_v4;
+    use fvm4::executor::ApplyRet as ApplyRet_v4;
+
+    const GAS_USED: u64 = 1_000_000;
+
+    /// A VM whose only actors are accounts, and whose only messages are transfers.
+    #[derive(Default)]
+    struct TransferVm {
+        actors: HashMap<Address, ActorState>,
+        /// Makes messages use all of their gas.
+        burn_all_gas: bool,
+    }
+
+    impl TransferVm {
+        fn balance(&self, addr: &Address) -> TokenAmount {
+            self.actors
+                .get(addr)
+                .map(|it| TokenAmount::from(&it.balance))
+                .unwrap_or_default()
+        }
+    }
+
+    impl BundleVm for TransferVm {
+        fn get_actor(&self, addr: &Address) -> anyhow::Result<Option<ActorState>> {
+            Ok(self.actors.get(addr).cloned())
+        }
+
+        fn apply_message(&mut self, msg: &ChainMessage) -> anyhow::Result<(ApplyRet, Duration)> {
+            let ret = |exit_code: ExitCode_v4, gas_used| {
+                let mut ret = ApplyRet_v4::prevalidation_fail(exit_code, "", Default::default());
+                ret.msg_receipt.gas_used = gas_used;
+                if exit_code.is_success() {
+                    ret.failure_info = None;
+                }
+                (ApplyRet::V4(ret), Duration::ZERO)
+            };
+            let Some(mut from) = self.actors.get(&msg.from()).cloned() else {
+                return Ok(ret(ExitCode_v4::SYS_SENDER_INVALID, 0));
+            };
+            if from.sequence != msg.sequence() {
+                return Ok(ret(ExitCode_v4::SYS_SENDER_STATE_INVALID, 0));
+            }
+            let gas_used = if self.burn_all_gas {
+                msg.gas_limit()
+            } else {
+                GAS_USED
+            };
+            from.sequence += 1;
+            let balance = TokenAmount::from(&from.balance);
+            if balance < msg.value() {
+                self.actors.insert(msg.from(), from);
+                return Ok(ret(ExitCode_v4::SYS_INSUFFICIENT_FUNDS, gas_used));
+            }
+            from.balance = (balance - msg.value()).into();
+            self.actors.insert(msg.from(), from);
+            let mut to = self
+                .actors
+                .get(&msg.to())
+                .cloned()
+                .unwrap_or_else(|| account(0));
+            to.balance = (TokenAmount::from(&to.balance) + msg.value()).into();
+            self.actors.insert(msg.to(), to);
+            Ok(ret(ExitCode_v4::OK, gas_used))
+        }
+    }
+
+    fn account(balance: u64) -> ActorState {
+        ActorState::new(
+            Cid::default(),
+            Cid::default(),
+            TokenAmount::from_atto(balance),
+            0,
+            None,
+        )
+    }
+
+    /// A transfer with a stale nonce and the block gas limit, as sent by RPC clients.
+    fn transfer(from: u64, to: u64, value: u64) -> ChainMessage {
+        ChainMessage::for_gas_estimation(
+            Message {
+                from: Address::new_id(from),
+                to: Address::new_id(to),
+                value: TokenAmount::from_atto(value),
+                gas_limit: BLOCK_GAS_LIMIT,
+                ..Default::default()
+            },
+            Protocol::ID,
+        )
+    }
+
+    fn exit_codes(results: &[ApiInvocResult]) -> Vec<u32> {
+        results
+            .iter()
+            .map(|it| it.msg_rct.as_ref().unwrap().exit_code().value())
+            .collect()
+    }
+
+    #[test]
+    fn bundle_messages_see_the_effects_of_earlier_ones() {
+        let (alice, bob, carol) = (1000, 1001, 1002);
+        let mut vm = TransferVm::default();
+        vm.actors.insert(Address::new_id(alice), account(100));
+        vm.actors.insert(Address::new_id(bob), account(0));
+
+        let results = apply_bundle(
+            &mut vm,
+            vec![
+                transfer(alice, bob, 30),
+                // Only funded by the previous message.
+                transfer(bob, carol, 20),
+                // Fails, bob only has 10 left.
+                transfer(bob, carol, 50),
+                transfer(bob, carol, 10),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            exit_codes(&results),
+            [0, 0, ExitCode::SYS_INSUFFICIENT_FUNDS.value(), 0]
+        );
+        assert!(!results[2].error.is_empty());
+        // Nonces are rewritten to the ones of the senders after the earlier messages.
+        let nonces = results.iter().map(|it| it.msg.sequence).collect_vec();
+        assert_eq!(nonces, [0, 0, 1, 2]);
+        assert_eq!(
+            vm.balance(&Address::new_id(alice)),
+            TokenAmount::from_atto(70)
+        );
+        assert_eq!(vm.balance(&Address::new_id(bob)), TokenAmount::default());
+        assert_eq!(
+            vm.balance(&Address::new_id(carol)),
+            TokenAmount::from_atto(30)
+        );
+    }
+
+    #[test]
+    fn bundle_is_bounded_by_the_gas_budget() {
+        let mut vm = TransferVm {
+            burn_all_gas: true,
+            ..Default::default()
+        };
+        vm.actors.insert(Address::new_id(1000), account(100));
+        let mut messages = (0..6).map(|_| transfer(1000, 1001, 1)).collect_vec();
+        messages[0].set_gas_limit(BLOCK_GAS_LIMIT / 2);
+
+        // The last message only gets the half of the block gas limit left.
+        let results = apply_bundle(&mut vm, messages.clone()).unwrap();
+        let gas_limits = results.iter().map(|it| it.msg.gas_limit).collect_vec();
+        assert_eq!(gas_limits[0], BLOCK_GAS_LIMIT / 2);
+        assert_eq!(gas_limits[1..5], [BLOCK_GAS_LIMIT; 4]);
+        assert_eq!(gas_limits[5], BLOCK_GAS_LIMIT / 2);
+
+        messages.push(transfer(1000, 1001, 1));
+        let err = apply_bundle(&mut vm, messages).unwrap_err();
+        assert!(err.to_string().contains("gas budget"), "{err}");
+    }
 }
@@ -14,11 +14,14 @@ mod execution;
 mod message_search;
 mod message_simulation;
 mod mining;
+pub mod parallel_validation;
 mod state_computation;
+pub mod trace_store;
 pub mod utils;
 
 use self::circulating_supply::GenesisInfo;
 pub use self::errors::*;
+pub use self::message_simulation::StateOverlay;
 pub use self::state_computation::{apply_block_messages_blocking, validate_tipsets_blocking};
 use crate::beacon::BeaconSchedule;
 use crate::blocks::{Tipset, TipsetKey};
@@ -50,6 +53,7 @@ use crate::shim::{
     version::NetworkVersion,
 };
 use crate::state_manager::cache::ForestCache;
+use crate::state_manager::trace_store::TraceStore;
 use crate::utils::cache::SizeTrackingCache;
 use crate::utils::get_size::GetSize;
 use anyhow::Context as _;
@@ -174,6 +178,8 @@ pub struct StateManager {
     cache: ForestCache<TipsetKey, ExecutedTipset>,
     /// This is a cache which indexes tipsets to their traces.
     trace_cache: ForestCache<TipsetKey, (CidWrapper, Vec<Arc<ApiInvocResult>>)>,
+    /// Optional disk-backed store of traces, consulted on trace cache misses.
+    trace_store: Option<Arc<TraceStore>>,
     /// `None` disables caching of ID -> deterministic-address resolution.
     /// Used by the RPC test-snapshot generator and replay harness so every
     /// `(id, tipset)` pair resolves independently, making recorded snapshots
@@ -192,6 +198,7 @@ impl ShallowClone for StateManager {
             cs: self.cs.shallow_clone(),
             cache: self.cache.shallow_clone(),
             trace_cache: self.trace_cache.shallow_clone(),
+            trace_store: self.trace_store.clone(),
             id_to_deterministic_address_cache: self
                 .id_to_deterministic_address_cache
                 .as_ref()
@@ -231,6 +238,7 @@ impl StateManager {
             cs,
             cache: ForestCache::new("tipset_state_executed_tipset"), // For StateOutput
             trace_cache: ForestCache::with_size("tipset_trace", DEFAULT_TRACE_CACHE_SIZE),
+            trace_store: None,
             beacon,
             engine,
             genesis_info,
@@ -270,6 +278,16 @@ impl StateManager {
             .expect("replay semaphore is never closed")
     }
 
+    /// Persists the traces of executed tipsets in `store`, see [`TraceStore`].
+    pub fn with_trace_store(mut self, store: Arc<TraceStore>) -> Self {
+        self.trace_store = Some(store);
+        self
+    }
+
+    pub fn trace_store(&self) -> Option<&Arc<TraceStore>> {
+        self.trace_store.as_ref()
+    }
+
     /// Disables caching of ID -> deterministic-address resolution. To be used strictly
     /// by the RPC test-snapshot generator and replay harness
     pub fn with_id_address_cache_disabled(mut self) -> Self {
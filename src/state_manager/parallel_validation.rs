@@ -0,0 +1,367 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Validation of historical tipsets on several threads, see
+//! [`validate_tipsets_parallel_blocking`].
+
+use super::state_computation::validate_tipset_blocking;
+use crate::beacon::BeaconSchedule;
+use crate::blocks::Tipset;
+use crate::chain::index::ChainIndex;
+use crate::networks::ChainConfig;
+use crate::prelude::*;
+use crate::shim::machine::MultiEngine;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of epochs validated by a worker in one go. Segments are aligned to multiples of
+/// this length, so that progress recorded for a range can be resumed with another range.
+pub const SEGMENT_LEN: ChainEpoch = 120;
+
+/// Outcome of validating the tipsets of a segment of epochs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentValidation {
+    /// Lowest validated epoch of the segment
+    pub start: ChainEpoch,
+    /// Highest validated epoch of the segment
+    pub end: ChainEpoch,
+    /// Key of the tipset recording the state of the highest epoch, which identifies the fork
+    /// the segment was validated on.
+    pub head_key: String,
+    /// First epoch whose computed state differs from the state recorded by its child. Later
+    /// epochs of the segment are not validated.
+    pub first_divergence: Option<ChainEpoch>,
+}
+
+/// Segments validated so far, optionally persisted to a JSON file after each segment so
+/// that an interrupted validation can be resumed.
+#[derive(Default)]
+pub struct ValidationProgress {
+    path: Option<PathBuf>,
+    segments: Mutex<Vec<SegmentValidation>>,
+}
+
+impl ValidationProgress {
+    /// Loads the progress stored at `path`, if any.
+    pub fn load(path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let segments = match &path {
+            Some(path) if path.exists() => serde_json::from_slice(&std::fs::read(path)?)
+                .with_context(|| format!("invalid validation progress file {}", path.display()))?,
+            _ => vec![],
+        };
+        Ok(Self {
+            path,
+            segments: Mutex::new(segments),
+        })
+    }
+
+    fn get(&self, start: ChainEpoch, end: ChainEpoch, head_key: &str) -> Option<SegmentValidation> {
+        self.segments
+            .lock()
+            .iter()
+            .find(|it| it.start == start && it.end == end && it.head_key == head_key)
+            .cloned()
+    }
+
+    fn record(&self, segment: SegmentValidation) -> anyhow::Result<()> {
+        let mut segments = self.segments.lock();
+        segments.push(segment);
+        if let Some(path) = &self.path {
+            // Write to a temporary file first, so that an interruption can't corrupt it.
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_vec_pretty(&*segments)?)?;
+            std::fs::rename(&tmp, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tipsets of one segment, as `(child, parent)` pairs in ascending epoch order.
+struct Segment {
+    pairs: Vec<(Tipset, Tipset)>,
+}
+
+impl Segment {
+    fn start(&self) -> ChainEpoch {
+        self.pairs
+            .first()
+            .map(|(_, parent)| parent.epoch())
+            .unwrap_or_default()
+    }
+
+    fn end(&self) -> ChainEpoch {
+        self.pairs
+            .last()
+            .map(|(_, parent)| parent.epoch())
+            .unwrap_or_default()
+    }
+
+    fn head_key(&self) -> String {
+        self.pairs
+            .last()
+            .map(|(child, _)| child.key().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Splits `tipsets`, in descending epoch order, into segments of `(child, parent)` pairs
+/// grouped by the [`SEGMENT_LEN`]-aligned window the parent epoch falls in.
+fn split_into_segments(tipsets: impl Iterator<Item = Tipset>) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = vec![];
+    for (child, parent) in tipsets.tuple_windows() {
+        let window = parent.epoch().div_euclid(SEGMENT_LEN);
+        match segments.last_mut() {
+            Some(segment) if segment.start().div_euclid(SEGMENT_LEN) == window => {
+                segment.pairs.push((child, parent))
+            }
+            _ => segments.push(Segment {
+                pairs: vec![(child, parent)],
+            }),
+        }
+    }
+    for segment in &mut segments {
+        segment.pairs.reverse();
+    }
+    segments.reverse();
+    segments
+}
+
+/// Validates `tipsets`, in descending epoch order, like
+/// [`validate_tipsets_blocking`](super::validate_tipsets_blocking), but on `jobs` threads.
+///
+/// Executing a tipset only needs the parent state recorded in the chain, so the range is
+/// split into independent segments, each validated in ascending epoch order until its first
+/// divergence. Workers are dedicated threads rather than rayon tasks, so that they can't
+/// wedge the global rayon pool used inside tipset execution. Segments already in `progress`
+/// are skipped, and each newly validated segment is recorded there.
+///
+/// Returns the outcome of every segment in ascending epoch order. Errors other than state
+/// mismatches (e.g. missing state) abort the validation.
+pub fn validate_tipsets_parallel_blocking(
+    chain_index: &ChainIndex,
+    chain_config: &Arc<ChainConfig>,
+    beacon: &Arc<BeaconSchedule>,
+    engine: &MultiEngine,
+    tipsets: impl Iterator<Item = Tipset>,
+    jobs: NonZeroUsize,
+    progress: &ValidationProgress,
+) -> anyhow::Result<Vec<SegmentValidation>> {
+    validate_segments(split_into_segments(tipsets), jobs, progress, |segment| {
+        for (child, parent) in &segment.pairs {
+            if !validate_tipset_blocking(
+                chain_index,
+                chain_config,
+                beacon,
+                engine,
+                child,
+                parent.shallow_clone(),
+            )? {
+                return Ok(Some(parent.epoch()));
+            }
+        }
+        Ok(None)
+    })
+}
+
+/// Validates the `segments` not in `progress` yet with `validate`, which returns the first
+/// divergence of a segment, on `jobs` threads. Once a worker fails, the others stop taking
+/// segments.
+fn validate_segments(
+    segments: Vec<Segment>,
+    jobs: NonZeroUsize,
+    progress: &ValidationProgress,
+    validate: impl Fn(&Segment) -> anyhow::Result<Option<ChainEpoch>> + Sync,
+) -> anyhow::Result<Vec<SegmentValidation>> {
+    let results = Mutex::new(Vec::with_capacity(segments.len()));
+    let queue = Mutex::new(VecDeque::new());
+    for segment in segments {
+        match progress.get(segment.start(), segment.end(), &segment.head_key()) {
+            Some(done) => {
+                info!(
+                    start = done.start,
+                    end = done.end,
+                    "segment already validated"
+                );
+                results.lock().push(done);
+            }
+            None => queue.lock().push_back(segment),
+        }
+    }
+
+    let failed = AtomicBool::new(false);
+    let validate_next = || -> anyhow::Result<bool> {
+        // Pop in a statement of its own, so that the queue is unlocked while the segment is
+        // validated.
+        let next = queue.lock().pop_front();
+        let Some(segment) = next else {
+            return Ok(false);
+        };
+        let first_divergence = validate(&segment)?;
+        let validation = SegmentValidation {
+            start: segment.start(),
+            end: segment.end(),
+            head_key: segment.head_key(),
+            first_divergence,
+        };
+        info!(
+            start = validation.start,
+            end = validation.end,
+            ?first_divergence,
+            "segment validated"
+        );
+        progress.record(validation.clone())?;
+        results.lock().push(validation);
+        Ok(true)
+    };
+
+    std::thread::scope(|scope| {
+        let workers = (0..jobs.get())
+            .map(|_| {
+                scope.spawn(|| -> anyhow::Result<()> {
+                    while !failed.load(Ordering::Relaxed) {
+                        match validate_next() {
+                            Ok(true) => {}
+                            Ok(false) => break,
+                            Err(e) => {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(e);
+                            }
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect_vec();
+        workers.into_iter().try_for_each(|worker| {
+            worker
+                .join()
+                .map_err(|_| anyhow::anyhow!("validation worker panicked"))?
+        })
+    })?;
+
+    let mut results = results.into_inner();
+    results.sort_by_key(|it| it.start);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use nonzero_ext::nonzero;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::{Duration, Instant};
+
+    fn chain(epochs: impl DoubleEndedIterator<Item = ChainEpoch>) -> impl Iterator<Item = Tipset> {
+        epochs.rev().map(|epoch| {
+            Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+                epoch,
+                ..Default::default()
+            }))
+        })
+    }
+
+    #[test]
+    fn segments_are_aligned_to_epochs() {
+        // Epochs 100..=370, with a null round at 240
+        let epochs = (100..=370).filter(|it| *it != 240).collect_vec();
+        let segments = split_into_segments(chain(epochs.into_iter()));
+        let bounds = segments
+            .iter()
+            .map(|it| (it.start(), it.end()))
+            .collect_vec();
+        // The highest tipset only records the state of its parent.
+        assert_eq!(bounds, [(100, 119), (120, 239), (241, 359), (360, 369)]);
+        assert_eq!(segments[2].pairs[0].0.epoch(), 242);
+        assert_eq!(segments[1].pairs.last().unwrap().0.epoch(), 241);
+    }
+
+    #[test]
+    fn segments_are_validated_concurrently() {
+        let segments = split_into_segments(chain(100..=200));
+        assert_eq!(segments.len(), 2);
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let results = validate_segments(
+            segments,
+            nonzero!(2_usize),
+            &ValidationProgress::default(),
+            |segment| {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                // Wait for the other segment, which only overlaps if the workers don't
+                // serialize.
+                let deadline = Instant::now() + Duration::from_secs(10);
+                while max_in_flight.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok((segment.start() == 120).then_some(150))
+            },
+        )
+        .unwrap();
+        assert_eq!(max_in_flight.into_inner(), 2);
+        assert_eq!(
+            results
+                .iter()
+                .map(|it| (it.start, it.first_divergence))
+                .collect_vec(),
+            [(100, None), (120, Some(150))]
+        );
+    }
+
+    #[test]
+    fn progress_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+        let segment = SegmentValidation {
+            start: 120,
+            end: 239,
+            head_key: "Cthulhu".into(),
+            first_divergence: Some(150),
+        };
+        ValidationProgress::load(Some(path.clone()))
+            .unwrap()
+            .record(segment.clone())
+            .unwrap();
+
+        let progress = ValidationProgress::load(Some(path)).unwrap();
+        assert_eq!(progress.get(120, 239, "Cthulhu"), Some(segment));
+        // Segments validated on another fork don't count.
+        assert_eq!(progress.get(120, 239, "Azathoth"), None);
+    }
+
+    #[test]
+    fn a_failing_segment_stops_the_validation() {
+        let segments = split_into_segments(chain(0..=1200));
+        assert_eq!(segments.len(), 10);
+        let validated = AtomicUsize::new(0);
+        let first_failed = AtomicBool::new(false);
+        let result = validate_segments(
+            segments,
+            nonzero!(2_usize),
+            &ValidationProgress::default(),
+            |segment| {
+                if segment.start() == 0 {
+                    first_failed.store(true, Ordering::SeqCst);
+                    anyhow::bail!("missing state");
+                }
+                // Give the failing worker time to stop the others.
+                while !first_failed.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                std::thread::sleep(Duration::from_millis(50));
+                validated.fetch_add(1, Ordering::SeqCst);
+                Ok(None)
+            },
+        );
+        assert!(result.is_err());
+        // The other worker may have taken the second segment before the first one failed.
+        assert!(validated.into_inner() <= 1);
+    }
+}
@@ -38,6 +38,11 @@ impl StateManager {
     pub fn clear_tipset_state_caches(&self) {
         self.cache.clear();
         self.trace_cache.clear();
+        if let Some(store) = &self.trace_store
+            && let Err(e) = store.clear()
+        {
+            warn!("failed to clear the trace store: {e:#}");
+        }
     }
 
     /// Verifies and repairs the tipset lookup table (see `ChainStore::repair_tipset_lookup`)
@@ -368,38 +373,54 @@ where
     // Sequential outer iteration leaves the entire rayon pool free for that
     // already-rich inner parallelism.
     for (child, parent) in tipsets.tuple_windows() {
-        info!(height = parent.epoch(), "compute parent state");
-        let ExecutedTipset {
-            state_root: actual_state,
-            receipt_root: actual_receipt,
-            ..
-        } = apply_block_messages_blocking(
-            chain_index.shallow_clone(),
-            chain_config.shallow_clone(),
-            beacon.shallow_clone(),
-            engine,
-            parent,
-            NO_CALLBACK,
-            VMTrace::NotTraced,
-        )
-        .context("couldn't compute tipset state")?;
-        let expected_receipt = child.min_ticket_block().message_receipts;
-        let expected_state = child.parent_state();
-        if (expected_state, expected_receipt) != (&actual_state, actual_receipt) {
-            error!(
-                height = child.epoch(),
-                ?expected_state,
-                ?expected_receipt,
-                ?actual_state,
-                ?actual_receipt,
-                "state mismatch"
-            );
+        if !validate_tipset_blocking(chain_index, chain_config, beacon, engine, &child, parent)? {
             bail!("state mismatch");
         }
     }
     Ok(())
 }
 
+/// Computes the state of `parent` and compares it with the state recorded by `child`,
+/// returning `false` on a mismatch.
+pub(super) fn validate_tipset_blocking(
+    chain_index: &ChainIndex,
+    chain_config: &Arc<ChainConfig>,
+    beacon: &Arc<BeaconSchedule>,
+    engine: &MultiEngine,
+    child: &Tipset,
+    parent: Tipset,
+) -> anyhow::Result<bool> {
+    info!(height = parent.epoch(), "compute parent state");
+    let ExecutedTipset {
+        state_root: actual_state,
+        receipt_root: actual_receipt,
+        ..
+    } = apply_block_messages_blocking(
+        chain_index.shallow_clone(),
+        chain_config.shallow_clone(),
+        beacon.shallow_clone(),
+        engine,
+        parent,
+        NO_CALLBACK,
+        VMTrace::NotTraced,
+    )
+    .context("couldn't compute tipset state")?;
+    let expected_receipt = child.min_ticket_block().message_receipts;
+    let expected_state = child.parent_state();
+    if (expected_state, expected_receipt) != (&actual_state, actual_receipt) {
+        error!(
+            height = child.epoch(),
+            ?expected_state,
+            ?expected_receipt,
+            ?actual_state,
+            ?actual_receipt,
+            "state mismatch"
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
 /// Shared context for creating VMs and preparing tipset state.
 ///
 /// Encapsulates randomness source, genesis info, VM construction,
@@ -0,0 +1,151 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Disk-backed store of tipset execution traces, see [`TraceStore`].
+
+use crate::blocks::Tipset;
+use crate::prelude::*;
+use crate::rpc::state::ApiInvocResult;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the trace store directory, relative to the chain data directory.
+pub const TRACE_STORE_DIR_NAME: &str = "traces";
+
+/// Stores the execution traces of tipsets, so that tracing a tipset again doesn't re-run the
+/// FVM, even across restarts. Backs the in-memory trace cache of the
+/// [`StateManager`](super::StateManager).
+///
+/// Each tipset is stored in its own zstd-compressed JSON file, named after its epoch and key.
+/// Traces are not content-addressed, so they are kept out of the blockstore and its garbage
+/// collection, and are instead pruned by epoch with [`TraceStore::prune_before`].
+pub struct TraceStore {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StoredTrace {
+    #[serde(with = "crate::lotus_json")]
+    state_root: Cid,
+    trace: Vec<ApiInvocResult>,
+}
+
+impl TraceStore {
+    pub fn open(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("couldn't create trace store {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, tipset: &Tipset) -> anyhow::Result<PathBuf> {
+        Ok(self.dir.join(format!(
+            "{}-{}.json.zst",
+            tipset.epoch(),
+            tipset.key().cid()?
+        )))
+    }
+
+    /// Epoch of the tipset stored in a file, [`None`] for unrelated files.
+    fn epoch_of(path: &Path) -> Option<ChainEpoch> {
+        let name = path.file_name()?.to_str()?.strip_suffix(".json.zst")?;
+        name.split_once('-')?.0.parse().ok()
+    }
+
+    /// Returns the state root and the trace of `tipset`, if stored.
+    pub fn get(&self, tipset: &Tipset) -> anyhow::Result<Option<(Cid, Vec<Arc<ApiInvocResult>>)>> {
+        let path = self.path(tipset)?;
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let StoredTrace { state_root, trace } =
+            serde_json::from_slice(&zstd::decode_all(bytes.as_slice())?)
+                .with_context(|| format!("corrupted trace {}", path.display()))?;
+        Ok(Some((
+            state_root,
+            trace.into_iter().map(Arc::new).collect(),
+        )))
+    }
+
+    pub fn put(
+        &self,
+        tipset: &Tipset,
+        state_root: Cid,
+        trace: &[Arc<ApiInvocResult>],
+    ) -> anyhow::Result<()> {
+        let stored = StoredTrace {
+            state_root,
+            trace: trace.iter().map(|it| (**it).clone()).collect(),
+        };
+        let bytes = zstd::encode_all(serde_json::to_vec(&stored)?.as_slice(), 3)?;
+        // Write to a temporary file first, so that readers never see a partial trace.
+        let path = self.path(tipset)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Deletes the traces of tipsets below `epoch`, returning the number of deleted traces.
+    pub fn prune_before(&self, epoch: ChainEpoch) -> anyhow::Result<usize> {
+        self.remove_where(|it| it < epoch)
+    }
+
+    /// Deletes all traces, e.g. when they might have been computed from corrupted inputs.
+    pub fn clear(&self) -> anyhow::Result<usize> {
+        self.remove_where(|_| true)
+    }
+
+    fn remove_where(&self, predicate: impl Fn(ChainEpoch) -> bool) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if Self::epoch_of(&path).is_some_and(&predicate) {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+
+    fn tipset(epoch: ChainEpoch) -> Tipset {
+        Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+            epoch,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn traces_are_stored_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TraceStore::open(dir.path()).unwrap();
+        let (old, new) = (tipset(100), tipset(200));
+        let state_root = Cid::default();
+        let trace = vec![Arc::new(ApiInvocResult {
+            error: "Yog-Sothoth".into(),
+            ..Default::default()
+        })];
+        store.put(&old, state_root, &trace).unwrap();
+        store.put(&new, state_root, &[]).unwrap();
+
+        let (root, stored) = store.get(&old).unwrap().unwrap();
+        assert_eq!(root, state_root);
+        assert_eq!(stored, trace);
+
+        assert_eq!(store.prune_before(150).unwrap(), 1);
+        assert!(store.get(&old).unwrap().is_none());
+        assert!(store.get(&new).unwrap().is_some());
+
+        assert_eq!(store.clear().unwrap(), 1);
+        assert!(store.get(&new).unwrap().is_none());
+    }
+}
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod resolve;
+mod structured;
+
+pub use structured::{STATE_DIFF_MAX_ACTORS, StateDiffPage, StateDiffPaging, diff_state_roots};
 
 use std::{
     fmt::Write as FmtWrite,
@@ -0,0 +1,546 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Machine-readable diff between two state trees, see [`diff_state_roots`].
+
+use crate::lotus_json::{LotusJson, lotus_json_with_self};
+use crate::prelude::*;
+use crate::shim::actors::state_load::*;
+use crate::shim::actors::{
+    account::State as AccountState, cron::State as CronState, datacap::State as DatacapState,
+    evm::State as EvmState, init::State as InitState, market::State as MarketState,
+    miner::State as MinerState, multisig::State as MultiSigState, power::State as PowerState,
+    reward::State as RewardState, system::State as SystemState, verifreg::State as VerifregState,
+};
+use crate::shim::address::Address;
+use crate::shim::econ::TokenAmount;
+use crate::shim::state_tree::{ActorState, StateTree};
+use crate::utils::db::CborStoreExt as _;
+use ahash::HashMap;
+use anyhow::bail;
+use fvm_ipld_blockstore::Blockstore;
+use ipld_core::ipld::Ipld;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ActorChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Difference of an actor between two state trees.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActorDiff {
+    #[schemars(with = "LotusJson<Address>")]
+    #[serde(with = "crate::lotus_json")]
+    pub address: Address,
+    pub change: ActorChange,
+    #[schemars(with = "LotusJson<Option<Cid>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub code_before: Option<Cid>,
+    #[schemars(with = "LotusJson<Option<Cid>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub code_after: Option<Cid>,
+    #[schemars(with = "LotusJson<Option<Cid>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub state_before: Option<Cid>,
+    #[schemars(with = "LotusJson<Option<Cid>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub state_after: Option<Cid>,
+    /// Balance after minus balance before, missing actors counting as empty
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub balance_delta: TokenAmount,
+    /// Nonce after minus nonce before, missing actors counting as zero
+    pub nonce_delta: i64,
+    /// Top-level fields of the decoded actor state that differ, for builtin actors with
+    /// the same state layout on both sides. Only computed when requested.
+    pub fields: Option<Vec<FieldDiff>>,
+}
+
+lotus_json_with_self!(ActorDiff);
+
+/// Difference of a field of a decoded actor state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Maximum number of actors returned by [`diff_state_roots`] at once.
+pub const STATE_DIFF_MAX_ACTORS: usize = 1000;
+
+/// Bit width of the actors HAMT.
+const HAMT_BIT_WIDTH: usize = 5;
+
+/// A page of the actors that differ between two state trees.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDiffPage {
+    pub actors: Vec<ActorDiff>,
+    /// Cursor of the next page, present if the page is full.
+    #[schemars(with = "LotusJson<Option<Address>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub next_cursor: Option<Address>,
+}
+
+lotus_json_with_self!(StateDiffPage);
+
+/// Page of [`StateDiffPage`] to return.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDiffPaging {
+    /// `nextCursor` of the previous page, if any.
+    #[schemars(with = "LotusJson<Option<Address>>")]
+    #[serde(with = "crate::lotus_json", default)]
+    pub cursor: Option<Address>,
+    /// Maximum number of actors of the page, [`STATE_DIFF_MAX_ACTORS`] by default.
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+lotus_json_with_self!(StateDiffPaging);
+
+/// Returns up to `limit` (at most [`STATE_DIFF_MAX_ACTORS`]) of the actors added, removed or
+/// changed from the state tree at `root_a` to the one at `root_b`, ordered by the HAMT layout
+/// of the actors, i.e. by the hash of their address. Only the actors after `cursor` in that
+/// order are returned.
+///
+/// Subtrees of the actors HAMT that are shared by both trees, or that are before the cursor,
+/// are skipped, and the traversal stops once the page is full. Diffing the states of
+/// neighbouring tipsets only loads the few nodes on the paths to changed actors.
+pub fn diff_state_roots<BS: Blockstore + ShallowClone>(
+    bs: &BS,
+    root_a: &Cid,
+    root_b: &Cid,
+    with_fields: bool,
+    cursor: Option<&Address>,
+    limit: usize,
+) -> anyhow::Result<StateDiffPage> {
+    let limit = limit.min(STATE_DIFF_MAX_ACTORS);
+    let (tree_a, tree_b) = (
+        StateTree::new_from_root(bs, root_a)?,
+        StateTree::new_from_root(bs, root_b)?,
+    );
+    let mut differ = HamtDiffer {
+        bs,
+        cursor: cursor.map(|it| key_hash(&it.to_bytes())),
+        limit,
+        keys: vec![],
+    };
+    let (hamt_a, hamt_b) = (actors_hamt_root(bs, root_a)?, actors_hamt_root(bs, root_b)?);
+    if hamt_a != hamt_b && limit > 0 {
+        differ.diff_nodes(load_node(bs, &hamt_a)?, load_node(bs, &hamt_b)?, 0, true)?;
+    }
+
+    let mut actors = Vec::with_capacity(differ.keys.len());
+    for key in &differ.keys {
+        let address = Address::from_bytes(key)?;
+        let before = tree_a.get_actor(&address)?;
+        let after = tree_b.get_actor(&address)?;
+        let change = match (&before, &after) {
+            (None, Some(_)) => ActorChange::Added,
+            (Some(_), None) => ActorChange::Removed,
+            (Some(_), Some(_)) => ActorChange::Changed,
+            (None, None) => continue,
+        };
+        let balance = |actor: &Option<ActorState>| {
+            actor
+                .as_ref()
+                .map(|it| TokenAmount::from(&it.balance))
+                .unwrap_or_default()
+        };
+        let nonce = |actor: &Option<ActorState>| {
+            actor
+                .as_ref()
+                .map(|it| i64::try_from(it.sequence).unwrap_or(i64::MAX))
+                .unwrap_or_default()
+        };
+        let fields = match (&before, &after) {
+            _ if !with_fields => None,
+            (Some(before), Some(after)) if before.state == after.state => Some(vec![]),
+            _ => diff_fields(bs, before.as_ref(), after.as_ref()),
+        };
+        actors.push(ActorDiff {
+            address,
+            change,
+            code_before: before.as_ref().map(|it| it.code),
+            code_after: after.as_ref().map(|it| it.code),
+            state_before: before.as_ref().map(|it| it.state),
+            state_after: after.as_ref().map(|it| it.state),
+            balance_delta: balance(&after) - balance(&before),
+            nonce_delta: nonce(&after) - nonce(&before),
+            fields,
+        });
+    }
+    let next_cursor = match differ.keys.last() {
+        Some(last) if differ.keys.len() >= limit => Some(Address::from_bytes(last)?),
+        _ => None,
+    };
+    Ok(StateDiffPage {
+        actors,
+        next_cursor,
+    })
+}
+
+/// Root of the actors HAMT: versioned state trees wrap it in a `StateRoot` tuple, while the
+/// root of legacy ones is the HAMT itself.
+fn actors_hamt_root(bs: &impl Blockstore, root: &Cid) -> anyhow::Result<Cid> {
+    match bs.get_cbor_required::<Ipld>(root)? {
+        Ipld::List(fields) => match fields.as_slice() {
+            [Ipld::Integer(_), Ipld::Link(actors), Ipld::Link(_)] => Ok(*actors),
+            _ => Ok(*root),
+        },
+        _ => bail!("invalid state root {root}"),
+    }
+}
+
+/// Hash of a HAMT key, which determines its position in the HAMT.
+fn key_hash(key: &[u8]) -> [u8; 32] {
+    sha2::Sha256::digest(key).into()
+}
+
+/// Index of the bucket of `hash` in a HAMT node at `depth`, i.e. the bits
+/// `[depth * HAMT_BIT_WIDTH, (depth + 1) * HAMT_BIT_WIDTH)` of the hash.
+fn bucket_index(hash: &[u8; 32], depth: usize) -> usize {
+    (0..HAMT_BIT_WIDTH).fold(0, |index, i| {
+        let bit = depth * HAMT_BIT_WIDTH + i;
+        let is_set = hash
+            .get(bit / 8)
+            .is_some_and(|byte| byte & (0x80 >> (bit % 8)) != 0);
+        (index << 1) | usize::from(is_set)
+    })
+}
+
+/// Pointer of a HAMT node: a link to a child node, or a bucket of key-value pairs.
+enum Pointer {
+    Link(Cid),
+    Values(Vec<(Vec<u8>, Ipld)>),
+}
+
+/// Loads a HAMT node, returning its pointers with their bucket index.
+fn load_node(bs: &impl Blockstore, cid: &Cid) -> anyhow::Result<Vec<(usize, Pointer)>> {
+    let Ipld::List(node) = bs.get_cbor_required::<Ipld>(cid)? else {
+        bail!("invalid HAMT node {cid}");
+    };
+    let [Ipld::Bytes(bitfield), Ipld::List(pointers)] =
+        <[Ipld; 2]>::try_from(node).map_err(|_| anyhow::anyhow!("invalid HAMT node {cid}"))?
+    else {
+        bail!("invalid HAMT node {cid}");
+    };
+    // The bitfield is big-endian, and pointers are ordered by bucket index.
+    let indices = bitfield
+        .iter()
+        .rev()
+        .enumerate()
+        .flat_map(|(byte_index, byte)| {
+            (0..8)
+                .filter(move |bit| byte & (1 << bit) != 0)
+                .map(move |bit| byte_index * 8 + bit)
+        })
+        .collect_vec();
+    anyhow::ensure!(
+        indices.len() == pointers.len(),
+        "invalid HAMT node {cid}: bitfield doesn't match pointers"
+    );
+    indices
+        .into_iter()
+        .zip(pointers)
+        .map(|(index, pointer)| Ok((index, parse_pointer(pointer)?)))
+        .collect()
+}
+
+fn parse_pointer(pointer: Ipld) -> anyhow::Result<Pointer> {
+    match pointer {
+        Ipld::Link(cid) => Ok(Pointer::Link(cid)),
+        Ipld::List(values) => values
+            .into_iter()
+            .map(|pair| match pair {
+                Ipld::List(pair) => match <[Ipld; 2]>::try_from(pair) {
+                    Ok([Ipld::Bytes(key), value]) => Ok((key, value)),
+                    _ => bail!("invalid HAMT key-value pair"),
+                },
+                _ => bail!("invalid HAMT key-value pair"),
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Pointer::Values),
+        // Legacy (v0) HAMTs tag pointers with a single-entry map.
+        Ipld::Map(mut map) if map.len() == 1 => match (map.remove("0"), map.remove("1")) {
+            (Some(link), None) => parse_pointer(link),
+            (None, Some(values)) => parse_pointer(values),
+            _ => bail!("invalid HAMT pointer"),
+        },
+        _ => bail!("invalid HAMT pointer"),
+    }
+}
+
+/// Collects the keys whose values differ between two HAMTs, in hash order.
+struct HamtDiffer<'a, BS> {
+    bs: &'a BS,
+    /// Hash of the key after which to start.
+    cursor: Option<[u8; 32]>,
+    limit: usize,
+    keys: Vec<Vec<u8>>,
+}
+
+impl<BS: Blockstore> HamtDiffer<'_, BS> {
+    /// Diffs the HAMT nodes `a` and `b` at `depth`. `on_cursor_path` is set if the node is
+    /// on the path to the cursor, whose earlier buckets are skipped then.
+    fn diff_nodes(
+        &mut self,
+        a: Vec<(usize, Pointer)>,
+        b: Vec<(usize, Pointer)>,
+        depth: usize,
+        on_cursor_path: bool,
+    ) -> anyhow::Result<()> {
+        let mut a = a.into_iter().peekable();
+        let mut b = b.into_iter().peekable();
+        while self.keys.len() < self.limit {
+            let (index, pa, pb) = match (a.peek(), b.peek()) {
+                (None, None) => break,
+                (Some((ia, _)), Some((ib, _))) if ia == ib => {
+                    let index = *ia;
+                    (index, a.next(), b.next())
+                }
+                (Some((ia, _)), Some((ib, _))) if ia < ib => (*ia, a.next(), None),
+                (Some((ia, _)), None) => (*ia, a.next(), None),
+                (_, Some((ib, _))) => (*ib, None, b.next()),
+            };
+            let on_cursor_path = match self.cursor.as_ref() {
+                Some(cursor) if on_cursor_path => {
+                    let cursor_index = bucket_index(cursor, depth);
+                    if index < cursor_index {
+                        continue;
+                    }
+                    index == cursor_index
+                }
+                _ => false,
+            };
+            match (pa.map(|it| it.1), pb.map(|it| it.1)) {
+                (Some(Pointer::Link(a)), Some(Pointer::Link(b))) if a == b => {}
+                (
+                    pa @ (None | Some(Pointer::Values(_))),
+                    pb @ (None | Some(Pointer::Values(_))),
+                ) => self.diff_values(pa, pb),
+                // At least one side is a subtree, expand both sides one level down.
+                (pa, pb) => {
+                    let a = self.expand(pa, depth + 1)?;
+                    let b = self.expand(pb, depth + 1)?;
+                    self.diff_nodes(a, b, depth + 1, on_cursor_path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the pointers of the node at `depth` that `pointer` stands for. A bucket of
+    /// values is split into the buckets its keys would fall in at that depth.
+    fn expand(
+        &self,
+        pointer: Option<Pointer>,
+        depth: usize,
+    ) -> anyhow::Result<Vec<(usize, Pointer)>> {
+        Ok(match pointer {
+            None => vec![],
+            Some(Pointer::Link(cid)) => load_node(self.bs, &cid)?,
+            Some(Pointer::Values(values)) => values
+                .into_iter()
+                .into_group_map_by(|(key, _)| bucket_index(&key_hash(key), depth))
+                .into_iter()
+                .sorted_by_key(|(index, _)| *index)
+                .map(|(index, values)| (index, Pointer::Values(values)))
+                .collect(),
+        })
+    }
+
+    /// Collects the keys whose values differ between the buckets `a` and `b`.
+    fn diff_values(&mut self, a: Option<Pointer>, b: Option<Pointer>) {
+        let values = |pointer| match pointer {
+            Some(Pointer::Values(values)) => values.into_iter().collect(),
+            _ => HashMap::default(),
+        };
+        let (values_a, values_b): (HashMap<_, _>, HashMap<_, _>) = (values(a), values(b));
+        let changed = values_a
+            .keys()
+            .chain(values_b.keys())
+            .filter(|key| values_a.get(*key) != values_b.get(*key))
+            .map(|key| (key_hash(key), key))
+            .filter(|(hash, _)| self.cursor.as_ref().is_none_or(|cursor| hash > cursor))
+            .collect::<BTreeSet<_>>();
+        let room = self.limit.saturating_sub(self.keys.len());
+        self.keys
+            .extend(changed.into_iter().take(room).map(|(_, key)| key.clone()));
+    }
+}
+
+/// Decodes the state of a builtin actor into its JSON representation.
+fn decode_state(bs: &impl Blockstore, actor: &ActorState) -> Option<serde_json::Value> {
+    macro_rules! try_decode {
+        ($($state:ty),*) => {
+            $(
+                if let Ok(state) = <$state>::load(bs, actor.code, actor.state) {
+                    return serde_json::to_value(state).ok();
+                }
+            )*
+        };
+    }
+    try_decode!(
+        MinerState,
+        CronState,
+        AccountState,
+        PowerState,
+        InitState,
+        RewardState,
+        SystemState,
+        MultiSigState,
+        MarketState,
+        DatacapState,
+        EvmState,
+        VerifregState
+    );
+    None
+}
+
+/// Diffs the top-level fields of the decoded states, [`None`] if they can't be decoded to
+/// objects.
+fn diff_fields(
+    bs: &impl Blockstore,
+    before: Option<&ActorState>,
+    after: Option<&ActorState>,
+) -> Option<Vec<FieldDiff>> {
+    let decode = |actor: Option<&ActorState>| match actor.map(|it| decode_state(bs, it)) {
+        None => Some(serde_json::Map::new()),
+        Some(Some(serde_json::Value::Object(fields))) => Some(fields),
+        Some(_) => None,
+    };
+    let (mut before, mut after) = (decode(before)?, decode(after)?);
+    let names = before
+        .keys()
+        .chain(after.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    Some(
+        names
+            .into_iter()
+            .filter_map(|field| {
+                let (before, after) = (before.remove(&field), after.remove(&field));
+                (before != after).then_some(FieldDiff {
+                    field,
+                    before,
+                    after,
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::state_tree::StateTreeVersion;
+
+    fn account(balance: u64, sequence: u64) -> ActorState {
+        ActorState::new(
+            Cid::default(),
+            Cid::default(),
+            TokenAmount::from_atto(balance),
+            sequence,
+            None,
+        )
+    }
+
+    #[test]
+    fn diff_skips_shared_actors() {
+        let db = Arc::new(MemoryDB::default());
+        let flush = |actors: &[(u64, ActorState)]| {
+            let mut tree = StateTree::new(&db, StateTreeVersion::V5).unwrap();
+            for (id, actor) in actors {
+                tree.set_actor(&Address::new_id(*id), actor.clone())
+                    .unwrap();
+            }
+            tree.flush().unwrap()
+        };
+        // Enough actors for the HAMT to have several levels.
+        let mut actors = (0..500).map(|id| (id, account(id, 0))).collect_vec();
+        let root_a = flush(&actors);
+        actors.retain(|(id, _)| *id != 7);
+        actors[41].1 = account(50, 3);
+        actors.push((1000, account(1, 0)));
+        let root_b = flush(&actors);
+
+        let mut diffs = diff_state_roots(&db, &root_a, &root_b, false, None, 10)
+            .unwrap()
+            .actors;
+        diffs.sort_by_key(|it| it.address.id().unwrap());
+        let summary = diffs
+            .iter()
+            .map(|it| {
+                (
+                    it.address.id().unwrap(),
+                    it.change,
+                    it.balance_delta.clone(),
+                    it.nonce_delta,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            [
+                (7, ActorChange::Removed, TokenAmount::from_atto(-7), 0),
+                (42, ActorChange::Changed, TokenAmount::from_atto(8), 3),
+                (1000, ActorChange::Added, TokenAmount::from_atto(1), 0),
+            ]
+        );
+        assert!(
+            diff_state_roots(&db, &root_a, &root_a, false, None, 10)
+                .unwrap()
+                .actors
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn diff_is_paged_in_hash_order() {
+        let db = Arc::new(MemoryDB::default());
+        let flush = |actors: &[(u64, ActorState)]| {
+            let mut tree = StateTree::new(&db, StateTreeVersion::V5).unwrap();
+            for (id, actor) in actors {
+                tree.set_actor(&Address::new_id(*id), actor.clone())
+                    .unwrap();
+            }
+            tree.flush().unwrap()
+        };
+        // Unrelated trees of different depths, so that buckets are diffed against subtrees.
+        let root_a = flush(&(0..20).map(|id| (id, account(id, 0))).collect_vec());
+        let root_b = flush(&(10..600).map(|id| (id, account(id, 1))).collect_vec());
+
+        let mut pages = vec![];
+        let mut cursor = None;
+        loop {
+            let page = diff_state_roots(&db, &root_a, &root_b, false, cursor.as_ref(), 7).unwrap();
+            assert!(page.actors.len() <= 7);
+            pages.extend(page.actors.into_iter().map(|it| it.address));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        let hashes = pages
+            .iter()
+            .map(|it| key_hash(&it.to_bytes()))
+            .collect_vec();
+        assert!(hashes.is_sorted(), "pages are not in hash order");
+        let mut ids = pages.iter().map(|it| it.id().unwrap()).collect_vec();
+        ids.sort();
+        assert_eq!(ids, (0..600).collect_vec());
+    }
+}
@@ -28,6 +28,7 @@ where
         Subcommand::Archive(cmd) => cmd.run().await,
         Subcommand::DB(cmd) => cmd.run().await,
         Subcommand::Index(cmd) => cmd.run().await,
+        Subcommand::Beacon(cmd) => cmd.run().await,
         Subcommand::Car(cmd) => cmd.run().await,
         Subcommand::Api(cmd) => cmd.run().await,
         Subcommand::Net(cmd) => cmd.run().await,
@@ -113,6 +113,7 @@ pub async fn offline_rpc_state(
         RPCState {
             state_manager,
             keystore: Arc::new(RwLock::new(keystore)),
+            token_revocations: Default::default(),
             mpool: message_pool,
             bad_blocks: Default::default(),
             sync_status: Arc::new(ArcSwap::from_pointee(SyncStatusReport::init())),
@@ -231,7 +232,7 @@ async fn start_offline_rpc(
     let mut terminate = signal(SignalKind::terminate())?;
     let (stop_handle, server_handle) = stop_channel();
     let result = tokio::select! {
-        ret = start_rpc(state, rpc_listener, stop_handle, None, crate::rpc::MetricsMode::Enabled) => ret,
+        ret = start_rpc(state, rpc_listener, None, stop_handle, None, None, None, crate::rpc::MetricsMode::Enabled) => ret,
         _ = ctrl_c() => {
             info!("Keyboard interrupt.");
             Ok(())
@@ -662,23 +662,27 @@ fn chain_tests_with_tipset<DB: Blockstore + ShallowClone>(
 }
 
 fn auth_tests() -> anyhow::Result<Vec<RpcTest>> {
-    // Note: The second optional parameter of `AuthNew` is not supported in Lotus
+    // Note: The optional parameters of `AuthNew` are not supported in Lotus
     Ok(vec![
         RpcTest::basic(AuthNew::request((
             AuthNewParams::process_perms(Permission::Admin.to_string())?,
             None,
+            None,
         ))?),
         RpcTest::basic(AuthNew::request((
             AuthNewParams::process_perms(Permission::Sign.to_string())?,
             None,
+            None,
         ))?),
         RpcTest::basic(AuthNew::request((
             AuthNewParams::process_perms(Permission::Write.to_string())?,
             None,
+            None,
         ))?),
         RpcTest::basic(AuthNew::request((
             AuthNewParams::process_perms(Permission::Read.to_string())?,
             None,
+            None,
         ))?),
     ])
 }
@@ -1531,7 +1535,7 @@ fn eth_tests(server_mode: ServerMode) -> anyhow::Result<Vec<RpcTest>> {
             };
 
             tests.push(RpcTest::identity(EthCall::request_with_alias(
-                (msg.clone(), Predefined::Latest.into()),
+                (msg.clone(), Predefined::Latest.into(), None),
                 use_alias,
             )?));
 
@@ -1539,7 +1543,7 @@ fn eth_tests(server_mode: ServerMode) -> anyhow::Result<Vec<RpcTest>> {
                 for api_path in [ApiPaths::V1, ApiPaths::V2] {
                     tests.push(RpcTest::identity(
                         EthCall::request_with_alias(
-                            (msg.clone(), BlockNumberOrHash::PredefinedBlock(tag)),
+                            (msg.clone(), BlockNumberOrHash::PredefinedBlock(tag), None),
                             use_alias,
                         )?
                         .with_api_path(api_path),
@@ -1635,8 +1639,12 @@ fn eth_call_api_err_tests(epoch: ChainEpoch) -> Vec<RpcTest> {
             ..EthCallMessage::default()
         };
 
-        let eth_call_request =
-            EthCall::request((msg.clone(), BlockNumberOrHash::from_block_number(epoch))).unwrap();
+        let eth_call_request = EthCall::request((
+            msg.clone(),
+            BlockNumberOrHash::from_block_number(epoch),
+            None,
+        ))
+        .unwrap();
         tests.extend([
             RpcTest::identity(eth_call_request.clone().with_api_path(ApiPaths::V1))
                 .policy_on_rejected(PolicyOnRejected::PassWithIdenticalError),
@@ -2288,6 +2296,7 @@ fn eth_tests_with_tipset<DB: Blockstore + ShallowClone>(
                                 ..Default::default()
                             },
                             Some(BlockNumberOrHash::BlockNumber(shared_tipset.epoch().into())),
+                            None,
                         ))?
                         .with_api_path(api_path),
                     )
@@ -2642,6 +2651,7 @@ fn eth_expensive_fork_error_tests(store: Arc<ManyCar>) -> anyhow::Result<Vec<Rpc
         RpcTest::identity(EthCall::request((
             EthCallMessage::default(),
             BlockNumberOrHash::from_block_number(expensive_fork_epoch),
+            None,
         ))?)
         .policy_on_rejected(PolicyOnRejected::PassWithQuasiIdenticalError),
         RpcTest::identity(EthEstimateGas::request((
@@ -2650,6 +2660,7 @@ fn eth_expensive_fork_error_tests(store: Arc<ManyCar>) -> anyhow::Result<Vec<Rpc
                 ..Default::default()
             },
             Some(BlockNumberOrHash::from_block_number(expensive_fork_epoch)),
+            None,
         ))?)
         .policy_on_rejected(PolicyOnRejected::PassWithQuasiIdenticalError),
     ])
@@ -156,6 +156,7 @@ async fn ctx(
     let rpc_state = Arc::new(RPCState {
         state_manager,
         keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory)?)),
+        token_revocations: Default::default(),
         mpool: message_pool,
         bad_blocks: Default::default(),
         sync_status: Arc::new(ArcSwap::from_pointee(SyncStatusReport::init())),
@@ -286,8 +287,12 @@ impl<T: SettingsStore> SettingsStore for ReadOpsTrackingStore<T> {
         Ok(result.is_some())
     }
 
-    fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
-        self.inner.setting_keys()
+    fn delete_setting(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete_setting(key)
+    }
+
+    fn setting_keys(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.setting_keys(prefix)
     }
 }
 
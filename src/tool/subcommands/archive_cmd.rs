@@ -26,6 +26,9 @@
 //!
 //! Additional reading: [`crate::db::car::plain`]
 
+mod storage;
+
+use self::storage::{StorageTarget, storage_target};
 use crate::blocks::Tipset;
 use crate::chain::{
     ChainEpochDelta, ExportOptions, FilecoinSnapshotMetadata, FilecoinSnapshotVersion,
@@ -36,8 +39,11 @@ use crate::cid_collections::FileBackedCidHashSet;
 use crate::cli_shared::{snapshot, snapshot::TrustedVendor};
 use crate::daemon::bundle::load_actor_bundles;
 use crate::db::DbImpl;
-use crate::db::car::{AnyCar, ManyCar, forest::DEFAULT_FOREST_CAR_COMPRESSION_LEVEL};
-use crate::f3::snapshot::F3SnapshotHeader;
+use crate::db::car::{
+    AnyCar, ManyCar, SNAPSHOT_MANIFEST_FILE_EXTENSION, SnapshotManifest,
+    forest::DEFAULT_FOREST_CAR_COMPRESSION_LEVEL,
+};
+use crate::f3::snapshot::{F3SnapshotHeader, verify_f3_snapshot};
 use crate::interpreter::VMTrace;
 use crate::ipld::{stream_chain, stream_graph};
 use crate::networks::{ChainConfig, NetworkChain, butterflynet, calibnet, mainnet};
@@ -106,6 +112,15 @@ pub enum ArchiveCommands {
         /// Path to a standalone F3 snapshot.
         snapshot: PathBuf,
     },
+    /// Verify the F3 finality certificates of a standalone F3 snapshot, or of the F3 data of a v2
+    /// Filecoin snapshot, without the F3 sidecar.
+    VerifyF3 {
+        /// Path to a standalone F3 snapshot, or to a v2 Filecoin snapshot.
+        snapshot: PathBuf,
+        /// The network the snapshot belongs to.
+        #[arg(long, default_value_t = NetworkChain::Mainnet)]
+        chain: NetworkChain,
+    },
     /// Trim a snapshot of the chain and write it to `<output_path>`
     Export {
         /// Snapshot input path. Currently supports only `.car` file format.
@@ -165,6 +180,41 @@ pub enum ArchiveCommands {
         #[arg(long)]
         output: PathBuf,
     },
+    /// Create a snapshot manifest listing a base snapshot and the diff
+    /// snapshots to apply on top of it, in order.
+    CreateManifest {
+        /// Path to the base snapshot.
+        #[arg(long)]
+        base: PathBuf,
+        /// Paths to the diff snapshots, in the order they have to be applied.
+        #[arg(long = "diff")]
+        diffs: Vec<PathBuf>,
+        /// Manifest output path, must end with `.manifest.json`.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Verify the checksums of the snapshots listed in a manifest, and that
+    /// the diffs form an unbroken chain on top of the base snapshot.
+    VerifyManifest {
+        /// Path to the snapshot manifest.
+        manifest: PathBuf,
+    },
+    /// Compact the base snapshot and diffs of a manifest into a new base
+    /// snapshot, at the head of the last diff.
+    CompactManifest {
+        /// Path to the snapshot manifest.
+        manifest: PathBuf,
+        /// Snapshot output filename or directory. Defaults to
+        /// `./forest_snapshot_{chain}_{year}-{month}-{day}_height_{epoch}.car.zst`.
+        #[arg(short, long, default_value = ".", verbatim_doc_comment)]
+        output_path: PathBuf,
+        /// How many state-roots to include. Lower limit is 900 for `calibnet` and `mainnet`.
+        #[arg(short, long, default_value_t = 2000)]
+        depth: ChainEpochDelta,
+        /// Overwrite output file without prompting.
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
     /// Show the difference between the canonical and computed state of a
     /// tipset.
     Diff {
@@ -184,7 +234,11 @@ pub enum ArchiveCommands {
     SyncBucket {
         #[arg(required = true)]
         snapshot_files: Vec<PathBuf>,
-        /// `S3` endpoint URL.
+        /// Where the snapshots are published: `s3://<bucket>[/<prefix>]`, a `WebDAV` URL with the
+        /// `dav://` or `davs://` scheme, or a local directory.
+        #[arg(long, default_value = FOREST_ARCHIVE_TARGET)]
+        target: String,
+        /// `S3` endpoint URL, for `s3://` targets.
         #[arg(long, default_value = FOREST_ARCHIVE_S3_ENDPOINT)]
         endpoint: String,
         /// Don't generate or upload files, just show what would be done.
@@ -253,6 +307,37 @@ impl ArchiveCommands {
                 println!("{f3_snap_header}");
                 Ok(())
             }
+            Self::VerifyF3 { snapshot, chain } => {
+                let manifest = crate::f3::get_f3_predefined_manifest(&chain)?;
+                let initial_power_table = ChainConfig::from_chain(&chain).f3_initial_power_table;
+                let verification = if let Ok(store) = AnyCar::try_from(snapshot.as_path()) {
+                    let f3_cid = store
+                        .metadata()
+                        .and_then(|metadata| metadata.f3_data)
+                        .context("the snapshot has no F3 data")?;
+                    let f3_data = store
+                        .get_reader(f3_cid)?
+                        .with_context(|| format!("f3 data not found, cid: {f3_cid}"))?;
+                    verify_f3_snapshot(
+                        BufReader::new(f3_data),
+                        &manifest.network_name,
+                        manifest.initial_instance,
+                        initial_power_table,
+                    )?
+                } else {
+                    let file = File::open(&snapshot).with_context(|| {
+                        format!("failed to open F3 snapshot '{}'", snapshot.display())
+                    })?;
+                    verify_f3_snapshot(
+                        BufReader::new(file),
+                        &manifest.network_name,
+                        manifest.initial_instance,
+                        initial_power_table,
+                    )?
+                };
+                println!("{verification}");
+                Ok(())
+            }
             Self::Export {
                 snapshot_files,
                 output_path,
@@ -290,6 +375,58 @@ impl ArchiveCommands {
                 f3,
                 output,
             } => merge_f3_snapshot(filecoin_v1, f3, output).await,
+            Self::CreateManifest {
+                base,
+                diffs,
+                output,
+            } => {
+                anyhow::ensure!(
+                    SnapshotManifest::is_manifest(&output),
+                    "manifest file name must end with {SNAPSHOT_MANIFEST_FILE_EXTENSION}"
+                );
+                let manifest = SnapshotManifest::create(&base, &diffs, &output)?;
+                manifest.save(&output)?;
+                println!(
+                    "Wrote manifest with {} diff(s) up to epoch {} to {}",
+                    manifest.diffs.len(),
+                    manifest.head_epoch(),
+                    output.display()
+                );
+                Ok(())
+            }
+            Self::VerifyManifest { manifest } => {
+                let manifest_path = manifest;
+                let manifest = SnapshotManifest::load(&manifest_path)?;
+                manifest.verify_and_record(&manifest_path)?;
+                println!(
+                    "Verified base snapshot at epoch {} and {} diff(s) up to epoch {}",
+                    manifest.base.epoch,
+                    manifest.diffs.len(),
+                    manifest.head_epoch()
+                );
+                Ok(())
+            }
+            Self::CompactManifest {
+                manifest,
+                output_path,
+                depth,
+                force,
+            } => {
+                let store = Arc::new(ManyCar::try_from(vec![manifest])?);
+                let heaviest_tipset = store.heaviest_tipset()?;
+                do_export(
+                    &store,
+                    heaviest_tipset,
+                    None,
+                    output_path,
+                    None,
+                    depth,
+                    None,
+                    None,
+                    force,
+                )
+                .await
+            }
             Self::Diff {
                 snapshot_files,
                 epoch,
@@ -297,10 +434,14 @@ impl ArchiveCommands {
             } => show_tipset_diff(snapshot_files, epoch, depth).await,
             Self::SyncBucket {
                 snapshot_files,
+                target,
                 endpoint,
                 dry_run,
                 export_mode,
-            } => sync_bucket(snapshot_files, endpoint, dry_run, export_mode).await,
+            } => {
+                let target = storage_target(&target, &endpoint)?;
+                sync_bucket(snapshot_files, target.as_ref(), dry_run, export_mode).await
+            }
         }
     }
 }
@@ -958,99 +1099,35 @@ fn format_diff_snapshot(
     ))
 }
 
-// Check if
-// forest-internal.chainsafe.dev/{network}/lite/forest_snapshot_{network}_{date}_height_{epoch}.forest.car.zst
-// exists.
-async fn bucket_has_lite_snapshot(
+/// Key of a lite snapshot in the storage target, `{network}/lite/{file name}`.
+fn lite_snapshot_key(
     network: &str,
     genesis_timestamp: u64,
     epoch: ChainEpoch,
-) -> anyhow::Result<bool> {
-    let url = format!(
-        "https://forest-internal.chainsafe.dev/{}/lite/{}",
-        network,
+) -> anyhow::Result<String> {
+    Ok(format!(
+        "{network}/lite/{}",
         format_lite_snapshot(network, genesis_timestamp, epoch)?
-    );
-    let response = reqwest::Client::new().get(url).send().await?;
-    Ok(response.status().is_success())
+    ))
 }
 
-// Check if
-// forest-internal.chainsafe.dev/{network}/diff/forest_diff_{network}_{date}_height_{epoch}+3000.forest.car.zst
-// exists.
-async fn bucket_has_diff_snapshot(
+/// Key of a diff snapshot in the storage target, `{network}/diff/{file name}`.
+fn diff_snapshot_key(
     network: &str,
     genesis_timestamp: u64,
     epoch: ChainEpoch,
-) -> anyhow::Result<bool> {
-    let url = format!(
-        "https://forest-internal.chainsafe.dev/{}/diff/{}",
-        network,
+) -> anyhow::Result<String> {
+    Ok(format!(
+        "{network}/diff/{}",
         format_diff_snapshot(network, genesis_timestamp, epoch)?
-    );
-    let response = reqwest::Client::new().head(url).send().await?;
-    Ok(response.status().is_success())
+    ))
 }
 
+const FOREST_ARCHIVE_TARGET: &str = "s3://forest-archive";
+
 const FOREST_ARCHIVE_S3_ENDPOINT: &str =
     "https://2238a825c5aca59233eab1f221f7aefb.r2.cloudflarestorage.com";
 
-/// Check if the AWS CLI is installed and correctly configured.
-fn check_aws_config(endpoint: &str) -> anyhow::Result<()> {
-    let status = std::process::Command::new("aws")
-        .arg("help")
-        .stdout(std::process::Stdio::null())
-        .status()
-        .map_err(|e| anyhow::anyhow!("Failed to execute 'aws help': {}", e))?;
-
-    if !status.success() {
-        bail!(
-            "'aws help' failed with status code: {}. Please ensure that the AWS CLI is installed and configured.",
-            status
-        );
-    }
-
-    let status = std::process::Command::new("aws")
-        .args(["s3", "ls", "s3://forest-archive/", "--endpoint", endpoint])
-        .stdout(std::process::Stdio::null())
-        .status()
-        .map_err(|e| anyhow::anyhow!("Failed to execute 'aws s3 ls': {}", e))?;
-
-    if !status.success() {
-        bail!(
-            "'aws s3 ls' failed with status code: {}. Please check your AWS credentials.",
-            status
-        );
-    }
-    Ok(())
-}
-
-/// Use the AWS CLI to upload a snapshot file to the `S3` bucket.
-fn upload_to_forest_bucket(path: PathBuf, network: &str, tag: &str) -> anyhow::Result<()> {
-    let path = path.to_str().context("snapshot path is not valid UTF-8")?;
-    let status = std::process::Command::new("aws")
-        .args([
-            "s3",
-            "cp",
-            "--acl",
-            "public-read",
-            path,
-            &format!("s3://forest-archive/{network}/{tag}/"),
-            "--endpoint",
-            FOREST_ARCHIVE_S3_ENDPOINT,
-        ])
-        .status()
-        .map_err(|e| anyhow::anyhow!("Failed to execute 'aws s3 cp': {}", e))?;
-
-    if !status.success() {
-        bail!(
-            "'aws s3 cp' failed with status code: {}. Upload failed.",
-            status
-        );
-    }
-    Ok(())
-}
-
 /// Given a block store, export a lite snapshot for a given epoch.
 async fn export_lite_snapshot<DB>(
     store: DB,
@@ -1125,18 +1202,18 @@ where
     Ok(output_path)
 }
 
-// This command is used for keeping the S3 bucket of archival snapshots
-// up-to-date. It takes a set of snapshot files and queries the S3 bucket to see
-// what is missing. If the input set of snapshot files can be used to generate
-// missing lite or diff snapshots, they'll be generated and uploaded to the S3
-// bucket.
+// This command is used for keeping a storage target (by default, the S3 bucket of
+// archival snapshots) up-to-date. It takes a set of snapshot files and queries the
+// target to see what is missing. If the input set of snapshot files can be used to
+// generate missing lite or diff snapshots, they'll be generated and uploaded to the
+// target.
 async fn sync_bucket(
     snapshot_files: Vec<PathBuf>,
-    endpoint: String,
+    target: &dyn StorageTarget,
     dry_run: bool,
     export_mode: ExportMode,
 ) -> anyhow::Result<()> {
-    check_aws_config(&endpoint)?;
+    target.check().await?;
 
     let store = Arc::new(ManyCar::try_from(snapshot_files)?);
     let heaviest_tipset = store.heaviest_tipset()?;
@@ -1162,27 +1239,22 @@ async fn sync_bucket(
     if export_mode.lite() {
         println!("Lites:",);
         for epoch in steps_in_range(&range, 30_000, 800) {
-            println!(
-                "  {}: {}",
-                epoch,
-                bucket_has_lite_snapshot(&info.network, genesis_timestamp, epoch).await?
-            );
+            let key = lite_snapshot_key(&info.network, genesis_timestamp, epoch)?;
+            println!("  {}: {}", epoch, target.exists(&key).await?);
         }
     }
     if export_mode.diff() {
         println!("Diffs:");
         for epoch in steps_in_range(&range, 3_000, 3_800) {
-            println!(
-                "  {}: {}",
-                epoch,
-                bucket_has_diff_snapshot(&info.network, genesis_timestamp, epoch).await?
-            );
+            let key = diff_snapshot_key(&info.network, genesis_timestamp, epoch)?;
+            println!("  {}: {}", epoch, target.exists(&key).await?);
         }
     }
 
     if export_mode.lite() {
         for epoch in steps_in_range(&range, 30_000, 800) {
-            if !bucket_has_lite_snapshot(&info.network, genesis_timestamp, epoch).await? {
+            let key = lite_snapshot_key(&info.network, genesis_timestamp, epoch)?;
+            if !target.exists(&key).await? {
                 println!("  {epoch}: Exporting lite snapshot",);
                 if !dry_run {
                     let output_path = export_lite_snapshot(
@@ -1193,9 +1265,12 @@ async fn sync_bucket(
                         epoch,
                     )
                     .await?;
-                    upload_to_forest_bucket(output_path, &info.network, "lite")?;
+                    target.upload(&output_path, &key).await?;
                 } else {
-                    println!("  {epoch}: Would upload lite snapshot to S3");
+                    println!(
+                        "  {epoch}: Would upload lite snapshot to {}",
+                        target.location(&key)
+                    );
                 }
             }
         }
@@ -1203,7 +1278,8 @@ async fn sync_bucket(
 
     if export_mode.diff() {
         for epoch in steps_in_range(&range, 3_000, 3_800) {
-            if !bucket_has_diff_snapshot(&info.network, genesis_timestamp, epoch).await? {
+            let key = diff_snapshot_key(&info.network, genesis_timestamp, epoch)?;
+            if !target.exists(&key).await? {
                 println!("  {epoch}: Exporting diff snapshot",);
                 if !dry_run {
                     let output_path = export_diff_snapshot(
@@ -1214,9 +1290,12 @@ async fn sync_bucket(
                         epoch,
                     )
                     .await?;
-                    upload_to_forest_bucket(output_path, &info.network, "diff")?;
+                    target.upload(&output_path, &key).await?;
                 } else {
-                    println!("  {epoch}: Would upload diff snapshot to S3");
+                    println!(
+                        "  {epoch}: Would upload diff snapshot to {}",
+                        target.location(&key)
+                    );
                 }
             }
         }
@@ -1227,7 +1306,7 @@ async fn sync_bucket(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::car::AnyCar;
+    use crate::db::car::{AnyCar, DiffChain, export_diff_chain};
     use crate::utils::db::car_stream::CarStream;
     use tempfile::TempDir;
     use tokio::io::BufReader;
@@ -1368,6 +1447,49 @@ mod tests {
         CarStream::new(BufReader::new(file)).await.unwrap();
     }
 
+    #[tokio::test]
+    #[serial_test::serial(chain_export)]
+    async fn compact_manifest() {
+        let dir = TempDir::new().unwrap();
+        let DiffChain { base, diff, .. } = export_diff_chain(dir.path()).await;
+        let manifest = dir
+            .path()
+            .join(format!("chain{SNAPSHOT_MANIFEST_FILE_EXTENSION}"));
+        ArchiveCommands::CreateManifest {
+            base,
+            diffs: vec![diff],
+            output: manifest.clone(),
+        }
+        .run()
+        .await
+        .unwrap();
+        ArchiveCommands::VerifyManifest {
+            manifest: manifest.clone(),
+        }
+        .run()
+        .await
+        .unwrap();
+
+        let output_path = dir.path().join("compacted.forest.car.zst");
+        ArchiveCommands::CompactManifest {
+            manifest,
+            output_path: output_path.clone(),
+            depth: 6,
+            force: true,
+        }
+        .run()
+        .await
+        .unwrap();
+        // The compacted snapshot holds the whole chain on its own.
+        let store = AnyCar::try_from(output_path.as_path()).unwrap();
+        let head = store.heaviest_tipset().unwrap();
+        assert_eq!(head.epoch(), 6);
+        assert_eq!(
+            head.chain(&store).map(|ts| ts.epoch()).collect_vec(),
+            (0..=6).rev().collect_vec()
+        );
+    }
+
     #[test]
     fn archive_info_calibnet() {
         let store = AnyCar::try_from(calibnet::DEFAULT_GENESIS).unwrap();
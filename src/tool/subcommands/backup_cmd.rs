@@ -1,14 +1,36 @@
 // Copyright 2019-2026 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use anyhow::bail;
+use age::secrecy::SecretString;
+use anyhow::{Context as _, bail, ensure};
 use clap::Subcommand;
+use dialoguer::console::Term;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use std::{
+    collections::BTreeMap,
+    ffi::OsString,
     fs::File,
-    path::{Path, PathBuf},
+    io::{BufRead as _, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
 };
+use walkdir::WalkDir;
 
-use crate::{cli_shared::read_config, networks::NetworkChain};
+use crate::{cli_shared::read_config, networks::NetworkChain, utils::encoding::hex};
+
+/// Name of the archive entry holding the [`BackupManifest`].
+const MANIFEST_ENTRY: &str = "forest-backup-manifest.json";
+const FOREST_BACKUP_PASSPHRASE_ENV: &str = "FOREST_BACKUP_PASSPHRASE";
+/// `log2(N)` of the `scrypt` work factor of passphrase-encrypted backups, the one the `age` tool
+/// typically picks.
+const SCRYPT_WORK_FACTOR: u8 = 18;
+/// Higher work factors are rejected when decrypting, as `scrypt` allocates `2^(N + 10)` bytes
+/// before the passphrase can be checked.
+const SCRYPT_MAX_WORK_FACTOR: u8 = 20;
+
+type Recipients = Vec<Box<dyn age::Recipient + Send>>;
+type Identities = Vec<Box<dyn age::Identity>>;
 
 #[derive(Subcommand)]
 pub enum BackupCommands {
@@ -36,6 +58,16 @@ pub enum BackupCommands {
         /// Optional TOML file containing forest daemon configuration. If not provided, the default configuration will be used.
         #[arg(short, long)]
         daemon_config: Option<PathBuf>,
+        /// Encrypt the backup to this `age` recipient (`age1...`). Can be repeated.
+        #[arg(long, conflicts_with = "passphrase")]
+        recipient: Vec<String>,
+        /// Encrypt the backup with a passphrase, read from `FOREST_BACKUP_PASSPHRASE` or prompted for
+        #[arg(long)]
+        passphrase: bool,
+        /// Manifest of a previous backup. Only files that are new or changed since that backup
+        /// are included.
+        #[arg(long)]
+        incremental_from: Option<PathBuf>,
     },
     /// Restore a backup of the node from a file. The node must be offline.
     Restore {
@@ -48,6 +80,19 @@ pub enum BackupCommands {
         /// WARNING: This will overwrite existing files.
         #[arg(long)]
         force: bool,
+        /// `age` identity file to decrypt the backup with. Without it, encrypted backups are
+        /// decrypted with a passphrase, read from `FOREST_BACKUP_PASSPHRASE` or prompted for.
+        #[arg(long)]
+        identity: Option<PathBuf>,
+    },
+    /// Check the integrity of a backup without restoring it
+    Verify {
+        /// Path to the backup file
+        backup_file: PathBuf,
+        /// `age` identity file to decrypt the backup with. Without it, encrypted backups are
+        /// decrypted with a passphrase, read from `FOREST_BACKUP_PASSPHRASE` or prompted for.
+        #[arg(long)]
+        identity: Option<PathBuf>,
     },
 }
 
@@ -62,6 +107,9 @@ impl BackupCommands {
                 backup_chain,
                 include_proof_params,
                 daemon_config,
+                recipient,
+                passphrase,
+                incremental_from,
             } => {
                 let (_, config) = read_config(daemon_config.as_ref(), backup_chain.clone())?;
 
@@ -82,12 +130,26 @@ impl BackupCommands {
                     )?
                 };
 
+                let recipients = if passphrase {
+                    passphrase_recipient(read_passphrase(true)?, SCRYPT_WORK_FACTOR)
+                } else {
+                    recipient
+                        .iter()
+                        .map(|r| parse_recipient(r))
+                        .collect::<anyhow::Result<_>>()?
+                };
+
+                let parent = incremental_from
+                    .map(|path| BackupManifest::load(&path))
+                    .transpose()?;
+
                 let backup_file_path = if let Some(backup_file) = backup_file {
                     backup_file
                 } else {
                     let path = PathBuf::from(format!(
-                        "forest-backup-{}.tar",
-                        chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S")
+                        "forest-backup-{}.tar{}",
+                        chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S"),
+                        if recipients.is_empty() { "" } else { ".age" }
                     ));
                     if path.exists() {
                         bail!("Backup file already exists at {}", path.display());
@@ -95,8 +157,20 @@ impl BackupCommands {
                     path
                 };
 
-                archive_entries(data_dir, backup_entries, &backup_file_path)?;
+                let manifest = archive_entries(
+                    data_dir,
+                    backup_entries,
+                    &backup_file_path,
+                    &recipients,
+                    parent.as_ref(),
+                )?;
                 println!("Backup complete: {}", backup_file_path.display());
+                println!(
+                    "Manifest: {} ({} of {} files included)",
+                    manifest_path(&backup_file_path).display(),
+                    manifest.files.values().filter(|f| f.in_archive).count(),
+                    manifest.files.len()
+                );
 
                 Ok(())
             }
@@ -104,64 +178,448 @@ impl BackupCommands {
                 backup_file,
                 daemon_config,
                 force,
+                identity,
             } => {
                 let (_, config) = read_config(daemon_config.as_ref(), None)?;
                 let data_dir = &config.client.data_dir;
 
-                extract_entries(data_dir, &backup_file, force)?;
+                extract_entries(data_dir, &backup_file, identity.as_deref(), force)?;
                 println!("Restore complete");
 
                 Ok(())
             }
+            BackupCommands::Verify {
+                backup_file,
+                identity,
+            } => verify_backup(&backup_file, identity.as_deref()),
         }
     }
 }
 
-fn extract_entries(data_dir: &Path, backup_file: &Path, force: bool) -> anyhow::Result<()> {
-    let backup_file = File::open(backup_file)?;
-    let mut archive = tar::Archive::new(backup_file);
+/// Describes the files of a backup. It is stored as the last entry of the archive, and as a
+/// `<backup>.manifest.json` file next to it, so that incremental backups can be based on it
+/// without decrypting the previous backup.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackupManifest {
+    /// Creation time, in RFC 3339 format
+    created: String,
+    /// SHA-256 digest of the manifest of the backup this one is based on, for incremental backups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    /// Every backed-up file, relative to the data directory, including the unchanged files of
+    /// incremental backups
+    files: BTreeMap<String, BackupFile>,
+    /// Files of the parent backup that no longer exist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupFile {
+    size: u64,
+    modified_ns: u64,
+    sha256: String,
+    /// Whether the file is in this archive, or only in one of its parents
+    in_archive: bool,
+}
+
+impl BackupManifest {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        serde_json::from_reader(BufReader::new(File::open(path).with_context(|| {
+            format!("failed to open backup manifest {}", path.display())
+        })?))
+        .with_context(|| format!("invalid backup manifest {}", path.display()))
+    }
+
+    fn digest(&self) -> anyhow::Result<String> {
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(self)?)))
+    }
+}
+
+fn manifest_path(backup_file_path: &Path) -> PathBuf {
+    let mut path = OsString::from(backup_file_path.as_os_str());
+    path.push(".manifest.json");
+    path.into()
+}
+
+/// Reads the backup passphrase from `FOREST_BACKUP_PASSPHRASE`, or prompts for it.
+fn read_passphrase(confirm: bool) -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var(FOREST_BACKUP_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let term = Term::stderr();
+    // `dialoguer::Password` doesn't fail if the terminal is not a tty, so check it ourselves.
+    ensure!(
+        term.is_term(),
+        "cannot read passphrase from non-terminal, set {FOREST_BACKUP_PASSPHRASE_ENV} instead"
+    );
+    let prompt = dialoguer::Password::new().with_prompt("Enter the backup passphrase");
+    let prompt = if confirm {
+        prompt.with_confirmation(
+            "Confirm passphrase",
+            "Error: the passphrases do not match. Try again or press Ctrl+C to abort.",
+        )
+    } else {
+        prompt
+    };
+    Ok(prompt.interact_on(&term)?)
+}
+
+fn parse_recipient(recipient: &str) -> anyhow::Result<Box<dyn age::Recipient + Send>> {
+    let recipient = recipient
+        .parse::<age::x25519::Recipient>()
+        .map_err(|e| anyhow::anyhow!("invalid age recipient {recipient}: {e}"))?;
+    Ok(Box::new(recipient))
+}
+
+fn passphrase_recipient(passphrase: String, work_factor: u8) -> Recipients {
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+    recipient.set_work_factor(work_factor);
+    vec![Box::new(recipient)]
+}
+
+fn passphrase_identity(passphrase: String) -> Identities {
+    let mut identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+    identity.set_max_work_factor(SCRYPT_MAX_WORK_FACTOR);
+    vec![Box::new(identity)]
+}
+
+/// Reads the identities of an `age` identity file, as written by `age-keygen`.
+fn read_identity_file(path: &Path) -> anyhow::Result<Identities> {
+    let file = File::open(path)
+        .with_context(|| format!("failed to open identity file {}", path.display()))?;
+    Ok(age::IdentityFile::from_buffer(BufReader::new(file))?.into_identities()?)
+}
+
+/// Whether `header` is the start of an `age` encrypted file.
+fn is_encrypted(header: &[u8]) -> bool {
+    header.starts_with(b"age-encryption.org/")
+}
+
+/// Opens a backup, decrypting it if needed.
+fn open_backup(backup_file: &Path, identity: Option<&Path>) -> anyhow::Result<Box<dyn Read>> {
+    decrypt_backup(backup_file, || match identity {
+        Some(identity) => read_identity_file(identity),
+        None => Ok(passphrase_identity(read_passphrase(false)?)),
+    })
+}
+
+/// Opens a backup, decrypting it with the `identities` if it is encrypted.
+fn decrypt_backup(
+    backup_file: &Path,
+    identities: impl FnOnce() -> anyhow::Result<Identities>,
+) -> anyhow::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(
+        File::open(backup_file)
+            .with_context(|| format!("failed to open backup {}", backup_file.display()))?,
+    );
+    if !is_encrypted(reader.fill_buf()?) {
+        return Ok(Box::new(reader));
+    }
+    let decryptor = age::Decryptor::new_buffered(reader)?;
+    let identities = identities()?;
+    Ok(Box::new(
+        decryptor.decrypt(identities.iter().map(|it| it.as_ref()))?,
+    ))
+}
+
+fn entry_key(path: &Path) -> anyhow::Result<String> {
+    path.to_str()
+        .map(str::to_owned)
+        .with_context(|| format!("non UTF-8 path {}", path.display()))
+}
+
+/// Resolves `key`, a path read from a backup, in `data_dir`. Only relative paths without `.`
+/// or `..` components are accepted, so that a crafted backup can't touch files outside of the
+/// data directory.
+fn data_dir_path(data_dir: &Path, key: &Path) -> anyhow::Result<PathBuf> {
+    ensure!(
+        key.components().next().is_some()
+            && key
+                .components()
+                .all(|component| matches!(component, Component::Normal(_))),
+        "invalid path {} in backup",
+        key.display()
+    );
+    let path = data_dir.join(key);
+    ensure!(
+        path.starts_with(data_dir),
+        "path {} in backup is outside of the data directory",
+        key.display()
+    );
+    Ok(path)
+}
+
+fn extract_entries(
+    data_dir: &Path,
+    backup_file: &Path,
+    identity: Option<&Path>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let mut archive = tar::Archive::new(open_backup(backup_file, identity)?);
+    std::fs::create_dir_all(data_dir)?;
+    let mut manifest = None;
     for file in archive.entries()? {
         let mut file = file?;
-        let path = file.path()?;
-        let path = data_dir.join(path);
-        if path.exists() && !force {
+        let path = file.path()?.into_owned();
+        if path == Path::new(MANIFEST_ENTRY) {
+            manifest = Some(serde_json::from_reader::<_, BackupManifest>(&mut file)?);
+            continue;
+        }
+        let entry_type = file.header().entry_type();
+        // Links could point outside of the data directory, and later entries be written
+        // through them.
+        ensure!(
+            entry_type.is_file() || entry_type.is_dir(),
+            "unsupported {entry_type:?} entry {} in backup",
+            path.display()
+        );
+        let target = data_dir_path(data_dir, &path)?;
+        // Directories are shared with the parent of an incremental backup.
+        if target.exists() && !force && !(entry_type.is_dir() && target.is_dir()) {
             bail!(
                 "File already exists at {}. Use --force to overwrite.",
-                path.display()
+                target.display()
             );
         }
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        println!("Restoring {}", target.display());
+        // Unlike `unpack`, `unpack_in` refuses to write through symbolic links leading out of
+        // the data directory.
+        ensure!(
+            file.unpack_in(data_dir)?,
+            "invalid path {} in backup",
+            path.display()
+        );
+    }
+
+    let canonical_data_dir = data_dir.canonicalize()?;
+    for removed in manifest.iter().flat_map(|m| &m.removed) {
+        let path = data_dir_path(data_dir, Path::new(removed))?;
+        if !path.symlink_metadata().is_ok_and(|it| it.is_file()) {
+            continue;
         }
-        println!("Restoring {}", path.display());
-        file.unpack(path)?;
+        ensure!(
+            path.parent()
+                .map(Path::canonicalize)
+                .transpose()?
+                .is_some_and(|parent| parent.starts_with(&canonical_data_dir)),
+            "removed path {removed} in backup is outside of the data directory"
+        );
+        println!("Removing {}", path.display());
+        std::fs::remove_file(path)?;
     }
 
     Ok(())
 }
 
 fn archive_entries(
-    data_dir: &PathBuf,
+    data_dir: &Path,
     backup_entries: Vec<PathBuf>,
     backup_file_path: &Path,
-) -> anyhow::Result<()> {
-    let backup_file = File::create(backup_file_path)?;
-    let mut archive = tar::Builder::new(backup_file);
+    recipients: &[Box<dyn age::Recipient + Send>],
+    parent: Option<&BackupManifest>,
+) -> anyhow::Result<BackupManifest> {
+    let backup_file = BufWriter::new(File::create(backup_file_path)?);
+    let manifest = if recipients.is_empty() {
+        let (mut writer, manifest) = write_archive(data_dir, backup_entries, backup_file, parent)?;
+        writer.flush()?;
+        manifest
+    } else {
+        let encryptor =
+            age::Encryptor::with_recipients(recipients.iter().map(|it| it.as_ref() as _))?
+                .wrap_output(backup_file)?;
+        let (encryptor, manifest) = write_archive(data_dir, backup_entries, encryptor, parent)?;
+        encryptor.finish()?.flush()?;
+        manifest
+    };
+    std::fs::write(
+        manifest_path(backup_file_path),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+fn write_archive<W: Write>(
+    data_dir: &Path,
+    backup_entries: Vec<PathBuf>,
+    writer: W,
+    parent: Option<&BackupManifest>,
+) -> anyhow::Result<(W, BackupManifest)> {
+    let mut archive = tar::Builder::new(writer);
+    let mut manifest = BackupManifest {
+        created: chrono::Utc::now().to_rfc3339(),
+        parent: parent.map(BackupManifest::digest).transpose()?,
+        ..Default::default()
+    };
     for entry in backup_entries {
         let entry_canonicalized = entry.canonicalize()?;
         let name = entry.strip_prefix(data_dir)?;
 
         println!("Adding {} to backup", entry_canonicalized.display());
-        if entry_canonicalized.is_dir() {
-            archive.append_dir_all(name, entry_canonicalized)?;
-        } else {
-            archive.append_path_with_name(entry_canonicalized, name)?;
+        for file in WalkDir::new(&entry_canonicalized)
+            .follow_links(true)
+            .sort_by_file_name()
+        {
+            let file = file?;
+            let relative = file.path().strip_prefix(&entry_canonicalized)?;
+            let name = if relative.as_os_str().is_empty() {
+                name.to_path_buf()
+            } else {
+                name.join(relative)
+            };
+            if file.file_type().is_dir() {
+                archive.append_dir(&name, file.path())?;
+                continue;
+            }
+
+            let metadata = file.metadata()?;
+            let key = entry_key(&name)?;
+            let modified_ns = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+                .unwrap_or_default();
+            // Files are assumed unchanged if their size and modification time are, so that
+            // incremental backups of large blockstores don't need to read them.
+            if let Some(unchanged) = parent
+                .and_then(|parent| parent.files.get(&key))
+                .filter(|f| f.size == metadata.len() && f.modified_ns == modified_ns)
+            {
+                manifest.files.insert(
+                    key,
+                    BackupFile {
+                        in_archive: false,
+                        ..unchanged.clone()
+                    },
+                );
+                continue;
+            }
+
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&metadata);
+            let mut reader = HashingReader::new(File::open(file.path())?);
+            archive.append_data(&mut header, &name, &mut reader)?;
+            manifest.files.insert(
+                key,
+                BackupFile {
+                    size: metadata.len(),
+                    modified_ns,
+                    sha256: reader.finish(),
+                    in_archive: true,
+                },
+            );
+        }
+    }
+    if let Some(parent) = parent {
+        manifest.removed = parent
+            .files
+            .keys()
+            .filter(|key| !manifest.files.contains_key(*key))
+            .cloned()
+            .collect();
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        chrono::Utc::now()
+            .timestamp()
+            .try_into()
+            .unwrap_or_default(),
+    );
+    archive.append_data(&mut header, MANIFEST_ENTRY, json.as_slice())?;
+
+    Ok((archive.into_inner()?, manifest))
+}
+
+/// Reads a whole backup, checking the digest of every file against the manifest.
+fn verify_backup(backup_file: &Path, identity: Option<&Path>) -> anyhow::Result<()> {
+    let mut archive = tar::Archive::new(open_backup(backup_file, identity)?);
+    let mut digests = BTreeMap::new();
+    let mut manifest = None;
+    for file in archive.entries()? {
+        let mut file = file?;
+        let key = entry_key(&file.path()?)?;
+        if key == MANIFEST_ENTRY {
+            manifest = Some(serde_json::from_reader::<_, BackupManifest>(&mut file)?);
+        } else if file.header().entry_type().is_file() {
+            let mut reader = HashingReader::new(&mut file);
+            std::io::copy(&mut reader, &mut std::io::sink())?;
+            digests.insert(key, reader.finish());
+        }
+    }
+
+    let Some(manifest) = manifest else {
+        println!(
+            "Backup has no manifest, only its structure was checked ({} files)",
+            digests.len()
+        );
+        return Ok(());
+    };
+    let mut problems = vec![];
+    for (key, file) in manifest.files.iter().filter(|(_, f)| f.in_archive) {
+        match digests.remove(key) {
+            None => problems.push(format!("{key} is missing")),
+            Some(digest) if digest != file.sha256 => {
+                problems.push(format!("{key} does not match its digest"))
+            }
+            Some(_) => {}
         }
     }
-    archive.into_inner()?;
+    problems.extend(
+        digests
+            .keys()
+            .map(|key| format!("{key} is not in the manifest")),
+    );
+    for problem in &problems {
+        println!("{problem}");
+    }
+    ensure!(
+        problems.is_empty(),
+        "Backup verification failed: {} problem(s) found",
+        problems.len()
+    );
+    println!(
+        "Backup is valid: {} files checked{}",
+        manifest.files.values().filter(|f| f.in_archive).count(),
+        if manifest.parent.is_some() {
+            " (incremental backup, restore it on top of its parent)"
+        } else {
+            ""
+        }
+    );
     Ok(())
 }
 
+/// Computes the SHA-256 digest of everything read through it.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn finish(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(buf.get(..n).unwrap_or_default());
+        Ok(n)
+    }
+}
+
 fn validate_and_add_entries(
     data_dir: &Path,
     no_keypair: bool,
@@ -233,6 +691,7 @@ fn validate_and_add_entries(
 
 #[cfg(test)]
 mod test {
+    use age::secrecy::ExposeSecret as _;
     use itertools::Itertools;
     use tempfile::TempDir;
     use walkdir::WalkDir;
@@ -409,11 +868,12 @@ mod test {
         let (temp_dir, entries) = create_test_data();
         let data_dir = temp_dir.path().to_path_buf();
 
-        let backup_file = tempfile::Builder::new().suffix(".tar").tempfile().unwrap();
-        archive_entries(&data_dir, entries.clone(), backup_file.path()).unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_file = backup_dir.path().join("backup.tar");
+        archive_entries(&data_dir, entries.clone(), &backup_file, &[], None).unwrap();
 
         let restore_dir = tempfile::tempdir().unwrap();
-        extract_entries(restore_dir.path(), backup_file.path(), true).unwrap();
+        extract_entries(restore_dir.path(), &backup_file, None, true).unwrap();
 
         // get all entries recursively
         let get_entries_recurse = |dir| {
@@ -430,4 +890,205 @@ mod test {
         assert!(restored.len() > entries.len());
         itertools::assert_equal(original.iter(), restored.iter());
     }
+
+    #[test]
+    fn encrypted_incremental_backup_roundtrip() {
+        let (temp_dir, entries) = create_test_data();
+        let data_dir = temp_dir.path().to_path_buf();
+        let db_dir = data_dir.join("calibnet").join("paritydb");
+        std::fs::create_dir_all(&db_dir).unwrap();
+        std::fs::write(db_dir.join("index_00_00"), b"yog-sothoth").unwrap();
+        std::fs::write(db_dir.join("table_01_00"), b"azathoth").unwrap();
+        std::fs::write(data_dir.join("keystore"), b"shub-niggurath").unwrap();
+
+        let backup_dir = tempfile::tempdir().unwrap();
+        let identity = age::x25519::Identity::generate();
+        let identity_file = backup_dir.path().join("identity.txt");
+        std::fs::write(
+            &identity_file,
+            format!("# created: now\n{}\n", identity.to_string().expose_secret()),
+        )
+        .unwrap();
+        let recipients: Recipients = vec![Box::new(identity.to_public())];
+
+        let full = backup_dir.path().join("full.tar.age");
+        archive_entries(&data_dir, entries.clone(), &full, &recipients, None).unwrap();
+        assert!(is_encrypted(&std::fs::read(&full).unwrap()));
+        verify_backup(&full, Some(&identity_file)).unwrap();
+
+        std::fs::write(db_dir.join("index_00_00"), b"yog-sothoth, the key").unwrap();
+        std::fs::write(db_dir.join("table_02_00"), b"nyarlathotep").unwrap();
+        std::fs::remove_file(db_dir.join("table_01_00")).unwrap();
+
+        let parent = BackupManifest::load(&manifest_path(&full)).unwrap();
+        let incremental = backup_dir.path().join("incremental.tar.age");
+        let manifest =
+            archive_entries(&data_dir, entries, &incremental, &recipients, Some(&parent)).unwrap();
+        assert_eq!(manifest.parent, Some(parent.digest().unwrap()));
+        itertools::assert_equal(
+            manifest
+                .files
+                .iter()
+                .filter(|(_, f)| f.in_archive)
+                .map(|(key, _)| key.as_str()),
+            [
+                "calibnet/paritydb/index_00_00",
+                "calibnet/paritydb/table_02_00",
+            ],
+        );
+        assert_eq!(manifest.removed, ["calibnet/paritydb/table_01_00"]);
+        verify_backup(&incremental, Some(&identity_file)).unwrap();
+
+        let restore_dir = tempfile::tempdir().unwrap();
+        // Existing directories are restored into.
+        std::fs::create_dir_all(restore_dir.path().join("calibnet/paritydb")).unwrap();
+        extract_entries(restore_dir.path(), &full, Some(&identity_file), false).unwrap();
+        extract_entries(restore_dir.path(), &incremental, Some(&identity_file), true).unwrap();
+        let read_files = |dir: &Path| {
+            WalkDir::new(dir)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| {
+                    (
+                        entry.path().strip_prefix(dir).unwrap().to_path_buf(),
+                        std::fs::read(entry.path()).unwrap(),
+                    )
+                })
+                .sorted()
+                .collect_vec()
+        };
+        assert_eq!(read_files(restore_dir.path()), read_files(&data_dir));
+
+        // The wrong identity cannot read the backup.
+        let other = age::x25519::Identity::generate();
+        std::fs::write(&identity_file, other.to_string().expose_secret()).unwrap();
+        verify_backup(&full, Some(&identity_file)).unwrap_err();
+    }
+
+    #[test]
+    fn verify_detects_corruption() {
+        let (temp_dir, entries) = create_test_data();
+        let data_dir = temp_dir.path().to_path_buf();
+        std::fs::write(data_dir.join("keystore"), b"the color out of space").unwrap();
+
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_file = backup_dir.path().join("backup.tar");
+        archive_entries(&data_dir, entries, &backup_file, &[], None).unwrap();
+        verify_backup(&backup_file, None).unwrap();
+
+        let data = std::fs::read(&backup_file).unwrap();
+        let position = data
+            .windows(5)
+            .position(|window| window == b"color")
+            .unwrap();
+        let mut data = data;
+        data[position] = b'k';
+        std::fs::write(&backup_file, data).unwrap();
+        verify_backup(&backup_file, None).unwrap_err();
+    }
+
+    #[test]
+    fn passphrase_backup_roundtrip() {
+        let (temp_dir, entries) = create_test_data();
+        let data_dir = temp_dir.path().to_path_buf();
+        std::fs::write(data_dir.join("keystore"), b"the dunwich horror").unwrap();
+
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_file = backup_dir.path().join("backup.tar.age");
+        let recipients = passphrase_recipient("ph'nglui mglw'nafh".into(), 10);
+        archive_entries(&data_dir, entries, &backup_file, &recipients, None).unwrap();
+
+        let mut plaintext = vec![];
+        decrypt_backup(&backup_file, || {
+            Ok(passphrase_identity("ph'nglui mglw'nafh".into()))
+        })
+        .unwrap()
+        .read_to_end(&mut plaintext)
+        .unwrap();
+        assert!(
+            plaintext
+                .windows(18)
+                .any(|window| window == b"the dunwich horror")
+        );
+        assert!(
+            decrypt_backup(&backup_file, || Ok(passphrase_identity("cthulhu".into()))).is_err()
+        );
+    }
+
+    #[test]
+    fn paths_outside_of_the_data_dir_are_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let data_dir = root.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        let victim = root.path().join("victim");
+        std::fs::write(&victim, b"innsmouth").unwrap();
+
+        for removed in ["../victim", "/etc/passwd", "./keystore", ""] {
+            let manifest = BackupManifest {
+                removed: vec![removed.into()],
+                ..Default::default()
+            };
+            let json = serde_json::to_vec(&manifest).unwrap();
+            let mut archive = tar::Builder::new(vec![]);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(json.len() as u64);
+            header.set_mode(0o644);
+            archive
+                .append_data(&mut header, MANIFEST_ENTRY, json.as_slice())
+                .unwrap();
+            let backup_file = root.path().join("backup.tar");
+            std::fs::write(&backup_file, archive.into_inner().unwrap()).unwrap();
+
+            assert!(extract_entries(&data_dir, &backup_file, None, true).is_err());
+        }
+        assert!(victim.exists());
+        assert!(data_dir_path(&data_dir, Path::new("libp2p/keypair")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let data_dir = root.path().join("data");
+        let outside = root.path().join("outside");
+        std::fs::create_dir(&data_dir).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+
+        let mut archive = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        archive.append_link(&mut header, "a", &outside).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(6);
+        header.set_mode(0o644);
+        archive
+            .append_data(&mut header, "a/passwd", &b"dagon!"[..])
+            .unwrap();
+        let backup_file = root.path().join("backup.tar");
+        std::fs::write(&backup_file, archive.into_inner().unwrap()).unwrap();
+
+        assert!(extract_entries(&data_dir, &backup_file, None, true).is_err());
+        assert!(!outside.join("passwd").exists());
+
+        // Nor are files removed through a symbolic link in the data directory.
+        std::fs::write(outside.join("victim"), b"innsmouth").unwrap();
+        std::os::unix::fs::symlink(&outside, data_dir.join("b")).unwrap();
+        let json = serde_json::to_vec(&BackupManifest {
+            removed: vec!["b/victim".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut archive = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(json.len() as u64);
+        header.set_mode(0o644);
+        archive
+            .append_data(&mut header, MANIFEST_ENTRY, json.as_slice())
+            .unwrap();
+        std::fs::write(&backup_file, archive.into_inner().unwrap()).unwrap();
+        assert!(extract_entries(&data_dir, &backup_file, None, true).is_err());
+        assert!(outside.join("victim").exists());
+    }
 }
@@ -0,0 +1,97 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::ops::RangeInclusive;
+use std::{path::PathBuf, sync::Arc};
+
+use clap::Subcommand;
+
+use crate::beacon::{Beacon as _, BeaconEntryStore, DrandNetwork};
+use crate::cli_shared::{chain_path, read_config};
+use crate::daemon::db_util::load_all_forest_cars;
+use crate::db::CAR_DB_DIR_NAME;
+use crate::db::car::ManyCar;
+use crate::db::db_engine::{db_root, open_db};
+use crate::genesis::read_genesis_header;
+use crate::networks::NetworkChain;
+use crate::prelude::*;
+use crate::tool::offline_server::server::handle_chain_config;
+
+#[derive(Debug, Subcommand)]
+pub enum BeaconCommands {
+    /// Verify the signatures of the beacon entries stored while syncing, offline, against the
+    /// public key of the configured `drand` network
+    Verify {
+        /// Inclusive range of `drand` rounds, e.g. `1000..2000`
+        #[arg(value_parser = parse_round_range)]
+        range: RangeInclusive<u64>,
+        /// The `drand` network of the rounds, defaults to the latest one of the chain
+        #[arg(long)]
+        network: Option<DrandNetwork>,
+        /// Optional TOML file containing forest daemon configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional chain, will override the chain section of configuration file if used
+        #[arg(long)]
+        chain: Option<NetworkChain>,
+    },
+}
+
+impl BeaconCommands {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Verify {
+                range,
+                network,
+                config,
+                chain,
+            } => {
+                let (_, config) = read_config(config.as_ref(), chain.clone())?;
+
+                let db_root_dir = db_root(&chain_path(&config))?;
+                let db_writer = open_db(db_root_dir.clone(), config.db_config())?;
+                let db = Arc::new(ManyCar::new(db_writer));
+                load_all_forest_cars(&db, &db_root_dir.join(CAR_DB_DIR_NAME))?;
+
+                let chain_config = handle_chain_config(&config.chain)?;
+                let genesis_header = read_genesis_header(
+                    None,
+                    chain_config.genesis_bytes(&db).await?.as_deref(),
+                    &db,
+                )
+                .await?;
+                let schedule = chain_config.get_beacon_schedule(genesis_header.timestamp);
+                let beacon = match network {
+                    Some(network) => schedule
+                        .beacon_for_network(*network)
+                        .with_context(|| format!("{network} is not in the beacon schedule"))?,
+                    None => schedule.beacon_for_epoch(ChainEpoch::MAX)?.1,
+                };
+
+                println!("Network: {}", beacon.network());
+                println!("Rounds:  {}..={}", range.start(), range.end());
+                let verification = BeaconEntryStore::new(&db).verify(beacon, range.clone())?;
+                println!("Valid:        {}", verification.valid);
+                println!("Invalid:      {}", verification.invalid.len());
+                println!("Missing:      {}", verification.missing);
+                println!("Unverifiable: {}", verification.unverifiable);
+                anyhow::ensure!(
+                    verification.invalid.is_empty(),
+                    "invalid beacon entries at rounds {}",
+                    verification.invalid.iter().join(", ")
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parses an inclusive range of rounds, `FROM..TO`.
+fn parse_round_range(s: &str) -> anyhow::Result<RangeInclusive<u64>> {
+    let (from, to) = s
+        .split_once("..")
+        .context("expected a range of rounds, e.g. `1000..2000`")?;
+    let range = from.parse()?..=to.trim_start_matches('=').parse()?;
+    anyhow::ensure!(!range.is_empty(), "empty range of rounds: {s}");
+    Ok(range)
+}
@@ -9,7 +9,7 @@ use crate::chain::ChainStore;
 use crate::chain::index::ResolveNullTipset;
 use crate::cli_shared::{chain_path, read_config};
 use crate::daemon::db_util::load_all_forest_cars;
-use crate::daemon::db_util::{RangeSpec, backfill_db};
+use crate::daemon::db_util::{RangeSpec, backfill_db, backfill_traces};
 use crate::db::CAR_DB_DIR_NAME;
 use crate::db::car::ManyCar;
 use crate::db::db_engine::{db_root, open_db};
@@ -18,6 +18,7 @@ use crate::networks::NetworkChain;
 use crate::prelude::*;
 use crate::shim::clock::ChainEpoch;
 use crate::state_manager::StateManager;
+use crate::state_manager::trace_store::{TRACE_STORE_DIR_NAME, TraceStore};
 use crate::tool::offline_server::server::handle_chain_config;
 
 #[derive(Debug, Subcommand)]
@@ -39,6 +40,9 @@ pub enum IndexCommands {
         /// Number of tipsets for back-filling
         #[arg(long, conflicts_with = "to")]
         n_tipsets: Option<usize>,
+        /// Also store the execution traces of the tipsets in the trace store
+        #[arg(long)]
+        traces: bool,
     },
 }
 
@@ -51,6 +55,7 @@ impl IndexCommands {
                 from,
                 to,
                 n_tipsets,
+                traces,
             } => {
                 let spec = RangeSpec::new(*to, *n_tipsets)?;
 
@@ -75,7 +80,11 @@ impl IndexCommands {
 
                 let chain_store = ChainStore::new(db, chain_config, genesis_header.clone())?;
 
-                let state_manager = StateManager::new(chain_store.shallow_clone())?;
+                let mut state_manager = StateManager::new(chain_store.shallow_clone())?;
+                if *traces {
+                    let store = TraceStore::open(chain_data_path.join(TRACE_STORE_DIR_NAME))?;
+                    state_manager = state_manager.with_trace_store(Arc::new(store));
+                }
 
                 let head_ts = chain_store.heaviest_tipset();
 
@@ -98,6 +107,11 @@ impl IndexCommands {
 
                 backfill_db(&state_manager, &from_ts, spec).await?;
 
+                if *traces {
+                    let traced = backfill_traces(&state_manager, &from_ts, spec).await?;
+                    println!("Traced tipsets: {traced}");
+                }
+
                 Ok(())
             }
         }
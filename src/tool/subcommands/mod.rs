@@ -4,6 +4,7 @@
 pub(crate) mod api_cmd;
 pub(crate) mod archive_cmd;
 mod backup_cmd;
+mod beacon_cmd;
 mod benchmark_cmd;
 mod car_cmd;
 mod db_cmd;
@@ -65,6 +66,10 @@ pub enum Subcommand {
     #[command(subcommand)]
     Index(index_cmd::IndexCommands),
 
+    /// Drand beacon entry utilities
+    #[command(subcommand)]
+    Beacon(beacon_cmd::BeaconCommands),
+
     /// Utilities for manipulating CAR files
     #[command(subcommand)]
     Car(car_cmd::CarCommands),
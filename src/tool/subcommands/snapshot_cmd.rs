@@ -25,6 +25,9 @@ use crate::shim::clock::ChainEpoch;
 use crate::shim::executor::{Receipt, StampedEvent};
 use crate::shim::fvm_shared_latest::address::Network;
 use crate::shim::machine::GLOBAL_MULTI_ENGINE;
+use crate::state_manager::parallel_validation::{
+    ValidationProgress, validate_tipsets_parallel_blocking,
+};
 use crate::state_manager::{ExecutedTipset, apply_block_messages_blocking};
 use crate::utils::db::car_stream::{CarBlock, CarStream};
 use crate::utils::proofs_api::ensure_proof_params_downloaded;
@@ -34,7 +37,8 @@ use dialoguer::{Confirm, theme::ColorfulTheme};
 use fil_actors_shared::fvm_ipld_hamt::Hamt;
 use futures::TryStreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
@@ -87,6 +91,14 @@ pub enum SnapshotCommands {
         /// Fail at the first invalid snapshot
         #[arg(long)]
         fail_fast: bool,
+        /// Number of threads executing tipsets when checking state roots. With more than one,
+        /// the epochs are validated in independent segments.
+        #[arg(long, default_value = "1")]
+        jobs: NonZeroUsize,
+        /// File recording the validated segments, so that an interrupted validation can be
+        /// resumed
+        #[arg(long)]
+        progress: Option<PathBuf>,
     },
 
     /// Validate a snapshot's associated augmented and tipset lookup snapshots.
@@ -183,6 +195,8 @@ impl SnapshotCommands {
                     check_links,
                     check_network,
                     check_stateroots,
+                    NonZeroUsize::MIN,
+                    None,
                 )
                 .await
             }
@@ -192,6 +206,8 @@ impl SnapshotCommands {
                 check_stateroots,
                 snapshot_files,
                 fail_fast,
+                jobs,
+                progress,
             } => {
                 let mut has_fail = false;
                 for file in snapshot_files {
@@ -205,6 +221,8 @@ impl SnapshotCommands {
                             check_links,
                             check_network.clone(),
                             check_stateroots,
+                            jobs,
+                            progress.as_deref(),
                         )
                         .await?;
                         Ok::<(), anyhow::Error>(())
@@ -440,6 +458,8 @@ async fn validate_with_blockstore<BlockstoreT>(
     check_links: u32,
     check_network: Option<NetworkChain>,
     check_stateroots: u32,
+    jobs: NonZeroUsize,
+    progress: Option<&Path>,
 ) -> anyhow::Result<()>
 where
     BlockstoreT: PersistentStore + Send + Sync + 'static,
@@ -465,7 +485,7 @@ where
         let network = check_network
             .map(anyhow::Ok)
             .unwrap_or_else(|| query_network(&root, &store))?;
-        validate_stateroots(root, &store, network, check_stateroots).await?;
+        validate_stateroots(root, &store, network, check_stateroots, jobs, progress).await?;
     }
 
     println!("Snapshot is valid");
@@ -543,6 +563,8 @@ async fn validate_stateroots<DB>(
     db: &Arc<DB>,
     network: NetworkChain,
     epochs: u32,
+    jobs: NonZeroUsize,
+    progress: Option<&Path>,
 ) -> anyhow::Result<()>
 where
     DB: PersistentStore + Send + Sync + 'static,
@@ -585,15 +607,46 @@ where
 
     let beacon = Arc::new(chain_config.get_beacon_schedule(genesis.min_ticket_block().timestamp));
 
-    // ProgressBar::wrap_iter believes the progress has been abandoned once the
-    // iterator is consumed.
-    crate::state_manager::validate_tipsets_blocking(
-        &chain_index,
-        &chain_config,
-        &beacon,
-        &GLOBAL_MULTI_ENGINE,
-        tipsets,
-    )?;
+    if jobs == NonZeroUsize::MIN && progress.is_none() {
+        // ProgressBar::wrap_iter believes the progress has been abandoned once the
+        // iterator is consumed.
+        crate::state_manager::validate_tipsets_blocking(
+            &chain_index,
+            &chain_config,
+            &beacon,
+            &GLOBAL_MULTI_ENGINE,
+            tipsets,
+        )?;
+    } else {
+        let progress = ValidationProgress::load(progress.map(Path::to_path_buf))?;
+        let segments = validate_tipsets_parallel_blocking(
+            &chain_index,
+            &chain_config,
+            &beacon,
+            &GLOBAL_MULTI_ENGINE,
+            tipsets,
+            jobs,
+            &progress,
+        )?;
+        pb.suspend(|| {
+            for segment in &segments {
+                match segment.first_divergence {
+                    Some(epoch) => println!(
+                        "Epochs {}..={}: ❌ diverges at epoch {epoch}",
+                        segment.start, segment.end
+                    ),
+                    None => println!("Epochs {}..={}: ✅", segment.start, segment.end),
+                }
+            }
+        });
+        let diverging = segments
+            .iter()
+            .filter(|it| it.first_divergence.is_some())
+            .count();
+        if diverging > 0 {
+            bail!("state mismatch in {diverging} segment(s)");
+        }
+    }
 
     pb.finish_with_message("✅ verified!");
     Ok(())
@@ -17,6 +17,7 @@ pub mod p2p;
 pub mod proofs_api;
 pub mod publisher;
 pub mod rand;
+pub mod rate_limit;
 pub mod reqwest_resume;
 mod shallow_clone;
 pub use shallow_clone::ShallowClone;
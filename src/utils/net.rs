@@ -3,6 +3,7 @@
 
 mod download_file;
 pub use download_file::*;
+pub mod tls;
 
 use crate::utils::io::WithProgress;
 use crate::utils::reqwest_resume;
@@ -0,0 +1,428 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! TLS termination for the RPC, metrics and healthcheck servers.
+
+use crate::cli_shared::cli::TlsConfig;
+use crate::prelude::*;
+use anyhow::Context as _;
+use parking_lot::RwLock;
+use rustls::pki_types::pem::PemObject as _;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinHandle;
+use tokio_rustls::server::TlsStream;
+use tokio_util::either::Either;
+
+/// How often certificate files are checked for changes.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Connections that do not complete the TLS handshake in time are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum number of TLS handshakes in progress. Further connections wait to be accepted.
+const MAX_CONCURRENT_HANDSHAKES: usize = 128;
+
+/// Modification times of the files a [`ServerConfig`] was built from.
+type FileStamps = Vec<Option<SystemTime>>;
+
+struct LoadedConfig {
+    server_config: Arc<ServerConfig>,
+    stamps: FileStamps,
+    checked_at: Instant,
+}
+
+/// A TLS acceptor that reloads its certificates when they change on disk.
+pub struct TlsAcceptor {
+    config: TlsConfig,
+    loaded: RwLock<LoadedConfig>,
+}
+
+impl TlsAcceptor {
+    pub fn new(config: TlsConfig) -> anyhow::Result<Self> {
+        let stamps = file_stamps(&config);
+        let server_config = load_server_config(&config)?;
+        Ok(Self {
+            config,
+            loaded: RwLock::new(LoadedConfig {
+                server_config,
+                stamps,
+                checked_at: Instant::now(),
+            }),
+        })
+    }
+
+    /// Returns the current server configuration, reloading it first if the certificate
+    /// files changed. A configuration that fails to load is logged and the previous one is
+    /// kept.
+    fn server_config(&self) -> Arc<ServerConfig> {
+        {
+            let loaded = self.loaded.read();
+            if loaded.checked_at.elapsed() < RELOAD_CHECK_INTERVAL {
+                return loaded.server_config.shallow_clone();
+            }
+        }
+        let mut loaded = self.loaded.write();
+        if loaded.checked_at.elapsed() >= RELOAD_CHECK_INTERVAL {
+            loaded.checked_at = Instant::now();
+            let stamps = file_stamps(&self.config);
+            if stamps != loaded.stamps {
+                match load_server_config(&self.config) {
+                    Ok(server_config) => {
+                        tracing::info!(
+                            "Reloaded TLS certificate from {}",
+                            self.config.cert_path.display()
+                        );
+                        loaded.server_config = server_config;
+                        loaded.stamps = stamps;
+                    }
+                    Err(e) => tracing::warn!("Failed to reload TLS certificate: {e:#}"),
+                }
+            }
+        }
+        loaded.server_config.shallow_clone()
+    }
+
+    pub async fn accept(&self, stream: TcpStream) -> io::Result<TlsStream<TcpStream>> {
+        let acceptor = tokio_rustls::TlsAcceptor::from(self.server_config());
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+    }
+}
+
+fn file_stamps(config: &TlsConfig) -> FileStamps {
+    std::iter::once(&config.cert_path)
+        .chain([&config.key_path])
+        .chain(&config.client_ca_path)
+        .map(|path| std::fs::metadata(path).and_then(|it| it.modified()).ok())
+        .collect()
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("failed to read certificates from {}", path.display()))?;
+    anyhow::ensure!(!certs.is_empty(), "no certificate in {}", path.display());
+    Ok(certs)
+}
+
+fn load_server_config(config: &TlsConfig) -> anyhow::Result<Arc<ServerConfig>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let certs = load_certs(&config.cert_path)?;
+    let key = PrivateKeyDer::from_pem_file(&config.key_path).with_context(|| {
+        format!(
+            "failed to read private key from {}",
+            config.key_path.display()
+        )
+    })?;
+    let builder = ServerConfig::builder_with_provider(provider.shallow_clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca_path {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(path)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider)
+                .build()
+                .context("invalid client CA certificates")?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config = builder
+        .with_single_cert(certs, key)
+        .context("invalid TLS certificate or key")?;
+    Ok(server_config.into())
+}
+
+/// Builds the TLS configuration of an RPC client that trusts the CA certificates in
+/// `FOREST_RPC_CA_CERT`, and authenticates with the certificate and key in
+/// `FOREST_RPC_CLIENT_CERT` and `FOREST_RPC_CLIENT_KEY` if set. Returns `None` if
+/// `FOREST_RPC_CA_CERT` is unset.
+pub fn client_config_from_env() -> anyhow::Result<Option<ClientConfig>> {
+    let path = |key| std::env::var_os(key).map(std::path::PathBuf::from);
+    let client = match (
+        path("FOREST_RPC_CLIENT_CERT"),
+        path("FOREST_RPC_CLIENT_KEY"),
+    ) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        (None, None) => None,
+        _ => anyhow::bail!("FOREST_RPC_CLIENT_CERT and FOREST_RPC_CLIENT_KEY must be set together"),
+    };
+    match path("FOREST_RPC_CA_CERT") {
+        Some(ca_path) => load_client_config(&ca_path, client.as_ref()).map(Some),
+        None => {
+            anyhow::ensure!(
+                client.is_none(),
+                "FOREST_RPC_CLIENT_CERT requires FOREST_RPC_CA_CERT to be set"
+            );
+            Ok(None)
+        }
+    }
+}
+
+fn load_client_config(
+    ca_path: &Path,
+    client: Option<&(std::path::PathBuf, std::path::PathBuf)>,
+) -> anyhow::Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca_path)? {
+        roots.add(cert)?;
+    }
+    let builder =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots);
+    Ok(match client {
+        Some((cert_path, key_path)) => {
+            let key = PrivateKeyDer::from_pem_file(key_path).with_context(|| {
+                format!("failed to read private key from {}", key_path.display())
+            })?;
+            builder
+                .with_client_auth_cert(load_certs(cert_path)?, key)
+                .context("invalid TLS client certificate or key")?
+        }
+        None => builder.with_no_client_auth(),
+    })
+}
+
+/// Returns the subject of the certificate a client authenticated with, e.g.
+/// `O=Miskatonic, CN=alice`.
+pub fn peer_subject(stream: &TlsStream<TcpStream>) -> Option<String> {
+    let cert = stream.get_ref().1.peer_certificates()?.first()?;
+    let (_, cert) = x509_parser::parse_x509_certificate(cert).ok()?;
+    Some(cert.subject().to_string())
+}
+
+/// A TCP listener that optionally terminates TLS. Up to [`MAX_CONCURRENT_HANDSHAKES`]
+/// handshakes run concurrently, so a slow client does not hold up the others.
+pub enum MaybeTlsListener {
+    Plain(TcpListener),
+    Tls {
+        local_addr: SocketAddr,
+        incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+        accept_task: JoinHandle<()>,
+    },
+}
+
+impl MaybeTlsListener {
+    /// Binds a listener with [`bind_tcp_listener`](super::bind_tcp_listener), terminating
+    /// TLS if configured.
+    pub async fn bind(
+        addr: SocketAddr,
+        backlog: u32,
+        tls: Option<&TlsConfig>,
+    ) -> anyhow::Result<Self> {
+        let acceptor = tls
+            .cloned()
+            .map(TlsAcceptor::new)
+            .transpose()?
+            .map(Arc::new);
+        let listener = super::bind_tcp_listener(addr, backlog).await?;
+        Ok(Self::new(listener, acceptor)?)
+    }
+
+    pub fn new(listener: TcpListener, acceptor: Option<Arc<TlsAcceptor>>) -> io::Result<Self> {
+        let Some(acceptor) = acceptor else {
+            return Ok(Self::Plain(listener));
+        };
+        let local_addr = listener.local_addr()?;
+        let (tx, incoming) = mpsc::channel(64);
+        let mut listener = listener;
+        let handshakes = Arc::new(Semaphore::new(MAX_CONCURRENT_HANDSHAKES));
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let Ok(permit) = handshakes.shallow_clone().acquire_owned().await else {
+                    break;
+                };
+                let (stream, remote_addr) = axum::serve::Listener::accept(&mut listener).await;
+                let _ = stream.set_nodelay(true); // Disable Nagle's algorithm
+                if tx.is_closed() {
+                    break;
+                }
+                let acceptor = acceptor.shallow_clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let accepted = acceptor.accept(stream).await;
+                    drop(permit);
+                    match accepted {
+                        Ok(stream) => {
+                            let _ = tx.send((stream, remote_addr)).await;
+                        }
+                        Err(e) => tracing::debug!("TLS handshake with {remote_addr} failed: {e}"),
+                    }
+                });
+            }
+        });
+        Ok(Self::Tls {
+            local_addr,
+            incoming,
+            accept_task,
+        })
+    }
+}
+
+impl Drop for MaybeTlsListener {
+    fn drop(&mut self) {
+        if let Self::Tls { accept_task, .. } = self {
+            accept_task.abort();
+        }
+    }
+}
+
+impl axum::serve::Listener for MaybeTlsListener {
+    type Io = Either<TcpStream, TlsStream<TcpStream>>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self {
+            Self::Plain(listener) => {
+                let (stream, addr) = axum::serve::Listener::accept(listener).await;
+                let _ = stream.set_nodelay(true); // Disable Nagle's algorithm
+                (Either::Left(stream), addr)
+            }
+            Self::Tls { incoming, .. } => match incoming.recv().await {
+                Some((stream, addr)) => (Either::Right(stream), addr),
+                // The accept task only stops when this listener is dropped.
+                None => std::future::pending().await,
+            },
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        match self {
+            Self::Plain(listener) => listener.local_addr(),
+            Self::Tls { local_addr, .. } => Ok(*local_addr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::pki_types::ServerName;
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    struct Pki {
+        dir: tempfile::TempDir,
+        ca: rcgen::CertifiedKey,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            let mut params = rcgen::CertificateParams::new(vec![]).unwrap();
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            let key_pair = rcgen::KeyPair::generate().unwrap();
+            let cert = params.self_signed(&key_pair).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("ca.pem"), cert.pem()).unwrap();
+            Self {
+                dir,
+                ca: rcgen::CertifiedKey { cert, key_pair },
+            }
+        }
+
+        /// Issues a certificate signed by the CA, returning the certificate and key paths.
+        fn issue(&self, name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+            let mut params = rcgen::CertificateParams::new(vec!["localhost".to_owned()]).unwrap();
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, name);
+            let key_pair = rcgen::KeyPair::generate().unwrap();
+            let cert = params
+                .signed_by(&key_pair, &self.ca.cert, &self.ca.key_pair)
+                .unwrap();
+            let cert_path = self.dir.path().join(format!("{name}.pem"));
+            let key_path = self.dir.path().join(format!("{name}.key"));
+            std::fs::write(&cert_path, cert.pem()).unwrap();
+            std::fs::write(&key_path, key_pair.serialize_pem()).unwrap();
+            (cert_path, key_path)
+        }
+
+        fn client_config(&self, client: Option<&str>) -> ClientConfig {
+            let client = client.map(|name| self.issue(name));
+            load_client_config(&self.dir.path().join("ca.pem"), client.as_ref()).unwrap()
+        }
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        config: rustls::ClientConfig,
+    ) -> io::Result<tokio_rustls::client::TlsStream<TcpStream>> {
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        // With TLS 1.3, a rejected client certificate only surfaces on the first read.
+        stream.write_all(b"ping").await?;
+        stream.flush().await?;
+        Ok(stream)
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_exposes_client_subject() {
+        let pki = Pki::new();
+        let (cert_path, key_path) = pki.issue("server");
+        let acceptor = TlsAcceptor::new(TlsConfig {
+            cert_path,
+            key_path,
+            client_ca_path: Some(pki.dir.path().join("ca.pem")),
+        })
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut listener = MaybeTlsListener::new(listener, Some(acceptor.into())).unwrap();
+
+        let client = tokio::spawn(connect(addr, pki.client_config(Some("Cthulhu"))));
+        let (stream, _) = axum::serve::Listener::accept(&mut listener).await;
+        let Either::Right(mut stream) = stream else {
+            panic!("expected a TLS stream");
+        };
+        assert_eq!(peer_subject(&stream).as_deref(), Some("CN=Cthulhu"));
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        client.await.unwrap().unwrap();
+
+        // Clients without a certificate are rejected.
+        if let Ok(mut client) = connect(addr, pki.client_config(None)).await {
+            assert!(matches!(client.read(&mut buf).await, Err(_) | Ok(0)));
+        }
+    }
+
+    #[test]
+    fn certificates_are_reloaded_when_changed() {
+        let pki = Pki::new();
+        let (cert_path, key_path) = pki.issue("server");
+        let acceptor = TlsAcceptor::new(TlsConfig {
+            cert_path: cert_path.clone(),
+            key_path,
+            client_ca_path: None,
+        })
+        .unwrap();
+        let initial = acceptor.server_config();
+
+        // Not reloaded while the certificate is unchanged.
+        acceptor.loaded.write().checked_at -= RELOAD_CHECK_INTERVAL;
+        assert!(Arc::ptr_eq(&initial, &acceptor.server_config()));
+
+        // A broken certificate is ignored.
+        std::fs::write(&cert_path, "Azathoth").unwrap();
+        acceptor.loaded.write().stamps.clear();
+        acceptor.loaded.write().checked_at -= RELOAD_CHECK_INTERVAL;
+        assert!(Arc::ptr_eq(&initial, &acceptor.server_config()));
+
+        // A valid one is picked up.
+        pki.issue("server");
+        acceptor.loaded.write().stamps.clear();
+        acceptor.loaded.write().checked_at -= RELOAD_CHECK_INTERVAL;
+        assert!(!Arc::ptr_eq(&initial, &acceptor.server_config()));
+    }
+}
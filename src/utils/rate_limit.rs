@@ -0,0 +1,77 @@
+// Copyright 2019-2026 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::num::NonZeroU32;
+use std::time::Instant;
+
+/// A token bucket holding up to `capacity` tokens and refilled continuously at
+/// `refill_per_sec` tokens per second. Each request takes its cost out of the bucket and
+/// is rejected if there are not enough tokens left.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            capacity,
+            refill_per_sec,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// A bucket allowing `limit` requests per minute, in bursts of up to `limit`.
+    pub fn per_minute(limit: NonZeroU32) -> Self {
+        let limit = f64::from(limit.get());
+        Self::new(limit, limit / 60.0)
+    }
+
+    /// Takes `cost` tokens out of the bucket, returning `false` if there are not enough.
+    pub fn try_acquire(&mut self, cost: f64) -> bool {
+        self.try_acquire_at(cost, Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, cost: f64, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let mut bucket = TokenBucket::new(2.0, 1.0);
+        let start = bucket.last_refill;
+        assert!(bucket.try_acquire_at(1.0, start));
+        assert!(bucket.try_acquire_at(1.0, start));
+        assert!(!bucket.try_acquire_at(1.0, start));
+
+        // Half a token is not enough...
+        assert!(!bucket.try_acquire_at(1.0, start + Duration::from_millis(500)));
+        // ...but a full one is.
+        assert!(bucket.try_acquire_at(1.0, start + Duration::from_secs(1)));
+
+        // The bucket never holds more than its capacity.
+        let later = start + Duration::from_secs(3600);
+        assert!(bucket.try_acquire_at(2.0, later));
+        assert!(!bucket.try_acquire_at(1.0, later));
+    }
+}